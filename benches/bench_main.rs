@@ -3,7 +3,13 @@
 use criterion::criterion_main;
 
 criterion_main! {
+    cached_string_set::comparisons,
+    lexer::lexer,
     once_array_v_rwlock::comparisons,
+    traveler::traveler,
 }
 
+mod cached_string_set;
+mod lexer;
 mod once_array_v_rwlock;
+mod traveler;
@@ -0,0 +1,68 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use std::collections::HashSet;
+
+use criterion::{
+    criterion_group,
+    Criterion,
+};
+use vase::util::{
+    CachedString,
+    CachedStringSet,
+    StringCache,
+};
+
+const TEST_SIZE: usize = 1000;
+
+fn test_strings(cache: &StringCache) -> Vec<CachedString> {
+    (0..TEST_SIZE)
+        .map(|i| cache.get_or_cache(&format!("identifier{}", i)))
+        .collect()
+}
+
+fn cached_set_insert(strings: &[CachedString]) -> CachedStringSet {
+    let mut set = CachedStringSet::new();
+    for string in strings {
+        set.insert(string.clone());
+    }
+    set
+}
+
+fn hash_set_insert(strings: &[CachedString]) -> HashSet<CachedString> {
+    let mut set = HashSet::new();
+    for string in strings {
+        set.insert(string.clone());
+    }
+    set
+}
+
+fn cached_set_contains(set: &CachedStringSet, strings: &[CachedString]) -> usize {
+    strings.iter().filter(|string| set.contains(string)).count()
+}
+
+fn hash_set_contains(set: &HashSet<CachedString>, strings: &[CachedString]) -> usize {
+    strings.iter().filter(|string| set.contains(*string)).count()
+}
+
+fn bench_comparison(c: &mut Criterion) {
+    let cache = StringCache::new();
+    let strings = test_strings(&cache);
+
+    let mut group = c.benchmark_group("CachedStringSet v HashSet");
+    group.bench_function("CachedStringSet Insert", |b| {
+        b.iter(|| cached_set_insert(&strings));
+    });
+    group.bench_function("HashSet Insert", |b| {
+        b.iter(|| hash_set_insert(&strings));
+    });
+    group.bench_function("CachedStringSet Contains", |b| {
+        let set = cached_set_insert(&strings[..TEST_SIZE / 2]);
+        b.iter(|| cached_set_contains(&set, &strings));
+    });
+    group.bench_function("HashSet Contains", |b| {
+        let set = hash_set_insert(&strings[..TEST_SIZE / 2]);
+        b.iter(|| hash_set_contains(&set, &strings));
+    });
+}
+
+criterion_group!(comparisons, bench_comparison);
@@ -0,0 +1,58 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use std::path::Path;
+
+use criterion::{
+    criterion_group,
+    Criterion,
+};
+use vase::{
+    c::{
+        CompileEnv,
+        CompileSettings,
+        Lexer,
+    },
+    sync::Arc,
+    util::{
+        CachedString,
+        FileId,
+    },
+};
+
+const LINE_COUNT: usize = 1000;
+
+fn integer_heavy_source() -> String {
+    let mut source = String::new();
+    for i in 0..LINE_COUNT {
+        source += &format!(
+            "int x{0}[{0}] = {{ {0}, 1, 2, 3, 4, {1}, 0, 255 }};\n",
+            i,
+            i * 7
+        );
+    }
+    source
+}
+
+fn lex(env: &CompileEnv, source: &str) -> usize {
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+    let mut lexer = Lexer::new(env, callback);
+    lexer.lex_bytes(FileId::new(0).unwrap(), source.as_bytes()).len()
+}
+
+fn bench_lex(c: &mut Criterion) {
+    let source = integer_heavy_source();
+    let mut group = c.benchmark_group("Lex");
+    group.bench_function("Integer-Heavy", |b| {
+        let env = CompileEnv::default();
+        b.iter(|| lex(&env, &source));
+    });
+    group.bench_function("Integer-Heavy (Integer Tokens)", |b| {
+        let env = CompileEnv::new(CompileSettings {
+            integer_tokens: true,
+            ..CompileSettings::default()
+        });
+        b.iter(|| lex(&env, &source));
+    });
+}
+
+criterion_group!(lexer, bench_lex);
@@ -0,0 +1,79 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use std::path::Path;
+
+use criterion::{
+    criterion_group,
+    Criterion,
+};
+use vase::{
+    c::{
+        CompileEnv,
+        Lexer,
+        TokenKind,
+        Traveler,
+        TravelerError,
+    },
+    sync::Arc,
+    util::{
+        CachedString,
+        FileId,
+    },
+};
+
+const LINE_COUNT: usize = 1000;
+
+fn macro_free_source() -> String {
+    let mut source = String::new();
+    for i in 0..LINE_COUNT {
+        source += &format!("int x{} = a + b * (c - d) / e;\n", i);
+    }
+    source
+}
+
+fn macro_heavy_source() -> String {
+    let mut source = String::from(
+        "#define JOIN(A, B) A ## B\n\
+        #define EXPR(A, B) JOIN(A, B) + (A - B) / JOIN(B, A)\n",
+    );
+    for i in 0..LINE_COUNT {
+        source += &format!("int x{} = EXPR(a, b) * c;\n", i);
+    }
+    source
+}
+
+fn lex(source: &str) -> CompileEnv {
+    let env = CompileEnv::default();
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+    let mut lexer = Lexer::new(&env, callback);
+    let tokens = lexer.lex_bytes(FileId::new(0).unwrap(), source.as_bytes());
+    env.file_id_to_tokens.push(Arc::new(tokens));
+    env
+}
+
+fn travel(env: &CompileEnv) -> usize {
+    let mut traveler = Traveler::new(env, |_: TravelerError| false);
+    traveler
+        .load_start(env.file_id_to_tokens.get_arc(FileId::new(0).unwrap()).unwrap())
+        .unwrap();
+    let mut count = 0;
+    while !matches!(traveler.head().kind(), TokenKind::Eof) {
+        traveler.move_forward().unwrap();
+        count += 1;
+    }
+    count
+}
+
+fn bench_travel(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Travel");
+    group.bench_function("Macro-Free", |b| {
+        let env = lex(&macro_free_source());
+        b.iter(|| travel(&env));
+    });
+    group.bench_function("Macro-Heavy", |b| {
+        let env = lex(&macro_heavy_source());
+        b.iter(|| travel(&env));
+    });
+}
+
+criterion_group!(traveler, bench_travel);
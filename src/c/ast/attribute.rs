@@ -0,0 +1,42 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use crate::{
+    c::TravelRange,
+    util::CachedString,
+};
+
+/// A single attribute from an attribute specifier (`[[...]]`).
+#[derive(Clone, Debug)]
+pub struct Attribute {
+    pub range: TravelRange,
+    /// The prefix of the attribute (such as `gnu` in `gnu::unused`).
+    pub prefix: Option<CachedString>,
+    pub name: CachedString,
+    /// The range of the argument clause (including the parentheses) if there was one.
+    ///
+    /// The arguments are a balanced token sequence, so they are not parsed.
+    pub args: Option<TravelRange>,
+}
+
+impl Attribute {
+    /// Returns whether this is the standard attribute with the given name.
+    ///
+    /// Standard attributes can also be written with surrounding double underscores
+    /// (`__fallthrough__`). Prefixed attributes are never standard attributes.
+    pub fn is_standard(&self, name: &str) -> bool {
+        if self.prefix.is_some() {
+            return false;
+        }
+        normalize_attribute_name(self.name.string()) == name
+    }
+}
+
+/// Removes the surrounding double underscores an attribute's name can be written with
+/// (like `__packed__` for `packed`).
+///
+/// Names without both underscores are returned unchanged.
+pub fn normalize_attribute_name(name: &str) -> &str {
+    name.strip_prefix("__")
+        .and_then(|name| name.strip_suffix("__"))
+        .unwrap_or(name)
+}
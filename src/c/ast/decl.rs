@@ -6,6 +6,8 @@ use smallvec::SmallVec;
 use crate::{
     c::{
         ast::{
+            normalize_attribute_name,
+            Attribute,
             BlockExpr,
             Expr,
             Id,
@@ -28,6 +30,11 @@ use crate::{
 pub struct Decl {
     pub type_: Type,
     pub postfix: DeclPostfix,
+    /// The attribute specifiers (`[[...]]`) before the declaration. They apply to every
+    /// declaration that was declared with them (like both `a` and `b` in `[[maybe_unused]] int a, b;`).
+    pub leading_attributes: Vec<Attribute>,
+    /// The GNU attribute lists after the declarator (like after a function's parameters).
+    pub attributes: Vec<AttributeList>,
 }
 
 impl Decl {
@@ -35,7 +42,17 @@ impl Decl {
         let mut type_ = Type::new(StorageKind::Declared);
         type_.name = Some(name);
         type_.root = TypeRoot::EnumForward(type_index);
-        Decl { type_, postfix: DeclPostfix::None }
+        Decl {
+            type_,
+            postfix: DeclPostfix::None,
+            leading_attributes: Vec::new(),
+            attributes: Vec::new(),
+        }
+    }
+
+    /// Returns whether the given standard attribute is applied to the declaration.
+    pub fn has_attribute(&self, name: &str) -> bool {
+        self.leading_attributes.iter().any(|attr| attr.is_standard(name))
     }
 
     pub fn is_typedef(&self) -> bool {
@@ -45,6 +62,50 @@ impl Decl {
     pub fn is_function(&self) -> bool {
         !self.is_typedef() && matches!(self.type_.segments.last(), Some(&TypeSegment::Func(..)))
     }
+
+    /// Returns whether this is an anonymous struct or union member (C11 6.7.2.1).
+    ///
+    /// The fields of anonymous members are accessed as if they were fields of the outer type.
+    pub fn is_anonymous_member(&self) -> bool {
+        self.type_.name.is_none()
+            && self.type_.segments.len() == self.type_.base_segments
+            && matches!(self.type_.root, TypeRoot::Type(..))
+    }
+}
+
+/// A GNU attribute specifier (`__attribute__((...))`).
+#[derive(Clone, Debug)]
+pub struct AttributeList {
+    /// The range of the attribute specifier (from `__attribute__` to the last `)`).
+    pub range: TravelRange,
+    pub attributes: Vec<GnuAttribute>,
+}
+
+impl AttributeList {
+    /// Returns the first attribute with the given name (see [GnuAttribute::is]).
+    pub fn get(&self, name: &str) -> Option<&GnuAttribute> {
+        self.attributes.iter().find(|attribute| attribute.is(name))
+    }
+}
+
+/// A single attribute in a GNU attribute specifier (such as `aligned(8)`).
+///
+/// Unlike [Attribute](super::Attribute)s, the arguments of GNU attributes are parsed as expressions.
+#[derive(Clone, Debug)]
+pub struct GnuAttribute {
+    pub range: TravelRange,
+    pub name: Id,
+    /// The arguments of the attribute (if it had an argument clause).
+    pub args: Option<Vec<Expr>>,
+}
+
+impl GnuAttribute {
+    /// Returns whether this attribute has the given name.
+    ///
+    /// GNU attributes can also be written with surrounding double underscores (`__packed__`).
+    pub fn is(&self, name: &str) -> bool {
+        normalize_attribute_name(self.name.text.string()) == name
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -68,6 +129,21 @@ pub struct DeclRefExpr {
     pub decl_id: Option<DeclIndex>,
 }
 
+/// A key that identifies a declaration by its qualified name and type signature.
+///
+/// Unlike a [DeclIndex], a key does not depend on where the declaration is in the file.
+/// A declaration that is unchanged between two parses of a file (using the same
+/// [CompileEnv](crate::c::CompileEnv)) will have the same key.
+/// See [SourceFile::decl_key](crate::c::ast::SourceFile::decl_key).
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct DeclKey {
+    /// The names of the functions the declaration is inside of (outermost first).
+    pub path: Vec<CachedString>,
+    pub name: Option<CachedString>,
+    /// A description of the declaration's type. It does not include any locations.
+    pub signature: String,
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct DeclIndex {
     pub scope_id: ScopeId,
@@ -21,9 +21,13 @@ pub enum Expr {
     DeclRef(DeclRefExpr),
     Number(Number),
     String(StringLiteral),
-    Block(BlockExpr),
+    Stmt(StmtExpr),
     Parens(ParenExpr),
     Init(InitExpr),
+    Generic(GenericExpr),
+    LabelAddress(LabelAddressExpr),
+    CompoundLiteral(CompoundLiteralExpr),
+    Nullptr(NullptrExpr),
     // Suffixes:
     Suffix(SuffixExpr),
     Access(AccessExpr),
@@ -42,7 +46,8 @@ impl Expr {
     pub fn precedence(&self) -> Precedence {
         use Expr::*;
         match *self {
-            DeclRef(..) | Number(..) | String(..) | Block(..) | Parens(..) | Init(..) => {
+            DeclRef(..) | Number(..) | String(..) | Stmt(..) | Parens(..) | Init(..)
+            | Generic(..) | LabelAddress(..) | CompoundLiteral(..) | Nullptr(..) => {
                 Precedence::Atoms
             },
             Suffix(..) | Access(..) | Array(..) | Call(..) => Precedence::Suffixes,
@@ -55,6 +60,19 @@ impl Expr {
         }
     }
 
+    /// Returns whether this expression designates an object (so it could be assigned to
+    /// or have its address taken if its type allows it).
+    pub fn is_lvalue(&self) -> bool {
+        use Expr::*;
+        match *self {
+            DeclRef(..) | String(..) | Array(..) | CompoundLiteral(..) => true,
+            Parens(ref expr) => expr.expr.is_lvalue(),
+            Access(ref expr) => expr.through_ptr || expr.expr.is_lvalue(),
+            Prefix(ref expr) => expr.op == PrefixOp::Dereference,
+            _ => false,
+        }
+    }
+
     pub fn add_op<T>(mut self: Box<Self>, precedence: Precedence, create: T) -> Box<Self>
     where T: FnOnce(Box<Expr>) -> Box<Expr> {
         match (self.precedence(), precedence) {
@@ -83,7 +101,8 @@ impl Expr {
         let replace_with = |rhs: Box<Expr>| rhs.add_op(precedence, create);
         use Expr::*;
         let right_item = match *self {
-            DeclRef(..) | Number(..) | String(..) | Block(..) | Parens(..) | Init(..) => panic!(
+            DeclRef(..) | Number(..) | String(..) | Stmt(..) | Parens(..) | Init(..)
+            | Generic(..) | LabelAddress(..) | CompoundLiteral(..) | Nullptr(..) => panic!(
                 "Can't take right on an atom (identifier/number/string/block/paren) expression."
             ),
             Suffix(..) | Access(..) | Array(..) | Call(..) => {
@@ -110,6 +129,24 @@ pub struct BlockExpr {
     pub scope_id: ScopeId,
 }
 
+/// A GNU statement expression (`({ stmts; value; })`).
+///
+/// The value of the expression is the value of the block's last statement
+/// (if that statement is an expression).
+#[derive(Clone, Debug)]
+pub struct StmtExpr {
+    /// The range of traveler indexes this expression covers.
+    ///
+    /// If parsed without error, the start index should be a LParen token
+    /// and the end index should be a RParen token.
+    pub range: TravelRange,
+    pub block: BlockExpr,
+    /// The index of the statement (in the block scope's stmts) that gives the expression its value.
+    ///
+    /// This is None if the last statement isn't an expression (so the expression is void).
+    pub value_index: Option<usize>,
+}
+
 #[derive(Clone, Debug)]
 pub struct ParenExpr {
     /// The range of traveler indexes this expression covers.
@@ -126,6 +163,26 @@ pub struct InitExpr {
     pub values: Vec<InitMember>,
 }
 
+impl InitExpr {
+    /// Returns how many elements an array initialized by this would have.
+    ///
+    /// Returns None if any member is designated (since designators may skip elements).
+    pub fn positional_len(&self) -> Option<usize> {
+        if self
+            .values
+            .iter()
+            .all(|member| !matches!(*member, InitMember::Array(..)))
+        {
+            Some(self.values.len())
+        } else {
+            None
+        }
+    }
+}
+
+// Most array designators have a single index, so it's kept inline (even though that makes
+// the Array variant much larger than the others).
+#[allow(clippy::large_enum_variant)]
 #[derive(Clone, Debug)]
 pub enum InitMember {
     Unnamed(Expr),
@@ -134,6 +191,53 @@ pub enum InitMember {
     SubInitializer(InitExpr),
 }
 
+/// A C99 compound literal (`(type){ initializer }`).
+///
+/// Compound literals create an unnamed object, so they're always lvalues.
+#[derive(Clone, Debug)]
+pub struct CompoundLiteralExpr {
+    /// The range of traveler indexes this expression covers.
+    ///
+    /// If parsed without error, the start index should be the LParen token
+    /// and the end index should be the RBrace token of the initializer.
+    pub range: TravelRange,
+    pub type_: Type,
+    pub init: InitExpr,
+}
+
+#[derive(Clone, Debug)]
+pub struct GenericExpr {
+    /// The range of traveler indexes this expression covers.
+    ///
+    /// If parsed without error, the start index should be the _Generic token
+    /// and the end index should be a RParen token.
+    pub range: TravelRange,
+    pub controller: Box<Expr>,
+    /// The associations of the generic selection. A type of None represents
+    /// the default association.
+    pub associations: SmallVec<[(Option<Type>, Box<Expr>); 2]>,
+}
+
+/// The C23 null pointer constant (`nullptr`). It has the type `nullptr_t`
+/// (see [TypeRoot::NullptrT]).
+#[derive(Clone, Debug)]
+pub struct NullptrExpr {
+    /// The traveler index of the nullptr keyword.
+    pub index: TravelIndex,
+}
+
+/// A GNU address-of-label expression (`&&label`).
+#[derive(Clone, Debug)]
+pub struct LabelAddressExpr {
+    /// The range of traveler indexes this expression covers.
+    ///
+    /// If parsed without error, the start index should be the AmpAmp token
+    /// and the end index should be the label's identifier.
+    pub range: TravelRange,
+    pub label_scope_id: Option<ScopeId>,
+    pub label: CachedString,
+}
+
 #[derive(Clone, Debug)]
 pub struct SuffixExpr {
     pub expr: Box<Expr>,
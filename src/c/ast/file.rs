@@ -4,12 +4,18 @@ use std::path::Path;
 
 use crate::{
     c::ast::{
+        ArrayKind,
         Decl,
         DeclIndex,
+        DeclKey,
         Scope,
         ScopeId,
         ScopeKind,
+        Type,
         TypeDecl,
+        TypeDeclField,
+        TypeRoot,
+        TypeSegment,
     },
     math::NonMaxU32,
     sync::Arc,
@@ -52,17 +58,17 @@ impl SourceFile {
     }
 
     pub fn get_scope(&self, id: ScopeId) -> &Scope {
-        &self.scopes[id]
+        &self.scopes[id.non_max()]
     }
 
     pub fn get_scope_mut(&mut self, id: ScopeId) -> &mut Scope {
-        &mut self.scopes[id]
+        &mut self.scopes[id.non_max()]
     }
 
     pub fn new_scope(&mut self, parent_id: ScopeId, kind: ScopeKind) -> ScopeId {
         let id = self.scopes.len();
         self.scopes.push(Scope::new(parent_id, kind));
-        id
+        ScopeId::from_non_max(id)
     }
 
     pub fn search_scopes<W, T>(&self, scope_id: ScopeId, mut when: W) -> Option<T>
@@ -111,6 +117,27 @@ impl SourceFile {
         })
     }
 
+    /// Finds the field with the given name in a struct or union.
+    ///
+    /// Fields of anonymous members are found as if they were fields of the type itself.
+    pub fn find_field(&self, type_index: DeclIndex, id: &CachedString) -> Option<&Decl> {
+        let mut body = self.get_type_decl(type_index).body.as_ref()?;
+        loop {
+            let field = body.fields.get(body.fields.get_index(id)?)?;
+            let member = match *field {
+                TypeDeclField::Direct(ref decl) => return Some(decl),
+                TypeDeclField::Indirect(index) => match *body.fields.get(index)? {
+                    TypeDeclField::Direct(ref member) => member,
+                    TypeDeclField::Indirect(..) => return None,
+                },
+            };
+            body = match member.type_.root {
+                TypeRoot::Type(inner_index) => self.get_type_decl(inner_index).body.as_ref()?,
+                _ => return None,
+            };
+        }
+    }
+
     pub fn get_decl(&self, index: DeclIndex) -> &Decl {
         &self.get_scope(index.scope_id).decls[index.into()]
     }
@@ -148,6 +175,9 @@ impl SourceFile {
             return self.add_new_type_decl(scope_id, decl);
         } else {
             type_decl.tags.append(&mut decl.tags);
+            if type_decl.underlying.is_none() {
+                type_decl.underlying = decl.underlying;
+            }
         }
         index
     }
@@ -157,4 +187,100 @@ impl SourceFile {
         let index = self.get_scope_mut(scope_id).types.add(name, decl);
         DeclIndex::new(scope_id, index)
     }
+
+    /// Returns a key for the declaration that is stable across parses of the file.
+    /// See [DeclKey] for more information.
+    pub fn decl_key(&self, index: DeclIndex) -> DeclKey {
+        let decl = self.get_decl(index);
+        let mut signature = String::new();
+        self.write_signature(&decl.type_, &mut signature);
+        DeclKey {
+            path: self.decl_path(index.scope_id),
+            name: decl.type_.name.clone(),
+            signature,
+        }
+    }
+
+    /// Returns the names of the functions that the scope is inside of.
+    fn decl_path(&self, scope_id: ScopeId) -> Vec<CachedString> {
+        let mut path = Vec::new();
+        let mut current = Some(scope_id);
+        while let Some(scope_id) = current {
+            let scope = self.get_scope(scope_id);
+            if let (ScopeKind::FuncDecl, Some(parent_id)) = (scope.kind, scope.parent) {
+                let owner = self
+                    .get_scope(parent_id)
+                    .decls
+                    .values()
+                    .find(|decl| decl.type_.get_func_scope_id() == Some(scope_id));
+                if let Some(name) = owner.and_then(|decl| decl.type_.name.clone()) {
+                    path.push(name);
+                }
+            }
+            current = scope.parent;
+        }
+        path.reverse();
+        path
+    }
+
+    fn write_signature(&self, type_: &Type, out: &mut String) {
+        out.push_str(&format!("{:?} ", type_.storage.kind));
+        match type_.root {
+            TypeRoot::Type(index) | TypeRoot::EnumForward(index) => {
+                let type_decl = self.get_type_decl(index);
+                if let Some(tag) = type_decl.tags.first() {
+                    out.push_str(&format!("{:?} ", tag.kind));
+                }
+                self.write_name(&type_decl.name, out);
+            },
+            TypeRoot::Typedef(index) => {
+                self.write_name(&self.get_decl(index).type_.name, out);
+            },
+            TypeRoot::TypeofExpr(..) => out.push_str("typeof(expr)"),
+            TypeRoot::TypeofUnqualExpr(..) => out.push_str("typeof_unqual(expr)"),
+            TypeRoot::TypeofType(ref inner) => {
+                out.push_str("typeof(");
+                self.write_signature(inner, out);
+                out.push(')');
+            },
+            ref root => out.push_str(&format!("{:?}", root)),
+        }
+
+        for segment in type_.segments.iter() {
+            match *segment {
+                TypeSegment::Pointer(..) => out.push_str(" *"),
+                TypeSegment::Array(ref array) => match array.kind {
+                    ArrayKind::Empty => out.push_str(" []"),
+                    ArrayKind::Star(..) => out.push_str(" [*]"),
+                    ArrayKind::Expr(..) => out.push_str(" [expr]"),
+                    ArrayKind::Inferred(len) => out.push_str(&format!(" [{}]", len)),
+                },
+                TypeSegment::Func(ref func) => {
+                    out.push_str(" (");
+                    let params = self.get_scope(func.scope_id).decls.values();
+                    for (i, param) in params.enumerate() {
+                        if i != 0 {
+                            out.push_str(", ");
+                        }
+                        self.write_signature(&param.type_, out);
+                    }
+                    if func.vararg_index.is_some() {
+                        out.push_str(", ...");
+                    }
+                    out.push(')');
+                },
+                TypeSegment::Modifier(ref modifier) => {
+                    out.push(' ');
+                    out.push_str(modifier.name());
+                },
+            }
+        }
+    }
+
+    fn write_name(&self, name: &Option<CachedString>, out: &mut String) {
+        match *name {
+            Some(ref name) => out.push_str(name.string()),
+            None => out.push_str("<anonymous>"),
+        }
+    }
 }
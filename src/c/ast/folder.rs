@@ -0,0 +1,234 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use replace_with::replace_with_or_abort as replace_or_abort;
+
+use crate::c::ast::*;
+
+/// A transformation of the statements and expressions of a [SourceFile] that can replace them.
+///
+/// The folder is driven by [fold_file], [fold_stmt], or [fold_expr]. The children of each
+/// node are folded first, so each hook is given a node whose children have already been
+/// replaced. A hook returns what should replace the node (the defaults keep it as it is).
+pub trait Folder {
+    fn fold_stmt(&mut self, stmt: Stmt) -> Stmt {
+        stmt
+    }
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        expr
+    }
+}
+
+/// Folds the statements of the file's root scope (and everything within them).
+pub fn fold_file(file: &mut SourceFile, folder: &mut impl Folder) {
+    Folding { file, folder }.scope(ScopeId::ROOT);
+}
+
+/// Folds the statement and everything within it. The file should be the one
+/// the statement was parsed from (since blocks refer to its scopes).
+pub fn fold_stmt(file: &mut SourceFile, stmt: &mut Stmt, folder: &mut impl Folder) {
+    Folding { file, folder }.stmt(stmt);
+}
+
+/// Folds the expression and everything within it. The file should be the one
+/// the expression was parsed from (since statement expressions refer to its scopes).
+pub fn fold_expr(file: &mut SourceFile, expr: &mut Expr, folder: &mut impl Folder) {
+    Folding { file, folder }.expr(expr);
+}
+
+struct Folding<'a, F: Folder> {
+    file: &'a mut SourceFile,
+    folder: &'a mut F,
+}
+
+impl<'a, F: Folder> Folding<'a, F> {
+    fn scope(&mut self, scope_id: ScopeId) {
+        // The statements are taken out of the scope so the file can be borrowed while folding them.
+        let mut stmts = std::mem::take(&mut self.file.get_scope_mut(scope_id).stmts);
+        for stmt in &mut stmts {
+            self.stmt(stmt);
+        }
+        self.file.get_scope_mut(scope_id).stmts = stmts;
+    }
+
+    fn decl(&mut self, decl: &mut Decl) {
+        self.type_(&mut decl.type_);
+        match decl.postfix {
+            DeclPostfix::None => {},
+            DeclPostfix::Bitfield(ref mut expr) | DeclPostfix::Initializer(ref mut expr) => {
+                self.expr(expr);
+            },
+            DeclPostfix::Block(ref block) => self.scope(block.scope_id),
+        }
+    }
+
+    /// Folds the expressions within the type (like the sizes of arrays).
+    fn type_(&mut self, type_: &mut Type) {
+        match type_.root {
+            TypeRoot::TypeofExpr(ref mut expr) | TypeRoot::TypeofUnqualExpr(ref mut expr) => {
+                self.expr(expr);
+            },
+            TypeRoot::TypeofType(ref mut type_) => self.type_(type_),
+            _ => {},
+        }
+
+        for segment in type_.segments.iter_mut() {
+            match *segment {
+                TypeSegment::Array(ArraySegment {
+                    kind: ArrayKind::Expr(ref mut expr), ..
+                })
+                | TypeSegment::Modifier(ModifierSegment::AlignasExpr(ref mut expr)) => {
+                    self.expr(expr);
+                },
+                TypeSegment::Modifier(ModifierSegment::AlignasType(ref mut type_)) => {
+                    self.type_(type_);
+                },
+                _ => {},
+            }
+        }
+    }
+
+    fn stmt(&mut self, stmt: &mut Stmt) {
+        match *stmt {
+            Stmt::Break(..) | Stmt::Continue(..) | Stmt::Empty(..) => {},
+            Stmt::Expr(ref mut expr) => self.expr(expr),
+            Stmt::Case(ref mut stmt) => {
+                self.maybe_expr(&mut stmt.case);
+                self.stmt(&mut stmt.stmt);
+            },
+            Stmt::Return(ref mut stmt) => self.maybe_expr(&mut stmt.expr),
+            Stmt::Goto(ref mut stmt) => {
+                if let GotoTarget::Computed(ref mut expr) = stmt.target {
+                    self.expr(expr);
+                }
+            },
+            Stmt::Block(ref block) => self.scope(block.scope_id),
+            Stmt::If(ref mut stmt) => {
+                self.expr(&mut stmt.condition);
+                self.stmt(&mut stmt.block);
+                if let Some(ref mut else_) = stmt.else_ {
+                    self.stmt(else_);
+                }
+            },
+            Stmt::While(ref mut stmt) => {
+                self.expr(&mut stmt.condition);
+                self.stmt(&mut stmt.block);
+            },
+            Stmt::Do(ref mut stmt) => {
+                self.stmt(&mut stmt.block);
+                self.expr(&mut stmt.condition);
+            },
+            Stmt::For(ref mut stmt) => {
+                self.stmt(&mut stmt.initial);
+                self.maybe_expr(&mut stmt.condition);
+                self.maybe_expr(&mut stmt.increment);
+                self.stmt(&mut stmt.block);
+            },
+            Stmt::Switch(ref mut stmt) => {
+                self.expr(&mut stmt.value);
+                self.stmt(&mut stmt.block);
+            },
+            Stmt::Decl(ref stmt) => {
+                for &decl_id in &stmt.decl_ids {
+                    // The declaration is copied out of its scope so the file can be borrowed
+                    // while folding it.
+                    let mut decl = self.file.get_scope(stmt.scope_id).decls[decl_id].clone();
+                    self.decl(&mut decl);
+                    self.file.get_scope_mut(stmt.scope_id).decls[decl_id] = decl;
+                }
+            },
+            Stmt::Attributed(ref mut stmt) => self.stmt(&mut stmt.stmt),
+            Stmt::StaticAssert(ref mut stmt) => self.expr(&mut stmt.condition),
+            Stmt::Labeled(ref mut stmt) => self.stmt(&mut stmt.stmt),
+            Stmt::Asm(ref mut stmt) => {
+                for operand in stmt.outputs.iter_mut().chain(&mut stmt.inputs) {
+                    self.expr(&mut operand.expr);
+                }
+            },
+        }
+        replace_or_abort(stmt, |stmt| self.folder.fold_stmt(stmt));
+    }
+
+    fn maybe_expr(&mut self, expr: &mut Option<Box<Expr>>) {
+        if let Some(ref mut expr) = *expr {
+            self.expr(expr);
+        }
+    }
+
+    fn expr(&mut self, expr: &mut Expr) {
+        match *expr {
+            Expr::DeclRef(..)
+            | Expr::Number(..)
+            | Expr::String(..)
+            | Expr::LabelAddress(..)
+            | Expr::Nullptr(..) => {},
+            Expr::Stmt(ref expr) => self.scope(expr.block.scope_id),
+            Expr::Parens(ref mut expr) => self.expr(&mut expr.expr),
+            Expr::Init(ref mut expr) => self.init(expr),
+            Expr::Generic(ref mut expr) => {
+                self.expr(&mut expr.controller);
+                for association in &mut expr.associations {
+                    if let Some(ref mut type_) = association.0 {
+                        self.type_(type_);
+                    }
+                    self.expr(&mut association.1);
+                }
+            },
+            Expr::CompoundLiteral(ref mut expr) => {
+                self.type_(&mut expr.type_);
+                self.init(&mut expr.init);
+            },
+            Expr::Suffix(ref mut expr) => self.expr(&mut expr.expr),
+            Expr::Access(ref mut expr) => self.expr(&mut expr.expr),
+            Expr::Array(ref mut expr) => {
+                self.expr(&mut expr.expr);
+                self.expr(&mut expr.offset);
+            },
+            Expr::Call(ref mut expr) => {
+                self.expr(&mut expr.expr);
+                for arg in &mut expr.args {
+                    self.expr(arg);
+                }
+            },
+            Expr::Type(ref mut expr) => match expr.of {
+                TypeOrExpr::Type(ref mut type_) => self.type_(type_),
+                TypeOrExpr::Expr(ref mut expr) => self.expr(expr),
+            },
+            Expr::Prefix(ref mut expr) => self.expr(&mut expr.expr),
+            Expr::Cast(ref mut expr) => {
+                self.type_(&mut expr.to);
+                self.expr(&mut expr.expr);
+            },
+            Expr::Binary(ref mut expr) => {
+                self.expr(&mut expr.lhs);
+                self.expr(&mut expr.rhs);
+            },
+            Expr::Ternary(ref mut expr) => {
+                self.expr(&mut expr.condition);
+                self.expr(&mut expr.if_true);
+                self.expr(&mut expr.if_false);
+            },
+            Expr::Assign(ref mut expr) => {
+                self.expr(&mut expr.to);
+                self.expr(&mut expr.value);
+            },
+        }
+        replace_or_abort(expr, |expr| self.folder.fold_expr(expr));
+    }
+
+    fn init(&mut self, init: &mut InitExpr) {
+        for member in &mut init.values {
+            match *member {
+                InitMember::Unnamed(ref mut expr) | InitMember::Named(_, ref mut expr) => {
+                    self.expr(expr);
+                },
+                InitMember::Array(ref mut indexes, ref mut expr) => {
+                    for index in indexes {
+                        self.expr(index);
+                    }
+                    self.expr(expr);
+                },
+                InitMember::SubInitializer(ref mut init) => self.init(init),
+            }
+        }
+    }
+}
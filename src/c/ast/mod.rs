@@ -1,14 +1,27 @@
 // Copyright 2021. remilia-dev
 // This source code is licensed under GPLv3 or any later version.
+pub use attribute::{
+    normalize_attribute_name,
+    Attribute,
+};
 pub use decl::{
+    AttributeList,
     Decl,
     DeclIndex,
+    DeclKey,
     DeclPostfix,
     DeclRefExpr,
     DeclStmt,
+    GnuAttribute,
 };
 pub use expr::*;
 pub use file::SourceFile;
+pub use folder::{
+    fold_expr,
+    fold_file,
+    fold_stmt,
+    Folder,
+};
 pub use number::{
     Number,
     NumberError,
@@ -23,6 +36,7 @@ pub use operators::{
     SuffixOp,
     TypeOp,
 };
+pub use printer::Printer;
 pub use qualifications::{
     Storage,
     StorageKind,
@@ -35,13 +49,23 @@ pub use scope::{
 pub use stmt::*;
 pub use string::StringLiteral;
 pub use types::*;
-pub use visitor::Visitor;
+pub use visitor::{
+    walk_expr,
+    walk_file,
+    walk_stmt,
+    ExprVisitor,
+    Flow,
+    Visitor,
+};
 
+mod attribute;
 mod decl;
 mod expr;
 mod file;
+mod folder;
 mod number;
 mod operators;
+mod printer;
 mod qualifications;
 mod scope;
 mod stmt;
@@ -5,6 +5,7 @@ use std::convert::TryFrom;
 use crate::{
     c::{
         StringEnc,
+        TokenKind,
         TravelIndex,
     },
     error::{
@@ -23,6 +24,7 @@ use crate::{
         create_intos,
         enum_with_properties,
         CharExt,
+        SourceLoc,
     },
 };
 
@@ -30,6 +32,18 @@ use crate::{
 pub struct Number {
     pub kind: NumberKind,
     pub index: TravelIndex,
+    /// The token the number was parsed from (for printing it as it was written).
+    ///
+    /// This is None for numbers that weren't written in the source (like the results of
+    /// `defined` in `#if` conditions).
+    pub token: Option<TokenKind>,
+}
+
+impl Number {
+    /// Creates a number that wasn't parsed from a token.
+    pub fn new(kind: NumberKind, index: TravelIndex) -> Self {
+        Number { kind, index, token: None }
+    }
 }
 
 #[create_intos]
@@ -88,6 +102,8 @@ enum_with_properties! {
         InvalidIntSuffix(String),
         #[values(Error, 603)]
         InvalidRealSuffix(String),
+        #[values(Error, 604)]
+        InvalidOctalDigit(char, usize),
         #[values(Error, 610)]
         InvalidEscape(Option<char>),
         #[values(Error, 611)]
@@ -133,6 +149,10 @@ enum_with_properties! {
                     "'{}' is not a valid suffix for a real number.",
                     suffix
                 ),
+                InvalidOctalDigit(digit, _) => format!(
+                    "'{}' is not a valid octal digit (integers starting with 0 are octal).",
+                    digit
+                ),
                 InvalidEscape(maybe) => match maybe {
                     Some(char) => format!(
                         "\\{} is not a valid escape sequence",
@@ -167,6 +187,22 @@ enum_with_properties! {
     }
 }
 
+impl NumberError {
+    /// Narrows the location of the number the error occured in to the part the error is about.
+    ///
+    /// The error's offset is into the number's spelling, so the whole number is used if the
+    /// offset doesn't fit in the location.
+    pub fn loc_in(&self, number_loc: SourceLoc) -> SourceLoc {
+        if let NumberError::InvalidOctalDigit(_, offset) = *self {
+            if offset < number_loc.byte_length as usize {
+                let byte = number_loc.byte + offset as u32;
+                return SourceLoc::new(number_loc.file_id(), byte, 1);
+            }
+        }
+        number_loc
+    }
+}
+
 struct NumberDecoder<'a> {
     errors: NumberReceiver<'a>,
     base: NumBase,
@@ -197,7 +233,13 @@ impl<'a> NumberDecoder<'a> {
 
         let number = &number[prefix_length..];
 
-        let (number_len, has_dot) = base.find_end_of_digits(number, true);
+        // Octal numbers are read as decimal digits so invalid octal digits (8 and 9) can be reported.
+        let digit_base = if base == NumBase::Octal {
+            NumBase::Decimal
+        } else {
+            base
+        };
+        let (number_len, has_dot) = digit_base.find_end_of_digits(number, true);
         let (number, post_number) = number.split_at(number_len);
 
         let exp_base = match post_number.get(0) {
@@ -206,6 +248,11 @@ impl<'a> NumberDecoder<'a> {
             _ => None,
         };
 
+        // A leading 0 doesn't make a real number octal (09.5 is a decimal number).
+        if base == NumBase::Octal && (has_dot || exp_base.is_some()) {
+            base = NumBase::Decimal;
+        }
+
         if exp_base.is_some() {
             let (negative_exp, post_number) = match post_number.get(1) {
                 Some(b'-') => (true, &post_number[2..]),
@@ -241,6 +288,7 @@ impl<'a> NumberDecoder<'a> {
 
     fn calc_number(&mut self) -> MayUnwind<NumberKind> {
         self.report_empty_segments()?;
+        self.report_invalid_octal_digits()?;
         let suffix = self.decode_suffix()?;
 
         match suffix {
@@ -386,11 +434,25 @@ impl<'a> NumberDecoder<'a> {
         }
     }
 
+    fn report_invalid_octal_digits(&mut self) -> MayUnwind<()> {
+        if self.base != NumBase::Octal {
+            return Ok(());
+        }
+        if let Some(pos) = self.number.iter().position(|&c| !self.base.is_digit_ascii(c)) {
+            let digit = char::from(self.number[pos]);
+            // The offset includes the leading 0.
+            self.errors.report(NumberError::InvalidOctalDigit(digit, pos + 1))?;
+            // Only the valid octal digits are used for the number's value.
+            self.number = &self.number[..pos];
+        }
+        Ok(())
+    }
+
     fn report_empty_segments(&mut self) -> MayUnwind<()> {
         if self.number.is_empty() {
             self.errors.report(NumberError::EmptyNumber)?;
         }
-        if self.exp_base.is_some() && self.suffix.is_empty() {
+        if self.exp_base.is_some() && self.exp.is_empty() {
             self.errors.report(NumberError::EmptyExponent)?;
         }
         Ok(())
@@ -500,3 +562,66 @@ fn parse_complex_character(
 
     Ok((parsed.number, digit_count))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(digits: &str) -> (NumberKind, Vec<NumberError>) {
+        let mut errors = Vec::new();
+        let kind = NumberKind::from_number(digits, &mut |error| {
+            errors.push(error);
+            false
+        })
+        .unwrap();
+        (kind, errors)
+    }
+
+    #[test]
+    fn invalid_octal_digits_are_reported() {
+        let (kind, errors) = decode("089");
+        assert!(matches!(kind, NumberKind::I32(0)), "{:?}", kind);
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert!(matches!(
+            errors[0],
+            NumberError::InvalidOctalDigit('8', 1)
+        ));
+
+        let (kind, errors) = decode("0778");
+        assert!(matches!(kind, NumberKind::I32(0o77)), "{:?}", kind);
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert!(matches!(
+            errors[0],
+            NumberError::InvalidOctalDigit('8', 3)
+        ));
+    }
+
+    #[test]
+    fn leading_zero_reals_are_decimal() {
+        let (kind, errors) = decode("089.5");
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert!(
+            matches!(kind, NumberKind::F64(f) if f == 89.5),
+            "{:?}",
+            kind
+        );
+    }
+
+    #[test]
+    fn only_exponents_without_digits_are_empty() {
+        let (kind, errors) = decode("1e10");
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert!(
+            matches!(kind, NumberKind::F64(f) if f == 1e10),
+            "{:?}",
+            kind
+        );
+
+        let (_, errors) = decode("1e");
+        assert!(
+            matches!(errors[..], [NumberError::EmptyExponent]),
+            "{:?}",
+            errors
+        );
+    }
+}
@@ -106,8 +106,8 @@ impl std::convert::TryFrom<&TokenKind> for TypeOp {
             Sizeof,
         };
         Ok(match *v {
-            TokenKind::Keyword(Alignof) => Self::AlignOf,
-            TokenKind::Keyword(Sizeof) => Self::SizeOf,
+            TokenKind::Keyword(Alignof, ..) => Self::AlignOf,
+            TokenKind::Keyword(Sizeof, ..) => Self::SizeOf,
             _ => return Err(()),
         })
     }
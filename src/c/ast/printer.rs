@@ -0,0 +1,990 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use std::{
+    convert::TryFrom,
+    fmt,
+};
+
+use crate::c::{
+    ast::*,
+    Keyword,
+    TokenKind,
+    TravelIndex,
+};
+
+/// Writes the declarations, statements, and expressions of a [SourceFile] back out as C source.
+///
+/// The output is not the original text of the file (comments and layout are lost), but lexing it
+/// again gives the same tokens. Parentheses are only added where the structure of an expression
+/// requires them. Attributes (both `[[...]]` and `__attribute__((...))`) are not printed.
+pub struct Printer<'a, W: fmt::Write> {
+    file: &'a SourceFile,
+    out: W,
+    indent: usize,
+    /// The last character that was written (or a space if nothing has been written).
+    last: char,
+}
+
+impl<'a, W: fmt::Write> Printer<'a, W> {
+    /// Creates a printer that writes to the given output. The file should be the one
+    /// the printed items were parsed from (since they refer to its scopes and declarations).
+    pub fn new(file: &'a SourceFile, out: W) -> Self {
+        Printer { file, out, indent: 0, last: ' ' }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    /// Prints the statements of the file's root scope (one per line).
+    pub fn print_file(&mut self) -> fmt::Result {
+        self.stmts(&self.file.root_scope().stmts)?;
+        self.newline()
+    }
+
+    pub fn print_stmt(&mut self, stmt: &Stmt) -> fmt::Result {
+        match *stmt {
+            Stmt::Expr(ref expr) => {
+                self.print_expr(expr)?;
+                self.write(";")
+            },
+            Stmt::Break(..) => self.write("break;"),
+            Stmt::Continue(..) => self.write("continue;"),
+            Stmt::Case(ref stmt) => self.case_stmt(stmt),
+            Stmt::Return(ref stmt) => self.return_stmt(stmt),
+            Stmt::Goto(ref stmt) => self.goto_stmt(stmt),
+            Stmt::Block(ref block) => self.block(block),
+            Stmt::If(ref stmt) => self.if_stmt(stmt),
+            Stmt::While(ref stmt) => {
+                self.write("while ")?;
+                self.print_expr(&stmt.condition)?;
+                self.body(&stmt.block)
+            },
+            Stmt::Do(ref stmt) => self.do_stmt(stmt),
+            Stmt::For(ref stmt) => self.for_stmt(stmt),
+            Stmt::Switch(ref stmt) => {
+                self.write("switch ")?;
+                self.print_expr(&stmt.value)?;
+                self.body(&stmt.block)
+            },
+            Stmt::Decl(ref stmt) => self.decl_stmt(stmt),
+            Stmt::Attributed(ref stmt) => self.print_stmt(&stmt.stmt),
+            Stmt::StaticAssert(ref stmt) => self.static_assert_stmt(stmt),
+            Stmt::Labeled(ref stmt) => {
+                self.write(stmt.name.string())?;
+                self.write(":")?;
+                self.newline()?;
+                self.print_stmt(&stmt.stmt)
+            },
+            Stmt::Asm(ref stmt) => self.asm_stmt(stmt),
+            Stmt::Empty(..) => self.write(";"),
+        }
+    }
+
+    pub fn print_expr(&mut self, expr: &Expr) -> fmt::Result {
+        match *expr {
+            Expr::DeclRef(ref expr) => self.write(expr.id.text.string()),
+            Expr::Number(ref number) => self.number(number),
+            Expr::String(ref literal) => self.string(literal),
+            Expr::Stmt(ref expr) => {
+                self.write("(")?;
+                self.block(&expr.block)?;
+                self.write(")")
+            },
+            Expr::Parens(ref expr) => {
+                self.write("(")?;
+                self.print_expr(&expr.expr)?;
+                self.write(")")
+            },
+            Expr::Init(ref init) => self.init(init),
+            Expr::Generic(ref expr) => self.generic(expr),
+            Expr::Nullptr(..) => self.write(Keyword::Nullptr.text()),
+            Expr::LabelAddress(ref expr) => {
+                self.write("&&")?;
+                self.write(expr.label.string())
+            },
+            Expr::CompoundLiteral(ref expr) => {
+                self.write("(")?;
+                self.print_type(&expr.type_)?;
+                self.write(")")?;
+                self.init(&expr.init)
+            },
+            Expr::Suffix(ref expr) => {
+                self.operand(&expr.expr, Precedence::Suffixes)?;
+                self.write(suffix_token(expr.op).text())
+            },
+            Expr::Access(ref expr) => {
+                self.operand(&expr.expr, Precedence::Suffixes)?;
+                self.write(if expr.through_ptr { "->" } else { "." })?;
+                self.write(expr.member.string())
+            },
+            Expr::Array(ref expr) => {
+                self.operand(&expr.expr, Precedence::Suffixes)?;
+                self.write("[")?;
+                self.print_expr(&expr.offset)?;
+                self.write("]")
+            },
+            Expr::Call(ref expr) => self.call(expr),
+            Expr::Type(ref expr) => self.type_expr(expr),
+            Expr::Prefix(ref expr) => {
+                self.write(prefix_token(expr.op).text())?;
+                self.operand(&expr.expr, Precedence::Prefixes)
+            },
+            Expr::Cast(ref expr) => {
+                self.write("(")?;
+                self.print_type(&expr.to)?;
+                self.write(")")?;
+                self.operand(&expr.expr, Precedence::Prefixes)
+            },
+            Expr::Binary(ref expr) => self.binary(expr),
+            Expr::Ternary(ref expr) => self.ternary(expr),
+            Expr::Assign(ref expr) => {
+                self.operand(&expr.to, Precedence::Prefixes)?;
+                self.write(" ")?;
+                self.write(assign_token(expr.op).text())?;
+                self.write(" ")?;
+                self.operand(&expr.value, Precedence::Assignment)
+            },
+        }
+    }
+
+    /// Prints the type's specifiers followed by its declarator (which includes the type's
+    /// name if it has one).
+    pub fn print_type(&mut self, type_: &Type) -> fmt::Result {
+        self.specifiers(type_)?;
+        self.declarator(type_)
+    }
+
+    // region: Layout
+    /// Writes the text (with a space before it if it would otherwise join with the last token).
+    fn write(&mut self, text: &str) -> fmt::Result {
+        let first = match text.chars().next() {
+            Some(first) => first,
+            None => return Ok(()),
+        };
+        if would_join(self.last, first) {
+            self.out.write_char(' ')?;
+        }
+        self.last = text.chars().last().unwrap_or(first);
+        self.out.write_str(text)
+    }
+
+    /// Writes a space unless the output is at the start of a line or just after an
+    /// opening parenthesis.
+    fn space(&mut self) -> fmt::Result {
+        if matches!(self.last, ' ' | '(' | '[') {
+            Ok(())
+        } else {
+            self.write(" ")
+        }
+    }
+
+    fn newline(&mut self) -> fmt::Result {
+        self.out.write_char('\n')?;
+        for _ in 0..self.indent {
+            self.out.write_str("    ")?;
+        }
+        self.last = ' ';
+        Ok(())
+    }
+
+    /// Prints the expression, wrapping it in parentheses if it binds looser than the precedence.
+    fn operand(&mut self, expr: &Expr, loosest: Precedence) -> fmt::Result {
+        self.wrapped(expr, expr.precedence() > loosest)
+    }
+
+    fn wrapped(&mut self, expr: &Expr, parens: bool) -> fmt::Result {
+        if parens {
+            self.write("(")?;
+            self.print_expr(expr)?;
+            self.write(")")
+        } else {
+            self.print_expr(expr)
+        }
+    }
+
+    fn comma_separated<T, F>(&mut self, items: &[T], mut print: F) -> fmt::Result
+    where F: FnMut(&mut Self, &T) -> fmt::Result {
+        for (i, item) in items.iter().enumerate() {
+            if i != 0 {
+                self.write(", ")?;
+            }
+            print(self, item)?;
+        }
+        Ok(())
+    }
+    // endregion: Layout
+
+    // region: Statements
+    fn stmts(&mut self, stmts: &[Stmt]) -> fmt::Result {
+        let mut after_prototype = false;
+        for (i, stmt) in stmts.iter().enumerate() {
+            // The ; after a function prototype is its own statement in the root scope,
+            // but the prototype has already printed it.
+            if after_prototype && matches!(*stmt, Stmt::Empty(..)) {
+                after_prototype = false;
+                continue;
+            }
+            if i != 0 {
+                self.newline()?;
+            }
+            self.print_stmt(stmt)?;
+            after_prototype = matches!(*stmt, Stmt::Decl(ref stmt) if self.is_prototype(stmt));
+        }
+        Ok(())
+    }
+
+    fn block(&mut self, block: &BlockExpr) -> fmt::Result {
+        let stmts = &self.file.get_scope(block.scope_id).stmts;
+        self.write("{")?;
+        if !stmts.is_empty() {
+            self.indent += 1;
+            self.newline()?;
+            self.stmts(stmts)?;
+            self.indent -= 1;
+            self.newline()?;
+        }
+        self.write("}")
+    }
+
+    /// Prints the body of a control statement. Blocks stay on the same line.
+    fn body(&mut self, stmt: &Stmt) -> fmt::Result {
+        if let Stmt::Block(ref block) = *stmt {
+            self.write(" ")?;
+            self.block(block)
+        } else {
+            self.indent += 1;
+            self.newline()?;
+            self.print_stmt(stmt)?;
+            self.indent -= 1;
+            Ok(())
+        }
+    }
+
+    /// Starts a keyword that follows a body (like else) on the body's line if it was a block.
+    fn after_body(&mut self, body: &Stmt, keyword: &str) -> fmt::Result {
+        if matches!(*body, Stmt::Block(..)) {
+            self.write(" ")?;
+        } else {
+            self.newline()?;
+        }
+        self.write(keyword)
+    }
+
+    fn case_stmt(&mut self, stmt: &CaseStmt) -> fmt::Result {
+        match stmt.case {
+            Some(ref case) => {
+                self.write("case ")?;
+                self.print_expr(case)?;
+                self.write(":")?;
+            },
+            None => self.write("default:")?,
+        }
+        self.newline()?;
+        self.print_stmt(&stmt.stmt)
+    }
+
+    fn return_stmt(&mut self, stmt: &ReturnStmt) -> fmt::Result {
+        self.write("return")?;
+        if let Some(ref expr) = stmt.expr {
+            self.write(" ")?;
+            self.print_expr(expr)?;
+        }
+        self.write(";")
+    }
+
+    fn goto_stmt(&mut self, stmt: &GotoStmt) -> fmt::Result {
+        self.write("goto")?;
+        match stmt.target {
+            GotoTarget::Label(Some(ref label)) => {
+                self.write(" ")?;
+                self.write(label.string())?;
+            },
+            GotoTarget::Label(None) => {},
+            GotoTarget::Computed(ref expr) => {
+                self.write(" *")?;
+                self.operand(expr, Precedence::Prefixes)?;
+            },
+        }
+        self.write(";")
+    }
+
+    fn if_stmt(&mut self, stmt: &IfStmt) -> fmt::Result {
+        self.write("if ")?;
+        self.print_expr(&stmt.condition)?;
+        self.body(&stmt.block)?;
+        if let Some(ref else_) = stmt.else_ {
+            self.after_body(&stmt.block, "else")?;
+            if let Stmt::If(ref else_if) = **else_ {
+                self.write(" ")?;
+                self.if_stmt(else_if)?;
+            } else {
+                self.body(else_)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn do_stmt(&mut self, stmt: &DoStmt) -> fmt::Result {
+        self.write("do")?;
+        self.body(&stmt.block)?;
+        self.after_body(&stmt.block, "while ")?;
+        self.print_expr(&stmt.condition)?;
+        self.write(";")
+    }
+
+    fn for_stmt(&mut self, stmt: &ForStmt) -> fmt::Result {
+        self.write("for (")?;
+        // The initial statement includes its ;
+        self.print_stmt(&stmt.initial)?;
+        if let Some(ref condition) = stmt.condition {
+            self.write(" ")?;
+            self.print_expr(condition)?;
+        }
+        self.write(";")?;
+        if let Some(ref increment) = stmt.increment {
+            self.write(" ")?;
+            self.print_expr(increment)?;
+        }
+        self.write(")")?;
+        self.body(&stmt.block)
+    }
+
+    fn static_assert_stmt(&mut self, stmt: &StaticAssertStmt) -> fmt::Result {
+        self.write(Keyword::StaticAssert.text())?;
+        self.write("(")?;
+        self.print_expr(&stmt.condition)?;
+        if let Some(ref message) = stmt.message {
+            self.write(", ")?;
+            self.string(message)?;
+        }
+        self.write(");")
+    }
+
+    fn asm_stmt(&mut self, stmt: &AsmStmt) -> fmt::Result {
+        self.write(Keyword::AsmBuiltin.text())?;
+        if stmt.volatile {
+            self.write(" volatile")?;
+        }
+        self.write(" (")?;
+        self.string(&stmt.template)?;
+        // A section can only be left out if the sections after it are too.
+        let sections = if !stmt.clobbers.is_empty() {
+            3
+        } else if !stmt.inputs.is_empty() {
+            2
+        } else {
+            usize::from(!stmt.outputs.is_empty())
+        };
+        for operands in [&stmt.outputs, &stmt.inputs].iter().take(sections) {
+            self.write(" : ")?;
+            self.comma_separated(operands, Self::asm_operand)?;
+        }
+        if sections == 3 {
+            self.write(" : ")?;
+            self.comma_separated(&stmt.clobbers, |printer, clobber| {
+                printer.write(&format!("\"{}\"", clobber.string()))
+            })?;
+        }
+        self.write(");")
+    }
+
+    fn asm_operand(&mut self, operand: &AsmOperand) -> fmt::Result {
+        if let Some(ref name) = operand.name {
+            self.write("[")?;
+            self.write(name.string())?;
+            self.write("] ")?;
+        }
+        self.string(&operand.constraint)?;
+        self.write(" (")?;
+        self.print_expr(&operand.expr)?;
+        self.write(")")
+    }
+    // endregion: Statements
+
+    // region: Declarations
+    /// Returns whether the statement declares a function without a body. The parser doesn't
+    /// include the ; after a prototype in its statement.
+    fn is_prototype(&self, stmt: &DeclStmt) -> bool {
+        let scope = self.file.get_scope(stmt.scope_id);
+        match stmt.decl_ids.last() {
+            Some(&id) => {
+                let decl = &scope.decls[id];
+                decl.is_function() && !matches!(decl.postfix, DeclPostfix::Block(..))
+            },
+            None => false,
+        }
+    }
+
+    fn decl_stmt(&mut self, stmt: &DeclStmt) -> fmt::Result {
+        let scope = self.file.get_scope(stmt.scope_id);
+        let decls: Vec<&Decl> = stmt.decl_ids.iter().map(|&id| &scope.decls[id]).collect();
+        self.decls(&decls)?;
+
+        if let Some(&&Decl { postfix: DeclPostfix::Block(..), .. }) = decls.last() {
+            Ok(())
+        } else {
+            self.write(";")
+        }
+    }
+
+    /// Prints declarations that share the same specifiers (separated by commas).
+    fn decls(&mut self, decls: &[&Decl]) -> fmt::Result {
+        for (i, decl) in decls.iter().enumerate() {
+            if i == 0 {
+                self.specifiers(&decl.type_)?;
+            } else {
+                self.write(",")?;
+            }
+            self.declarator(&decl.type_)?;
+            self.decl_postfix(&decl.postfix)?;
+        }
+        Ok(())
+    }
+
+    fn decl_postfix(&mut self, postfix: &DeclPostfix) -> fmt::Result {
+        match *postfix {
+            DeclPostfix::None => Ok(()),
+            DeclPostfix::Bitfield(ref expr) => {
+                self.write(" : ")?;
+                self.operand(expr, Precedence::Ternary)
+            },
+            DeclPostfix::Initializer(ref expr) => {
+                self.write(" = ")?;
+                self.operand(expr, Precedence::Assignment)
+            },
+            DeclPostfix::Block(ref block) => {
+                self.write(" ")?;
+                self.block(block)
+            },
+        }
+    }
+
+    /// Prints the storage class, base modifiers, and root of the type (in the order they were
+    /// written if they were parsed).
+    fn specifiers(&mut self, type_: &Type) -> fmt::Result {
+        let mut specifiers = Vec::new();
+        if let Some(index) = type_.storage.kind_index {
+            specifiers.push((Some(index), Specifier::Storage(type_.storage.kind)));
+        }
+        for segment in type_.segments.iter().take(type_.base_segments.get() as usize) {
+            if let TypeSegment::Modifier(ref modifier) = *segment {
+                specifiers.push((modifier_index(modifier), Specifier::Modifier(modifier)));
+            }
+        }
+        if !matches!(type_.root, TypeRoot::AutoInt) {
+            specifiers.push((type_.root_index, Specifier::Root));
+        }
+        specifiers.sort_by_key(|specifier| specifier.0.map(TravelIndex::get));
+
+        for specifier in specifiers {
+            self.space()?;
+            match specifier.1 {
+                Specifier::Storage(kind) => self.write(storage_keyword(kind).text())?,
+                Specifier::Modifier(modifier) => self.modifier(modifier)?,
+                Specifier::Root => self.root(type_)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn modifier(&mut self, modifier: &ModifierSegment) -> fmt::Result {
+        self.write(modifier.name())?;
+        match *modifier {
+            ModifierSegment::AlignasExpr(ref expr) => {
+                self.write("(")?;
+                self.print_expr(expr)?;
+                self.write(")")
+            },
+            ModifierSegment::AlignasType(ref type_) => {
+                self.write("(")?;
+                self.print_type(type_)?;
+                self.write(")")
+            },
+            _ => Ok(()),
+        }
+    }
+
+    fn root(&mut self, type_: &Type) -> fmt::Result {
+        let keyword = match type_.root {
+            TypeRoot::AutoInt | TypeRoot::EnumValue | TypeRoot::EnumForward(..) => return Ok(()),
+            TypeRoot::Bool => Keyword::Bool,
+            TypeRoot::Char => Keyword::Char,
+            TypeRoot::Int => Keyword::Int,
+            TypeRoot::Float => Keyword::Float,
+            TypeRoot::Double => Keyword::Double,
+            TypeRoot::Void => Keyword::Void,
+            TypeRoot::Decimal32 => Keyword::Decimal32,
+            TypeRoot::Decimal64 => Keyword::Decimal64,
+            TypeRoot::Decimal128 => Keyword::Decimal128,
+            // A C23 inferred type is only the auto storage class.
+            TypeRoot::Inferred if type_.root_index.is_none() => return Ok(()),
+            TypeRoot::Inferred => Keyword::AutoType,
+            TypeRoot::NullptrT => return self.write("nullptr_t"),
+            TypeRoot::Type(index) => return self.type_decl(index, type_.root_index),
+            TypeRoot::Typedef(index) => {
+                let name = self.file.get_decl(index).type_.name.as_ref();
+                return self.write(name.map_or("", |name| name.string()));
+            },
+            TypeRoot::TypeofExpr(ref expr) | TypeRoot::TypeofUnqualExpr(ref expr) => {
+                let keyword = match type_.root {
+                    TypeRoot::TypeofUnqualExpr(..) => Keyword::TypeofUnqual,
+                    _ => Keyword::GnuTypeof,
+                };
+                self.write(keyword.text())?;
+                self.write("(")?;
+                self.print_expr(expr)?;
+                return self.write(")");
+            },
+            TypeRoot::TypeofType(ref inner) => {
+                self.write(Keyword::GnuTypeof.text())?;
+                self.write("(")?;
+                self.print_type(inner)?;
+                return self.write(")");
+            },
+        };
+        self.write(keyword.text())
+    }
+
+    /// Prints a struct, union, or enum specifier. The body is included if the specifier
+    /// at the root index is the one that defined it.
+    fn type_decl(&mut self, index: DeclIndex, root_index: Option<TravelIndex>) -> fmt::Result {
+        let type_decl = self.file.get_type_decl(index);
+        let kind = type_decl.tags.first().map_or(TypeDeclKind::Struct, |tag| tag.kind);
+        self.write(tag_keyword(kind).text())?;
+        if let Some(ref name) = type_decl.name {
+            self.write(" ")?;
+            self.write(name.string())?;
+        }
+
+        let body = match type_decl.body {
+            Some(ref body) if defines_body(type_decl, body, root_index) => body,
+            _ => return Ok(()),
+        };
+        if let Some(ref underlying) = type_decl.underlying {
+            self.write(" : ")?;
+            self.print_type(underlying)?;
+        }
+        self.write(" {")?;
+        self.indent += 1;
+        if body.kind == TypeDeclKind::Enum {
+            self.enum_body(body)?;
+        } else {
+            self.fields(body)?;
+        }
+        self.indent -= 1;
+        self.newline()?;
+        self.write("}")
+    }
+
+    fn enum_body(&mut self, body: &TypeDeclBody) -> fmt::Result {
+        for (i, field) in body.fields.values().enumerate() {
+            let decl = match *field {
+                TypeDeclField::Direct(ref decl) => decl,
+                TypeDeclField::Indirect(..) => continue,
+            };
+            if i != 0 {
+                self.write(",")?;
+            }
+            self.newline()?;
+            self.write(decl.type_.name.as_ref().map_or("", |name| name.string()))?;
+            self.decl_postfix(&decl.postfix)?;
+        }
+        Ok(())
+    }
+
+    /// Prints the fields of a struct or union. Fields that were declared together are
+    /// printed together.
+    fn fields(&mut self, body: &TypeDeclBody) -> fmt::Result {
+        let mut group: Vec<&Decl> = Vec::new();
+        let direct_fields = body.fields.values().filter_map(|field| match *field {
+            TypeDeclField::Direct(ref decl) => Some(decl),
+            TypeDeclField::Indirect(..) => None,
+        });
+        for decl in direct_fields {
+            let shares_specifiers =
+                matches!(group.last(), Some(last) if same_specifiers(&last.type_, &decl.type_));
+            if !shares_specifiers && !group.is_empty() {
+                self.field_group(&group)?;
+                group.clear();
+            }
+            group.push(decl);
+        }
+        if !group.is_empty() {
+            self.field_group(&group)?;
+        }
+        Ok(())
+    }
+
+    fn field_group(&mut self, group: &[&Decl]) -> fmt::Result {
+        self.newline()?;
+        self.decls(group)?;
+        self.write(";")
+    }
+
+    /// Prints the part of the type after its specifiers (like the `*p[3]` in `int *p[3]`).
+    ///
+    /// The segments go from the root outward while a declarator is read from the name outward,
+    /// so the prefixes (pointers) are printed in order and the suffixes (arrays and functions)
+    /// in reverse. A pointer to an array or function needs parentheses around it.
+    fn declarator(&mut self, type_: &Type) -> fmt::Result {
+        let parts =
+            declarator_parts(type_.segments.iter().skip(type_.base_segments.get() as usize));
+        let needs_parens = |i: usize| {
+            matches!(parts[i], DeclaratorPart::Suffix(..))
+                && matches!(parts.get(i + 1), Some(&DeclaratorPart::Pointer(..)))
+        };
+        // An abstract declarator that starts with a suffix stays next to the specifiers.
+        let starts_with_suffix =
+            matches!(parts.first(), Some(&DeclaratorPart::Suffix(..))) && !needs_parens(0);
+        if type_.name.is_some() || !(parts.is_empty() || starts_with_suffix) {
+            self.space()?;
+        }
+
+        for (i, part) in parts.iter().enumerate() {
+            match *part {
+                DeclaratorPart::Pointer(ref modifiers) => {
+                    self.write("*")?;
+                    for &modifier in modifiers {
+                        self.space()?;
+                        self.modifier(modifier)?;
+                    }
+                },
+                DeclaratorPart::Modifier(modifier) => self.modifier(modifier)?,
+                DeclaratorPart::Suffix(..) if needs_parens(i) => self.write("(")?,
+                DeclaratorPart::Suffix(..) => {},
+            }
+        }
+        if let Some(ref name) = type_.name {
+            self.write(name.string())?;
+        }
+        for (i, part) in parts.iter().enumerate().rev() {
+            if let DeclaratorPart::Suffix(segment) = *part {
+                if needs_parens(i) {
+                    self.write(")")?;
+                }
+                self.suffix(segment)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn suffix(&mut self, segment: &TypeSegment) -> fmt::Result {
+        match *segment {
+            TypeSegment::Array(ref array) => self.array_suffix(array),
+            TypeSegment::Func(ref func) => self.func_suffix(func),
+            _ => Ok(()),
+        }
+    }
+
+    fn array_suffix(&mut self, array: &ArraySegment) -> fmt::Result {
+        self.write("[")?;
+        let keywords = [
+            (array.static_, Keyword::Static),
+            (array.const_, Keyword::Const),
+            (array.restrict, Keyword::Restrict),
+        ];
+        for keyword in keywords.iter().filter(|keyword| keyword.0.is_some()) {
+            self.space()?;
+            self.write(keyword.1.text())?;
+        }
+        match array.kind {
+            ArrayKind::Expr(ref expr) => {
+                self.space()?;
+                self.operand(expr, Precedence::Assignment)?;
+            },
+            ArrayKind::Star(..) => {
+                self.space()?;
+                self.write("*")?;
+            },
+            ArrayKind::Empty | ArrayKind::Inferred(..) => {},
+        }
+        self.write("]")
+    }
+
+    fn func_suffix(&mut self, func: &FuncSegment) -> fmt::Result {
+        let params: Vec<&Decl> = self
+            .file
+            .get_scope(func.scope_id)
+            .decls
+            .values()
+            .filter(|decl| !matches!(decl.type_.root, TypeRoot::EnumForward(..)))
+            .collect();
+
+        self.write("(")?;
+        self.comma_separated(&params, |printer, param| printer.print_type(&param.type_))?;
+        if func.has_vararg() {
+            if !params.is_empty() {
+                self.write(", ")?;
+            }
+            self.write("...")?;
+        }
+        self.write(")")
+    }
+    // endregion: Declarations
+
+    // region: Expressions
+    fn number(&mut self, number: &Number) -> fmt::Result {
+        // Numbers from the source are printed as they were written.
+        if let Some(ref token) = number.token {
+            return self.write(&token.to_string());
+        }
+        // Suffixes are only added when the number wouldn't have the same type without them.
+        let text = match number.kind {
+            NumberKind::I32(value) => format!("{}", value),
+            NumberKind::U32(value) => format!("{}u", value),
+            NumberKind::I64(value) if i32::try_from(value).is_err() => format!("{}", value),
+            NumberKind::I64(value) => format!("{}l", value),
+            NumberKind::U64(value) if u32::try_from(value).is_err() => format!("{}u", value),
+            NumberKind::U64(value) => format!("{}ul", value),
+            NumberKind::F32(value) => format!("{:?}f", value),
+            NumberKind::F64(value) => format!("{:?}", value),
+        };
+        self.write(&text)
+    }
+
+    fn string(&mut self, literal: &StringLiteral) -> fmt::Result {
+        for (i, segment) in literal.segments.iter().enumerate() {
+            if i != 0 {
+                self.write(" ")?;
+            }
+            // The encoding prefix only needs to be on the first segment.
+            let prefix = match literal.encoding.prefix() {
+                Some(prefix) if i == 0 => prefix,
+                _ => "",
+            };
+            self.write(&format!("{}\"{}\"", prefix, segment))?;
+        }
+        Ok(())
+    }
+
+    fn init(&mut self, init: &InitExpr) -> fmt::Result {
+        self.write("{")?;
+        self.comma_separated(&init.values, Self::init_member)?;
+        self.write("}")
+    }
+
+    fn init_member(&mut self, member: &InitMember) -> fmt::Result {
+        match *member {
+            InitMember::Unnamed(ref expr) => self.operand(expr, Precedence::Assignment),
+            InitMember::Named(ref id, ref expr) => {
+                self.write(".")?;
+                self.write(id.text.string())?;
+                self.write(" = ")?;
+                self.operand(expr, Precedence::Assignment)
+            },
+            InitMember::Array(ref indexes, ref expr) => {
+                for index in indexes {
+                    self.write("[")?;
+                    self.print_expr(index)?;
+                    self.write("]")?;
+                }
+                self.write(" = ")?;
+                self.operand(expr, Precedence::Assignment)
+            },
+            InitMember::SubInitializer(ref init) => self.init(init),
+        }
+    }
+
+    fn generic(&mut self, expr: &GenericExpr) -> fmt::Result {
+        self.write(Keyword::Generic.text())?;
+        self.write("(")?;
+        self.operand(&expr.controller, Precedence::Assignment)?;
+        for association in &expr.associations {
+            self.write(", ")?;
+            match association.0 {
+                Some(ref type_) => self.print_type(type_)?,
+                None => self.write(Keyword::Default.text())?,
+            }
+            self.write(": ")?;
+            self.operand(&association.1, Precedence::Assignment)?;
+        }
+        self.write(")")
+    }
+
+    fn call(&mut self, expr: &CallExpr) -> fmt::Result {
+        self.operand(&expr.expr, Precedence::Suffixes)?;
+        self.write("(")?;
+        self.comma_separated(&expr.args, |printer, arg| {
+            printer.operand(arg, Precedence::Assignment)
+        })?;
+        self.write(")")
+    }
+
+    fn type_expr(&mut self, expr: &TypeExpr) -> fmt::Result {
+        let keyword = match expr.op {
+            TypeOp::AlignOf => Keyword::Alignof,
+            TypeOp::SizeOf => Keyword::Sizeof,
+        };
+        self.write(keyword.text())?;
+        match expr.of {
+            TypeOrExpr::Type(ref type_) => {
+                self.write("(")?;
+                self.print_type(type_)?;
+                self.write(")")
+            },
+            TypeOrExpr::Expr(ref expr) => self.operand(expr, Precedence::Prefixes),
+        }
+    }
+
+    fn binary(&mut self, expr: &BinaryExpr) -> fmt::Result {
+        let precedence = expr.op.precedence();
+        // Binary operators are left-to-right, so an equal operator on the right needs parentheses.
+        self.operand(&expr.lhs, precedence)?;
+        if expr.op != BinaryOp::Comma {
+            self.write(" ")?;
+        }
+        self.write(expr.op.text())?;
+        self.write(" ")?;
+        self.wrapped(&expr.rhs, expr.rhs.precedence() >= precedence)
+    }
+
+    fn ternary(&mut self, expr: &TernaryExpr) -> fmt::Result {
+        self.operand(&expr.condition, Precedence::LogicalOr)?;
+        self.write(" ? ")?;
+        self.print_expr(&expr.if_true)?;
+        self.write(" : ")?;
+        self.operand(&expr.if_false, Precedence::Ternary)
+    }
+    // endregion: Expressions
+}
+
+enum Specifier<'a> {
+    Storage(StorageKind),
+    Modifier(&'a ModifierSegment),
+    Root,
+}
+
+/// A piece of a declarator. The modifiers of a pointer are printed after its `*`.
+enum DeclaratorPart<'a> {
+    Pointer(Vec<&'a ModifierSegment>),
+    /// A modifier that isn't after a pointer.
+    Modifier(&'a ModifierSegment),
+    /// An array or function segment.
+    Suffix(&'a TypeSegment),
+}
+
+fn declarator_parts<'a, I>(segments: I) -> Vec<DeclaratorPart<'a>>
+where I: Iterator<Item = &'a TypeSegment> {
+    let mut parts = Vec::new();
+    for segment in segments {
+        match *segment {
+            TypeSegment::Pointer(..) => parts.push(DeclaratorPart::Pointer(Vec::new())),
+            TypeSegment::Modifier(ref modifier) => match parts.last_mut() {
+                Some(&mut DeclaratorPart::Pointer(ref mut modifiers)) => modifiers.push(modifier),
+                _ => parts.push(DeclaratorPart::Modifier(modifier)),
+            },
+            TypeSegment::Array(..) | TypeSegment::Func(..) => {
+                parts.push(DeclaratorPart::Suffix(segment));
+            },
+        }
+    }
+    parts
+}
+
+/// Returns whether the specifier at the root index is the one that declared the body.
+/// Later uses of the type (like `struct S s;`) only refer to it.
+fn defines_body(
+    type_decl: &TypeDecl,
+    body: &TypeDeclBody,
+    root_index: Option<TravelIndex>,
+) -> bool {
+    let root_index = match root_index {
+        Some(root_index) => root_index,
+        None => return false,
+    };
+    let last_tag = type_decl
+        .tags
+        .iter()
+        .map(|tag| tag.range.start.get())
+        .filter(|&start| start < body.range.start.get())
+        .max();
+    last_tag == Some(root_index.get())
+}
+
+/// Returns whether the types were declared by the same declaration (so they share specifiers).
+fn same_specifiers(a: &Type, b: &Type) -> bool {
+    let a_index = a.root_index.or(a.storage.kind_index);
+    a_index.is_some() && a_index == b.root_index.or(b.storage.kind_index)
+}
+
+fn modifier_index(modifier: &ModifierSegment) -> Option<TravelIndex> {
+    use ModifierSegment::*;
+    match *modifier {
+        Const(index) | Inline(index) | Long(index) | Restrict(index) | Short(index)
+        | Signed(index) | Unsigned(index) | Volatile(index) | Atomic(index) | Complex(index)
+        | Imaginary(index) | NoReturn(index) | ThreadLocal(index) => Some(index),
+        AlignasExpr(..) | AlignasType(..) => None,
+    }
+}
+
+fn storage_keyword(kind: StorageKind) -> Keyword {
+    match kind {
+        StorageKind::Declared | StorageKind::Auto => Keyword::Auto,
+        StorageKind::Static => Keyword::Static,
+        StorageKind::Extern => Keyword::Extern,
+        StorageKind::Register => Keyword::Register,
+        StorageKind::Typedef => Keyword::Typedef,
+    }
+}
+
+fn tag_keyword(kind: TypeDeclKind) -> Keyword {
+    match kind {
+        TypeDeclKind::Enum => Keyword::Enum,
+        TypeDeclKind::Struct => Keyword::Struct,
+        TypeDeclKind::Union => Keyword::Union,
+    }
+}
+
+fn prefix_token(op: PrefixOp) -> TokenKind {
+    match op {
+        PrefixOp::Increment => TokenKind::PlusPlus,
+        PrefixOp::Decrement => TokenKind::MinusMinus,
+        PrefixOp::Posate => TokenKind::Plus,
+        PrefixOp::Negate => TokenKind::Minus,
+        PrefixOp::LogicalNot => TokenKind::Bang,
+        PrefixOp::BitNot => TokenKind::Tilde,
+        PrefixOp::Dereference => TokenKind::Star,
+        PrefixOp::AddressOf => TokenKind::Amp,
+    }
+}
+
+fn suffix_token(op: SuffixOp) -> TokenKind {
+    match op {
+        SuffixOp::Increment => TokenKind::PlusPlus,
+        SuffixOp::Decrement => TokenKind::MinusMinus,
+    }
+}
+
+fn assign_token(op: AssignOp) -> TokenKind {
+    match op {
+        AssignOp::None => TokenKind::Equal,
+        AssignOp::Multiplication => TokenKind::StarEqual,
+        AssignOp::Divide => TokenKind::SlashEqual,
+        AssignOp::Modulo => TokenKind::PercentEqual,
+        AssignOp::Addition => TokenKind::PlusEqual,
+        AssignOp::Subtraction => TokenKind::MinusEqual,
+        AssignOp::LShift => TokenKind::LShiftEqual,
+        AssignOp::RShift => TokenKind::RShiftEqual,
+        AssignOp::BitAnd => TokenKind::AmpEqual,
+        AssignOp::BitXor => TokenKind::CarrotEqual,
+        AssignOp::BitOr => TokenKind::BarEqual,
+    }
+}
+
+/// Returns whether the characters would be lexed as part of the same token if they
+/// were written next to each other.
+fn would_join(last: char, next: char) -> bool {
+    const JOINING: &[&str] = &[
+        "++", "+=", "--", "-=", "->", "&&", "&=", "||", "|=", "<<", "<=", ">>", ">=", "==", "!=",
+        "%=", "^=", "*=", "/=", "##", "<:", ":>", "<%", "%>", "%:", "/*", "//", "..",
+    ];
+    let is_id_char = |c: char| c.is_alphanumeric() || c == '_';
+    if is_id_char(last) {
+        return is_id_char(next) || next == '"' || next == '\'';
+    }
+    JOINING.iter().any(|joined| {
+        let mut chars = joined.chars();
+        chars.next() == Some(last) && chars.next() == Some(next)
+    })
+}
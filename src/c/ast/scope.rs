@@ -1,14 +1,19 @@
 // Copyright 2021. remilia-dev
 // This source code is licensed under GPLv3 or any later version.
+use std::collections::HashMap;
+
 use smallvec::SmallVec;
 
 use crate::{
-    c::ast::{
-        Decl,
-        Stmt,
-        TypeDecl,
+    c::{
+        ast::{
+            Decl,
+            Stmt,
+            TypeDecl,
+        },
+        TravelIndex,
     },
-    math::NonMaxU32,
+    math::non_max_id,
     util::{
         CachedString,
         RedeclMap,
@@ -16,7 +21,15 @@ use crate::{
     },
 };
 
-pub type ScopeId = NonMaxU32;
+non_max_id! {
+    /// The id of a scope (which is its index in [SourceFile](crate::c::ast::SourceFile)'s scopes).
+    pub struct ScopeId;
+}
+
+impl ScopeId {
+    /// The id of a file's root scope.
+    pub const ROOT: ScopeId = ScopeId::MIN;
+}
 
 #[derive(Clone, Debug)]
 pub struct Scope {
@@ -25,6 +38,10 @@ pub struct Scope {
     pub stmts: Vec<Stmt>,
     pub types: RedeclMap<CachedString, TypeDecl>,
     pub decls: RedeclMap<CachedString, Decl>,
+    /// A map from the labels declared in this scope to the index of their names.
+    ///
+    /// Only scopes that [manage labels](ScopeKind::manages_labels) have labels.
+    pub labels: HashMap<CachedString, TravelIndex>,
 }
 
 impl Scope {
@@ -35,6 +52,7 @@ impl Scope {
             stmts: Vec::new(),
             types: RedeclMap::default(),
             decls: RedeclMap::default(),
+            labels: HashMap::new(),
         }
     }
 
@@ -45,6 +63,7 @@ impl Scope {
             stmts: Vec::new(),
             types: RedeclMap::default(),
             decls: RedeclMap::default(),
+            labels: HashMap::new(),
         }
     }
 
@@ -3,10 +3,12 @@
 use crate::{
     c::{
         ast::{
+            Attribute,
             BlockExpr,
             DeclStmt,
             Expr,
             ScopeId,
+            StringLiteral,
         },
         TravelIndex,
         TravelRange,
@@ -33,6 +35,10 @@ pub enum Stmt {
     For(ForStmt),
     Switch(SwitchStmt),
     Decl(DeclStmt),
+    Attributed(AttributedStmt),
+    StaticAssert(StaticAssertStmt),
+    Labeled(LabeledStmt),
+    Asm(AsmStmt),
     Empty(TravelIndex),
 }
 
@@ -41,9 +47,15 @@ impl Stmt {
         use Stmt::*;
         matches!(
             *self,
-            Expr(..) | Break(..) | Continue(..) | Return(..) | Goto(..) | Do(..)
+            Expr(..) | Break(..) | Continue(..) | Return(..) | Goto(..) | Do(..) | Asm(..)
         )
     }
+    /// Returns whether this statement is marked with the `[[fallthrough]]` attribute.
+    ///
+    /// An implicit fallthrough lint should not trigger on these statements.
+    pub fn is_fallthrough(&self) -> bool {
+        matches!(*self, Stmt::Attributed(ref stmt) if stmt.has_attribute("fallthrough"))
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -76,7 +88,15 @@ pub struct ReturnStmt {
 pub struct GotoStmt {
     pub range: TravelRange,
     pub label_scope_id: Option<ScopeId>,
-    pub label: Option<CachedString>,
+    pub target: GotoTarget,
+}
+
+#[derive(Clone, Debug)]
+pub enum GotoTarget {
+    /// A `goto label;` statement. The label is None if it was missing.
+    Label(Option<CachedString>),
+    /// A GNU computed goto (`goto *expr;`) that jumps to the address of a label.
+    Computed(Box<Expr>),
 }
 
 #[derive(Clone, Debug)]
@@ -110,6 +130,28 @@ pub struct ForStmt {
     pub block: Box<Stmt>,
 }
 
+#[derive(Clone, Debug)]
+pub struct AttributedStmt {
+    pub attributes: Vec<Attribute>,
+    pub stmt: Box<Stmt>,
+}
+
+impl AttributedStmt {
+    /// Returns whether the given standard attribute is applied to the statement.
+    pub fn has_attribute(&self, name: &str) -> bool {
+        self.attributes.iter().any(|attr| attr.is_standard(name))
+    }
+}
+
+/// A `_Static_assert(condition, message);` statement.
+#[derive(Clone, Debug)]
+pub struct StaticAssertStmt {
+    pub range: TravelRange,
+    pub condition: Box<Expr>,
+    /// The message is optional starting in C23.
+    pub message: Option<StringLiteral>,
+}
+
 #[derive(Clone, Debug)]
 pub struct SwitchStmt {
     pub range: TravelRange,
@@ -117,12 +159,37 @@ pub struct SwitchStmt {
     pub block: Box<Stmt>,
 }
 
+/// A `label: stmt` statement that can be the target of a goto.
+#[derive(Clone, Debug)]
 pub struct LabeledStmt {
     pub range: TravelRange,
     pub name: CachedString,
+    pub colon_index: TravelIndex,
     pub stmt: Box<Stmt>,
 }
 
+/// A GNU inline assembly statement (`asm volatile ("template" : outputs : inputs : clobbers)`).
+#[derive(Clone, Debug)]
+pub struct AsmStmt {
+    pub range: TravelRange,
+    pub volatile: bool,
+    pub template: StringLiteral,
+    pub outputs: Vec<AsmOperand>,
+    pub inputs: Vec<AsmOperand>,
+    /// The registers (or `"cc"` and `"memory"`) the assembly changes.
+    pub clobbers: Vec<CachedString>,
+}
+
+/// An input or output operand of an [AsmStmt] (`[name] "constraint" (expr)`).
+#[derive(Clone, Debug)]
+pub struct AsmOperand {
+    pub range: TravelRange,
+    /// The optional symbolic name the template can refer to the operand with (`%[name]`).
+    pub name: Option<CachedString>,
+    pub constraint: StringLiteral,
+    pub expr: Box<Expr>,
+}
+
 pub struct CasedStmt {
     pub range: TravelRange,
     pub switch_scope: Option<ScopeId>,
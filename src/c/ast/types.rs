@@ -5,6 +5,7 @@ use smallvec::SmallVec;
 use crate::{
     c::{
         ast::{
+            AttributeList,
             Decl,
             DeclIndex,
             Expr,
@@ -36,6 +37,8 @@ pub struct Type {
     pub storage: Storage,
     pub segments: Vec32<TypeSegment>,
     pub base_segments: NonMaxU32,
+    /// The GNU attribute lists (`__attribute__((...))`) in the type's specifiers or declarator.
+    pub attributes: Vec<AttributeList>,
 }
 
 impl Type {
@@ -47,6 +50,7 @@ impl Type {
             storage: Storage::new(default),
             segments: Vec32::new(),
             base_segments: 0.into(),
+            attributes: Vec::new(),
         }
     }
 
@@ -58,6 +62,7 @@ impl Type {
             storage: Storage::new(StorageKind::Declared),
             segments: Vec32::new(),
             base_segments: 0.into(),
+            attributes: Vec::new(),
         }
     }
 
@@ -65,8 +70,53 @@ impl Type {
         matches!(self.root, TypeRoot::AutoInt)
     }
 
-    pub fn add_modifier(&mut self, modifier: Keyword, index: TravelIndex) {
-        self.segments.push(ModifierSegment::new(modifier, index).into())
+    pub fn is_inferred(&self) -> bool {
+        matches!(self.root, TypeRoot::Inferred)
+    }
+
+    /// Adds the modifier to the type.
+    ///
+    /// Returns false (and does not add the modifier) if the modifier is a qualifier
+    /// that already qualifies the current segment. Duplicate qualifiers collapse into one.
+    pub fn add_modifier(&mut self, modifier: Keyword, index: TravelIndex) -> bool {
+        let modifier = ModifierSegment::new(modifier, index);
+        if modifier.is_qualifier() && self.has_current_modifier(&modifier) {
+            return false;
+        }
+        self.segments.push(modifier.into());
+        true
+    }
+
+    /// Returns whether the segment being modified already has the same kind of modifier.
+    fn has_current_modifier(&self, modifier: &ModifierSegment) -> bool {
+        let modifier = std::mem::discriminant(modifier);
+        self.segments
+            .iter()
+            .rev()
+            .map_while(|segment| match *segment {
+                TypeSegment::Modifier(ref existing) => Some(existing),
+                _ => None,
+            })
+            .any(|existing| std::mem::discriminant(existing) == modifier)
+    }
+
+    /// Removes the qualifiers (const, volatile, and restrict) that qualify the outermost
+    /// segment of the type (or the root if there isn't one).
+    pub fn remove_qualifiers(&mut self) {
+        let mut index = self.segments.len().get();
+        while index > 0 {
+            index -= 1;
+            match self.segments[index] {
+                TypeSegment::Modifier(ref modifier) if modifier.is_qualifier() => {},
+                TypeSegment::Modifier(..) => continue,
+                _ => break,
+            }
+
+            self.segments.remove(index);
+            if index < self.base_segments.get() {
+                self.base_segments = NonMaxU32::new(self.base_segments.get() - 1).unwrap();
+            }
+        }
     }
 
     pub fn clone_base(&self) -> Self {
@@ -79,6 +129,7 @@ impl Type {
             storage: self.storage.clone(),
             segments,
             base_segments: self.base_segments,
+            attributes: self.attributes.clone(),
         }
     }
 
@@ -117,6 +168,8 @@ pub enum TypeRoot {
     Decimal32,
     Decimal64,
     Decimal128,
+    /// The type of `nullptr` (the predeclared `nullptr_t` typedef name, since C23).
+    NullptrT,
     Type(DeclIndex),
     Typedef(DeclIndex),
     /// Represents that the type is part of an enum.
@@ -124,6 +177,16 @@ pub enum TypeRoot {
     EnumValue,
     /// Represents an enum literal that has been inlined into the scope.
     EnumForward(DeclIndex),
+    /// Represents a type that should be inferred from the declaration's initializer
+    /// (`__auto_type` or, since C23, `auto` without any other type specifiers).
+    Inferred,
+    /// The type of an expression (`typeof(expr)` or `__typeof__(expr)`).
+    TypeofExpr(Box<Expr>),
+    /// The type of an expression without its qualifiers (`typeof_unqual(expr)`).
+    TypeofUnqualExpr(Box<Expr>),
+    /// A type given to typeof (`typeof(type)`). The qualifiers of `typeof_unqual(type)`
+    /// have already been removed.
+    TypeofType(Box<Type>),
 }
 
 impl std::convert::TryFrom<Keyword> for TypeRoot {
@@ -140,6 +203,7 @@ impl std::convert::TryFrom<Keyword> for TypeRoot {
             Keyword::Decimal32 => Ok(TypeRoot::Decimal32),
             Keyword::Decimal64 => Ok(TypeRoot::Decimal64),
             Keyword::Decimal128 => Ok(TypeRoot::Decimal128),
+            Keyword::AutoType => Ok(TypeRoot::Inferred),
             keyword if keyword.is_base_type() => unimplemented!(),
             _ => Err(()),
         }
@@ -160,11 +224,15 @@ pub enum ModifierSegment {
     Const(TravelIndex),
     Inline(TravelIndex),
     Long(TravelIndex),
+    Restrict(TravelIndex),
     Short(TravelIndex),
     Signed(TravelIndex),
     Unsigned(TravelIndex),
     Volatile(TravelIndex),
-    Alignas(Box<Expr>),
+    /// An `_Alignas(constant-expr)` specifier.
+    AlignasExpr(Box<Expr>),
+    /// An `_Alignas(type-name)` specifier. The alignment is the alignment of the type.
+    AlignasType(Box<Type>),
     Atomic(TravelIndex),
     Complex(TravelIndex),
     Imaginary(TravelIndex),
@@ -177,6 +245,7 @@ impl ModifierSegment {
             Keyword::Const => ModifierSegment::Const(index),
             Keyword::Inline => ModifierSegment::Inline(index),
             Keyword::Long => ModifierSegment::Long(index),
+            Keyword::Restrict => ModifierSegment::Restrict(index),
             Keyword::Short => ModifierSegment::Short(index),
             Keyword::Signed => ModifierSegment::Signed(index),
             Keyword::Unsigned => ModifierSegment::Unsigned(index),
@@ -189,6 +258,37 @@ impl ModifierSegment {
             _ => panic!("Only type modifier keywords should be passed to add_modifier"),
         }
     }
+
+    /// Returns the name of the modifier (as it would be written in code).
+    pub fn name(&self) -> &'static str {
+        use ModifierSegment::*;
+        match *self {
+            Const(..) => "const",
+            Inline(..) => "inline",
+            Long(..) => "long",
+            Restrict(..) => "restrict",
+            Short(..) => "short",
+            Signed(..) => "signed",
+            Unsigned(..) => "unsigned",
+            Volatile(..) => "volatile",
+            AlignasExpr(..) | AlignasType(..) => "_Alignas",
+            Atomic(..) => "_Atomic",
+            Complex(..) => "_Complex",
+            Imaginary(..) => "_Imaginary",
+            NoReturn(..) => "_Noreturn",
+            ThreadLocal(..) => "_Thread_local",
+        }
+    }
+
+    /// Returns whether this modifier is a type qualifier (const, volatile, or restrict).
+    pub fn is_qualifier(&self) -> bool {
+        matches!(
+            *self,
+            ModifierSegment::Const(..)
+                | ModifierSegment::Volatile(..)
+                | ModifierSegment::Restrict(..)
+        )
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -209,6 +309,8 @@ pub enum ArrayKind {
     Empty,
     Expr(Box<Expr>),
     Star(TravelIndex),
+    /// An empty size that was inferred from an initializer (like `(int[]){ 1, 2 }`).
+    Inferred(usize),
 }
 
 #[derive(Clone, Debug)]
@@ -228,6 +330,8 @@ impl FuncSegment {
 pub struct TypeDeclTag {
     pub range: TravelRange,
     pub kind: TypeDeclKind,
+    /// The GNU attributes between the tag's keyword and its name (like `struct __attribute__((packed)) S`).
+    pub attributes: Vec<AttributeList>,
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -242,11 +346,18 @@ pub struct TypeDecl {
     pub name: Option<CachedString>,
     pub tags: Vec<TypeDeclTag>,
     pub body: Option<TypeDeclBody>,
+    /// The explicit underlying type of an enum (C23's `enum E : type`).
+    pub underlying: Option<Type>,
 }
 
 impl TypeDecl {
     pub fn new(tag: TypeDeclTag, name: Option<CachedString>) -> Self {
-        Self { name, tags: vec![tag], body: None }
+        Self {
+            name,
+            tags: vec![tag],
+            body: None,
+            underlying: None,
+        }
     }
 
     pub fn incomplete(&self) -> bool {
@@ -259,44 +370,84 @@ pub struct TypeDeclBody {
     pub range: TravelRange,
     pub kind: TypeDeclKind,
     pub fields: RedeclMap<CachedString, TypeDeclField>,
+    /// The indexes of the anonymous struct and union members in [fields](Self::fields).
+    pub anonymous_members: Vec<RedeclMapIndex>,
+    /// The `#pragma pack` value the body was declared under (None for the default packing).
+    pub pack: Option<u32>,
 }
 
 impl TypeDeclBody {
     pub fn new(kind: TypeDeclKind) -> Self {
         Self {
-            range: 0.into()..0.into(),
+            range: TravelIndex::MIN..TravelIndex::MIN,
             kind,
             fields: RedeclMap::new(),
+            anonymous_members: Vec::new(),
+            pack: None,
         }
     }
 
-    pub fn add_decls(&mut self, file: &SourceFile, decls: SmallVec<[Decl; 1]>) {
+    /// Adds the declarations as fields. The fields of anonymous members are forwarded
+    /// to the member (see [TypeDeclField::Indirect]).
+    ///
+    /// Returns the names of any fields that became ambiguous because an anonymous
+    /// member has a field with the same name as another field.
+    pub fn add_decls(
+        &mut self,
+        file: &SourceFile,
+        decls: SmallVec<[Decl; 1]>,
+    ) -> Vec<CachedString> {
+        let mut ambiguous = Vec::new();
         for decl in decls.into_iter() {
             let decl_name = decl.type_.name.clone();
             match decl.type_.root {
-                TypeRoot::Type(inner_index) if decl.type_.name.is_none() => {
+                TypeRoot::Type(inner_index) if decl.is_anonymous_member() => {
                     let indirect_index = self.fields.add(decl_name, decl.into());
+                    self.anonymous_members.push(indirect_index);
                     let inner_type = file.get_type_decl(inner_index);
                     if let Some(ref body) = inner_type.body {
-                        self.add_forwards(body, indirect_index);
+                        self.add_forwards(body, indirect_index, &mut ambiguous);
                     }
                 },
                 _ => {
+                    if let Some(ref name) = decl_name {
+                        if self.is_forwarded(name) {
+                            ambiguous.push(name.clone());
+                        }
+                    }
                     self.fields.add(decl_name, decl.into());
                 },
             }
         }
+        ambiguous
     }
 
-    fn add_forwards(&mut self, inner: &TypeDeclBody, to: RedeclMapIndex) {
+    fn add_forwards(
+        &mut self,
+        inner: &TypeDeclBody,
+        to: RedeclMapIndex,
+        ambiguous: &mut Vec<CachedString>,
+    ) {
         if inner.kind == TypeDeclKind::Enum {
             return;
         }
 
         for field in inner.fields.keys() {
+            if self.fields.get_index(field).is_some() {
+                ambiguous.push(field.clone());
+            }
             self.fields.add_keyed(field.clone(), to.into());
         }
     }
+
+    /// Returns whether the field with the given name is forwarded to an anonymous member.
+    fn is_forwarded(&self, name: &CachedString) -> bool {
+        let index = match self.fields.get_index(name) {
+            Some(index) => index,
+            None => return false,
+        };
+        matches!(self.fields.get(index), Some(&TypeDeclField::Indirect(..)))
+    }
 }
 
 #[create_intos]
@@ -15,8 +15,12 @@ pub trait ExprVisitor {
             Expr::String(..) => todo!(),
             Expr::Number(ref mut lit) => self.on_number(lit),
             Expr::Parens(ref mut expr) => self.on_parens(expr),
-            Expr::Init(_) => todo!(),  // TODO: ?
-            Expr::Block(_) => todo!(), // TODO: DO
+            Expr::Init(_) => todo!(), // TODO: ?
+            Expr::Stmt(_) => todo!(), // TODO: DO
+            Expr::Generic(ref mut expr) => self.on_generic(expr),
+            Expr::LabelAddress(_) => Ok(()),
+            Expr::CompoundLiteral(ref mut expr) => self.on_compound_literal(expr),
+            Expr::Nullptr(_) => Ok(()),
             Expr::Suffix(_) => todo!(),
             Expr::Access(_) => todo!(), // TODO: ?
             Expr::Array(_) => todo!(),
@@ -41,6 +45,42 @@ pub trait ExprVisitor {
         self.on_expr(&mut expr.expr)
     }
 
+    fn on_generic(&mut self, expr: &mut GenericExpr) -> MayUnwind<()> {
+        self.visit_generic(expr)
+    }
+    fn visit_generic(&mut self, expr: &mut GenericExpr) -> MayUnwind<()> {
+        self.on_expr(&mut expr.controller)?;
+        for association in &mut expr.associations {
+            self.on_expr(&mut association.1)?;
+        }
+        Ok(())
+    }
+
+    fn on_compound_literal(&mut self, expr: &mut CompoundLiteralExpr) -> MayUnwind<()> {
+        self.visit_compound_literal(expr)
+    }
+    fn visit_compound_literal(&mut self, expr: &mut CompoundLiteralExpr) -> MayUnwind<()> {
+        self.visit_init(&mut expr.init)
+    }
+    /// Visits the expressions of the initializer's members (and of its sub-initializers).
+    fn visit_init(&mut self, init: &mut InitExpr) -> MayUnwind<()> {
+        for member in &mut init.values {
+            match *member {
+                InitMember::Unnamed(ref mut expr) | InitMember::Named(_, ref mut expr) => {
+                    self.on_expr(expr)?;
+                },
+                InitMember::Array(ref mut indexes, ref mut expr) => {
+                    for index in indexes.iter_mut() {
+                        self.on_expr(index)?;
+                    }
+                    self.on_expr(expr)?;
+                },
+                InitMember::SubInitializer(ref mut init) => self.visit_init(init)?,
+            }
+        }
+        Ok(())
+    }
+
     fn on_cast(&mut self, expr: &mut CastExpr) -> MayUnwind<()> {
         self.visit_cast(expr)
     }
@@ -77,3 +117,282 @@ pub trait ExprVisitor {
         self.on_expr(&mut expr.to)
     }
 }
+
+/// Whether a [walk](walk_file) should go into the children of what was just visited.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Flow {
+    /// Walk through the children and then continue on.
+    Continue,
+    /// Skip the children but continue on with the rest of the walk.
+    SkipChildren,
+    /// Stop the walk entirely.
+    Stop,
+}
+
+/// A read-only visitor of the declarations, statements, and expressions of a [SourceFile].
+///
+/// The visitor is driven by [walk_file], [walk_stmt], or [walk_expr], which call the hooks
+/// in source order. Each hook returns a [Flow] to prune or stop the walk.
+pub trait Visitor {
+    fn visit_decl(&mut self, _decl: &Decl) -> Flow {
+        Flow::Continue
+    }
+    fn visit_stmt(&mut self, _stmt: &Stmt) -> Flow {
+        Flow::Continue
+    }
+    fn visit_expr(&mut self, _expr: &Expr) -> Flow {
+        Flow::Continue
+    }
+}
+
+/// Walks through the statements of the file's root scope (and everything within them).
+///
+/// Returns [Flow::Stop] if the visitor stopped the walk.
+pub fn walk_file(file: &SourceFile, visitor: &mut impl Visitor) -> Flow {
+    Walker { file, visitor }.scope(file.root_scope()).into()
+}
+
+/// Walks through the statement and everything within it. The file should be the one
+/// the statement was parsed from (since blocks refer to its scopes).
+///
+/// Returns [Flow::Stop] if the visitor stopped the walk.
+pub fn walk_stmt(file: &SourceFile, stmt: &Stmt, visitor: &mut impl Visitor) -> Flow {
+    Walker { file, visitor }.stmt(stmt).into()
+}
+
+/// Walks through the expression and everything within it. The file should be the one
+/// the expression was parsed from (since statement expressions refer to its scopes).
+///
+/// Returns [Flow::Stop] if the visitor stopped the walk.
+pub fn walk_expr(file: &SourceFile, expr: &Expr, visitor: &mut impl Visitor) -> Flow {
+    Walker { file, visitor }.expr(expr).into()
+}
+
+/// Represents that the visitor stopped the walk.
+struct Stopped;
+
+impl From<Result<(), Stopped>> for Flow {
+    fn from(result: Result<(), Stopped>) -> Self {
+        match result {
+            Ok(()) => Flow::Continue,
+            Err(Stopped) => Flow::Stop,
+        }
+    }
+}
+
+/// Returns whether the children should be walked (or an error if the walk should stop).
+fn should_walk_children(flow: Flow) -> Result<bool, Stopped> {
+    match flow {
+        Flow::Continue => Ok(true),
+        Flow::SkipChildren => Ok(false),
+        Flow::Stop => Err(Stopped),
+    }
+}
+
+struct Walker<'a, V: Visitor> {
+    file: &'a SourceFile,
+    visitor: &'a mut V,
+}
+
+impl<'a, V: Visitor> Walker<'a, V> {
+    fn scope(&mut self, scope: &Scope) -> Result<(), Stopped> {
+        for stmt in &scope.stmts {
+            self.stmt(stmt)?;
+        }
+        Ok(())
+    }
+
+    fn block(&mut self, block: &BlockExpr) -> Result<(), Stopped> {
+        self.scope(self.file.get_scope(block.scope_id))
+    }
+
+    fn decl(&mut self, decl: &Decl) -> Result<(), Stopped> {
+        if !should_walk_children(self.visitor.visit_decl(decl))? {
+            return Ok(());
+        }
+
+        self.type_(&decl.type_)?;
+        match decl.postfix {
+            DeclPostfix::None => Ok(()),
+            DeclPostfix::Bitfield(ref expr) | DeclPostfix::Initializer(ref expr) => self.expr(expr),
+            DeclPostfix::Block(ref block) => self.block(block),
+        }
+    }
+
+    /// Walks through the expressions within the type (like the sizes of arrays).
+    fn type_(&mut self, type_: &Type) -> Result<(), Stopped> {
+        match type_.root {
+            TypeRoot::TypeofExpr(ref expr) | TypeRoot::TypeofUnqualExpr(ref expr) => {
+                self.expr(expr)?;
+            },
+            TypeRoot::TypeofType(ref type_) => self.type_(type_)?,
+            _ => {},
+        }
+
+        for segment in type_.segments.iter() {
+            match *segment {
+                TypeSegment::Array(ArraySegment { kind: ArrayKind::Expr(ref expr), .. })
+                | TypeSegment::Modifier(ModifierSegment::AlignasExpr(ref expr)) => {
+                    self.expr(expr)?;
+                },
+                TypeSegment::Modifier(ModifierSegment::AlignasType(ref type_)) => {
+                    self.type_(type_)?;
+                },
+                _ => {},
+            }
+        }
+        Ok(())
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) -> Result<(), Stopped> {
+        if !should_walk_children(self.visitor.visit_stmt(stmt))? {
+            return Ok(());
+        }
+
+        match *stmt {
+            Stmt::Break(..) | Stmt::Continue(..) | Stmt::Empty(..) => {},
+            Stmt::Expr(ref expr) => self.expr(expr)?,
+            Stmt::Case(ref stmt) => {
+                self.maybe_expr(&stmt.case)?;
+                self.stmt(&stmt.stmt)?;
+            },
+            Stmt::Return(ref stmt) => self.maybe_expr(&stmt.expr)?,
+            Stmt::Goto(ref stmt) => {
+                if let GotoTarget::Computed(ref expr) = stmt.target {
+                    self.expr(expr)?;
+                }
+            },
+            Stmt::Block(ref block) => self.block(block)?,
+            Stmt::If(ref stmt) => {
+                self.expr(&stmt.condition)?;
+                self.stmt(&stmt.block)?;
+                if let Some(ref else_) = stmt.else_ {
+                    self.stmt(else_)?;
+                }
+            },
+            Stmt::While(ref stmt) => {
+                self.expr(&stmt.condition)?;
+                self.stmt(&stmt.block)?;
+            },
+            Stmt::Do(ref stmt) => {
+                self.stmt(&stmt.block)?;
+                self.expr(&stmt.condition)?;
+            },
+            Stmt::For(ref stmt) => {
+                self.stmt(&stmt.initial)?;
+                self.maybe_expr(&stmt.condition)?;
+                self.maybe_expr(&stmt.increment)?;
+                self.stmt(&stmt.block)?;
+            },
+            Stmt::Switch(ref stmt) => {
+                self.expr(&stmt.value)?;
+                self.stmt(&stmt.block)?;
+            },
+            Stmt::Decl(ref stmt) => {
+                let scope = self.file.get_scope(stmt.scope_id);
+                for &decl_id in &stmt.decl_ids {
+                    self.decl(&scope.decls[decl_id])?;
+                }
+            },
+            Stmt::Attributed(ref stmt) => self.stmt(&stmt.stmt)?,
+            Stmt::StaticAssert(ref stmt) => self.expr(&stmt.condition)?,
+            Stmt::Labeled(ref stmt) => self.stmt(&stmt.stmt)?,
+            Stmt::Asm(ref stmt) => {
+                for operand in stmt.outputs.iter().chain(&stmt.inputs) {
+                    self.expr(&operand.expr)?;
+                }
+            },
+        }
+        Ok(())
+    }
+
+    fn maybe_expr(&mut self, expr: &Option<Box<Expr>>) -> Result<(), Stopped> {
+        match *expr {
+            Some(ref expr) => self.expr(expr),
+            None => Ok(()),
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) -> Result<(), Stopped> {
+        if !should_walk_children(self.visitor.visit_expr(expr))? {
+            return Ok(());
+        }
+
+        match *expr {
+            Expr::DeclRef(..)
+            | Expr::Number(..)
+            | Expr::String(..)
+            | Expr::LabelAddress(..)
+            | Expr::Nullptr(..) => {},
+            Expr::Stmt(ref expr) => self.block(&expr.block)?,
+            Expr::Parens(ref expr) => self.expr(&expr.expr)?,
+            Expr::Init(ref expr) => self.init(expr)?,
+            Expr::Generic(ref expr) => {
+                self.expr(&expr.controller)?;
+                for association in &expr.associations {
+                    if let Some(ref type_) = association.0 {
+                        self.type_(type_)?;
+                    }
+                    self.expr(&association.1)?;
+                }
+            },
+            Expr::CompoundLiteral(ref expr) => {
+                self.type_(&expr.type_)?;
+                self.init(&expr.init)?;
+            },
+            Expr::Suffix(ref expr) => self.expr(&expr.expr)?,
+            Expr::Access(ref expr) => self.expr(&expr.expr)?,
+            Expr::Array(ref expr) => {
+                self.expr(&expr.expr)?;
+                self.expr(&expr.offset)?;
+            },
+            Expr::Call(ref expr) => {
+                self.expr(&expr.expr)?;
+                for arg in &expr.args {
+                    self.expr(arg)?;
+                }
+            },
+            Expr::Type(ref expr) => match expr.of {
+                TypeOrExpr::Type(ref type_) => self.type_(type_)?,
+                TypeOrExpr::Expr(ref expr) => self.expr(expr)?,
+            },
+            Expr::Prefix(ref expr) => self.expr(&expr.expr)?,
+            Expr::Cast(ref expr) => {
+                self.type_(&expr.to)?;
+                self.expr(&expr.expr)?;
+            },
+            Expr::Binary(ref expr) => {
+                self.expr(&expr.lhs)?;
+                self.expr(&expr.rhs)?;
+            },
+            Expr::Ternary(ref expr) => {
+                self.expr(&expr.condition)?;
+                self.expr(&expr.if_true)?;
+                self.expr(&expr.if_false)?;
+            },
+            Expr::Assign(ref expr) => {
+                self.expr(&expr.to)?;
+                self.expr(&expr.value)?;
+            },
+        }
+        Ok(())
+    }
+
+    fn init(&mut self, init: &InitExpr) -> Result<(), Stopped> {
+        for member in &init.values {
+            match *member {
+                InitMember::Unnamed(ref expr) | InitMember::Named(_, ref expr) => {
+                    self.expr(expr)?;
+                },
+                InitMember::Array(ref indexes, ref expr) => {
+                    for index in indexes {
+                        self.expr(index)?;
+                    }
+                    self.expr(expr)?;
+                },
+                InitMember::SubInitializer(ref init) => self.init(init)?,
+            }
+        }
+        Ok(())
+    }
+}
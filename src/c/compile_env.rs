@@ -1,7 +1,10 @@
 // Copyright 2021. remilia-dev
 // This source code is licensed under GPLv3 or any later version.
 use std::{
-    collections::HashMap,
+    collections::{
+        HashMap,
+        HashSet,
+    },
     path::Path,
 };
 
@@ -17,51 +20,202 @@ use crate::{
         FileTokens,
         IncludeType,
         Keyword,
+        Lexer,
         StringEnc,
         TokenKind,
+        TravelerErrorKind,
     },
     sync::{
         Arc,
+        AtomicU32,
+        Mutex,
         OnceArray,
+        RwLock,
     },
     util::{
         CachedString,
+        DateTime,
+        FileId,
+        MappedSourceLoc,
+        SourceMap,
         StringCache,
     },
 };
 
-pub struct CompileEnv {
-    settings: CompileSettings,
-    #[cfg(feature = "multithreading")]
-    threads: Arc<ThreadPool>,
-    cache: StringCache,
+/// Lexes a file whose tokens are needed but haven't been added to
+/// [file_id_to_tokens](CompileEnv::file_id_to_tokens) yet (see [CompileEnv::set_lazy_lexer]).
+pub trait LazyLexer = Fn(&CompileEnv, FileId) -> Option<FileTokens> + Send + Sync;
+
+/// A [predefined macro](CompileSettings::defines) (its name and value).
+type Define = (String, Option<String>);
+
+/// The settings of a [CompileEnv] along with the maps built from them.
+///
+/// A config doesn't change once it's built. Code that looks things up for every token should
+/// keep the env's current [config](CompileEnv::config) instead of going through the env each time.
+pub struct EnvConfig {
+    settings: Arc<CompileSettings>,
     // OPTIMIZATION: A two-way map may be better than two separate maps.
     cached_to_keywords: HashMap<CachedString, Keyword>,
     keyword_to_cached: HashMap<Keyword, CachedString>,
     cached_to_preprocessor: HashMap<CachedString, TokenKind>,
     cached_to_str_prefix: HashMap<CachedString, StringEnc>,
-    pub file_id_to_tokens: OnceArray<FileTokens>,
+    /// See [is_known_builtin](Self::is_known_builtin).
+    known_builtins: HashSet<CachedString>,
+}
+
+pub struct CompileEnv {
+    /// The current config. A [reconfigure](Self::reconfigure) swaps in a new one, so an old
+    /// config is dropped once nothing holds it anymore.
+    config: RwLock<Arc<EnvConfig>>,
+    #[cfg(feature = "multithreading")]
+    threads: Arc<ThreadPool>,
+    cache: StringCache,
+    /// The cached `__VA_ARGS__` id (see [va_args_id](Self::va_args_id)).
+    va_args_id: CachedString,
+    /// The cached `__VA_OPT__` id (see [va_opt_id](Self::va_opt_id)).
+    va_opt_id: CachedString,
+    /// See [known_c_attributes](Self::known_c_attributes).
+    known_c_attributes: HashSet<CachedString>,
+    /// See [known_gnu_attributes](Self::known_gnu_attributes).
+    known_gnu_attributes: HashSet<CachedString>,
+    pub file_id_to_tokens: OnceArray<FileTokens, FileId>,
+    /// The paths and original bytes of every file that has been lexed.
+    pub source_map: SourceMap,
+    lazy_lexer: Option<Box<dyn LazyLexer>>,
+    /// The defines the [predefined_tokens](Self::predefined_tokens) were last lexed from
+    /// along with their tokens.
+    predefined_tokens: Mutex<Option<(Vec<Define>, Arc<FileTokens>)>>,
+    /// See [counter](Self::counter).
+    counter: Arc<AtomicU32>,
+    /// When the environment was created, formatted like `__DATE__` (see [start_date](Self::start_date)).
+    start_date: Arc<Box<str>>,
+    /// When the environment was created, formatted like `__TIME__` (see [start_time](Self::start_time)).
+    start_time: Arc<Box<str>>,
 }
 impl CompileEnv {
     pub fn new(settings: CompileSettings) -> CompileEnv {
         // OPTIMIZATION: May be able to improve the hashmaps by using a different hasher or hashmap.
-        let mut env = CompileEnv {
-            settings,
+        let now = DateTime::now();
+        let cache = StringCache::new();
+        let va_args_id = cache.get_or_cache("__VA_ARGS__");
+        let va_opt_id = cache.get_or_cache("__VA_OPT__");
+        let known_c_attributes = cache_all(&cache, KNOWN_C_ATTRIBUTES);
+        let known_gnu_attributes = cache_all(&cache, KNOWN_GNU_ATTRIBUTES);
+        let config = EnvConfig::new(&cache, settings);
+        CompileEnv {
+            config: RwLock::new(Arc::new(config)),
             #[cfg(feature = "multithreading")]
             threads: Arc::new(ThreadPoolBuilder::new().build().unwrap()),
-            cache: StringCache::new(),
-            cached_to_keywords: HashMap::new(),
-            keyword_to_cached: HashMap::new(),
-            cached_to_preprocessor: HashMap::new(),
-            cached_to_str_prefix: HashMap::new(),
+            cache,
+            va_args_id,
+            va_opt_id,
+            known_c_attributes,
+            known_gnu_attributes,
             file_id_to_tokens: OnceArray::default(),
-        };
-        update_cache_maps(&mut env);
-        env
+            source_map: SourceMap::new(),
+            lazy_lexer: None,
+            predefined_tokens: Mutex::new(None),
+            counter: Arc::new(AtomicU32::new(0)),
+            start_date: Arc::new(now.date_macro_string().into_boxed_str()),
+            start_time: Arc::new(now.time_macro_string().into_boxed_str()),
+        }
     }
 
-    pub fn settings(&self) -> &CompileSettings {
-        &self.settings
+    /// Returns the current config. The returned config stays the same even if the env is
+    /// [reconfigured](Self::reconfigure) while it's held.
+    pub fn config(&self) -> Arc<EnvConfig> {
+        self.config.read().clone()
+    }
+
+    /// Returns the current settings. The returned settings stay the same even if the env is
+    /// [reconfigured](Self::reconfigure) while they're held.
+    pub fn settings(&self) -> Arc<CompileSettings> {
+        self.config.read().settings.clone()
+    }
+
+    /// Returns whether the path is in one of the [system include](CompileSettings::system_includes)
+    /// directories.
+    pub fn is_system_header(&self, path: &Path) -> bool {
+        self.settings()
+            .system_includes
+            .iter()
+            .any(|dir| path.starts_with(dir))
+    }
+
+    /// Replaces the settings and rebuilds the keyword, preprocessor, and string prefix
+    /// maps to match them. The [StringCache] (and every string cached in it) is kept.
+    ///
+    /// The new settings and maps are swapped in together, so a lookup sees either the old
+    /// config or the new one (never a mix of the two). Lexing that's already underway may
+    /// still see the old config for its earlier tokens.
+    pub fn reconfigure(&self, settings: CompileSettings) {
+        let config = Arc::new(EnvConfig::new(&self.cache, settings));
+        *self.config.write() = config;
+    }
+
+    /// Sets the callback used to lex files on demand (see [get_or_lex_tokens](Self::get_or_lex_tokens)).
+    pub fn set_lazy_lexer<L: LazyLexer + 'static>(&mut self, lexer: L) {
+        self.lazy_lexer = Some(Box::new(lexer));
+    }
+
+    /// Returns the tokens of the given file. If the file hasn't been lexed yet, the
+    /// [lazy lexer](Self::set_lazy_lexer) (if there is one) is used to lex it now.
+    ///
+    /// This is None if the id was never reserved (like an unknown id from an include callback).
+    pub fn get_or_lex_tokens(&self, file_id: FileId) -> Option<Arc<FileTokens>> {
+        if let Some(tokens) = self.file_id_to_tokens.get_arc(file_id) {
+            return Some(tokens);
+        }
+        if file_id.get() as usize >= self.file_id_to_tokens.len() {
+            return None;
+        }
+
+        let lazy_lexer = self.lazy_lexer.as_ref()?;
+        let tokens = lazy_lexer(self, file_id)?;
+        // Another thread may have set the tokens first. Either way, the set tokens are returned.
+        self.file_id_to_tokens.set_if_none(file_id, Arc::new(tokens));
+        self.file_id_to_tokens.get_arc(file_id)
+    }
+
+    /// Returns the [predefined macros](CompileSettings::defines) lexed into a file of `#define`s
+    /// (None if there are no predefined macros).
+    ///
+    /// They're lexed the first time they're needed, so every traveler shares the same tokens.
+    /// They're only lexed again if a [reconfigure](Self::reconfigure) changed the defines.
+    /// If there's no file id left for them, an [OutOfFileIds](TravelerErrorKind::OutOfFileIds)
+    /// error is returned.
+    pub fn predefined_tokens(&self) -> Result<Option<Arc<FileTokens>>, TravelerErrorKind> {
+        let settings = self.settings();
+        if settings.defines.is_empty() {
+            return Ok(None);
+        }
+        let mut predefined = self.predefined_tokens.lock();
+        if let Some((ref defines, ref tokens)) = *predefined {
+            if *defines == settings.defines {
+                return Ok(Some(tokens.clone()));
+            }
+        }
+        let tokens = lex_predefined(self, &settings.defines);
+        let tokens = tokens.ok_or(TravelerErrorKind::OutOfFileIds)?;
+        *predefined = Some((settings.defines.clone(), tokens.clone()));
+        Ok(Some(tokens))
+    }
+
+    /// The number `__COUNTER__` will expand to next. It's shared by every traveler of the env,
+    /// so each use of `__COUNTER__` expands to a different number.
+    pub fn counter(&self) -> &Arc<AtomicU32> {
+        &self.counter
+    }
+
+    /// Returns the (UTC) date the environment was created on, formatted like `__DATE__` (`Mmm dd yyyy`).
+    pub fn start_date(&self) -> &Arc<Box<str>> {
+        &self.start_date
+    }
+
+    /// Returns the (UTC) time the environment was created at, formatted like `__TIME__` (`hh:mm:ss`).
+    pub fn start_time(&self) -> &Arc<Box<str>> {
+        &self.start_time
     }
 
     #[cfg(feature = "multithreading")]
@@ -73,18 +227,44 @@ impl CompileEnv {
         &self.cache
     }
 
+    /// Returns the id of `__VA_ARGS__`, the name a variadic parameter gets when it isn't named.
+    pub fn va_args_id(&self) -> &CachedString {
+        &self.va_args_id
+    }
+
+    /// Returns the id of `__VA_OPT__`. This id can't be used as a function macro parameter.
+    pub fn va_opt_id(&self) -> &CachedString {
+        &self.va_opt_id
+    }
+
+    /// The standard attributes (like `nodiscard`) that `__has_c_attribute` reports as supported.
+    pub fn known_c_attributes(&self) -> &HashSet<CachedString> {
+        &self.known_c_attributes
+    }
+
+    /// The GNU attributes (like `packed`) that `__has_attribute` reports as supported
+    /// (along with the [standard attributes](Self::known_c_attributes)).
+    pub fn known_gnu_attributes(&self) -> &HashSet<CachedString> {
+        &self.known_gnu_attributes
+    }
+
+    /// See [EnvConfig::is_known_builtin].
+    pub fn is_known_builtin(&self, name: &CachedString) -> bool {
+        self.config().is_known_builtin(name)
+    }
+
     pub fn get_keyword(&self, v: &CachedString) -> Option<Keyword> {
-        self.cached_to_keywords.get(v).cloned()
+        self.config().get_keyword(v)
     }
 
-    pub fn get_keyword_string(&self, v: Keyword) -> Option<&CachedString> {
-        self.keyword_to_cached.get(&v)
+    pub fn get_keyword_string(&self, v: Keyword) -> Option<CachedString> {
+        self.config().get_keyword_string(v).cloned()
     }
 
     pub fn get_definable_id<'a>(&'a self, v: &'a TokenKind) -> &'a CachedString {
         match *v {
             TokenKind::Identifier(ref id) => id,
-            TokenKind::Keyword(keyword) => &self.keyword_to_cached[&keyword],
+            TokenKind::Keyword(_, ref spelling) => spelling,
             _ => panic!(
                 "Non-definable token does not have a definable id: {:?}",
                 v
@@ -92,12 +272,40 @@ impl CompileEnv {
         }
     }
 
+    /// Returns the presumed file name along with the line and column the location starts at.
+    /// The line and column both start at 1.
+    ///
+    /// If the location is [mapped](MappedSourceLoc) by a `#line` directive (or a line marker),
+    /// the directive's line and file name are returned instead of the physical ones. Otherwise,
+    /// the file name is the path of the location's file (None if it doesn't have one).
+    ///
+    /// A plain [SourceLoc] isn't remapped since it doesn't know which directive applies to it.
+    /// Use the traveler's mapped location (see
+    /// [TravelerState::mapped_loc](crate::c::TravelerState::mapped_loc)) for that.
+    ///
+    /// This is None if the location's file isn't in the [source map](Self::source_map).
+    pub fn resolve_location(
+        &self,
+        loc: impl Into<MappedSourceLoc>,
+    ) -> Option<(Option<String>, u32, u32)> {
+        let mapped = loc.into();
+        let (line, column) = self.source_map.line_col(&mapped.loc)?;
+        let file_name = match mapped.file_name() {
+            Some(file_name) => Some(file_name.to_string()),
+            None => {
+                let path = self.source_map.path(mapped.loc.file_id());
+                path.map(|path| path.display().to_string())
+            },
+        };
+        Some((file_name, mapped.line().unwrap_or(line), column))
+    }
+
     pub fn get_preprocessor(&self, v: &CachedString) -> Option<TokenKind> {
-        self.cached_to_preprocessor.get(v).cloned()
+        self.config().get_preprocessor(v)
     }
 
     pub fn get_string_prefix(&self, v: &CachedString) -> Option<StringEnc> {
-        self.cached_to_str_prefix.get(v).cloned()
+        self.config().get_string_prefix(v)
     }
 
     pub fn find_include(
@@ -127,14 +335,14 @@ impl CompileEnv {
                 }
             }
 
-            for search_dir in &self.settings.local_includes {
+            for search_dir in &self.settings().local_includes {
                 if let Some(target) = get_child_file_if_exists(search_dir) {
                     return Some(target);
                 }
             }
         }
 
-        for search_dir in &self.settings.system_includes {
+        for search_dir in &self.settings().system_includes {
             if let Some(target) = get_child_file_if_exists(search_dir) {
                 return Some(target);
             }
@@ -142,6 +350,20 @@ impl CompileEnv {
 
         None
     }
+
+    /// Returns the include as the [include rewriter](CompileSettings::include_rewriter)
+    /// rewrites it (or as it is if there is no rewriter or the rewriter leaves it).
+    pub fn rewrite_include(
+        &self,
+        inc_type: IncludeType,
+        path: &CachedString,
+    ) -> (IncludeType, CachedString) {
+        let settings = self.settings();
+        let rewriter = settings.include_rewriter.as_ref();
+        rewriter
+            .and_then(|rewrite| rewrite(inc_type, path, self.cache()))
+            .unwrap_or_else(|| (inc_type, path.clone()))
+    }
 }
 
 impl Default for CompileEnv {
@@ -150,31 +372,155 @@ impl Default for CompileEnv {
     }
 }
 
-fn update_cache_maps(env: &mut CompileEnv) {
+/// The standard attributes that are known (see [CompileEnv::known_c_attributes]).
+const KNOWN_C_ATTRIBUTES: &[&str] = &[
+    "deprecated",
+    "fallthrough",
+    "maybe_unused",
+    "nodiscard",
+    "noreturn",
+];
+
+/// The GNU attributes that are known (see [CompileEnv::known_gnu_attributes]).
+const KNOWN_GNU_ATTRIBUTES: &[&str] = &[
+    "aligned",
+    "always_inline",
+    "cold",
+    "const",
+    "constructor",
+    "destructor",
+    "format",
+    "hot",
+    "malloc",
+    "noinline",
+    "nonnull",
+    "packed",
+    "pure",
+    "section",
+    "unused",
+    "used",
+    "visibility",
+    "warn_unused_result",
+    "weak",
+];
+
+/// The builtin functions that are known (see [CompileEnv::is_known_builtin]).
+const KNOWN_BUILTINS: &[&str] = &[
+    "__builtin_bswap32",
+    "__builtin_bswap64",
+    "__builtin_clz",
+    "__builtin_ctz",
+    "__builtin_expect",
+    "__builtin_offsetof",
+    "__builtin_popcount",
+    "__builtin_trap",
+    "__builtin_unreachable",
+];
+
+/// Lexes the defines into a file of `#define`s (None if there are no file ids left).
+fn lex_predefined(env: &CompileEnv, defines: &[Define]) -> Option<Arc<FileTokens>> {
+    let file_id = env.file_id_to_tokens.reserve()?;
+    let mut source = String::new();
+    for define in defines {
+        source.push_str("#define ");
+        source.push_str(&define.0);
+        source.push(' ');
+        source.push_str(define.1.as_deref().unwrap_or("1"));
+        source.push('\n');
+    }
+
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+    let mut lexer = Lexer::new(env, callback);
+    let tokens = Arc::new(lexer.lex_bytes(file_id, source.as_bytes()));
+    env.file_id_to_tokens.set_or_panic(file_id, tokens.clone());
+    Some(tokens)
+}
+
+fn cache_all(cache: &StringCache, strings: &[&str]) -> HashSet<CachedString> {
+    strings.iter().map(|string| cache.get_or_cache(string)).collect()
+}
+
+impl EnvConfig {
+    fn new(cache: &StringCache, settings: CompileSettings) -> EnvConfig {
+        let mut config = EnvConfig {
+            settings: Arc::new(settings),
+            cached_to_keywords: HashMap::new(),
+            keyword_to_cached: HashMap::new(),
+            cached_to_preprocessor: HashMap::new(),
+            cached_to_str_prefix: HashMap::new(),
+            known_builtins: HashSet::new(),
+        };
+        update_cache_maps(&mut config, cache);
+        config
+    }
+
+    pub fn settings(&self) -> &CompileSettings {
+        &self.settings
+    }
+
+    /// Returns whether the name is a builtin function (like `__builtin_expect`) that
+    /// `__has_builtin` reports as supported.
+    ///
+    /// This includes the [extra builtins](CompileSettings::extra_builtins) of the settings.
+    pub fn is_known_builtin(&self, name: &CachedString) -> bool {
+        self.known_builtins.contains(name)
+    }
+
+    pub fn get_keyword(&self, v: &CachedString) -> Option<Keyword> {
+        self.cached_to_keywords.get(v).cloned()
+    }
+
+    pub fn get_keyword_string(&self, v: Keyword) -> Option<&CachedString> {
+        self.keyword_to_cached.get(&v)
+    }
+
+    pub fn get_preprocessor(&self, v: &CachedString) -> Option<TokenKind> {
+        self.cached_to_preprocessor.get(v).cloned()
+    }
+
+    pub fn get_string_prefix(&self, v: &CachedString) -> Option<StringEnc> {
+        self.cached_to_str_prefix.get(v).cloned()
+    }
+}
+
+fn update_cache_maps(config: &mut EnvConfig, cache: &StringCache) {
+    let builtins = KNOWN_BUILTINS.iter().copied();
+    let extra_builtins = config.settings.extra_builtins.iter().map(String::as_str);
+    for builtin in builtins.chain(extra_builtins) {
+        config.known_builtins.insert(cache.get_or_cache(builtin));
+    }
+
     for &keyword in &Keyword::VARIANTS {
-        if keyword.should_add(&env.settings) {
-            let cached = env.cache.get_or_cache(keyword.text());
-            env.cached_to_keywords.insert(cached.clone(), keyword);
-            env.keyword_to_cached.insert(keyword, cached);
+        if keyword.should_add(&config.settings) {
+            let cached = cache.get_or_cache(keyword.text());
+            config.cached_to_keywords.insert(cached.clone(), keyword);
+            config.keyword_to_cached.insert(keyword, cached);
+        }
+
+        if config.settings.enable_gnu_extensions {
+            for &spelling in keyword.alt_spellings() {
+                let cached = cache.get_or_cache(spelling);
+                config.cached_to_keywords.insert(cached, keyword);
+            }
         }
     }
 
     for &encoding in &StringEnc::VARIANTS {
-        if !encoding.should_add(&env.settings) {
+        if !encoding.should_add(&config.settings) {
             continue;
         }
 
         if let Some(prefix) = encoding.prefix() {
-            let cached = env.cache.get_or_cache(prefix);
-            env.cached_to_str_prefix.insert(cached, encoding);
+            let cached = cache.get_or_cache(prefix);
+            config.cached_to_str_prefix.insert(cached, encoding);
         }
     }
 
     {
         use TokenKind::*;
         let mut map_preprocessor = |s: &str, pre: TokenKind| {
-            let cached = env.cache.get_or_cache(s);
-            env.cached_to_preprocessor.insert(cached, pre);
+            let cached = cache.get_or_cache(s);
+            config.cached_to_preprocessor.insert(cached, pre);
         };
         map_preprocessor("if", PreIf { link: usize::MAX });
         map_preprocessor("ifdef", PreIfDef { link: usize::MAX });
@@ -190,5 +536,7 @@ fn update_cache_maps(env: &mut CompileEnv) {
         map_preprocessor("include", PreInclude);
         map_preprocessor("include_next", PreIncludeNext);
         map_preprocessor("warning", PreWarning);
+        map_preprocessor("assert", PreAssert);
+        map_preprocessor("unassert", PreUnassert);
     }
 }
@@ -2,11 +2,14 @@
 // This source code is licensed under GPLv3 or any later version.
 use std::convert::TryFrom;
 
-use crate::util::{
-    CharExt,
-    FileId,
-    SourceLoc,
-    Utf8DecodeError,
+use crate::{
+    c::InvalidUtf8Policy,
+    util::{
+        CharExt,
+        FileId,
+        SourceLoc,
+        Utf8DecodeError,
+    },
 };
 
 pub struct FileReader {
@@ -14,6 +17,12 @@ pub struct FileReader {
     position: usize,
     file_id: FileId,
     last_byte: u32,
+    /// The bytes that were skipped because they weren't valid UTF-8 (in the order they occur).
+    decode_errors: Vec<Utf8DecodeError>,
+    next_decode_error: usize,
+    /// The trigraphs that were replaced (in the order they occur).
+    trigraphs: Vec<CharLoc>,
+    next_trigraph: usize,
 }
 
 impl FileReader {
@@ -23,54 +32,118 @@ impl FileReader {
             position: 0,
             file_id: FileId::MAX,
             last_byte: 0,
+            decode_errors: Vec::new(),
+            next_decode_error: 0,
+            trigraphs: Vec::new(),
+            next_trigraph: 0,
         }
     }
 
-    #[must_use]
-    pub fn load_bytes(&mut self, file_id: FileId, bytes: &[u8]) -> Option<Utf8DecodeError> {
+    /// Loads the given bytes to be read. A leading UTF-8 byte order mark is skipped.
+    ///
+    /// Bytes that aren't valid UTF-8 are handled according to the given policy.
+    /// Any bytes that are skipped can be retrieved with [take_decode_error](Self::take_decode_error).
+    ///
+    /// If trigraphs are enabled, they are replaced before escaped new lines are joined (so `??/`
+    /// can join lines). The replaced trigraphs can be retrieved with [take_trigraph](Self::take_trigraph).
+    pub fn load_bytes(
+        &mut self,
+        file_id: FileId,
+        bytes: &[u8],
+        on_invalid: InvalidUtf8Policy,
+        trigraphs: bool,
+    ) {
         self.position = 0;
         self.file_id = file_id;
         self.line_chars.clear();
+        self.decode_errors.clear();
+        self.next_decode_error = 0;
+        self.trigraphs.clear();
+        self.next_trigraph = 0;
 
-        let mut byte_pos = 0usize;
+        let mut byte_pos = if bytes.starts_with(b"\xEF\xBB\xBF") {
+            3
+        } else {
+            0
+        };
         while byte_pos < bytes.len() {
-            let char_bytes = match char::decode_utf8(bytes, byte_pos) {
-                Ok(cb) => cb,
-                Err(err) => {
-                    self.line_chars.clear();
-                    return Some(err);
+            let trigraph = if trigraphs {
+                trigraph_replacement(bytes, byte_pos)
+            } else {
+                None
+            };
+            let decoded = match trigraph {
+                Some(replacement) => {
+                    self.trigraphs.push(CharLoc {
+                        char: replacement,
+                        byte: u32::try_from(byte_pos).unwrap_or(u32::MAX),
+                        length: 3,
+                    });
+                    Ok((replacement, 3))
+                },
+                None => char::decode_utf8(bytes, byte_pos).map(|cb| (cb.char(), cb.byte_count())),
+            };
+            let (decoded, byte_count) = match decoded {
+                Ok(decoded) => decoded,
+                Err(err) => match on_invalid {
+                    InvalidUtf8Policy::Error => {
+                        self.decode_errors.push(err);
+                        byte_pos += 1;
+                        continue;
+                    },
+                    InvalidUtf8Policy::Replace => (char::REPLACEMENT_CHARACTER, 1),
+                    InvalidUtf8Policy::Latin1 => (char::from(bytes[byte_pos]), 1),
                 },
             };
 
-            let add_char = match char_bytes.char() {
-                '\\' => match bytes.get(byte_pos + 1) {
-                    Some(b'\r') if bytes.get(byte_pos + 2) == Some(&b'\n') => {
-                        byte_pos += 3;
+            let add_char = match decoded {
+                '\\' => match bytes.get(byte_pos + byte_count) {
+                    Some(&b'\r') if bytes.get(byte_pos + byte_count + 1) == Some(&b'\n') => {
+                        byte_pos += byte_count + 2;
                         continue;
                     },
-                    Some(b'\n') => {
-                        byte_pos += 2;
+                    Some(&b'\n') => {
+                        byte_pos += byte_count + 1;
                         continue;
                     },
                     _ => '\\',
                 },
                 // OPTIMIZATION: Skip all spaces after a new line character (they can't be within strings)
-                // TODO: Trigraph support could be added here (remember ??/ acts like \ )
                 c => c,
             };
 
             self.line_chars.push(CharLoc {
                 char: add_char,
                 byte: u32::try_from(byte_pos).unwrap_or(u32::MAX),
-                length: char_bytes.byte_count() as u32,
+                length: byte_count as u32,
             });
 
-            byte_pos += char_bytes.byte_count();
+            byte_pos += byte_count;
         }
 
         self.last_byte = byte_pos as u32;
+    }
 
-        None
+    /// Returns the next skipped invalid byte if it occurs before the given byte position.
+    pub fn take_decode_error(&mut self, before_byte: u32) -> Option<Utf8DecodeError> {
+        let error = self.decode_errors.get(self.next_decode_error)?;
+        if error.byte_position() >= before_byte as usize {
+            return None;
+        }
+        self.next_decode_error += 1;
+        Some(error.clone())
+    }
+
+    /// Returns the next replaced trigraph (and what it was replaced with) if it occurs
+    /// before the given byte position.
+    pub fn take_trigraph(&mut self, before_byte: u32) -> Option<(char, SourceLoc)> {
+        let trigraph = self.trigraphs.get(self.next_trigraph)?;
+        if trigraph.byte >= before_byte {
+            return None;
+        }
+        self.next_trigraph += 1;
+        let loc = SourceLoc::new(self.file_id, trigraph.byte, trigraph.length as u16);
+        Some((trigraph.char, loc))
     }
 
     pub fn last_byte(&self) -> u32 {
@@ -157,6 +230,26 @@ impl Default for FileReader {
     }
 }
 
+/// Returns the character the trigraph at the given byte position is replaced with
+/// (None if there isn't a trigraph there).
+fn trigraph_replacement(bytes: &[u8], byte_pos: usize) -> Option<char> {
+    if bytes.get(byte_pos..byte_pos + 2)? != b"??" {
+        return None;
+    }
+    Some(match *bytes.get(byte_pos + 2)? {
+        b'=' => '#',
+        b'(' => '[',
+        b'/' => '\\',
+        b')' => ']',
+        b'\'' => '^',
+        b'<' => '{',
+        b'!' => '|',
+        b'>' => '}',
+        b'-' => '~',
+        _ => return None,
+    })
+}
+
 #[derive(Copy, Clone)]
 struct CharLoc {
     char: char,
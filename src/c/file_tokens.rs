@@ -7,10 +7,12 @@ use std::{
 
 use crate::{
     c::{
+        IncludeType,
         LexerError,
         LexerErrorKind,
         Token,
         TokenKind,
+        TokenStats,
     },
     sync::Arc,
     util::{
@@ -23,10 +25,16 @@ use crate::{
 #[derive(Debug)]
 pub struct FileTokens {
     tokens: Vec<Token>,
-    file_references: HashMap<CachedString, Option<FileId>>,
+    /// The files that includes resolved to. Includes of the same path with different
+    /// include types are resolved separately.
+    file_references: HashMap<(IncludeType, CachedString), Option<FileId>>,
     errors: Vec<LexerError>,
+    /// The span of whitespace before each token. This is empty unless the layout was recorded.
+    layout: Vec<SourceLoc>,
     path: Option<Arc<Path>>,
     file_id: FileId,
+    /// Whether the file was found in a system include directory.
+    is_system_header: bool,
 }
 impl FileTokens {
     pub fn new(file_id: FileId, path: Option<Arc<Path>>) -> Self {
@@ -34,8 +42,10 @@ impl FileTokens {
             tokens: Vec::new(),
             file_references: HashMap::new(),
             errors: Vec::new(),
+            layout: Vec::new(),
             file_id,
             path,
+            is_system_header: false,
         }
     }
 
@@ -62,8 +72,13 @@ impl FileTokens {
         index
     }
 
-    pub fn add_reference(&mut self, include_name: &CachedString, file_id: Option<FileId>) {
-        self.file_references.insert(include_name.clone(), file_id);
+    pub fn add_reference(
+        &mut self,
+        inc_type: IncludeType,
+        include_name: &CachedString,
+        file_id: Option<FileId>,
+    ) {
+        self.file_references.insert((inc_type, include_name.clone()), file_id);
     }
 
     pub fn add_error_token(&mut self, error: LexerError) {
@@ -74,6 +89,19 @@ impl FileTokens {
         self.append(error_token);
     }
 
+    /// Records the span of whitespace before the next token that will be appended.
+    pub fn add_layout(&mut self, whitespace: SourceLoc) {
+        self.layout.push(whitespace);
+    }
+    /// Returns the span of whitespace (and comments) between the token at the given index
+    /// and the token before it.
+    ///
+    /// This is None if the layout was not recorded (see
+    /// [CompileSettings::record_layout](crate::c::CompileSettings::record_layout)).
+    pub fn layout_before(&self, index: usize) -> Option<SourceLoc> {
+        self.layout.get(index).copied()
+    }
+
     pub fn file_id(&self) -> FileId {
         self.file_id
     }
@@ -82,8 +110,19 @@ impl FileTokens {
         &self.path
     }
 
-    pub fn get_file_ref(&self, inc_str: &CachedString) -> Option<FileId> {
-        *self.file_references.get(inc_str)?
+    /// Sets whether the file is a system header (see [is_system_header](Self::is_system_header)).
+    pub fn set_system_header(&mut self, is_system_header: bool) {
+        self.is_system_header = is_system_header;
+    }
+    /// Returns whether the file was found in a system include directory.
+    /// Some warnings (like macro redefinitions) aren't reported in system headers.
+    pub fn is_system_header(&self) -> bool {
+        self.is_system_header
+    }
+
+    pub fn get_file_ref(&self, inc_type: IncludeType, inc_str: &CachedString) -> Option<FileId> {
+        // OPTIMIZATION: This clones the string (incrementing its reference count) for the lookup.
+        *self.file_references.get(&(inc_type, inc_str.clone()))?
     }
 
     pub fn errors(&self) -> &Vec<LexerError> {
@@ -93,6 +132,15 @@ impl FileTokens {
     pub fn has_errors(&self) -> bool {
         !self.errors.is_empty()
     }
+    /// Counts the tokens in each category (see [TokenStats]).
+    pub fn stats(&self) -> TokenStats {
+        let mut stats = TokenStats::default();
+        for token in &self.tokens {
+            stats.add(token.kind());
+        }
+        stats.errors = self.errors.len();
+        stats
+    }
 
     pub fn finalize(&mut self) {
         let difference = self.tokens.capacity() - self.tokens.len();
@@ -1,6 +1,10 @@
 // Copyright 2021. remilia-dev
 // This source code is licensed under GPLv3 or any later version.
-use std::path::Path;
+use std::{
+    convert::TryFrom,
+    io::Read,
+    path::Path,
+};
 
 use unicode_normalization::UnicodeNormalization;
 
@@ -8,6 +12,7 @@ use crate::{
     c::{
         token::*,
         CompileEnv,
+        EnvConfig,
         FileReader,
         FileTokens,
         LexerError,
@@ -28,9 +33,11 @@ pub struct Lexer<'a, OnInclude: IncludeCallback> {
     env: &'a CompileEnv,
     include_callback: OnInclude,
     reader: FileReader,
+    read_buffer: Vec<u8>,
     str_builder: StringBuilder,
     norm_buffer: StringBuilder,
     link_stack: Vec<usize>,
+    bracket_stack: Vec<bool>,
 }
 
 impl<'a, OnInclude: IncludeCallback> Lexer<'a, OnInclude> {
@@ -39,9 +46,11 @@ impl<'a, OnInclude: IncludeCallback> Lexer<'a, OnInclude> {
             env,
             include_callback,
             reader: FileReader::new(),
+            read_buffer: Vec::new(),
             str_builder: StringBuilder::with_capacity(30),
             norm_buffer: StringBuilder::with_capacity(30),
             link_stack: Vec::with_capacity(5),
+            bracket_stack: Vec::with_capacity(5),
         }
     }
 
@@ -61,8 +70,14 @@ impl<'a, OnInclude: IncludeCallback> Lexer<'a, OnInclude> {
                 Ok(f) => f,
             };
 
-            if file.metadata().unwrap().len() == 0 {
+            let length = file.metadata().unwrap().len();
+            let max_size = self.env.settings().max_source_size;
+            if length > u64::from(max_size) {
+                let error = LexerErrorKind::SourceTooLarge(max_size);
+                return FileTokens::new_error(file_id, Some(file_path), error);
+            } else if length == 0 {
                 // Can't memory map a 0-byte file.
+                self.env.source_map.insert(file_id, Some(file_path.clone()), &[]);
                 return FileTokens::new_empty(file_id, Some(file_path));
             }
 
@@ -76,18 +91,65 @@ impl<'a, OnInclude: IncludeCallback> Lexer<'a, OnInclude> {
                 Ok(m) => m,
             };
 
-            if let Some(error) = self.reader.load_bytes(file_id, &mmap) {
-                return FileTokens::new_error(file_id, Some(file_path), error);
-            }
+            let settings = self.env.settings();
+            self.reader.load_bytes(
+                file_id,
+                &mmap,
+                settings.on_invalid_utf8,
+                settings.trigraphs,
+            );
+            self.env.source_map.insert(file_id, Some(file_path.clone()), &mmap);
         }
 
         self.lex(file_id, Some(file_path))
     }
 
     pub fn lex_bytes(&mut self, file_id: FileId, bytes: &[u8]) -> FileTokens {
-        if let Some(error) = self.reader.load_bytes(file_id, bytes) {
+        let settings = self.env.settings();
+        if bytes.len() as u64 > u64::from(settings.max_source_size) {
+            let error = LexerErrorKind::SourceTooLarge(settings.max_source_size);
+            return FileTokens::new_error(file_id, None, error);
+        }
+        self.reader.load_bytes(
+            file_id,
+            bytes,
+            settings.on_invalid_utf8,
+            settings.trigraphs,
+        );
+        self.env.source_map.insert(file_id, None, bytes);
+        self.lex(file_id, None)
+    }
+
+    /// Reads the given stream to completion and then lexes its contents.
+    ///
+    /// The bytes are read into a buffer owned by the lexer, so the buffer is reused between calls.
+    /// # Errors
+    /// If reading fails, the returned tokens contain an [Io](LexerErrorKind::Io) error
+    /// (just like [lex_file](Self::lex_file) when a file can't be read).
+    ///
+    /// The stream stops being read once it's longer than the
+    /// [max source size](crate::c::CompileSettings::max_source_size), and the returned tokens
+    /// contain a [SourceTooLarge](LexerErrorKind::SourceTooLarge) error instead.
+    pub fn lex_reader<R: Read>(&mut self, file_id: FileId, reader: R) -> FileTokens {
+        let settings = self.env.settings();
+        let max_size = settings.max_source_size;
+        self.read_buffer.clear();
+        // Reading one byte past the limit is enough to know the stream is too large.
+        let mut reader = reader.take(u64::from(max_size) + 1);
+        if let Err(error) = reader.read_to_end(&mut self.read_buffer) {
+            return FileTokens::new_error(file_id, None, error);
+        }
+        if self.read_buffer.len() as u64 > u64::from(max_size) {
+            let error = LexerErrorKind::SourceTooLarge(max_size);
             return FileTokens::new_error(file_id, None, error);
         }
+        self.reader.load_bytes(
+            file_id,
+            &self.read_buffer,
+            settings.on_invalid_utf8,
+            settings.trigraphs,
+        );
+        self.env.source_map.insert(file_id, None, &self.read_buffer);
         self.lex(file_id, None)
     }
 
@@ -101,7 +163,13 @@ impl<'a, OnInclude: IncludeCallback> Lexer<'a, OnInclude> {
 enum CLexerMode {
     Normal,
     Preprocessor,
-    Include { next: bool },
+    Include {
+        next: bool,
+    },
+    /// After a `__has_include` (or `__has_include_next`) in a preprocessor instruction.
+    HasInclude {
+        next: bool,
+    },
     Message,
 }
 
@@ -110,13 +178,32 @@ struct LexerState<'a, OnInclude: IncludeCallback> {
     at_start_of_line: bool,
     have_skipped_whitespace: bool,
     start_loc: SourceLoc,
+    /// Whether the whitespace before each token should be recorded.
+    record_layout: bool,
+    /// Whether a `/*` within a multiline comment should be reported.
+    warn_nested_comments: bool,
+    /// The byte after the end of the last token (used for recording the layout).
+    layout_start: u32,
     tokens: FileTokens,
     env: &'a CompileEnv,
+    /// The env's config when the file started being lexed (so each token doesn't have to
+    /// fetch it).
+    config: Arc<EnvConfig>,
     include_callback: &'a mut OnInclude,
     reader: &'a mut FileReader,
     str_builder: &'a mut StringBuilder,
     norm_buffer: &'a mut StringBuilder,
     link_stack: &'a mut Vec<usize>,
+    /// The brackets that haven't been closed yet (true for a `[[`).
+    ///
+    /// A `]]` is only lexed as one token when it would close a `[[` (otherwise
+    /// `a[b[0]]` would end with one).
+    bracket_stack: &'a mut Vec<bool>,
+    /// The length of the bracket stack when the current preprocessor instruction started.
+    /// Brackets opened or closed within an instruction don't affect the brackets outside of it.
+    directive_brackets: usize,
+    /// The invalid universal character names of the last identifier. They're added after it.
+    ucn_errors: Vec<LexerError>,
 }
 
 impl<'a, OnInclude: IncludeCallback> LexerState<'a, OnInclude> {
@@ -125,18 +212,31 @@ impl<'a, OnInclude: IncludeCallback> LexerState<'a, OnInclude> {
         path: Option<Arc<Path>>,
         shared_data: &'a mut Lexer<'_, OnInclude>,
     ) -> FileTokens {
+        shared_data.bracket_stack.clear();
+        let is_system_header =
+            matches!(path, Some(ref path) if shared_data.env.is_system_header(path));
+        let mut tokens = FileTokens::new(file_id, path);
+        tokens.set_system_header(is_system_header);
+        let config = shared_data.env.config();
         LexerState {
             mode: CLexerMode::Normal,
             at_start_of_line: true,
             have_skipped_whitespace: false,
             start_loc: SourceLoc::new_first_byte(file_id),
-            tokens: FileTokens::new(file_id, path),
+            record_layout: config.settings().record_layout,
+            warn_nested_comments: config.settings().warn_nested_comments,
+            layout_start: 0,
+            tokens,
             env: shared_data.env,
+            config,
             include_callback: &mut shared_data.include_callback,
             reader: &mut shared_data.reader,
             str_builder: &mut shared_data.str_builder,
             norm_buffer: &mut shared_data.norm_buffer,
             link_stack: &mut shared_data.link_stack,
+            bracket_stack: &mut shared_data.bracket_stack,
+            directive_brackets: 0,
+            ucn_errors: Vec::new(),
         }
         .lex()
     }
@@ -149,10 +249,16 @@ impl<'a, OnInclude: IncludeCallback> LexerState<'a, OnInclude> {
             let (character, loc) = match self.reader.front_loc() {
                 Some((char, loc)) => (char, loc),
                 None => {
+                    self.add_decode_errors(u32::MAX);
+                    self.add_trigraph_warnings(u32::MAX);
+                    self.add_ucn_errors();
                     self.end_line();
                     break;
                 },
             };
+            self.add_decode_errors(loc.byte);
+            self.add_trigraph_warnings(loc.byte);
+            self.add_ucn_errors();
             self.start_loc = loc;
 
             match character {
@@ -160,12 +266,12 @@ impl<'a, OnInclude: IncludeCallback> LexerState<'a, OnInclude> {
                 '/' if self.reader.move_forward_if_next('*') => self.lex_comment(true),
                 '\n' => self.end_line(),
                 c if matches!(self.mode, CLexerMode::Message) => self.lex_message(c),
-                '"' | '<' if matches!(self.mode, CLexerMode::Include { .. }) => {
-                    self.lex_include(character)
-                },
+                '"' | '<' if self.is_include_path_next() => self.lex_include(character),
                 '\'' | '"' => self.lex_string(StringEnc::Default, character == '\''),
+                '\\' if self.is_ucn_next() => self.lex_identifier(character),
                 c if r"~!@#%^&*()[]{}-+=:;\|,.<>/?".contains(c) => self.lex_symbol(c),
                 c if c.is_ascii_digit() => self.lex_number(false, c),
+                c if is_stray_control(c) => self.lex_stray_control(c),
                 c => self.lex_identifier(c),
             };
         }
@@ -181,6 +287,7 @@ impl<'a, OnInclude: IncludeCallback> LexerState<'a, OnInclude> {
         }
 
         let eof_token = Token::new(self.reader.loc(), false, TokenKind::Eof);
+        self.add_layout(eof_token.loc());
         self.tokens.append(eof_token);
 
         self.tokens.finalize();
@@ -195,9 +302,8 @@ impl<'a, OnInclude: IncludeCallback> LexerState<'a, OnInclude> {
         // exploding the line count. For now, each branch also returns a boolean that
         // signals whether a move forward is required (true) or not (false).
         let (move_forward, kind) = match first_char {
-            // TODO: Add double [[ and ]] support for C2X attributes
-            '[' => (true, TokenKind::LBracket { alt: false }),
-            ']' => (true, TokenKind::RBracket { alt: false }),
+            '[' => (true, self.lex_left_bracket()),
+            ']' => (true, self.lex_right_bracket()),
             '(' => (true, TokenKind::LParen),
             ')' => (true, TokenKind::RParen),
             '{' => (true, TokenKind::LBrace { alt: false }),
@@ -311,6 +417,33 @@ impl<'a, OnInclude: IncludeCallback> LexerState<'a, OnInclude> {
         self.add_token(kind);
     }
 
+    /// Lexes a `[` or (if another `[` is directly after it) a `[[`.
+    fn lex_left_bracket(&mut self) -> TokenKind {
+        let is_double = self.reader.move_forward_if_next('[');
+        self.bracket_stack.push(is_double);
+        if is_double {
+            TokenKind::LDoubleBracket
+        } else {
+            TokenKind::LBracket { alt: false }
+        }
+    }
+
+    /// Lexes a `]` or (if another `]` is directly after it and it would end a `[[`) a `]]`.
+    fn lex_right_bracket(&mut self) -> TokenKind {
+        match self.bracket_stack.last() {
+            Some(&true) if self.reader.move_forward_if_next(']') => {
+                self.bracket_stack.pop();
+                TokenKind::RDoubleBracket
+            },
+            // A lone ] within a [[ is a mistake. It's left to the parser to report.
+            Some(&true) => TokenKind::RBracket { alt: false },
+            _ => {
+                self.bracket_stack.pop();
+                TokenKind::RBracket { alt: false }
+            },
+        }
+    }
+
     fn lex_preprocessor(&mut self, alt_start: bool) {
         if self.mode == CLexerMode::Preprocessor || !self.at_start_of_line {
             return self.add_token(TokenKind::Hash { alt: alt_start });
@@ -318,15 +451,21 @@ impl<'a, OnInclude: IncludeCallback> LexerState<'a, OnInclude> {
 
         // The # or %: should have already been passed
         self.reader.skip_most_whitespace();
+        self.directive_brackets = self.bracket_stack.len();
 
         let first_char = match self.reader.front() {
             Some(c) if c != '\n' => c,
             // If the EOF or a new line is next, we just want to return a blank preprocessor instruction.
             _ => return self.add_token(TokenKind::PreBlank),
         };
+        // GNU line markers start with the line number (which is lexed separately).
+        if first_char.is_ascii_digit() && self.config.settings().enable_gnu_extensions {
+            self.mode = CLexerMode::Preprocessor;
+            return self.add_token(TokenKind::PreLineMarker);
+        }
 
         let pre_id = self.read_cached_identifier(first_char);
-        let pre_type = match self.env.get_preprocessor(&pre_id) {
+        let pre_type = match self.config.get_preprocessor(&pre_id) {
             Some(pre_type) => pre_type,
             None => {
                 self.mode = CLexerMode::Preprocessor;
@@ -387,19 +526,47 @@ impl<'a, OnInclude: IncludeCallback> LexerState<'a, OnInclude> {
             self.add_error_token(error);
         }
 
-        if let CLexerMode::Include { next } = self.mode {
-            if next {
-                inc_type = IncludeType::IncludeNext;
-            }
+        match self.mode {
+            CLexerMode::Include { next: true } => inc_type = IncludeType::IncludeNext,
+            CLexerMode::HasInclude { next } => {
+                if next {
+                    inc_type = IncludeType::IncludeNext;
+                }
+                // The rest of the condition is lexed like any other instruction.
+                self.mode = CLexerMode::Preprocessor;
+            },
+            _ => {},
         }
         let path = self.env.cache().get_or_cache(self.str_builder.current());
 
-        let inc_id = (self.include_callback)(inc_type, &path, &self.tokens.path());
-        self.tokens.add_reference(&path, inc_id);
+        let inc_id = self.resolve_include(inc_type, &path);
+        self.tokens.add_reference(inc_type, &path, inc_id);
 
         self.add_token(TokenKind::IncludePath { inc_type, path })
     }
 
+    /// Resolves the include using the include callback. The include is rewritten first
+    /// if there is an [include rewriter](crate::c::CompileSettings::include_rewriter).
+    fn resolve_include(&mut self, inc_type: IncludeType, path: &CachedString) -> Option<FileId> {
+        let (inc_type, path) = self.env.rewrite_include(inc_type, path);
+        (self.include_callback)(inc_type, &path, self.tokens.path())
+    }
+
+    /// Returns whether a `"` or `<` would start an include path (instead of a string or symbol).
+    fn is_include_path_next(&self) -> bool {
+        match self.mode {
+            CLexerMode::Include { .. } => true,
+            // Only the ( directly after a __has_include is followed by a path.
+            CLexerMode::HasInclude { .. } => {
+                matches!(
+                    self.tokens.last().map(Token::kind),
+                    Some(&TokenKind::LParen)
+                )
+            },
+            _ => false,
+        }
+    }
+
     fn lex_message(&mut self, first_char: char) {
         self.str_builder.clear();
         self.str_builder.append_char(first_char);
@@ -455,6 +622,8 @@ impl<'a, OnInclude: IncludeCallback> LexerState<'a, OnInclude> {
         })
     }
 
+    /// Lexes a number (which is only decoded later). Base prefixes (like `0x` and `0b`)
+    /// and exponents (like `e-5`) are kept as part of the number.
     fn lex_number(&mut self, dot_start: bool, first_char: char) {
         self.str_builder.clear();
         if dot_start {
@@ -466,6 +635,8 @@ impl<'a, OnInclude: IncludeCallback> LexerState<'a, OnInclude> {
 
         while let Some(char) = self.reader.move_forward() {
             match char {
+                // In hexadecimal numbers, e and E are digits (so they aren't followed by a sign).
+                'e' | 'E' if self.is_hex_number() => self.str_builder.append_ascii(char as u8),
                 'e' | 'E' | 'p' | 'P' => {
                     self.str_builder.append_ascii(char as u8);
                     if self.reader.move_forward_if_next('-') {
@@ -475,30 +646,77 @@ impl<'a, OnInclude: IncludeCallback> LexerState<'a, OnInclude> {
                     }
                 },
                 '.' | '_' => self.str_builder.append_ascii(char as u8),
+                // Digit separators are left out of the number.
+                '\'' if self.is_digit_separator() => {},
                 c if c.is_whitespace() | c.is_ascii_punctuation() => break,
+                c if is_stray_control(c) => break,
                 c => self.str_builder.append_char(c),
             }
         }
 
+        if self.config.settings().integer_tokens {
+            if let Some(value) = plain_integer_value(self.str_builder.current()) {
+                return self.add_token(TokenKind::Integer(value));
+            }
+        }
         let num_data = self.env.cache().get_or_cache(self.str_builder.current());
         self.add_token(TokenKind::Number(num_data));
     }
 
+    /// Returns whether the number being lexed starts with `0x` (or `0X`).
+    fn is_hex_number(&self) -> bool {
+        let prefix = self.str_builder.current().get(..2);
+        matches!(prefix, Some("0x") | Some("0X"))
+    }
+
+    /// Returns whether the `'` the reader is on is a digit separator (see
+    /// [digit_separators](crate::c::CompileSettings::digit_separators)).
+    ///
+    /// The separator has to be between two digits. Only hex numbers can have a-f as digits
+    /// (so `1'e5` isn't separated).
+    fn is_digit_separator(&self) -> bool {
+        if !self.config.settings().digit_separators {
+            return false;
+        }
+        let is_hex = self.is_hex_number();
+        let is_digit = |c: char| (is_hex && c.is_ascii_hexdigit()) || c.is_ascii_digit();
+        let previous = self.str_builder.current().chars().last();
+        matches!(previous, Some(c) if is_digit(c))
+            && matches!(self.reader.next_char(), Some(c) if is_digit(c))
+    }
+
     fn lex_identifier(&mut self, first_char: char) {
         let cached = self.read_cached_identifier(first_char);
+        // An identifier made of only invalid universal character names is left out entirely
+        // (the names are still reported).
+        if cached.string().is_empty() {
+            return;
+        }
 
-        if let Some(keyword) = self.env.get_keyword(&cached) {
-            return self.add_token(TokenKind::Keyword(keyword));
-        } else if let Some(str_type) = self.env.get_string_prefix(&cached) {
+        if let Some(keyword) = self.config.get_keyword(&cached) {
+            return self.add_token(TokenKind::Keyword(keyword, cached));
+        } else if let Some(str_type) = self.config.get_string_prefix(&cached) {
             let front_char = self.reader.front().unwrap_or('\0');
             if front_char == '"' || front_char == '\'' {
                 return self.lex_string(str_type, front_char == '\'');
             }
         }
 
+        if self.mode == CLexerMode::Preprocessor {
+            match cached.string() {
+                "__has_include" => self.mode = CLexerMode::HasInclude { next: false },
+                "__has_include_next" => self.mode = CLexerMode::HasInclude { next: true },
+                _ => {},
+            }
+        }
         self.add_token(TokenKind::Identifier(cached));
     }
 
+    fn lex_stray_control(&mut self, control: char) {
+        self.reader.move_forward();
+        self.add_error_token(LexerErrorKind::StrayControlCharacter(control));
+    }
+
     fn lex_comment(&mut self, multi_line: bool) {
         loop {
             let char = match self.reader.move_forward() {
@@ -513,6 +731,14 @@ impl<'a, OnInclude: IncludeCallback> LexerState<'a, OnInclude> {
 
             match char {
                 '\n' if !multi_line => return,
+                // The star isn't skipped so /*/ still ends the comment.
+                '/' if multi_line && self.warn_nested_comments => {
+                    if self.reader.next_char() == Some('*') {
+                        let slash = self.reader.loc();
+                        let loc = SourceLoc::new(slash.file_id(), slash.byte, 2);
+                        self.add_error_token_at(LexerErrorKind::NestedComment, loc);
+                    }
+                },
                 '*' if multi_line => {
                     if self.reader.move_forward_if_next('/') {
                         self.reader.move_forward();
@@ -527,17 +753,23 @@ impl<'a, OnInclude: IncludeCallback> LexerState<'a, OnInclude> {
 
     fn read_cached_identifier(&mut self, first_char: char) -> CachedString {
         self.str_builder.clear();
-        self.str_builder.append_char(first_char);
-
-        while let Some(char) = self.reader.move_forward() {
-            match char {
-                c if c.is_whitespace() => break,
-                '_' => {},
-                c if c.is_ascii_punctuation() => break,
-                _ => {},
+        let mut char = first_char;
+        loop {
+            if char == '\\' {
+                self.read_ucn();
+            } else {
+                self.str_builder.append_char(char);
             }
 
-            self.str_builder.append_char(char);
+            char = match self.reader.move_forward() {
+                Some('\\') if self.is_ucn_next() => '\\',
+                Some(c) if c.is_whitespace() => break,
+                Some('_') => '_',
+                Some(c) if c.is_ascii_punctuation() => break,
+                Some(c) if is_stray_control(c) => break,
+                Some(c) => c,
+                None => break,
+            };
         }
 
         let identifier = if self.str_builder.is_ascii() {
@@ -552,9 +784,51 @@ impl<'a, OnInclude: IncludeCallback> LexerState<'a, OnInclude> {
         return self.env.cache().get_or_cache(identifier);
     }
 
+    /// Returns whether the reader is on the backslash of a universal character name
+    /// (`\u` or `\U`).
+    fn is_ucn_next(&self) -> bool {
+        matches!(self.reader.next_char(), Some('u') | Some('U'))
+    }
+
+    /// Reads the universal character name the reader is on and appends its character to
+    /// the string builder. The reader is left on the last character of the name.
+    ///
+    /// Invalid names are left out of the identifier (and reported after it).
+    fn read_ucn(&mut self) {
+        let start = self.reader.loc();
+        let digit_count = if self.reader.move_forward() == Some('u') {
+            4
+        } else {
+            8
+        };
+        let mut code_point = 0u32;
+        let mut digits_read = 0;
+        while digits_read < digit_count {
+            match self.reader.next_char().and_then(|c| c.to_digit(16)) {
+                Some(digit) => code_point = code_point * 16 + digit,
+                None => break,
+            }
+            self.reader.move_forward();
+            digits_read += 1;
+        }
+
+        let kind = if digits_read < digit_count {
+            LexerErrorKind::IncompleteUcn
+        } else {
+            match char::from_u32(code_point) {
+                Some(c) if is_ucn_identifier_char(c) => return self.str_builder.append_char(c),
+                _ => LexerErrorKind::InvalidUcnInIdentifier(code_point),
+            }
+        };
+        let loc = start.through(&self.reader.loc()).unwrap_or(start);
+        self.ucn_errors.push(LexerError { loc, kind });
+    }
+
     fn end_line(&mut self) {
         if self.mode != CLexerMode::Normal {
             self.mode = CLexerMode::Normal;
+            self.bracket_stack.truncate(self.directive_brackets);
+            self.add_layout(self.reader.loc());
             self.tokens.append(Token::new(
                 self.reader.loc(), //
                 false,
@@ -569,18 +843,90 @@ impl<'a, OnInclude: IncludeCallback> LexerState<'a, OnInclude> {
     fn add_token(&mut self, kind: TokenKind) {
         let loc = self.source_loc();
         let token = Token::new(loc, self.have_skipped_whitespace, kind);
+        self.add_layout(loc);
         self.tokens.append(token);
         self.at_start_of_line = false;
         self.have_skipped_whitespace = false;
     }
 
+    /// Adds an error token for each invalid UTF-8 byte that was skipped before the given byte.
+    fn add_decode_errors(&mut self, before_byte: u32) {
+        while let Some(error) = self.reader.take_decode_error(before_byte) {
+            let byte = u32::try_from(error.byte_position()).unwrap_or(u32::MAX);
+            let loc = SourceLoc::new(self.tokens.file_id(), byte, 1);
+            self.add_error_token_at(error.into(), loc);
+        }
+    }
+
+    /// Adds a warning token for each trigraph that was replaced before the given byte.
+    fn add_trigraph_warnings(&mut self, before_byte: u32) {
+        while let Some((replacement, loc)) = self.reader.take_trigraph(before_byte) {
+            self.add_error_token_at(LexerErrorKind::Trigraph(replacement), loc);
+        }
+    }
+
+    /// Adds an error token for each invalid universal character name of the last identifier.
+    fn add_ucn_errors(&mut self) {
+        for error in std::mem::take(&mut self.ucn_errors) {
+            self.add_error_token_at(error.kind, error.loc);
+        }
+    }
+
     fn add_error_token(&mut self, kind: LexerErrorKind) {
-        let error = LexerError { loc: self.source_loc(), kind };
+        self.add_error_token_at(kind, self.source_loc());
+    }
+
+    fn add_error_token_at(&mut self, kind: LexerErrorKind, loc: SourceLoc) {
+        let error = LexerError { loc, kind };
+        self.add_layout(error.loc);
         self.tokens.add_error_token(error);
     }
 
+    /// Records the whitespace between the last token and a token at the given location.
+    fn add_layout(&mut self, token_loc: SourceLoc) {
+        if !self.record_layout {
+            return;
+        }
+        let start = self.layout_start.min(token_loc.byte);
+        let length = u16::try_from(token_loc.byte - start).unwrap_or(u16::MAX);
+        let whitespace = SourceLoc::new(token_loc.file_id(), start, length);
+        self.tokens.add_layout(whitespace);
+        let end = token_loc.byte + u32::from(token_loc.byte_length);
+        self.layout_start = self.layout_start.max(end);
+    }
+
     fn source_loc(&self) -> SourceLoc {
         let end = self.reader.previous_loc();
         self.start_loc.through(&end).unwrap_or(self.start_loc)
     }
 }
+
+/// Returns whether a universal character name can give the character in an identifier.
+///
+/// Like other non-ASCII characters in identifiers, this is more lenient than C's allowed ranges.
+/// Only the basic character set (anything below U+00A0), whitespace, and control characters
+/// are rejected.
+fn is_ucn_identifier_char(c: char) -> bool {
+    u32::from(c) >= 0xA0 && !c.is_whitespace() && !c.is_control()
+}
+
+/// Returns the value of the number if it is a decimal number without a suffix that fits in
+/// an int (see [Integer](TokenKind::Integer)). Numbers with a leading 0 are octal, so they
+/// aren't included (other than 0 itself).
+fn plain_integer_value(digits: &str) -> Option<i32> {
+    let is_decimal = digits.bytes().all(|byte| byte.is_ascii_digit())
+        && (digits == "0" || !digits.starts_with('0'));
+    if is_decimal {
+        digits.parse().ok()
+    } else {
+        None
+    }
+}
+
+/// Returns whether the character is a control character that isn't whitespace.
+///
+/// Whitespace control characters (such as form feeds and vertical tabs) are skipped like
+/// any other whitespace. Other control characters (such as NUL) are reported and ignored.
+fn is_stray_control(c: char) -> bool {
+    c.is_control() && !c.is_whitespace()
+}
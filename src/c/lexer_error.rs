@@ -37,6 +37,10 @@ impl CodedError for LexerError {
     fn message(&self) -> String {
         self.kind.message()
     }
+
+    fn loc(&self) -> Option<SourceLoc> {
+        Some(self.loc)
+    }
 }
 
 enum_with_properties! {
@@ -44,10 +48,10 @@ enum_with_properties! {
     #[derive(Clone, Debug)]
     pub enum LexerErrorKind {
         // == Fatals
-        #[values(Fatal, 800)]
-        Utf8Decode(Utf8DecodeError),
         #[values(Fatal, 801)]
         Io(Arc<std::io::Error>),
+        #[values(Fatal, 802)]
+        SourceTooLarge(u32),
         // == Errors
         #[values(Error, 500)]
         MissingCorrespondingIf(TokenKind),
@@ -59,6 +63,19 @@ enum_with_properties! {
         UnendedInclude(bool),
         #[values(Error, 512)]
         UnendedString(bool),
+        #[values(Error, 520)]
+        StrayControlCharacter(char),
+        #[values(Error, 521)]
+        Utf8Decode(Utf8DecodeError),
+        #[values(Error, 522)]
+        IncompleteUcn,
+        #[values(Error, 523)]
+        InvalidUcnInIdentifier(u32),
+        // == Warnings
+        #[values(Warning, 200)]
+        NestedComment,
+        #[values(Warning, 201)]
+        Trigraph(char),
         // NOTE: Error codes 600-610 and warning codes 300-310 are reserved for literals
     }
 
@@ -78,13 +95,17 @@ enum_with_properties! {
             use LexerErrorKind::*;
             match *self {
                 Utf8Decode(ref error) => format!(
-                    "{}. Only UTF-8 text is supported.",
+                    "{} The byte was ignored.",
                     error
                 ),
                 Io(ref error) => format!(
                     "An IO error occured. {}",
                     error
                 ),
+                SourceTooLarge(max_size) => format!(
+                    "The source is larger than {} bytes (see max_source_size).",
+                    max_size
+                ),
                 MissingCorrespondingIf(ref end_token) => format!(
                     "{} does not have a corresponding #if, #ifdef, #ifndef, or #elif.",
                     end_token
@@ -106,6 +127,25 @@ enum_with_properties! {
                     if is_char { "Character" } else { "String" },
                     if is_char { '\'' } else { '"' }
                 ),
+                NestedComment => {
+                    "/* within a multiline comment. Multiline comments do not nest.".to_owned()
+                },
+                Trigraph(replacement) => format!(
+                    "The trigraph was replaced with {}. Trigraphs are usually unintended.",
+                    replacement
+                ),
+                StrayControlCharacter(c) => format!(
+                    "The control character U+{:04X} can only appear in strings and comments. It was ignored.",
+                    c as u32
+                ),
+                IncompleteUcn => {
+                    "A universal character name needs 4 (for \\u) or 8 (for \\U) hex digits. It was ignored."
+                        .to_owned()
+                },
+                InvalidUcnInIdentifier(code_point) => format!(
+                    "The universal character name U+{:04X} can't appear in an identifier. It was ignored.",
+                    code_point
+                ),
             }
         }
     }
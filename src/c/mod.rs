@@ -1,9 +1,16 @@
 // Copyright 2021. remilia-dev
 // This source code is licensed under GPLv3 or any later version.
-pub use compile_env::CompileEnv;
+pub use compile_env::{
+    CompileEnv,
+    EnvConfig,
+    LazyLexer,
+};
 pub use file_reader::FileReader;
 pub use file_tokens::FileTokens;
-pub use lexer::Lexer;
+pub use lexer::{
+    IncludeCallback,
+    Lexer,
+};
 pub use lexer_error::{
     LexerError,
     LexerErrorKind,
@@ -17,6 +24,12 @@ pub use parser::{
 };
 pub use settings::*;
 pub use token::*;
+pub use token_stats::TokenStats;
+pub use travel_diff::{
+    diff_include_configs,
+    diff_travels,
+    TravelDivergence,
+};
 pub use traveler::*;
 
 pub mod ast;
@@ -30,4 +43,6 @@ mod multi_lexer;
 mod parser;
 mod settings;
 mod token;
+mod token_stats;
+mod travel_diff;
 mod traveler;
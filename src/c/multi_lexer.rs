@@ -9,6 +9,7 @@ use std::{
 use crate::{
     c::{
         CompileEnv,
+        FileTokens,
         IncludeType,
         Lexer,
     },
@@ -38,11 +39,56 @@ impl MultiLexer {
     }
 
     pub fn lex_multi_threaded(&mut self, files: &[Arc<Path>]) {
+        self.lex_multi_threaded_with(files, |_, _| {});
+    }
+
+    /// Lexes the files (and everything they include) like
+    /// [lex_multi_threaded](Self::lex_multi_threaded), calling on_file as each file finishes
+    /// lexing.
+    ///
+    /// The callback is called on the worker thread that lexed the file, after the file's
+    /// tokens have been added to [file_id_to_tokens](CompileEnv::file_id_to_tokens). Files
+    /// are given in the order they finish (not the order they were given or included in).
+    pub fn lex_multi_threaded_with(
+        &mut self,
+        files: &[Arc<Path>],
+        on_file: impl Fn(FileId, &FileTokens) + Sync,
+    ) {
+        let file_ids = self.env.file_id_to_tokens.reserve_many(files.len());
+        let file_ids = file_ids.expect("Ran out of file ids.");
+        self.lex_all(files.iter().cloned().zip(file_ids).collect(), on_file);
+    }
+
+    /// Resolves the include and lexes the file it leads to (and everything that file includes)
+    /// if it wasn't already lexed. The file's tokens are in
+    /// [file_id_to_tokens](CompileEnv::file_id_to_tokens) once this returns.
+    ///
+    /// This can be used as a traveler's
+    /// [include callback](crate::c::Traveler::set_include_callback)
+    /// for includes whose paths come from macros (which the lexer can't resolve).
+    pub fn lex_include(
+        &self,
+        inc_type: IncludeType,
+        filename: &CachedString,
+        curr_file: &Option<Arc<Path>>,
+    ) -> Option<FileId> {
+        match self.find_or_add_include(inc_type, filename, curr_file.as_ref()) {
+            (Some(path), Some(file_id)) => {
+                self.lex_all(vec![(path, file_id)], |_, _| {});
+                Some(file_id)
+            },
+            (_, file_id) => file_id,
+        }
+    }
+
+    /// Lexes the files with their reserved file ids (along with the files they include).
+    fn lex_all(
+        &self,
+        files: Vec<(Arc<Path>, FileId)>,
+        on_file: impl Fn(FileId, &FileTokens) + Sync,
+    ) {
         let mut work_queue = WorkQueue::<(Arc<Path>, FileId)>::new(self.env.threads());
-        work_queue.add_tasks_mut(files.iter().map(|file| {
-            let file_id = self.env.file_id_to_tokens.reserve();
-            (file.clone(), file_id.expect("Ran out of file ids."))
-        }));
+        work_queue.add_tasks_mut(files.into_iter());
 
         let include_callback =
             |inc_type, filename: &CachedString, curr_file: &Option<Arc<Path>>| -> Option<FileId> {
@@ -61,8 +107,9 @@ impl MultiLexer {
                 let mut lexer = tl_lexer
                     .get_or(|| RefCell::new(Lexer::new(&self.env, &include_callback)))
                     .borrow_mut();
-                let tokens = lexer.lex_file(file_id, to_lex);
-                self.env.file_id_to_tokens.set_or_panic(file_id, tokens.into());
+                let tokens = Arc::new(lexer.lex_file(file_id, to_lex));
+                self.env.file_id_to_tokens.set_or_panic(file_id, tokens.clone());
+                on_file(file_id, &tokens);
             });
         }
     }
@@ -3,6 +3,9 @@
 use crate::{
     c::{
         ast::*,
+        Token,
+        TokenKind,
+        TravelIndex,
         TravelerError,
         TravelerErrorKind,
         TravelerState,
@@ -11,7 +14,11 @@ use crate::{
         CodedError,
         Severity,
     },
-    util::enum_with_properties,
+    util::{
+        enum_with_properties,
+        CachedString,
+        SourceLoc,
+    },
 };
 
 #[derive(Clone, Debug)]
@@ -36,6 +43,18 @@ impl CodedError for ParseError {
     fn message(&self) -> String {
         self.kind.message()
     }
+
+    fn loc(&self) -> Option<SourceLoc> {
+        let loc = self.state.loc()?;
+        match self.kind {
+            ParseErrorKind::Number(ref error) => Some(error.loc_in(loc)),
+            _ => Some(loc),
+        }
+    }
+
+    fn notes(&self) -> Vec<(SourceLoc, String)> {
+        self.kind.notes()
+    }
 }
 
 impl From<TravelerError> for ParseError {
@@ -60,6 +79,120 @@ enum_with_properties! {
         Unimplemented(&'static str),
         #[values(Internal, 901)]
         Unreachable(&'static str),
+        // == Errors
+        #[values(Error, 500)]
+        AttributeExpectedBrackets(Token),
+        #[values(Error, 501)]
+        AttributeExpectedId(Token),
+        #[values(Error, 502)]
+        AttributeExpectedSeparator(Token),
+        #[values(Error, 503)]
+        AttributeUnendedArgs,
+        #[values(Error, 504)]
+        GnuAttributeExpectedParens(Token),
+        #[values(Error, 505)]
+        GnuAttributeExpectedSeparator(Token),
+        #[values(Error, 506)]
+        GnuAttributeArgsExpectedSeparator(Token),
+        #[values(Error, 510)]
+        GenericExpectedLParen(Token),
+        #[values(Error, 511)]
+        GenericExpectedSeparator(Token),
+        #[values(Error, 512)]
+        GenericExpectedColon(Token),
+        #[values(Error, 513)]
+        EmptyGenericAssociations,
+        #[values(Error, 514)]
+        GenericTrailingComma,
+        #[values(Error, 515)]
+        GenericMultipleDefault(Token),
+        #[values(Error, 520)]
+        LabelAddressExpectedId(Token),
+        #[values(Error, 521)]
+        DuplicateLabel(CachedString),
+        #[values(Error, 522)]
+        UndeclaredLabel(CachedString),
+        #[values(Error, 523)]
+        LabelAtEndOfBlock,
+        #[values(Error, 530)]
+        InferredTypeWithoutInitializer(Token),
+        #[values(Error, 531)]
+        RestrictOnNonPointer(TravelIndex),
+        #[values(Error, 532)]
+        EnumUnderlyingNotInteger(TravelIndex),
+        #[values(Error, 533)]
+        AmbiguousAnonymousMember(CachedString),
+        #[values(Error, 534)]
+        TypeofExpectedLParen(Token),
+        #[values(Error, 535)]
+        TypeofExpectedRParen(Token),
+        #[values(Error, 536)]
+        AlignasExpectedLParen(Token),
+        #[values(Error, 537)]
+        AlignasExpectedRParen(Token),
+        #[values(Error, 538)]
+        EnumValueOutOfRange(TravelIndex, CachedString, i128),
+        #[values(Error, 540)]
+        StaticAssertExpectedLParen(Token),
+        #[values(Error, 541)]
+        StaticAssertExpectedRParen(Token),
+        #[values(Error, 542)]
+        StaticAssertExpectedSemicolon(Token),
+        #[values(Error, 543)]
+        StaticAssertExpectedComma(Token),
+        #[values(Error, 544)]
+        StaticAssertExpectedMessage(Token),
+        #[values(Error, 550)]
+        KRMixedParameters,
+        #[values(Error, 551)]
+        KRDeclaredNonParameter(CachedString),
+        #[values(Error, 560)]
+        AsmExpectedLParen(Token),
+        #[values(Error, 561)]
+        AsmExpectedTemplate(Token),
+        #[values(Error, 562)]
+        AsmExpectedRParen(Token),
+        #[values(Error, 563)]
+        AsmOperandExpectedConstraint(Token),
+        #[values(Error, 564)]
+        AsmOperandExpectedExpr(Token),
+        #[values(Error, 565)]
+        AsmExpectedClobber(Token),
+        #[values(Error, 566)]
+        AsmOperandExpectedName(Token),
+        #[values(Error, 570)]
+        GnuExtension(&'static str),
+        #[values(Error, 571)]
+        StmtExprExpectedRParen(Token),
+        #[values(Error, 580)]
+        ArrayExpectedRBracket(Token),
+        #[values(Error, 581)]
+        CallTrailingComma,
+        #[values(Error, 582)]
+        ParamsTrailingComma,
+        #[values(Error, 583)]
+        DesignatorExpectedEqual(Token),
+        #[values(Error, 584)]
+        TypeNameHasName(CachedString),
+        #[values(Error, 585)]
+        TypeNameExpectedEnd(Token),
+        #[values(Error, 586)]
+        CallExpectedArg(Token),
+        #[values(Error, 587)]
+        CallExpectedRParen(Token),
+        #[values(Error, 588)]
+        DeclExpectedSemicolon(Token),
+        #[values(Error, 589)]
+        DeclaratorUnexpectedToken(Token),
+        // == Warnings
+        #[values(Warning, 200)]
+        DuplicateQualifier(Token),
+        #[values(Warning, 201)]
+        StaticAssertWithoutMessage,
+        #[values(Warning, 202)]
+        AmbiguousAssignment(Token),
+        #[values(Warning, 203)]
+        EnumUnderlyingTypeBeforeC23,
     }
 
     impl CodedError for ParseErrorKind {
@@ -78,6 +211,16 @@ enum_with_properties! {
             }
         }
 
+        fn notes(&self) -> Vec<(SourceLoc, String)> {
+            match *self {
+                Self::Travel(ref error) => error.notes(),
+                Self::Number(ref error) => error.notes(),
+                _ => Vec::new(),
+            }
+        }
+
+        // The lines are just a result of the number of errors.
+        #[allow(clippy::too_many_lines)]
         fn message(&self) -> String {
             use ParseErrorKind::*;
             match *self {
@@ -91,6 +234,221 @@ enum_with_properties! {
                     "Unreachable condition: {}. This is an internal error.",
                     thing
                 ),
+                AttributeExpectedBrackets(ref token) => format!(
+                    "An attribute specifier should start with [[ and end with ]] (found {}).",
+                    token
+                ),
+                AttributeExpectedId(ref token) => format!(
+                    "Expected the identifier of an attribute (not a {}).",
+                    token
+                ),
+                AttributeExpectedSeparator(ref token) => match *token.kind() {
+                    TokenKind::LParen => {
+                        "An attribute can only have one argument clause.".to_owned()
+                    },
+                    _ => format!(
+                        "Expected a , between attributes or a ]] to end them (not a {}).",
+                        token
+                    ),
+                },
+                AttributeUnendedArgs => {
+                    "An attribute's arguments were not ended with a corresponding ).".to_owned()
+                },
+                GnuAttributeExpectedParens(ref token) => format!(
+                    "__attribute__ should be followed by (( and end with )) (found {}).",
+                    token
+                ),
+                GnuAttributeExpectedSeparator(ref token) => format!(
+                    "Expected a , between attributes or a )) to end them (not a {}).",
+                    token
+                ),
+                GnuAttributeArgsExpectedSeparator(ref token) => format!(
+                    "Expected a , between an attribute's arguments or a ) to end them (not a {}).",
+                    token
+                ),
+                GenericExpectedLParen(ref token) => format!(
+                    "_Generic should be followed by a ( (not a {}).",
+                    token
+                ),
+                GenericExpectedSeparator(ref token) => format!(
+                    "Expected a , before the next _Generic association or a ) to end them (not a {}).",
+                    token
+                ),
+                GenericExpectedColon(ref token) => format!(
+                    "A _Generic association's type should be followed by a : (not a {}).",
+                    token
+                ),
+                EmptyGenericAssociations => {
+                    "_Generic requires at least one association after its controlling expression."
+                        .to_owned()
+                },
+                GenericTrailingComma => {
+                    "_Generic's associations should not end with a trailing comma.".to_owned()
+                },
+                GenericMultipleDefault(..) => {
+                    "_Generic can only have one default association.".to_owned()
+                },
+                LabelAddressExpectedId(ref token) => format!(
+                    "&& should be followed by the identifier of a label to take the address of (not a {}).",
+                    token
+                ),
+                DuplicateLabel(ref label) => format!(
+                    "The label {} was already declared in this function.",
+                    label
+                ),
+                UndeclaredLabel(ref label) => format!(
+                    "The label {} is never declared in this function.",
+                    label
+                ),
+                LabelAtEndOfBlock => "A label at the end of a block is a C23 feature.".to_owned(),
+                InferredTypeWithoutInitializer(ref token) => format!(
+                    "A declaration with an inferred type (auto or __auto_type) needs an initializer (found {} instead).",
+                    token
+                ),
+                RestrictOnNonPointer(..) => {
+                    "restrict can only qualify pointer types.".to_owned()
+                },
+                EnumUnderlyingNotInteger(..) => {
+                    "An enum's underlying type must be an integer type.".to_owned()
+                },
+                AmbiguousAnonymousMember(ref name) => format!(
+                    "The field {} is declared more than once through anonymous members.",
+                    name
+                ),
+                TypeofExpectedLParen(ref token) => format!(
+                    "typeof should be followed by a ( (not a {}).",
+                    token
+                ),
+                TypeofExpectedRParen(ref token) => format!(
+                    "Expected a ) to end the typeof (not a {}).",
+                    token
+                ),
+                AlignasExpectedLParen(ref token) => format!(
+                    "_Alignas should be followed by a ( (not a {}).",
+                    token
+                ),
+                AlignasExpectedRParen(ref token) => format!(
+                    "Expected a ) to end the _Alignas (not a {}).",
+                    token
+                ),
+                EnumValueOutOfRange(_, ref name, value) => format!(
+                    "The value of {} ({}) doesn't fit in the enum's type.",
+                    name, value
+                ),
+                StaticAssertExpectedLParen(ref token) => format!(
+                    "_Static_assert should be followed by a ( (not a {}).",
+                    token
+                ),
+                StaticAssertExpectedRParen(ref token) => format!(
+                    "Expected a ) to end the _Static_assert (not a {}).",
+                    token
+                ),
+                StaticAssertExpectedSemicolon(ref token) => format!(
+                    "Expected a ; after the _Static_assert (not a {}).",
+                    token
+                ),
+                StaticAssertExpectedComma(ref token) => format!(
+                    "Expected a , between the _Static_assert's condition and message (found {}).",
+                    token
+                ),
+                StaticAssertExpectedMessage(ref token) => format!(
+                    "A _Static_assert's message should be a string literal (not a {}).",
+                    token
+                ),
+                KRMixedParameters => {
+                    "A function's parameters cannot mix K&R identifiers with typed parameters."
+                        .to_owned()
+                },
+                KRDeclaredNonParameter(ref name) => format!(
+                    "{} was declared before the function's body but it isn't one of its parameters.",
+                    name
+                ),
+                AsmExpectedLParen(ref token) => format!(
+                    "asm should be followed by a ( (not a {}).",
+                    token
+                ),
+                AsmExpectedTemplate(ref token) => format!(
+                    "An asm statement's template should be a string literal (not a {}).",
+                    token
+                ),
+                AsmExpectedRParen(ref token) => format!(
+                    "Expected a : before the next asm operands or a ) to end the asm statement (not a {}).",
+                    token
+                ),
+                AsmOperandExpectedConstraint(ref token) => format!(
+                    "An asm operand should start with a string literal constraint (not a {}).",
+                    token
+                ),
+                AsmOperandExpectedExpr(ref token) => format!(
+                    "An asm operand's constraint should be followed by a parenthesized expression (not a {}).",
+                    token
+                ),
+                AsmExpectedClobber(ref token) => format!(
+                    "An asm clobber should be a string literal (not a {}).",
+                    token
+                ),
+                AsmOperandExpectedName(ref token) => format!(
+                    "An asm operand's symbolic name should be an identifier between [ and ] (found {}).",
+                    token
+                ),
+                GnuExtension(feature) => format!(
+                    "{} are a GNU extension (which are disabled).",
+                    feature
+                ),
+                StmtExprExpectedRParen(ref token) => format!(
+                    "Expected a ) after the }} of a statement expression (not a {}).",
+                    token
+                ),
+                ArrayExpectedRBracket(ref token) => format!(
+                    "Expected a ] to end the array (not a {}).",
+                    token
+                ),
+                CallTrailingComma => {
+                    "A function call's arguments should not end with a trailing comma.".to_owned()
+                },
+                ParamsTrailingComma => {
+                    "A function's parameters should not end with a trailing comma.".to_owned()
+                },
+                DesignatorExpectedEqual(ref token) => format!(
+                    "A designator should be followed by an = and the value to initialize (not a {}).",
+                    token
+                ),
+                TypeNameHasName(ref name) => format!(
+                    "A type name should not declare a name (found {}).",
+                    name
+                ),
+                TypeNameExpectedEnd(ref token) => format!(
+                    "Expected the type name to end (not a {}).",
+                    token
+                ),
+                CallExpectedArg(ref token) => format!(
+                    "Expected an argument before the {} in the function call.",
+                    token
+                ),
+                CallExpectedRParen(ref token) => format!(
+                    "Expected a , or a ) to end the function call's arguments (not a {}).",
+                    token
+                ),
+                DeclExpectedSemicolon(ref token) => {
+                    format!("Expected a ; to end the declaration (not a {}).", token)
+                },
+                DeclaratorUnexpectedToken(ref token) => {
+                    format!("Unexpected {} in the declarator.", token)
+                },
+                DuplicateQualifier(ref token) => format!(
+                    "{} was specified more than once for the same type.",
+                    token
+                ),
+                StaticAssertWithoutMessage => {
+                    "_Static_assert without a message is a C23 feature.".to_owned()
+                },
+                AmbiguousAssignment(ref token) => format!(
+                    "={0} assigns a value with a unary {0}. Did you mean {0}= instead?",
+                    token
+                ),
+                EnumUnderlyingTypeBeforeC23 => {
+                    "An enum with an explicit underlying type is a C23 feature.".to_owned()
+                },
             }
         }
     }
@@ -1,6 +1,12 @@
 // Copyright 2021. remilia-dev
 // This source code is licensed under GPLv3 or any later version.
-use std::cell::RefCell;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    convert::TryFrom,
+    ops::RangeInclusive,
+    path::Path,
+};
 
 use smallvec::SmallVec;
 
@@ -9,7 +15,10 @@ use crate::{
         ast::*,
         CompileEnv,
         FileTokens,
+        IncludeCallback,
         Keyword,
+        LangVersion,
+        Lexer,
         ParseError,
         ParseErrorKind,
         StringEnc,
@@ -17,6 +26,8 @@ use crate::{
         TravelIndex,
         Traveler,
         TravelerError,
+        TravelerErrorKind,
+        TravelerState,
     },
     error::{
         ErrorReceiver,
@@ -24,7 +35,10 @@ use crate::{
         Unwind,
     },
     sync::Arc,
-    util::Conversions,
+    util::{
+        CachedString,
+        Conversions,
+    },
 };
 
 type Error = ParseErrorKind;
@@ -46,15 +60,55 @@ impl<'a, E: ErrorReceiver<ParseError>> Parser<'a, E> {
         }
     }
 
+    /// Sets the callback that resolves includes whose paths come from macros (see
+    /// [Traveler::set_include_callback]).
+    pub fn set_include_callback(&mut self, callback: impl 'a + IncludeCallback) {
+        self.traveler.set_include_callback(callback);
+    }
+
     pub fn parse(&mut self, tokens: Arc<FileTokens>) -> MayUnwind<SourceFile> {
         ParseState::create_and_parse(self, tokens)
     }
+
+    /// Parses the source as a type name (the type of a cast or sizeof, like `char (*)(void)`).
+    ///
+    /// A type name can't have a name in its declarator, so a declaration like `int x` is
+    /// reported as an error (as are any tokens after the type name). The source is parsed on
+    /// its own, so no typedefs are known and any struct, union, or enum in the type refers to
+    /// a declaration in a file that is not returned.
+    ///
+    /// Every call's source is added to [file_id_to_tokens](CompileEnv::file_id_to_tokens) with
+    /// its own file id, so errors in it can be resolved like any other file's. If there are no
+    /// file ids left, a fatal [OutOfFileIds](TravelerErrorKind::OutOfFileIds) is reported.
+    pub fn parse_type_name(&mut self, source: &str) -> MayUnwind<Type> {
+        let env = self.traveler.env();
+        let file_id = match env.file_id_to_tokens.reserve() {
+            Some(file_id) => file_id,
+            None => {
+                let state = self.traveler.save_state();
+                let kind = Error::Travel(TravelerErrorKind::OutOfFileIds);
+                self.errors.report(ParseError { state, kind })?;
+                return Err(Unwind::Fatal);
+            },
+        };
+        let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+        let mut lexer = Lexer::new(env, callback);
+        let tokens = Arc::new(lexer.lex_bytes(file_id, source.as_bytes()));
+        env.file_id_to_tokens.set_or_panic(file_id, tokens.clone());
+
+        ParseState::create_and_parse_type_name(self, tokens)
+    }
 }
 
 struct ParseState<'a, 'b, E: 'b + ErrorReceiver<ParseError>> {
     traveler: &'a mut Traveler<'b, Box<dyn 'b + FnMut(TravelerError) -> bool>>,
     errors: &'a mut Arc<RefCell<E>>,
     file: SourceFile,
+    /// The gotos (and label addresses) whose labels still need to be checked once their function
+    /// is parsed (the ones to labels that were already declared aren't included).
+    ///
+    /// Each goto has the scope that manages its labels and the state at its label.
+    gotos: Vec<(ScopeId, CachedString, TravelerState)>,
 }
 
 impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
@@ -66,19 +120,51 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
             traveler: &mut parser.traveler,
             errors: &mut parser.errors,
             file: SourceFile::new(tokens.file_id(), tokens.path().clone()),
+            gotos: Vec::new(),
         };
         parser.traveler.load_start(tokens)?;
         parser.file()?;
         Ok(parser.file)
     }
 
+    fn create_and_parse_type_name(
+        parser: &'a mut Parser<'b, E>,
+        tokens: Arc<FileTokens>,
+    ) -> MayUnwind<Type> {
+        let mut parser = Self {
+            traveler: &mut parser.traveler,
+            errors: &mut parser.errors,
+            file: SourceFile::new(tokens.file_id(), tokens.path().clone()),
+            gotos: Vec::new(),
+        };
+        parser.traveler.load_start(tokens)?;
+        parser.standalone_type_name()
+    }
+
+    /// Parses a type name that should be followed by the end of the file.
+    fn standalone_type_name(&mut self) -> MayUnwind<Type> {
+        let scope_id = ScopeId::ROOT;
+        let mut type_ = self.type_base(scope_id, true)?;
+        type_ = self.type_name(type_, scope_id)?;
+
+        if let Some(ref name) = type_.name {
+            self.report_error(Error::TypeNameHasName(name.clone()))?;
+            return Err(Unwind::Block);
+        }
+        if !matches!(*self.traveler.head().kind(), TokenKind::Eof) {
+            let token = self.traveler.head().clone();
+            self.report_error(Error::TypeNameExpectedEnd(token))?;
+            return Err(Unwind::Block);
+        }
+        Ok(type_)
+    }
+
     fn file(&mut self) -> MayUnwind<()> {
-        let scope_id = 0.into();
+        let scope_id = ScopeId::ROOT;
         loop {
             let stmt = match *self.traveler.head().kind() {
-                TokenKind::Keyword(keyword) if keyword == Keyword::StaticAssert => {
-                    // TODO: Parse static assert
-                    todo!("_Static_assert parsing")
+                TokenKind::Keyword(Keyword::StaticAssert, ..) => {
+                    self.static_assert_stmt(scope_id)?.into()
                 },
                 TokenKind::Semicolon => {
                     let stmt = Stmt::Empty(self.traveler.index());
@@ -87,7 +173,7 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
                     stmt
                 },
                 TokenKind::Eof => break,
-                _ => self.decl_stmt(scope_id)?.into(),
+                _ => self.decl_stmt(scope_id, Vec::new())?.into(),
             };
 
             self.file.get_scope_mut(scope_id).stmts.push(stmt);
@@ -95,12 +181,21 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
         Ok(())
     }
 
-    fn decls(&mut self, scope_id: ScopeId, local: bool) -> MayUnwind<SmallVec<[Decl; 1]>> {
+    /// Parses the declarations of a declaration. Any attribute specifiers (`[[...]]`)
+    /// at the head are added to the given attributes (which apply to every declaration).
+    fn decls(
+        &mut self,
+        scope_id: ScopeId,
+        local: bool,
+        mut leading_attributes: Vec<Attribute>,
+    ) -> MayUnwind<SmallVec<[Decl; 1]>> {
         let mut decls = SmallVec::new();
 
+        leading_attributes.extend(self.attributes()?);
         let mut type_ = self.type_base(scope_id, local)?;
         loop {
             type_ = self.type_name(type_, scope_id)?;
+            let attributes = self.gnu_attribute_lists(scope_id)?;
 
             let postfix = match *self.traveler.head().kind() {
                 TokenKind::Colon => {
@@ -124,14 +219,29 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
                 _ => DeclPostfix::None,
             };
 
+            if type_.is_inferred() && !matches!(postfix, DeclPostfix::Initializer(..)) {
+                let token = self.traveler.head().clone();
+                self.report_error(Error::InferredTypeWithoutInitializer(token))?;
+            }
+
             if !matches!(*self.traveler.head().kind(), TokenKind::Comma) {
-                decls.push(Decl { type_, postfix });
+                decls.push(Decl {
+                    type_,
+                    postfix,
+                    leading_attributes,
+                    attributes,
+                });
                 break;
             } else {
                 let mut decl_type = type_.clone_base();
                 std::mem::swap(&mut type_, &mut decl_type);
 
-                decls.push(Decl { type_: decl_type, postfix });
+                decls.push(Decl {
+                    type_: decl_type,
+                    postfix,
+                    leading_attributes: leading_attributes.clone(),
+                    attributes,
+                });
                 self.traveler.move_forward()?;
             }
         }
@@ -150,20 +260,23 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
         loop {
             let index = self.traveler.index();
             match *self.traveler.head().kind() {
-                TokenKind::Keyword(keyword) => match keyword {
-                    keyword if keyword.is_type_modifier() => type_.add_modifier(keyword, index),
+                TokenKind::Keyword(keyword, ..) => match keyword {
+                    keyword if keyword.is_type_modifier() => {
+                        self.add_modifier(&mut type_, keyword)?;
+                    },
                     Keyword::Alignas => {
-                        if let TokenKind::LParen = *self.traveler.move_forward()?.kind() {
-                            let alignment = self.expr(scope_id, true)?;
-                            type_.segments.push(ModifierSegment::Alignas(alignment).into());
-                        } else {
-                            // TODO: Error
-                            todo!("AlignAs expects (")
-                        }
+                        let segment = self.alignas_segment(scope_id)?;
+                        type_.segments.push(segment.into());
+                        continue;
                     },
                     keyword if keyword.is_base_type() => {
                         type_.try_set_base_type(keyword, index);
                     },
+                    keyword if keyword.is_typeof() => {
+                        type_.root = self.typeof_type(scope_id, keyword)?;
+                        type_.root_index = Some(index);
+                        continue;
+                    },
                     keyword if keyword.is_type_tag() => {
                         let type_index = self.type_decl(scope_id)?;
                         type_.root = TypeRoot::Type(type_index);
@@ -176,12 +289,19 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
                             todo!("Multiple storage classes")
                         }
                     },
+                    Keyword::Attribute => {
+                        let list = self.gnu_attribute_list(scope_id)?;
+                        type_.attributes.push(list);
+                        continue;
+                    },
                     _ => break,
                 },
                 TokenKind::Identifier(ref id) => {
                     if let Some(decl_index) = self.file.find_decl_index(scope_id, id) {
                         let decl = self.file.get_decl(decl_index);
-                        if !(decl.is_typedef() || type_.root_index.is_some()) {
+                        // Once the type has a root, the identifier is the name being declared
+                        // (even if it shadows a typedef or variable from an outer scope).
+                        if !decl.is_typedef() || type_.root_index.is_some() {
                             break;
                         }
 
@@ -189,6 +309,11 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
                         type_.root = TypeRoot::Typedef(decl_index);
                         self.traveler.move_forward()?;
                         continue;
+                    } else if self.is_nullptr_t(id) && type_.root_index.is_none() {
+                        type_.root_index = Some(self.traveler.index());
+                        type_.root = TypeRoot::NullptrT;
+                        self.traveler.move_forward()?;
+                        continue;
                     }
                     break;
                 },
@@ -197,21 +322,41 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
 
             self.traveler.move_forward()?;
         }
+        // Since C23, auto without any type specifiers infers the type instead of being an int.
+        let explicit_auto = !type_.storage.is_implicit() && type_.storage.kind == StorageKind::Auto;
+        if explicit_auto
+            && type_.is_implicit()
+            && self.traveler.settings().version >= LangVersion::C23
+        {
+            type_.root = TypeRoot::Inferred;
+        }
         type_.base_segments = type_.segments.len();
         Ok(type_)
     }
 
+    fn add_modifier(&mut self, type_: &mut Type, keyword: Keyword) -> MayUnwind<()> {
+        if !type_.add_modifier(keyword, self.traveler.index()) {
+            let token = self.traveler.head().clone();
+            self.report_error(Error::DuplicateQualifier(token))?;
+        }
+        Ok(())
+    }
+
     fn type_name(&mut self, mut type_: Type, scope_id: ScopeId) -> MayUnwind<Type> {
         let mut insert_points = Vec::new();
         loop {
             match *self.traveler.head().kind() {
-                TokenKind::Keyword(keyword) => match keyword {
-                    _ if keyword.is_type_modifier() => {
-                        type_.add_modifier(keyword, self.traveler.index())
+                TokenKind::Keyword(keyword, ..) => match keyword {
+                    _ if keyword.is_type_modifier() => self.add_modifier(&mut type_, keyword)?,
+                    Keyword::Attribute => {
+                        let list = self.gnu_attribute_list(scope_id)?;
+                        type_.attributes.push(list);
+                        continue;
                     },
                     _ => {
-                        // TODO: Error
-                        todo!()
+                        let token = self.traveler.head().clone();
+                        self.report_error(Error::DeclaratorUnexpectedToken(token))?;
+                        return Err(Unwind::Block);
                     },
                 },
                 TokenKind::LParen => {
@@ -249,11 +394,14 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
                 },
                 TokenKind::RParen
                 | TokenKind::LBracket { .. }
+                | TokenKind::Colon
                 | TokenKind::Comma
-                | TokenKind::Semicolon => break,
+                | TokenKind::Semicolon
+                | TokenKind::Eof => break,
                 _ => {
-                    // TODO: Error
-                    todo!("{:?}", self.traveler.head());
+                    let token = self.traveler.head().clone();
+                    self.report_error(Error::DeclaratorUnexpectedToken(token))?;
+                    return Err(Unwind::Block);
                 },
             }
 
@@ -288,16 +436,114 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
             self.traveler.move_forward()?;
         }
 
+        self.check_restrict(&type_)?;
         Ok(type_)
     }
 
+    /// Reports every restrict qualifier in the type that doesn't qualify a pointer.
+    fn check_restrict(&mut self, type_: &Type) -> MayUnwind<()> {
+        // Modifiers qualify the segment before them (or the root if there isn't one).
+        let mut qualifies_pointer = self.is_root_pointer(type_);
+        for segment in type_.segments.iter() {
+            match *segment {
+                TypeSegment::Modifier(ModifierSegment::Restrict(index)) if !qualifies_pointer => {
+                    self.report_error(Error::RestrictOnNonPointer(index))?;
+                },
+                TypeSegment::Modifier(..) => {},
+                TypeSegment::Pointer(..) => qualifies_pointer = true,
+                _ => qualifies_pointer = false,
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns whether the root of the type is a typedef of a pointer type.
+    fn is_root_pointer(&self, type_: &Type) -> bool {
+        let root_type = match type_.root {
+            TypeRoot::Typedef(decl_index) => &self.file.get_decl(decl_index).type_,
+            TypeRoot::TypeofType(ref inner) => inner,
+            _ => return false,
+        };
+        let outermost = root_type
+            .segments
+            .iter()
+            .rev()
+            .find(|segment| !matches!(**segment, TypeSegment::Modifier(..)));
+        match outermost {
+            Some(&TypeSegment::Pointer(..)) => true,
+            Some(..) => false,
+            None => self.is_root_pointer(root_type),
+        }
+    }
+
+    /// Parses a typeof specifier (`typeof(...)`, `typeof_unqual(...)`, or `__typeof__(...)`).
+    /// The head should be the typeof keyword.
+    fn typeof_type(&mut self, scope_id: ScopeId, keyword: Keyword) -> MayUnwind<TypeRoot> {
+        if !matches!(*self.traveler.move_forward()?.kind(), TokenKind::LParen) {
+            let token = self.traveler.head().clone();
+            self.report_error(Error::TypeofExpectedLParen(token))?;
+            return Err(Unwind::Block);
+        }
+        self.traveler.move_forward()?;
+
+        let unqual = keyword == Keyword::TypeofUnqual;
+        let root = if self.is_head_a_type(scope_id) {
+            let mut inner = self.type_base(scope_id, true)?;
+            inner = self.type_name(inner, scope_id)?;
+            if unqual {
+                inner.remove_qualifiers();
+            }
+            TypeRoot::TypeofType(Box::new(inner))
+        } else {
+            let expr = self.expr(scope_id, true)?;
+            if unqual {
+                TypeRoot::TypeofUnqualExpr(expr)
+            } else {
+                TypeRoot::TypeofExpr(expr)
+            }
+        };
+
+        if !matches!(*self.traveler.head().kind(), TokenKind::RParen) {
+            let token = self.traveler.head().clone();
+            self.report_error(Error::TypeofExpectedRParen(token))?;
+            return Err(Unwind::Block);
+        }
+        self.traveler.move_forward()?;
+        Ok(root)
+    }
+
+    fn alignas_segment(&mut self, scope_id: ScopeId) -> MayUnwind<ModifierSegment> {
+        if !matches!(*self.traveler.move_forward()?.kind(), TokenKind::LParen) {
+            let token = self.traveler.head().clone();
+            self.report_error(Error::AlignasExpectedLParen(token))?;
+            return Err(Unwind::Block);
+        }
+        self.traveler.move_forward()?;
+
+        let segment = if self.is_head_a_type(scope_id) {
+            let mut aligned_as = self.type_base(scope_id, true)?;
+            aligned_as = self.type_name(aligned_as, scope_id)?;
+            ModifierSegment::AlignasType(Box::new(aligned_as))
+        } else {
+            ModifierSegment::AlignasExpr(self.expr(scope_id, false)?)
+        };
+
+        if !matches!(*self.traveler.head().kind(), TokenKind::RParen) {
+            let token = self.traveler.head().clone();
+            self.report_error(Error::AlignasExpectedRParen(token))?;
+            return Err(Unwind::Block);
+        }
+        self.traveler.move_forward()?;
+        Ok(segment)
+    }
+
     fn type_array(&mut self, scope_id: ScopeId) -> MayUnwind<ArraySegment> {
         let start_index = self.traveler.index();
 
         let mut const_ = None;
         let mut restrict = None;
         let mut static_ = None;
-        while let TokenKind::Keyword(keyword) = *self.traveler.move_forward()?.kind() {
+        while let TokenKind::Keyword(keyword, ..) = *self.traveler.move_forward()?.kind() {
             match keyword {
                 Keyword::Const if const_.is_none() => {
                     const_ = Some(self.traveler.index());
@@ -332,8 +578,9 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
         if matches!(*self.traveler.head().kind(), TokenKind::RBracket { .. }) {
             self.traveler.move_forward()?;
         } else {
-            // TODO: Report error
-            todo!()
+            // The ] was likely forgotten, so the array is treated as ended.
+            let token = self.traveler.head().clone();
+            self.report_error(Error::ArrayExpectedRBracket(token))?;
         }
 
         let range = start_index..self.traveler.index();
@@ -351,20 +598,19 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
         parent_id: ScopeId,
         start_index: TravelIndex,
     ) -> MayUnwind<FuncSegment> {
-        // TODO: Parse K&R functions
-
         // A function type can appear in a function's arguments as a func-pointer.
         let parent_id = self.file.nearest_non_func_decl_scope(parent_id);
         // NOTE: This function should be called after the (
         let scope_id = self.file.new_scope(parent_id, ScopeKind::FuncDecl);
         let mut decls = Vec::new();
         let mut vararg_index = None;
+        // The number of parameters that were only an identifier (like in a K&R function).
+        let mut kr_parameters = 0;
         loop {
             match *self.traveler.head().kind() {
                 TokenKind::RParen => {
                     if !decls.is_empty() {
-                        // TODO: Error ,)
-                        todo!()
+                        self.report_error(Error::ParamsTrailingComma)?;
                     }
                     break;
                 },
@@ -376,9 +622,19 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
                 _ => {},
             }
 
+            let leading_attributes = self.attributes()?;
             let mut type_ = self.type_base(scope_id, true)?;
             type_ = self.type_name(type_, scope_id)?;
-            decls.push(Decl { type_, postfix: DeclPostfix::None });
+            if is_kr_parameter(&type_) {
+                kr_parameters += 1;
+            }
+            let attributes = self.gnu_attribute_lists(scope_id)?;
+            decls.push(Decl {
+                type_,
+                postfix: DeclPostfix::None,
+                leading_attributes,
+                attributes,
+            });
 
             match *self.traveler.head().kind() {
                 TokenKind::RParen | TokenKind::LBrace { .. } => break,
@@ -392,6 +648,10 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
             }
         }
 
+        let is_kr = kr_parameters > 0 && kr_parameters == decls.len();
+        if kr_parameters > 0 && !is_kr {
+            self.report_error(Error::KRMixedParameters)?;
+        }
         let scope = self.file.get_scope_mut(scope_id);
         scope.add_decls(decls);
 
@@ -410,22 +670,64 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
         }
 
         let range = start_index..self.traveler.index();
+        if is_kr {
+            self.kr_parameter_decls(scope_id)?;
+        }
 
         Ok(FuncSegment { range, scope_id, vararg_index })
     }
 
+    /// Parses the declarations between a K&R function's parameter list and its body.
+    ///
+    /// Each declaration replaces the type of the parameter it names. Parameters without
+    /// a declaration keep their implicit int type.
+    fn kr_parameter_decls(&mut self, scope_id: ScopeId) -> MayUnwind<()> {
+        loop {
+            let is_storage = matches!(
+                *self.traveler.head().kind(),
+                TokenKind::Keyword(keyword, ..) if keyword.is_storage_class()
+            );
+            if !(is_storage || self.is_head_a_type(scope_id)) {
+                break;
+            }
+
+            for decl in self.decls(scope_id, true, Vec::new())? {
+                let name = match decl.type_.name {
+                    Some(ref name) => name.clone(),
+                    None => continue,
+                };
+                let scope = self.file.get_scope_mut(scope_id);
+                match scope.decls.get_index(&name) {
+                    Some(index) if is_kr_parameter(&scope.decls[index].type_) => {
+                        scope.decls[index] = decl;
+                    },
+                    _ => self.report_error(Error::KRDeclaredNonParameter(name))?,
+                }
+            }
+
+            if matches!(*self.traveler.head().kind(), TokenKind::Semicolon) {
+                self.traveler.move_forward()?;
+            } else {
+                break;
+            }
+        }
+        Ok(())
+    }
+
     fn type_decl(&mut self, scope_id: ScopeId) -> MayUnwind<DeclIndex> {
         let type_kind = match *self.traveler.head().kind() {
-            TokenKind::Keyword(Keyword::Enum) => TypeDeclKind::Enum,
-            TokenKind::Keyword(Keyword::Struct) => TypeDeclKind::Struct,
-            TokenKind::Keyword(Keyword::Union) => TypeDeclKind::Union,
+            TokenKind::Keyword(Keyword::Enum, ..) => TypeDeclKind::Enum,
+            TokenKind::Keyword(Keyword::Struct, ..) => TypeDeclKind::Struct,
+            TokenKind::Keyword(Keyword::Union, ..) => TypeDeclKind::Union,
             _ => {
                 // TODO: Internal error
                 todo!()
             },
         };
         let tag_index = self.traveler.index();
-        let name = match *self.traveler.move_forward()?.kind() {
+        self.traveler.move_forward()?;
+        let attributes = self.gnu_attribute_lists(scope_id)?;
+        let name = match *self.traveler.head().kind() {
             TokenKind::Identifier(ref id) => {
                 let result = Some(id.clone());
                 self.traveler.move_forward()?;
@@ -440,9 +742,13 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
         let tag = TypeDeclTag {
             range: tag_index..self.traveler.index(),
             kind: type_kind,
+            attributes,
         };
 
-        let type_decl = TypeDecl::new(tag, name);
+        let mut type_decl = TypeDecl::new(tag, name);
+        if type_kind == TypeDeclKind::Enum {
+            type_decl.underlying = self.enum_underlying_type(scope_id)?;
+        }
         let has_body = matches!(*self.traveler.head().kind(), TokenKind::LBrace { .. });
         let index = self.file.add_type_decl(scope_id, type_decl, has_body);
 
@@ -458,12 +764,52 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
         Ok(index)
     }
 
+    /// Parses the underlying type of an enum (`: type`) if there is one.
+    ///
+    /// A : that isn't followed by a type is left alone since it may start a bit-field's width.
+    fn enum_underlying_type(&mut self, scope_id: ScopeId) -> MayUnwind<Option<Type>> {
+        if !matches!(*self.traveler.head().kind(), TokenKind::Colon) {
+            return Ok(None);
+        }
+        let state = self.traveler.save_state();
+        self.traveler.move_forward()?;
+        if !self.is_head_a_type(scope_id) {
+            self.traveler.load_state(state);
+            return Ok(None);
+        }
+
+        if self.traveler.settings().version < LangVersion::C23 {
+            self.report_error_with_state(Error::EnumUnderlyingTypeBeforeC23, state)?;
+        }
+        let start_index = self.traveler.index();
+        let underlying = self.type_base(scope_id, false)?;
+        if !self.is_integer_type(&underlying) {
+            self.report_error(Error::EnumUnderlyingNotInteger(start_index))?;
+        }
+        Ok(Some(underlying))
+    }
+
+    /// Returns the range of values the constants of an enum must fit in (None if it isn't known).
+    ///
+    /// Without an underlying type, the constants must fit in an int before C23. Since C23,
+    /// the enum's type grows to fit its constants (up to a 64-bit integer).
+    fn enum_value_range(&self, type_index: DeclIndex) -> Option<RangeInclusive<i128>> {
+        match self.file.get_type_decl(type_index).underlying {
+            Some(ref underlying) => self.integer_range(underlying),
+            None if self.traveler.settings().version < LangVersion::C23 => {
+                Some(i32::MIN.into()..=i32::MAX.into())
+            },
+            None => Some(i64::MIN.into()..=u64::MAX.into()),
+        }
+    }
+
     fn type_decl_body(&mut self, scope_id: ScopeId, kind: TypeDeclKind) -> MayUnwind<TypeDeclBody> {
         let start_index = self.traveler.index();
         // Move past the {
         self.traveler.move_forward()?;
 
         let mut body = TypeDeclBody::new(kind);
+        body.pack = self.traveler.pack();
 
         loop {
             match *self.traveler.head().kind() {
@@ -473,8 +819,10 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
                     self.traveler.move_forward()?;
                 },
                 _ => {
-                    let decls = self.decls(scope_id, false)?;
-                    body.add_decls(&self.file, decls);
+                    let decls = self.decls(scope_id, false, Vec::new())?;
+                    for name in body.add_decls(&self.file, decls) {
+                        self.report_error(Error::AmbiguousAnonymousMember(name))?;
+                    }
                     match *self.traveler.head().kind() {
                         TokenKind::Semicolon => {
                             self.traveler.move_forward()?;
@@ -511,6 +859,9 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
         self.traveler.move_forward()?;
 
         let mut body = TypeDeclBody::new(TypeDeclKind::Enum);
+        let range = self.enum_value_range(type_index);
+        let mut values = HashMap::new();
+        let mut next_value = Some(0);
 
         loop {
             let id = match *self.traveler.head().kind() {
@@ -527,6 +878,9 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
                 },
             };
 
+            // Out of range values are reported once the value is known (after the initializer), so
+            // the constant's index is recorded to point the error at it.
+            let index = self.traveler.index();
             let postfix = if matches!(*self.traveler.move_forward()?.kind(), TokenKind::Equal) {
                 self.traveler.move_forward()?;
                 DeclPostfix::Initializer(self.expr(scope_id, false)?)
@@ -534,11 +888,28 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
                 DeclPostfix::None
             };
 
+            let value = match postfix {
+                DeclPostfix::Initializer(ref expr) => enum_constant(expr, &values),
+                _ => next_value,
+            };
+            if let Some(value) = value {
+                if let Some(ref range) = range {
+                    if !range.contains(&value) {
+                        let error = Error::EnumValueOutOfRange(index, id.clone(), value);
+                        self.report_error(error)?;
+                    }
+                }
+                values.insert(id.clone(), value);
+            }
+            next_value = value.and_then(|value| value.checked_add(1));
+
             body.fields.add_keyed(
                 id.clone(),
                 Decl {
                     type_: Type::new_enum(id.clone()),
                     postfix,
+                    leading_attributes: Vec::new(),
+                    attributes: Vec::new(),
                 }
                 .into(),
             );
@@ -577,7 +948,7 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
     // region: Statement Parsing
     fn stmt(&mut self, scope_id: ScopeId) -> MayUnwind<Stmt> {
         let stmt: Stmt = match *self.traveler.head().kind() {
-            TokenKind::Keyword(keyword) => match keyword {
+            TokenKind::Keyword(keyword, ..) => match keyword {
                 Keyword::Break => self.break_stmt(scope_id)?.into(),
                 Keyword::Continue => self.continue_stmt(scope_id)?.into(),
                 Keyword::Case => self.case_stmt(true, scope_id)?.into(),
@@ -589,16 +960,18 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
                 Keyword::For => self.for_stmt(scope_id)?.into(),
                 Keyword::Do => self.do_stmt(scope_id)?.into(),
                 Keyword::Switch => self.switch_stmt(scope_id)?.into(),
-                Keyword::StaticAssert => {
-                    todo!("_Static_assert")
-                },
-                _ if keyword.is_type_starter() => self.decl_stmt(scope_id)?.into(),
+                Keyword::StaticAssert => self.static_assert_stmt(scope_id)?.into(),
+                Keyword::Asm | Keyword::AsmBuiltin => self.asm_stmt(scope_id)?.into(),
+                _ if keyword.is_type_starter() => self.decl_stmt(scope_id, Vec::new())?.into(),
                 _ => (*self.expr(scope_id, true)?).into(),
             },
             TokenKind::LBrace { .. } => self.block(scope_id, ScopeKind::Block)?.into(),
-            TokenKind::Identifier(ref id) => match self.file.find_decl(scope_id, id) {
-                Some(decl) if decl.is_typedef() => self.decl_stmt(scope_id)?.into(),
-                _ => (*self.expr(scope_id, true)?).into(),
+            TokenKind::LDoubleBracket => self.attributed_stmt(scope_id)?,
+            TokenKind::Identifier(ref id) if self.is_label(scope_id, id) => {
+                self.labeled_stmt(scope_id)?.into()
+            },
+            TokenKind::Identifier(ref id) if self.is_typedef_name(scope_id, id) => {
+                self.decl_stmt(scope_id, Vec::new())?.into()
             },
             TokenKind::Semicolon => {
                 let index = self.traveler.index();
@@ -622,6 +995,94 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
         Ok(stmt)
     }
 
+    /// Parses the attribute specifiers at the head and the statement they apply to.
+    ///
+    /// If the statement is a declaration, the attributes are applied to its declarations instead.
+    fn attributed_stmt(&mut self, scope_id: ScopeId) -> MayUnwind<Stmt> {
+        let attributes = self.attributes()?;
+        let is_decl = match *self.traveler.head().kind() {
+            TokenKind::Keyword(keyword, ..) => keyword.is_type_starter(),
+            TokenKind::Identifier(ref id) if !self.is_label(scope_id, id) => {
+                self.is_typedef_name(scope_id, id)
+            },
+            _ => false,
+        };
+        if is_decl {
+            return Ok(self.decl_stmt(scope_id, attributes)?.into());
+        }
+        let stmt = Box::new(self.stmt(scope_id)?);
+        Ok(AttributedStmt { attributes, stmt }.into())
+    }
+
+    fn is_label(&self, scope_id: ScopeId, id: &CachedString) -> bool {
+        !self.is_typedef_name(scope_id, id)
+            && matches!(self.traveler.preview_next_kind(), Some(&TokenKind::Colon))
+    }
+
+    /// Returns whether the identifier names a type (a typedef or the predeclared `nullptr_t`).
+    fn is_typedef_name(&self, scope_id: ScopeId, id: &CachedString) -> bool {
+        match self.file.find_decl(scope_id, id) {
+            Some(decl) => decl.is_typedef(),
+            None => self.is_nullptr_t(id),
+        }
+    }
+
+    /// Returns whether the identifier is the predeclared `nullptr_t` type (since C23).
+    ///
+    /// Unlike `nullptr`, `nullptr_t` isn't a keyword. It acts like a typedef name declared
+    /// before the file, so it should only be checked if no declaration of the identifier is
+    /// visible.
+    fn is_nullptr_t(&self, id: &CachedString) -> bool {
+        self.traveler.settings().version >= LangVersion::C23 && id.string() == "nullptr_t"
+    }
+
+    fn labeled_stmt(&mut self, scope_id: ScopeId) -> MayUnwind<LabeledStmt> {
+        let start_index = self.traveler.index();
+        let name = match *self.traveler.head().kind() {
+            TokenKind::Identifier(ref id) => id.clone(),
+            _ => {
+                self.report_error(Error::Unreachable(
+                    "labels should start with an identifier",
+                ))?;
+                return Err(Unwind::Fatal);
+            },
+        };
+        self.traveler.move_forward()?;
+        let colon_index = self.traveler.index();
+        self.traveler.move_forward()?;
+
+        let label_scope_id = self.file.find_scope_kind(scope_id, |kind| kind.manages_labels());
+        if let Some(label_scope_id) = label_scope_id {
+            let labels = &mut self.file.get_scope_mut(label_scope_id).labels;
+            if labels.contains_key(&name) {
+                self.report_error(Error::DuplicateLabel(name.clone()))?;
+            } else {
+                labels.insert(name.clone(), start_index);
+            }
+        }
+
+        let stmt = self.labeled_stmt_body(scope_id)?;
+
+        Ok(LabeledStmt {
+            range: start_index..self.traveler.index(),
+            name,
+            colon_index,
+            stmt: Box::new(stmt),
+        })
+    }
+
+    /// Parses the statement after a label (or a case). A label at the end of a block is given
+    /// an empty statement (which is only allowed since C23).
+    fn labeled_stmt_body(&mut self, scope_id: ScopeId) -> MayUnwind<Stmt> {
+        if !matches!(*self.traveler.head().kind(), TokenKind::RBrace { .. }) {
+            return self.stmt(scope_id);
+        }
+        if self.traveler.settings().version < LangVersion::C23 {
+            self.report_error(Error::LabelAtEndOfBlock)?;
+        }
+        Ok(self.traveler.index().into())
+    }
+
     fn break_stmt(&mut self, scope_id: ScopeId) -> MayUnwind<BreakStmt> {
         let break_index = self.traveler.index();
         self.traveler.move_forward()?;
@@ -656,12 +1117,7 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
             },
         }
 
-        let stmt = if matches!(*self.traveler.head().kind(), TokenKind::RBrace { .. }) {
-            // TODO: Error label at end of block
-            self.traveler.index().into()
-        } else {
-            self.stmt(scope_id)?
-        };
+        let stmt = self.labeled_stmt_body(scope_id)?;
 
         Ok(CaseStmt {
             range: start_index..self.traveler.index(),
@@ -682,79 +1138,320 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
 
     fn goto_stmt(&mut self, scope_id: ScopeId) -> MayUnwind<GotoStmt> {
         let start_index = self.traveler.index();
-        let label = match *self.traveler.move_forward()?.kind() {
+        let label_scope_id = self.file.find_scope_kind(scope_id, |kind| kind.manages_labels());
+        let target = match *self.traveler.move_forward()?.kind() {
             TokenKind::Identifier(ref id) => {
                 let id = id.clone();
+                self.check_label_later(label_scope_id, &id);
+                self.traveler.move_forward()?;
+                GotoTarget::Label(Some(id))
+            },
+            TokenKind::Star => {
+                if !self.traveler.settings().enable_gnu_extensions {
+                    // The target can still be parsed to recover.
+                    self.report_error(Error::GnuExtension("Computed gotos"))?;
+                }
                 self.traveler.move_forward()?;
-                Some(id)
+                GotoTarget::Computed(self.expr(scope_id, true)?)
             },
             TokenKind::Semicolon => {
                 // TODO: Missing id
-                None
+                GotoTarget::Label(None)
             },
             _ => {
                 // TODO: Expected id to follow.
                 return Err(Unwind::Block);
             },
         };
-        let label_scope_id = self.file.find_scope_kind(scope_id, |kind| kind.manages_labels());
         let range = start_index..self.traveler.index();
-        Ok(GotoStmt { range, label_scope_id, label })
+        Ok(GotoStmt { range, label_scope_id, target })
     }
 
-    fn if_stmt(&mut self, parent_id: ScopeId) -> MayUnwind<IfStmt> {
-        let scope_id = self.file.new_scope(parent_id, ScopeKind::Block);
-
+    fn static_assert_stmt(&mut self, scope_id: ScopeId) -> MayUnwind<StaticAssertStmt> {
         let start_index = self.traveler.index();
-        self.traveler.move_forward()?;
-        let condition = self.condition(scope_id)?;
-        let block = Box::new(self.stmt(scope_id)?);
-        let else_ = if matches!(
-            *self.traveler.head().kind(),
-            TokenKind::Keyword(Keyword::Else)
-        ) {
-            let else_scope_id = self.file.new_scope(parent_id, ScopeKind::Block);
+        if matches!(*self.traveler.move_forward()?.kind(), TokenKind::LParen) {
             self.traveler.move_forward()?;
-            Some(Box::new(self.stmt(else_scope_id)?))
         } else {
-            None
+            // The condition can still be parsed as if the ( was there.
+            let token = self.traveler.head().clone();
+            self.report_error(Error::StaticAssertExpectedLParen(token))?;
+        }
+        let condition = self.expr(scope_id, false)?;
+
+        let message = match *self.traveler.head().kind() {
+            TokenKind::Comma => match *self.traveler.move_forward()?.kind() {
+                TokenKind::String { is_char: false, .. } => Some(self.string_expr()?),
+                _ => {
+                    let token = self.traveler.head().clone();
+                    self.report_error(Error::StaticAssertExpectedMessage(token))?;
+                    // Skip past whatever was used as the message instead.
+                    self.expr(scope_id, false)?;
+                    None
+                },
+            },
+            TokenKind::String { is_char: false, .. } => {
+                let token = self.traveler.head().clone();
+                self.report_error(Error::StaticAssertExpectedComma(token))?;
+                Some(self.string_expr()?)
+            },
+            _ => {
+                if self.traveler.settings().version < LangVersion::C23 {
+                    self.report_error(Error::StaticAssertWithoutMessage)?;
+                }
+                None
+            },
         };
-        let range = start_index..self.traveler.index();
-        Ok(IfStmt { range, condition, block, else_ })
-    }
 
-    fn while_stmt(&mut self, parent_id: ScopeId) -> MayUnwind<WhileStmt> {
-        let scope_id = self.file.new_scope(parent_id, ScopeKind::Loop);
+        match *self.traveler.head().kind() {
+            TokenKind::RParen => {
+                self.traveler.move_forward()?;
+            },
+            // The ) was likely forgotten, so the ; can still end the assertion.
+            TokenKind::Semicolon => {
+                let token = self.traveler.head().clone();
+                self.report_error(Error::StaticAssertExpectedRParen(token))?;
+            },
+            _ => {
+                let token = self.traveler.head().clone();
+                self.report_error(Error::StaticAssertExpectedRParen(token))?;
+                return Err(Unwind::Block);
+            },
+        }
+
+        if matches!(*self.traveler.head().kind(), TokenKind::Semicolon) {
+            self.traveler.move_forward()?;
+        } else {
+            let token = self.traveler.head().clone();
+            self.report_error(Error::StaticAssertExpectedSemicolon(token))?;
+        }
 
-        let start_index = self.traveler.index();
-        self.traveler.move_forward()?;
-        let condition = self.condition(scope_id)?;
-        let block = Box::new(self.stmt(scope_id)?);
         let range = start_index..self.traveler.index();
-        Ok(WhileStmt { range, condition, block })
+        Ok(StaticAssertStmt { range, condition, message })
     }
 
-    fn do_stmt(&mut self, parent_id: ScopeId) -> MayUnwind<DoStmt> {
-        let scope_id = self.file.new_scope(parent_id, ScopeKind::Loop);
-
+    fn asm_stmt(&mut self, scope_id: ScopeId) -> MayUnwind<AsmStmt> {
         let start_index = self.traveler.index();
-        self.traveler.move_forward()?;
-        let block = Box::new(self.stmt(scope_id)?);
+        let volatile = matches!(
+            *self.traveler.move_forward()?.kind(),
+            TokenKind::Keyword(Keyword::Volatile, ..)
+        );
+        if volatile {
+            self.traveler.move_forward()?;
+        }
 
-        let condition = if matches!(
-            *self.traveler.head().kind(),
-            TokenKind::Keyword(Keyword::While)
-        ) {
+        if matches!(*self.traveler.head().kind(), TokenKind::LParen) {
             self.traveler.move_forward()?;
-            self.condition(scope_id)?
         } else {
-            // TODO: Report missing while condition
-            Box::new(self.number_expr("0", None)?.into())
+            // The template can still be parsed as if the ( was there.
+            let token = self.traveler.head().clone();
+            self.report_error(Error::AsmExpectedLParen(token))?;
+        }
+        let template = match *self.traveler.head().kind() {
+            TokenKind::String { is_char: false, .. } => self.string_expr()?,
+            _ => {
+                let token = self.traveler.head().clone();
+                self.report_error(Error::AsmExpectedTemplate(token))?;
+                return Err(Unwind::Block);
+            },
         };
 
-        let range = start_index..self.traveler.index();
-        Ok(DoStmt { range, block, condition })
-    }
+        // Each section is started by a : and any of the sections can be empty.
+        let mut outputs = Vec::new();
+        let mut inputs = Vec::new();
+        let mut clobbers = Vec::new();
+        if self.asm_section_start()? {
+            outputs = self.asm_operands(scope_id)?;
+            if self.asm_section_start()? {
+                inputs = self.asm_operands(scope_id)?;
+                if self.asm_section_start()? {
+                    clobbers = self.asm_clobbers()?;
+                }
+            }
+        }
+
+        if matches!(*self.traveler.head().kind(), TokenKind::RParen) {
+            self.traveler.move_forward()?;
+        } else {
+            let token = self.traveler.head().clone();
+            self.report_error(Error::AsmExpectedRParen(token))?;
+            return Err(Unwind::Block);
+        }
+
+        let range = start_index..self.traveler.index();
+        Ok(AsmStmt {
+            range,
+            volatile,
+            template,
+            outputs,
+            inputs,
+            clobbers,
+        })
+    }
+
+    /// Moves past the : that starts the next section of an asm statement (if there is one).
+    fn asm_section_start(&mut self) -> MayUnwind<bool> {
+        if matches!(*self.traveler.head().kind(), TokenKind::Colon) {
+            self.traveler.move_forward()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn asm_operands(&mut self, scope_id: ScopeId) -> MayUnwind<Vec<AsmOperand>> {
+        let mut operands = Vec::new();
+        if matches!(
+            *self.traveler.head().kind(),
+            TokenKind::Colon | TokenKind::RParen
+        ) {
+            return Ok(operands);
+        }
+
+        loop {
+            operands.push(self.asm_operand(scope_id)?);
+            if matches!(*self.traveler.head().kind(), TokenKind::Comma) {
+                self.traveler.move_forward()?;
+            } else {
+                return Ok(operands);
+            }
+        }
+    }
+
+    fn asm_operand(&mut self, scope_id: ScopeId) -> MayUnwind<AsmOperand> {
+        let start_index = self.traveler.index();
+        let name = match *self.traveler.head().kind() {
+            TokenKind::LBracket { .. } => Some(self.asm_operand_name()?),
+            _ => None,
+        };
+
+        let constraint = match *self.traveler.head().kind() {
+            TokenKind::String { is_char: false, .. } => self.string_expr()?,
+            _ => {
+                let token = self.traveler.head().clone();
+                self.report_error(Error::AsmOperandExpectedConstraint(token))?;
+                return Err(Unwind::Block);
+            },
+        };
+
+        if !matches!(*self.traveler.head().kind(), TokenKind::LParen) {
+            let token = self.traveler.head().clone();
+            self.report_error(Error::AsmOperandExpectedExpr(token))?;
+            return Err(Unwind::Block);
+        }
+        self.traveler.move_forward()?;
+        let expr = self.expr(scope_id, true)?;
+        if matches!(*self.traveler.head().kind(), TokenKind::RParen) {
+            self.traveler.move_forward()?;
+        } else {
+            let token = self.traveler.head().clone();
+            self.report_error(Error::AsmOperandExpectedExpr(token))?;
+            return Err(Unwind::Block);
+        }
+
+        let range = start_index..self.traveler.index();
+        Ok(AsmOperand { range, name, constraint, expr })
+    }
+
+    fn asm_operand_name(&mut self) -> MayUnwind<CachedString> {
+        // This function should have been called on the [.
+        let name = match *self.traveler.move_forward()?.kind() {
+            TokenKind::Identifier(ref id) => id.clone(),
+            _ => {
+                let token = self.traveler.head().clone();
+                self.report_error(Error::AsmOperandExpectedName(token))?;
+                return Err(Unwind::Block);
+            },
+        };
+        if !matches!(
+            *self.traveler.move_forward()?.kind(),
+            TokenKind::RBracket { .. }
+        ) {
+            let token = self.traveler.head().clone();
+            self.report_error(Error::AsmOperandExpectedName(token))?;
+            return Err(Unwind::Block);
+        }
+        self.traveler.move_forward()?;
+        Ok(name)
+    }
+
+    fn asm_clobbers(&mut self) -> MayUnwind<Vec<CachedString>> {
+        let mut clobbers = Vec::new();
+        loop {
+            match *self.traveler.head().kind() {
+                TokenKind::String { is_char: false, .. } => {
+                    let literal = self.string_expr()?;
+                    let text: String = literal.segments.iter().map(|segment| &***segment).collect();
+                    clobbers.push(self.traveler.env().cache().get_or_cache(&text));
+                },
+                TokenKind::RParen if clobbers.is_empty() => return Ok(clobbers),
+                _ => {
+                    let token = self.traveler.head().clone();
+                    self.report_error(Error::AsmExpectedClobber(token))?;
+                    return Err(Unwind::Block);
+                },
+            }
+
+            if matches!(*self.traveler.head().kind(), TokenKind::Comma) {
+                self.traveler.move_forward()?;
+            } else {
+                return Ok(clobbers);
+            }
+        }
+    }
+
+    fn if_stmt(&mut self, parent_id: ScopeId) -> MayUnwind<IfStmt> {
+        let scope_id = self.file.new_scope(parent_id, ScopeKind::Block);
+
+        let start_index = self.traveler.index();
+        self.traveler.move_forward()?;
+        let condition = self.condition(scope_id)?;
+        let block = Box::new(self.stmt(scope_id)?);
+        let else_ = if matches!(
+            *self.traveler.head().kind(),
+            TokenKind::Keyword(Keyword::Else, ..)
+        ) {
+            let else_scope_id = self.file.new_scope(parent_id, ScopeKind::Block);
+            self.traveler.move_forward()?;
+            Some(Box::new(self.stmt(else_scope_id)?))
+        } else {
+            None
+        };
+        let range = start_index..self.traveler.index();
+        Ok(IfStmt { range, condition, block, else_ })
+    }
+
+    fn while_stmt(&mut self, parent_id: ScopeId) -> MayUnwind<WhileStmt> {
+        let scope_id = self.file.new_scope(parent_id, ScopeKind::Loop);
+
+        let start_index = self.traveler.index();
+        self.traveler.move_forward()?;
+        let condition = self.condition(scope_id)?;
+        let block = Box::new(self.stmt(scope_id)?);
+        let range = start_index..self.traveler.index();
+        Ok(WhileStmt { range, condition, block })
+    }
+
+    fn do_stmt(&mut self, parent_id: ScopeId) -> MayUnwind<DoStmt> {
+        let scope_id = self.file.new_scope(parent_id, ScopeKind::Loop);
+
+        let start_index = self.traveler.index();
+        self.traveler.move_forward()?;
+        let block = Box::new(self.stmt(scope_id)?);
+
+        let condition = if matches!(
+            *self.traveler.head().kind(),
+            TokenKind::Keyword(Keyword::While, ..)
+        ) {
+            self.traveler.move_forward()?;
+            self.condition(scope_id)?
+        } else {
+            // TODO: Report missing while condition
+            Box::new(self.number_expr("0", None)?.into())
+        };
+
+        let range = start_index..self.traveler.index();
+        Ok(DoStmt { range, block, condition })
+    }
 
     fn for_stmt(&mut self, parent_id: ScopeId) -> MayUnwind<ForStmt> {
         let start_index = self.traveler.index();
@@ -864,23 +1561,22 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
         }
     }
 
-    fn decl_stmt(&mut self, scope_id: ScopeId) -> MayUnwind<DeclStmt> {
+    /// Parses a declaration statement. The attributes are the attribute specifiers
+    /// that have already been parsed before the declaration.
+    fn decl_stmt(&mut self, scope_id: ScopeId, attributes: Vec<Attribute>) -> MayUnwind<DeclStmt> {
         let start_index = self.traveler.index();
 
-        let decls = self.decls(scope_id, true)?;
+        let decls = self.decls(scope_id, true, attributes)?;
         let requires_semicolon = !decls.last().unwrap().is_function();
         let scope = self.file.get_scope_mut(scope_id);
         let decl_ids = scope.add_decls(decls);
 
         if requires_semicolon {
-            match *self.traveler.head().kind() {
-                TokenKind::Semicolon => {
-                    self.traveler.move_forward()?;
-                },
-                _ => {
-                    // TODO: Expected ; to end declaration
-                    todo!("{:?}", self.traveler.head())
-                },
+            if matches!(*self.traveler.head().kind(), TokenKind::Semicolon) {
+                self.traveler.move_forward()?;
+            } else {
+                let token = self.traveler.head().clone();
+                self.report_error(Error::DeclExpectedSemicolon(token))?;
             }
         }
 
@@ -918,11 +1614,252 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
             todo!()
         }
 
+        if kind.manages_labels() {
+            self.check_goto_labels(scope_id)?;
+        }
+
         let range = start_index..self.traveler.index();
         Ok(BlockExpr { range, scope_id })
     }
+
+    /// Saves the state at the head (a label's identifier) if the label hasn't been declared yet.
+    ///
+    /// The label is then checked once its scope ends (see [check_goto_labels](Self::check_goto_labels)).
+    /// Only labels that haven't been declared yet have to be checked later, so the state (for
+    /// reporting an undeclared label) is only saved for them.
+    fn check_label_later(&mut self, label_scope_id: Option<ScopeId>, label: &CachedString) {
+        if let Some(label_scope_id) = label_scope_id {
+            if !self.file.get_scope(label_scope_id).labels.contains_key(label) {
+                let state = self.traveler.save_state();
+                self.gotos.push((label_scope_id, label.clone(), state));
+            }
+        }
+    }
+
+    /// Reports the gotos to labels that weren't declared in the given scope.
+    fn check_goto_labels(&mut self, label_scope_id: ScopeId) -> MayUnwind<()> {
+        let (gotos, others): (Vec<_>, Vec<_>) = std::mem::take(&mut self.gotos)
+            .into_iter()
+            .partition(|&(scope_id, ..)| scope_id == label_scope_id);
+        self.gotos = others;
+
+        for (_, label, state) in gotos {
+            if !self.file.get_scope(label_scope_id).labels.contains_key(&label) {
+                self.report_error_with_state(Error::UndeclaredLabel(label), state)?;
+            }
+        }
+        Ok(())
+    }
     // endregion: Statement Parsing
 
+    // region: Attribute Parsing
+    /// Parses every attribute specifier (`[[...]]`) at the head.
+    fn attributes(&mut self) -> MayUnwind<Vec<Attribute>> {
+        let mut attributes = Vec::new();
+        while let TokenKind::LDoubleBracket = *self.traveler.head().kind() {
+            self.traveler.move_forward()?;
+            loop {
+                match *self.traveler.head().kind() {
+                    TokenKind::RDoubleBracket => break,
+                    // Empty attributes are allowed.
+                    TokenKind::Comma => {
+                        self.traveler.move_forward()?;
+                        continue;
+                    },
+                    _ => attributes.push(self.attribute()?),
+                }
+
+                match *self.traveler.head().kind() {
+                    TokenKind::RDoubleBracket => break,
+                    TokenKind::Comma => {
+                        self.traveler.move_forward()?;
+                    },
+                    // The ]] was split up (like by whitespace or a macro).
+                    TokenKind::RBracket { .. } => {
+                        let token = self.traveler.head().clone();
+                        self.report_error(Error::AttributeExpectedBrackets(token))?;
+                        return Err(Unwind::Block);
+                    },
+                    _ => {
+                        let token = self.traveler.head().clone();
+                        self.report_error(Error::AttributeExpectedSeparator(token))?;
+                        return Err(Unwind::Block);
+                    },
+                }
+            }
+            // Move past the ]]
+            self.traveler.move_forward()?;
+        }
+        Ok(attributes)
+    }
+
+    fn attribute(&mut self) -> MayUnwind<Attribute> {
+        let start_index = self.traveler.index();
+        let mut prefix = None;
+        let mut name = self.attribute_id()?;
+        if matches!(*self.traveler.head().kind(), TokenKind::Colon) {
+            if !matches!(*self.traveler.move_forward()?.kind(), TokenKind::Colon) {
+                let token = self.traveler.head().clone();
+                self.report_error(Error::AttributeExpectedSeparator(token))?;
+                return Err(Unwind::Block);
+            }
+            self.traveler.move_forward()?;
+            prefix = Some(std::mem::replace(&mut name, self.attribute_id()?));
+        }
+
+        let args = if matches!(*self.traveler.head().kind(), TokenKind::LParen) {
+            let args_index = self.traveler.index();
+            let mut depth = 0usize;
+            loop {
+                match *self.traveler.head().kind() {
+                    TokenKind::LParen => depth += 1,
+                    TokenKind::RParen => depth -= 1,
+                    TokenKind::Eof => {
+                        self.report_error(Error::AttributeUnendedArgs)?;
+                        return Err(Unwind::Block);
+                    },
+                    _ => {},
+                }
+                self.traveler.move_forward()?;
+                if depth == 0 {
+                    break;
+                }
+            }
+            Some(args_index..self.traveler.index())
+        } else {
+            None
+        };
+
+        let range = start_index..self.traveler.index();
+        Ok(Attribute { range, prefix, name, args })
+    }
+
+    fn attribute_id(&mut self) -> MayUnwind<CachedString> {
+        match *self.traveler.head().kind() {
+            // Keywords can be attribute names (or prefixes) too (like const).
+            TokenKind::Identifier(ref id) | TokenKind::Keyword(_, ref id) => {
+                let id = id.clone();
+                self.traveler.move_forward()?;
+                Ok(id)
+            },
+            _ => {
+                let token = self.traveler.head().clone();
+                self.report_error(Error::AttributeExpectedId(token))?;
+                Err(Unwind::Block)
+            },
+        }
+    }
+
+    /// Parses every GNU attribute specifier (`__attribute__((...))`) at the head.
+    fn gnu_attribute_lists(&mut self, scope_id: ScopeId) -> MayUnwind<Vec<AttributeList>> {
+        let mut lists = Vec::new();
+        while let TokenKind::Keyword(Keyword::Attribute, ..) = *self.traveler.head().kind() {
+            lists.push(self.gnu_attribute_list(scope_id)?);
+        }
+        Ok(lists)
+    }
+
+    /// Parses a GNU attribute specifier. The head should be the `__attribute__` keyword.
+    ///
+    /// Attributes that aren't recognized are kept without any errors.
+    fn gnu_attribute_list(&mut self, scope_id: ScopeId) -> MayUnwind<AttributeList> {
+        let start_index = self.traveler.index();
+        self.traveler.move_forward()?;
+        self.expect_gnu_attribute_parens(true)?;
+
+        let mut attributes = Vec::new();
+        loop {
+            match *self.traveler.head().kind() {
+                TokenKind::RParen => break,
+                // Empty attributes are allowed.
+                TokenKind::Comma => {
+                    self.traveler.move_forward()?;
+                    continue;
+                },
+                _ => attributes.push(self.gnu_attribute(scope_id)?),
+            }
+
+            match *self.traveler.head().kind() {
+                TokenKind::RParen => break,
+                TokenKind::Comma => {
+                    self.traveler.move_forward()?;
+                },
+                _ => {
+                    let token = self.traveler.head().clone();
+                    self.report_error(Error::GnuAttributeExpectedSeparator(token))?;
+                    return Err(Unwind::Block);
+                },
+            }
+        }
+        self.expect_gnu_attribute_parens(false)?;
+
+        let range = start_index..self.traveler.index();
+        Ok(AttributeList { range, attributes })
+    }
+
+    fn gnu_attribute(&mut self, scope_id: ScopeId) -> MayUnwind<GnuAttribute> {
+        let start_index = self.traveler.index();
+        // Keywords can be attribute names too (like const).
+        let text = match *self.traveler.head().kind() {
+            TokenKind::Identifier(ref id) | TokenKind::Keyword(_, ref id) => Some(id.clone()),
+            _ => None,
+        };
+        let text = match text {
+            Some(text) => text,
+            None => {
+                let token = self.traveler.head().clone();
+                self.report_error(Error::AttributeExpectedId(token))?;
+                return Err(Unwind::Block);
+            },
+        };
+        let name = Id { text, index: start_index };
+
+        let args = if matches!(*self.traveler.move_forward()?.kind(), TokenKind::LParen) {
+            let mut args = Vec::new();
+            self.traveler.move_forward()?;
+            while !matches!(*self.traveler.head().kind(), TokenKind::RParen) {
+                args.push(*self.expr(scope_id, false)?);
+                match *self.traveler.head().kind() {
+                    TokenKind::RParen => break,
+                    TokenKind::Comma => {
+                        self.traveler.move_forward()?;
+                    },
+                    _ => {
+                        let token = self.traveler.head().clone();
+                        self.report_error(Error::GnuAttributeArgsExpectedSeparator(token))?;
+                        return Err(Unwind::Block);
+                    },
+                }
+            }
+            self.traveler.move_forward()?;
+            Some(args)
+        } else {
+            None
+        };
+
+        let range = start_index..self.traveler.index();
+        Ok(GnuAttribute { range, name, args })
+    }
+
+    /// Moves past the two parentheses that start (or end) a GNU attribute specifier.
+    fn expect_gnu_attribute_parens(&mut self, left: bool) -> MayUnwind<()> {
+        for _ in 0..2 {
+            let is_paren = match *self.traveler.head().kind() {
+                TokenKind::LParen => left,
+                TokenKind::RParen => !left,
+                _ => false,
+            };
+            if !is_paren {
+                let token = self.traveler.head().clone();
+                self.report_error(Error::GnuAttributeExpectedParens(token))?;
+                return Err(Unwind::Block);
+            }
+            self.traveler.move_forward()?;
+        }
+        Ok(())
+    }
+    // endregion: Attribute Parsing
+
     // region: Expression Parsing
     fn expr(&mut self, scope_id: ScopeId, comma_support: bool) -> MayUnwind<Box<Expr>> {
         let mut expr = self.expr_atom(scope_id)?;
@@ -940,7 +1877,11 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
                 })
             } else if let Ok(op) = head.try_into() {
                 let op_index = self.traveler.index();
+                let spaced = self.traveler.head().whitespace_before();
                 self.traveler.move_forward()?;
+                if op == AssignOp::None && spaced {
+                    self.check_ambiguous_assignment()?;
+                }
                 let value = self.expr_atom(scope_id)?;
                 expr.add_op(Precedence::Assignment, |to| {
                     Box::new(AssignExpr { to, op, op_index, value }.into())
@@ -1003,8 +1944,9 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
                 self.traveler.move_forward()?;
             },
             _ => {
-                // TODO: Error about unended array
-                todo!()
+                // The ] was likely forgotten, so the array is treated as ended.
+                let token = self.traveler.head().clone();
+                self.report_error(Error::ArrayExpectedRBracket(token))?;
             },
         }
 
@@ -1025,13 +1967,19 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
         loop {
             match *self.traveler.head().kind() {
                 TokenKind::Comma => {
-                    // TODO: Error about missing parameter
-                    todo!()
+                    // The argument is skipped (as if it wasn't there).
+                    let token = self.traveler.head().clone();
+                    self.report_error(Error::CallExpectedArg(token))?;
+                    self.traveler.move_forward()?;
+                    continue;
                 },
-                TokenKind::RParen | TokenKind::Eof => {
-                    // TODO: Error expected parameter expression
+                TokenKind::RParen => {
+                    if !args.is_empty() {
+                        self.report_error(Error::CallTrailingComma)?;
+                    }
                     break;
                 },
+                TokenKind::Eof => break,
                 _ => {
                     args.push(*self.expr(scope_id, false)?);
                 },
@@ -1041,11 +1989,7 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
                 TokenKind::Comma => {
                     self.traveler.move_forward()?;
                 },
-                TokenKind::RParen | TokenKind::Eof => break,
-                _ => {
-                    // TODO: Error
-                    todo!()
-                },
+                _ => break,
             }
         }
 
@@ -1054,8 +1998,9 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
                 self.traveler.move_forward()?;
             },
             _ => {
-                // TODO: Error
-                todo!()
+                let token = self.traveler.head().clone();
+                self.report_error(Error::CallExpectedRParen(token))?;
+                self.skip_to_call_end()?;
             },
         }
 
@@ -1069,6 +2014,28 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
         }))
     }
 
+    /// Skips the rest of a call's arguments after a malformed one. Tokens are skipped up to
+    /// and including the ) that ends the call. If the statement or block ends first (or the
+    /// file does), the call is treated as ended there instead.
+    fn skip_to_call_end(&mut self) -> MayUnwind<()> {
+        let mut depth = 0usize;
+        loop {
+            match *self.traveler.head().kind() {
+                TokenKind::LParen => depth += 1,
+                TokenKind::RParen if depth == 0 => {
+                    self.traveler.move_forward()?;
+                    return Ok(());
+                },
+                TokenKind::RParen => depth -= 1,
+                TokenKind::Semicolon | TokenKind::RBrace { .. } | TokenKind::Eof => {
+                    return Ok(());
+                },
+                _ => {},
+            }
+            self.traveler.move_forward()?;
+        }
+    }
+
     fn ternary_expr(&mut self, scope_id: ScopeId, expr: Box<Expr>) -> MayUnwind<Box<Expr>> {
         let qmark_index = self.traveler.index();
         // Move past the ?
@@ -1111,6 +2078,19 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
                 let digits = digits.clone();
                 Ok(Box::new(self.number_expr(digits.string(), None)?.into()))
             },
+            TokenKind::Integer(value) => {
+                let index = self.traveler.index();
+                let token = Some(self.traveler.head().kind().clone());
+                self.traveler.move_forward()?;
+                Ok(Box::new(
+                    Number {
+                        kind: NumberKind::I32(value),
+                        index,
+                        token,
+                    }
+                    .into(),
+                ))
+            },
             TokenKind::String {
                 is_char: true,
                 ref str_data,
@@ -1128,18 +2108,23 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
                 self.traveler.move_forward()?;
                 match *self.traveler.head().kind() {
                     _ if self.is_head_a_type(scope_id) => {
-                        Ok(Box::new(self.cast_expr(start_index, scope_id)?.into()))
+                        Ok(Box::new(self.cast_expr(start_index, scope_id)?))
                     },
                     TokenKind::LBrace { .. } => {
-                        Ok(Box::new(self.block_expr(start_index, scope_id)?.into()))
+                        Ok(Box::new(self.stmt_expr(start_index, scope_id)?.into()))
                     },
                     _ => Ok(Box::new(self.parens_expr(start_index, scope_id)?.into())),
                 }
             },
             TokenKind::LBrace { .. } => Ok(Box::new(self.init_expr(scope_id)?.into())),
-            TokenKind::Keyword(Keyword::Generic) => {
-                // TODO: Parse generic
-                todo!("_Generic")
+            TokenKind::Keyword(Keyword::Generic, ..) => {
+                Ok(Box::new(self.generic_expr(scope_id)?.into()))
+            },
+            TokenKind::AmpAmp => Ok(Box::new(self.label_address_expr(scope_id)?.into())),
+            TokenKind::Keyword(Keyword::Nullptr, ..) => {
+                let index = self.traveler.index();
+                self.traveler.move_forward()?;
+                Ok(Box::new(NullptrExpr { index }.into()))
             },
             TokenKind::Identifier(ref id) => {
                 let id = Id {
@@ -1158,8 +2143,111 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
         }
     }
 
+    fn label_address_expr(&mut self, scope_id: ScopeId) -> MayUnwind<LabelAddressExpr> {
+        let start_index = self.traveler.index();
+        if !self.traveler.settings().enable_gnu_extensions {
+            // The expression can still be parsed to recover.
+            self.report_error(Error::GnuExtension("Label addresses"))?;
+        }
+        let label = match *self.traveler.move_forward()?.kind() {
+            TokenKind::Identifier(ref id) => id.clone(),
+            _ => {
+                let token = self.traveler.head().clone();
+                self.report_error(Error::LabelAddressExpectedId(token))?;
+                return Err(Unwind::Block);
+            },
+        };
+        let label_scope_id = self.file.find_scope_kind(scope_id, |kind| kind.manages_labels());
+        self.check_label_later(label_scope_id, &label);
+        self.traveler.move_forward()?;
+        Ok(LabelAddressExpr {
+            range: start_index..self.traveler.index(),
+            label_scope_id,
+            label,
+        })
+    }
+
+    fn generic_expr(&mut self, scope_id: ScopeId) -> MayUnwind<GenericExpr> {
+        let start_index = self.traveler.index();
+        if !matches!(*self.traveler.move_forward()?.kind(), TokenKind::LParen) {
+            let token = self.traveler.head().clone();
+            self.report_error(Error::GenericExpectedLParen(token))?;
+            return Err(Unwind::Block);
+        }
+        self.traveler.move_forward()?;
+        let controller = self.expr(scope_id, false)?;
+
+        let mut associations = SmallVec::new();
+        let mut has_default = false;
+        loop {
+            let is_default = matches!(
+                *self.traveler.head().kind(),
+                TokenKind::Keyword(Keyword::Default, ..)
+            );
+            match *self.traveler.head().kind() {
+                TokenKind::Comma => {
+                    if matches!(*self.traveler.move_forward()?.kind(), TokenKind::RParen) {
+                        self.report_error(Error::GenericTrailingComma)?;
+                        break;
+                    }
+                },
+                TokenKind::RParen => break,
+                // The , was likely forgotten before another association.
+                _ if is_default || self.is_head_a_type(scope_id) => {
+                    let token = self.traveler.head().clone();
+                    self.report_error(Error::GenericExpectedSeparator(token))?;
+                },
+                _ => {
+                    let token = self.traveler.head().clone();
+                    self.report_error(Error::GenericExpectedSeparator(token))?;
+                    return Err(Unwind::Block);
+                },
+            }
+
+            let type_ = if matches!(
+                *self.traveler.head().kind(),
+                TokenKind::Keyword(Keyword::Default, ..)
+            ) {
+                if has_default {
+                    let token = self.traveler.head().clone();
+                    self.report_error(Error::GenericMultipleDefault(token))?;
+                }
+                has_default = true;
+                self.traveler.move_forward()?;
+                None
+            } else {
+                let type_ = self.type_base(scope_id, true)?;
+                Some(self.type_name(type_, scope_id)?)
+            };
+
+            if matches!(*self.traveler.head().kind(), TokenKind::Colon) {
+                self.traveler.move_forward()?;
+            } else {
+                let token = self.traveler.head().clone();
+                self.report_error(Error::GenericExpectedColon(token))?;
+                return Err(Unwind::Block);
+            }
+
+            let expr = self.expr(scope_id, false)?;
+            associations.push((type_, expr));
+        }
+
+        if associations.is_empty() {
+            self.report_error(Error::EmptyGenericAssociations)?;
+        }
+        // Move past the )
+        self.traveler.move_forward()?;
+
+        Ok(GenericExpr {
+            range: start_index..self.traveler.index(),
+            controller,
+            associations,
+        })
+    }
+
     fn number_expr(&mut self, digits: &str, enc: Option<StringEnc>) -> MayUnwind<Number> {
         let index = self.traveler.index();
+        let token = Some(self.traveler.head().kind().clone());
         let mut error_callback = |err: NumberError| self.report_error(err.into()).is_err();
         let kind = if let Some(enc) = enc {
             NumberKind::from_character(&*digits, enc, &mut error_callback)?
@@ -1167,7 +2255,7 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
             NumberKind::from_number(&*digits, &mut error_callback)?
         };
         self.traveler.move_forward()?;
-        Ok(Number { kind, index })
+        Ok(Number { kind, index, token })
     }
 
     fn string_expr(&mut self) -> MayUnwind<StringLiteral> {
@@ -1246,13 +2334,16 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
                             self.traveler.move_forward()?;
                         },
                         TokenKind::Comma => {
-                            // TODO: Error
+                            let token = self.traveler.head().clone();
+                            self.report_error(Error::DesignatorExpectedEqual(token))?;
                             self.traveler.move_forward()?;
                             continue;
                         },
                         TokenKind::RBrace { .. } | TokenKind::Eof => break,
+                        // The = was likely forgotten before the value.
                         _ => {
-                            // TODO: Error missing =
+                            let token = self.traveler.head().clone();
+                            self.report_error(Error::DesignatorExpectedEqual(token))?;
                         },
                     }
 
@@ -1270,22 +2361,21 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
                                 self.traveler.move_forward()?;
                                 break;
                             },
+                            // The = was likely forgotten before the value.
                             _ => {
-                                // TODO: Error
-                                todo!()
+                                let token = self.traveler.head().clone();
+                                self.report_error(Error::DesignatorExpectedEqual(token))?;
+                                break;
                             },
                         }
 
                         let expr = self.expr(scope_id, true)?;
                         indexes.push(*expr);
-                        match *self.traveler.move_forward()?.kind() {
-                            TokenKind::RBracket { .. } => {
-                                self.traveler.move_forward()?;
-                            },
-                            _ => {
-                                // TODO: Error
-                                todo!()
-                            },
+                        if matches!(*self.traveler.head().kind(), TokenKind::RBracket { .. }) {
+                            self.traveler.move_forward()?;
+                        } else {
+                            let token = self.traveler.head().clone();
+                            self.report_error(Error::ArrayExpectedRBracket(token))?;
                         }
                     }
 
@@ -1331,18 +2421,46 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
         })
     }
 
-    fn block_expr(&mut self, start_index: TravelIndex, scope_id: ScopeId) -> MayUnwind<BlockExpr> {
+    fn stmt_expr(&mut self, start_index: TravelIndex, scope_id: ScopeId) -> MayUnwind<StmtExpr> {
         // This function should have been called after the (.
-        let mut block = self.block(scope_id, ScopeKind::Block)?;
-        block.range.start = start_index;
+        if !self.traveler.settings().enable_gnu_extensions {
+            // The expression can still be parsed to recover.
+            self.report_error(Error::GnuExtension("Statement expressions"))?;
+        }
+
+        let block = self.block(scope_id, ScopeKind::Block)?;
         if matches!(*self.traveler.head().kind(), TokenKind::RParen) {
             self.traveler.move_forward()?;
-            block.range.end = self.traveler.index();
         } else {
-            // TODO: Report error
-            todo!()
+            let token = self.traveler.head().clone();
+            self.report_error(Error::StmtExprExpectedRParen(token))?;
+            return Err(Unwind::Block);
         }
-        Ok(block)
+
+        let stmts = &self.file.get_scope(block.scope_id).stmts;
+        let value_index = match stmts.last() {
+            Some(&Stmt::Expr(..)) => Some(stmts.len() - 1),
+            _ => None,
+        };
+        let range = start_index..self.traveler.index();
+        Ok(StmtExpr { range, block, value_index })
+    }
+
+    /// Warns if the head is a +, -, or * directly after an = (like `x =- 1`).
+    ///
+    /// These were likely meant to be compound assignments (like `x -= 1`). This is only
+    /// checked if there was whitespace before the = (so `x=-1` isn't ambiguous).
+    fn check_ambiguous_assignment(&mut self) -> MayUnwind<()> {
+        let head = self.traveler.head();
+        let is_ambiguous = matches!(
+            *head.kind(),
+            TokenKind::Plus | TokenKind::Minus | TokenKind::Star
+        );
+        if is_ambiguous && !head.whitespace_before() {
+            let token = head.clone();
+            self.report_error(Error::AmbiguousAssignment(token))?;
+        }
+        Ok(())
     }
 
     fn prefix_expr(&mut self, scope_id: ScopeId, op: PrefixOp) -> MayUnwind<PrefixExpr> {
@@ -1388,7 +2506,8 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
         })
     }
 
-    fn cast_expr(&mut self, start_index: TravelIndex, scope_id: ScopeId) -> MayUnwind<CastExpr> {
+    /// Parses a cast (or a compound literal if the type is followed by an initializer).
+    fn cast_expr(&mut self, start_index: TravelIndex, scope_id: ScopeId) -> MayUnwind<Expr> {
         // This function should have been called after the (.
         let mut to = self.type_base(scope_id, true)?;
         to = self.type_name(to, scope_id)?;
@@ -1398,35 +2517,178 @@ impl<'a, 'b, E: 'b + ErrorReceiver<ParseError>> ParseState<'a, 'b, E> {
             // TODO: Report error
             todo!()
         }
+
+        if matches!(*self.traveler.head().kind(), TokenKind::LBrace { .. }) {
+            return Ok(self.compound_literal_expr(start_index, to, scope_id)?.into());
+        }
+
         let expr = self.expr_atom(scope_id)?;
         let range = start_index..self.traveler.index();
-        Ok(CastExpr { range, to, expr })
+        Ok(CastExpr { range, to, expr }.into())
+    }
+
+    fn compound_literal_expr(
+        &mut self,
+        start_index: TravelIndex,
+        mut type_: Type,
+        scope_id: ScopeId,
+    ) -> MayUnwind<CompoundLiteralExpr> {
+        let init = self.init_expr(scope_id)?;
+        // Only the outermost array can have an unspecified size.
+        let empty_array = type_.segments.iter().position(|segment| {
+            matches!(*segment, TypeSegment::Array(ref array) if matches!(array.kind, ArrayKind::Empty))
+        });
+        if let (Some(index), Some(len)) = (empty_array, init.positional_len()) {
+            if let TypeSegment::Array(ref mut array) = type_.segments[index as u32] {
+                array.kind = ArrayKind::Inferred(len);
+            }
+        }
+
+        let range = start_index..self.traveler.index();
+        Ok(CompoundLiteralExpr { range, type_, init })
     }
     // endregion: Expression parsing
 
     fn is_head_a_type(&self, scope_id: ScopeId) -> bool {
         match *self.traveler.head().kind() {
-            TokenKind::Keyword(keyword) => match keyword {
+            TokenKind::Keyword(keyword, ..) => match keyword {
                 _ if keyword.is_base_type() => true,
                 _ if keyword.is_type_modifier() => true,
                 _ if keyword.is_type_tag() => true,
+                _ if keyword.is_typeof() => true,
                 _ => false,
             },
-            TokenKind::Identifier(ref id) => {
-                matches!(
-                    self.file.find_decl(scope_id, id),
-                    Some(decl) if decl.is_typedef()
-                )
+            TokenKind::Identifier(ref id) => self.is_typedef_name(scope_id, id),
+            _ => false,
+        }
+    }
+
+    /// Returns whether the type is an integer type (including enums and typedefs of integer types).
+    fn is_integer_type(&self, type_: &Type) -> bool {
+        let only_modifiers = type_
+            .segments
+            .iter()
+            .all(|segment| matches!(*segment, TypeSegment::Modifier(..)));
+        if !only_modifiers {
+            return false;
+        }
+
+        match type_.root {
+            TypeRoot::AutoInt | TypeRoot::Bool | TypeRoot::Char | TypeRoot::Int => true,
+            TypeRoot::Typedef(index) => self.is_integer_type(&self.file.get_decl(index).type_),
+            TypeRoot::Type(index) => {
+                let type_decl = self.file.get_type_decl(index);
+                matches!(type_decl.tags.first(), Some(tag) if tag.kind == TypeDeclKind::Enum)
             },
             _ => false,
         }
     }
 
-    fn report_error(&mut self, error: Error) -> MayUnwind<()> {
-        let full_error = ParseError {
-            kind: error,
-            state: self.traveler.save_state(),
+    /// Returns the range of values the integer type can hold (None if it isn't known).
+    ///
+    /// This assumes an LP64 target, so long and long long are both 64 bits.
+    fn integer_range(&self, type_: &Type) -> Option<RangeInclusive<i128>> {
+        let mut long = false;
+        let mut short = false;
+        let mut unsigned = false;
+        for segment in type_.segments.iter() {
+            match *segment {
+                TypeSegment::Modifier(ModifierSegment::Long(..)) => long = true,
+                TypeSegment::Modifier(ModifierSegment::Short(..)) => short = true,
+                TypeSegment::Modifier(ModifierSegment::Unsigned(..)) => unsigned = true,
+                TypeSegment::Modifier(..) => {},
+                _ => return None,
+            }
+        }
+
+        let bits = match type_.root {
+            TypeRoot::Bool => return Some(0..=1),
+            TypeRoot::Typedef(index) => {
+                return self.integer_range(&self.file.get_decl(index).type_)
+            },
+            TypeRoot::Char => 8,
+            TypeRoot::AutoInt | TypeRoot::Int if short => 16,
+            TypeRoot::AutoInt | TypeRoot::Int if long => 64,
+            TypeRoot::AutoInt | TypeRoot::Int => 32,
+            _ => return None,
         };
-        self.errors.report(full_error)
+        Some(if unsigned {
+            0..=(1 << bits) - 1
+        } else {
+            -(1 << (bits - 1))..=(1 << (bits - 1)) - 1
+        })
+    }
+
+    fn report_error(&mut self, error: Error) -> MayUnwind<()> {
+        self.report_error_with_state(error, self.traveler.save_state())
+    }
+
+    fn report_error_with_state(&mut self, error: Error, state: TravelerState) -> MayUnwind<()> {
+        self.errors.report(ParseError { kind: error, state })
+    }
+}
+
+/// Returns whether the parameter was only an identifier (like the parameters of a K&R function).
+fn is_kr_parameter(type_: &Type) -> bool {
+    type_.is_implicit()
+        && type_.storage.is_implicit()
+        && type_.segments.is_empty()
+        && type_.name.is_some()
+}
+
+/// Evaluates the initializer of an enum constant (None if it can't be evaluated).
+///
+/// Only integer literals, parentheses, unary and binary operators, and the earlier constants
+/// of the same enum are evaluated. The operators work on mathematical integers, so
+/// unsigned wrapping isn't modeled.
+fn enum_constant(expr: &Expr, values: &HashMap<CachedString, i128>) -> Option<i128> {
+    match *expr {
+        Expr::Number(ref number) => match number.kind {
+            NumberKind::I32(i) => Some(i.into()),
+            NumberKind::U32(u) => Some(u.into()),
+            NumberKind::I64(i) => Some(i.into()),
+            NumberKind::U64(u) => Some(u.into()),
+            NumberKind::F32(..) | NumberKind::F64(..) => None,
+        },
+        Expr::DeclRef(ref expr) => values.get(&expr.id.text).copied(),
+        Expr::Parens(ref expr) => enum_constant(&expr.expr, values),
+        Expr::Prefix(ref expr) => {
+            let value = enum_constant(&expr.expr, values)?;
+            match expr.op {
+                PrefixOp::Posate => Some(value),
+                PrefixOp::Negate => value.checked_neg(),
+                PrefixOp::BitNot => Some(!value),
+                PrefixOp::LogicalNot => Some((value == 0).into()),
+                _ => None,
+            }
+        },
+        Expr::Binary(ref expr) => {
+            let lhs = enum_constant(&expr.lhs, values)?;
+            let rhs = enum_constant(&expr.rhs, values)?;
+            use BinaryOp::*;
+            match expr.op {
+                Multiplication => lhs.checked_mul(rhs),
+                Divide => lhs.checked_div(rhs),
+                Modulo => lhs.checked_rem(rhs),
+                Addition => lhs.checked_add(rhs),
+                Subtraction => lhs.checked_sub(rhs),
+                LShift => lhs.checked_shl(u32::try_from(rhs).ok()?),
+                RShift => lhs.checked_shr(u32::try_from(rhs).ok()?),
+                LessThan => Some((lhs < rhs).into()),
+                LessThanOrEqual => Some((lhs <= rhs).into()),
+                GreaterThan => Some((lhs > rhs).into()),
+                GreaterThanOrEqual => Some((lhs >= rhs).into()),
+                Equals => Some((lhs == rhs).into()),
+                NotEquals => Some((lhs != rhs).into()),
+                BitAnd => Some(lhs & rhs),
+                BitXor => Some(lhs ^ rhs),
+                BitOr => Some(lhs | rhs),
+                LogicalOr => Some((lhs != 0 || rhs != 0).into()),
+                LogicalAnd => Some((lhs != 0 && rhs != 0).into()),
+                // A comma can't appear in a constant expression.
+                Comma => None,
+            }
+        },
+        _ => None,
     }
 }
@@ -1,8 +1,24 @@
 // Copyright 2021. remilia-dev
 // This source code is licensed under GPLv3 or any later version.
-use std::path::Path;
+use std::{
+    path::Path,
+    time::Duration,
+};
 
-use crate::sync::Arc;
+use crate::{
+    c::IncludeType,
+    sync::Arc,
+    util::{
+        CachedString,
+        StringCache,
+    },
+};
+
+/// Rewrites the path (and include type) of an include before it is resolved
+/// (see [CompileSettings::include_rewriter]). Returning None leaves the include as it is.
+pub trait IncludeRewriter = Fn(IncludeType, &CachedString, &StringCache) -> Option<(IncludeType, CachedString)>
+    + Send
+    + Sync;
 
 pub struct CompileSettings {
     pub version: LangVersion,
@@ -10,6 +26,66 @@ pub struct CompileSettings {
     pub local_includes: Vec<Box<Path>>,
     pub source_files: Vec<Arc<Path>>,
     pub wchar_is_16_bytes: bool,
+    /// Whether the lexer should record the exact whitespace before each token.
+    /// See [FileTokens::layout_before](crate::c::FileTokens::layout_before).
+    pub record_layout: bool,
+    /// Whether a `/*` inside of a multiline comment should be warned about (like GCC's `-Wcomment`).
+    pub warn_nested_comments: bool,
+    /// Whether function macro parameters that aren't used in the macro's body should be warned about.
+    pub warn_unused_macro_params: bool,
+    /// Macros to define before every source file (like `-D NAME=VALUE` on other compilers).
+    ///
+    /// A define without a value is defined as `1`.
+    pub defines: Vec<(String, Option<String>)>,
+    /// Whether `__FILE__`, `__LINE__`, `__COUNTER__`, `__INCLUDE_LEVEL__`, `__DATE__`, `__TIME__`,
+    /// `__STDC__`, and `__STDC_VERSION__` should be defined.
+    pub builtin_macros: bool,
+    /// Whether GNU extensions that aren't part of standard C are enabled (like the `asm`
+    /// keyword and statement expressions).
+    ///
+    /// Reserved GNU keywords (like `__asm__` and `__typeof__`) are always enabled but
+    /// [alternate spellings](crate::c::Keyword::alt_spellings) of standard keywords aren't.
+    /// Keywords like `_Bool` and `_Generic` are also enabled in versions before they were added.
+    pub enable_gnu_extensions: bool,
+    /// How bytes that aren't valid UTF-8 are handled when a file is read.
+    pub on_invalid_utf8: InvalidUtf8Policy,
+    /// Whether trigraphs (like `??<` for `{`) are replaced. A warning is reported for each
+    /// trigraph that is replaced.
+    pub trigraphs: bool,
+    /// Whether a `'` between two digits of a number is a digit separator (like `1'000'000`)
+    /// rather than the start of a character literal. Digit separators are left out of the
+    /// number token.
+    pub digit_separators: bool,
+    /// Whether decimal numbers without a suffix that fit in an int are lexed as
+    /// [Integer](crate::c::TokenKind::Integer) tokens (so their digits aren't cached and decoded
+    /// again). Their spelling is still available from [Token::spelling](crate::c::Token::spelling).
+    pub integer_tokens: bool,
+    /// Rewrites include paths before they are given to the lexer's include callback
+    /// (like prefixing a sysroot or redirecting a header to a generated file).
+    ///
+    /// Includes are still recorded by the path written in the source.
+    pub include_rewriter: Option<Box<dyn IncludeRewriter>>,
+    /// How many function macros can be expanded within each other before a
+    /// [MacroExpansionDepthExceeded](crate::c::TravelerErrorKind::MacroExpansionDepthExceeded)
+    /// error stops traveling.
+    pub max_macro_depth: usize,
+    /// How many files can be included within each other before an
+    /// [IncludeDepthExceeded](crate::c::TravelerErrorKind::IncludeDepthExceeded) error skips
+    /// the include (a [CircularInclude](crate::c::TravelerErrorKind::CircularInclude) error if
+    /// the file is already being included). Without a limit, a file that includes itself forever
+    /// would never finish.
+    pub max_include_depth: usize,
+    /// How many bytes a source can have before the lexer rejects it with a
+    /// [SourceTooLarge](crate::c::LexerErrorKind::SourceTooLarge) error. Locations store their
+    /// byte as a u32, so larger sources can't be lexed properly anyway.
+    pub max_source_size: u32,
+    /// How long traveling a file can take before a
+    /// [TimeLimitExceeded](crate::c::TravelerErrorKind::TimeLimitExceeded) error stops it
+    /// (None for no limit).
+    pub deadline: Option<Duration>,
+    /// The names of builtin functions `__has_builtin` should report as supported (in addition
+    /// to the [known builtins](crate::c::CompileEnv::known_builtins) vase provides).
+    pub extra_builtins: Vec<String>,
 }
 
 impl CompileSettings {}
@@ -24,6 +100,22 @@ impl Default for CompileSettings {
             local_includes: Vec::new(),
             source_files: Vec::new(),
             wchar_is_16_bytes: false,
+            record_layout: false,
+            warn_nested_comments: false,
+            warn_unused_macro_params: false,
+            defines: Vec::new(),
+            builtin_macros: true,
+            enable_gnu_extensions: true,
+            on_invalid_utf8: InvalidUtf8Policy::Error,
+            trigraphs: false,
+            digit_separators: false,
+            integer_tokens: false,
+            include_rewriter: None,
+            max_macro_depth: 256,
+            max_include_depth: 200,
+            max_source_size: u32::MAX,
+            deadline: None,
+            extra_builtins: Vec::new(),
         };
         #[cfg(feature = "file-reading")]
         {
@@ -48,3 +140,16 @@ pub enum LangVersion {
     C17,
     C23,
 }
+
+/// What the lexer does with a byte that isn't part of a valid UTF-8 character.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum InvalidUtf8Policy {
+    /// Report an [Utf8Decode](crate::c::LexerErrorKind::Utf8Decode) error and skip the byte.
+    Error,
+    /// Replace the byte with U+FFFD (the replacement character).
+    Replace,
+    /// Treat the byte as a Latin-1 character (U+0000 to U+00FF).
+    ///
+    /// Valid UTF-8 characters in the same file are still read as UTF-8.
+    Latin1,
+}
@@ -2,7 +2,7 @@
 // This source code is licensed under GPLv3 or any later version.
 use std::fmt;
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 #[repr(u8)]
 pub enum IncludeType {
     IncludeSystem, // For #include <file>
@@ -64,6 +64,14 @@ pub enum Keyword {
     Pragma,
     StaticAssert,
     ThreadLocal,
+    AutoType,
+    Attribute,
+    Typeof,
+    TypeofUnqual,
+    Nullptr,
+    GnuTypeof,
+    Asm,
+    AsmBuiltin,
 }
 
 impl Keyword {
@@ -118,18 +126,71 @@ impl Keyword {
             Pragma => "_Pragma",
             StaticAssert => "_Static_assert",
             ThreadLocal => "_Thread_local",
+            AutoType => "__auto_type",
+            Attribute => "__attribute__",
+            Typeof => "typeof",
+            TypeofUnqual => "typeof_unqual",
+            Nullptr => "nullptr",
+            GnuTypeof => "__typeof__",
+            Asm => "asm",
+            AsmBuiltin => "__asm__",
         }
     }
 
     pub fn should_add(self, settings: &CompileSettings) -> bool {
         match self {
             Self::Inline | Self::Restrict => settings.version >= LangVersion::C99,
+            // Like GCC, the reserved spellings of newer keywords are accepted as an extension.
+            Self::Bool | Self::Complex | Self::Imaginary => {
+                settings.version >= LangVersion::C99 || settings.enable_gnu_extensions
+            },
+            Self::Alignas
+            | Self::Alignof
+            | Self::Atomic
+            | Self::Generic
+            | Self::Noreturn
+            | Self::StaticAssert
+            | Self::ThreadLocal => {
+                settings.version >= LangVersion::C11 || settings.enable_gnu_extensions
+            },
+            Self::Typeof | Self::TypeofUnqual | Self::Nullptr => {
+                settings.version >= LangVersion::C23
+            },
+            Self::Asm => settings.enable_gnu_extensions,
             _ => true,
         }
     }
 
+    /// Returns the GNU alternate spellings of this keyword (like `__inline__` for `inline`).
+    ///
+    /// These are only keywords when [GNU extensions](CompileSettings::enable_gnu_extensions) are
+    /// enabled. Unlike the keyword's main spelling, they're keywords in every language version.
+    pub fn alt_spellings(self) -> &'static [&'static str] {
+        match self {
+            Self::Const => &["__const", "__const__"],
+            Self::Inline => &["__inline", "__inline__"],
+            Self::Restrict => &["__restrict", "__restrict__"],
+            Self::Signed => &["__signed", "__signed__"],
+            Self::Volatile => &["__volatile", "__volatile__"],
+            Self::ThreadLocal => &["__thread"],
+            Self::AsmBuiltin => &["__asm"],
+            _ => &[],
+        }
+    }
+
     pub fn is_type_starter(self) -> bool {
-        self.is_base_type() | self.is_type_modifier() | self.is_storage_class() | self.is_type_tag()
+        self.is_base_type()
+            | self.is_type_modifier()
+            | self.is_storage_class()
+            | self.is_type_tag()
+            | self.is_typeof()
+            | (self == Self::Alignas)
+            | (self == Self::Attribute)
+    }
+
+    /// Returns whether this keyword is a typeof specifier (typeof, typeof_unqual, or __typeof__).
+    pub fn is_typeof(self) -> bool {
+        matches!(self, Self::Typeof | Self::TypeofUnqual | Self::GnuTypeof)
     }
 
     pub fn is_type_modifier(self) -> bool {
@@ -138,6 +199,7 @@ impl Keyword {
             Self::Const
                 | Self::Inline
                 | Self::Long
+                | Self::Restrict
                 | Self::Short
                 | Self::Signed
                 | Self::Unsigned
@@ -169,6 +231,7 @@ impl Keyword {
                 | Self::Decimal32
                 | Self::Decimal64
                 | Self::Decimal128
+                | Self::AutoType
         )
     }
 
@@ -26,8 +26,17 @@ pub enum TokenKind {
     // OPTIMIZATION: Remove the excess Box (See String too). This would involve using some thin-dst type.
     Message(Arc<Box<str>>),
     Identifier(CachedString),
-    Keyword(Keyword),
+    /// A keyword and how it was spelled. The spelling is different from the
+    /// [keyword's text](Keyword::text) for alternate spellings (like `__inline__`).
+    ///
+    /// Note: The spelling was added along with alternate spellings, so patterns like
+    /// `Keyword(keyword)` need to become `Keyword(keyword, _)` (or `Keyword(keyword, ..)`).
+    Keyword(Keyword, CachedString),
     Number(CachedString),
+    /// A decimal number without a suffix that fits in an int (like `42`). This is only lexed if
+    /// [integer_tokens](crate::c::CompileSettings::integer_tokens) is enabled (other numbers
+    /// are still [Number](TokenKind::Number) tokens).
+    Integer(i32),
     String {
         encoding: StringEnc,
         has_escapes: bool,
@@ -67,6 +76,13 @@ pub enum TokenKind {
     // GCC Extensions
     PreIncludeNext,
     PreWarning,
+    /// A GNU line marker (like `# 1 "file.c" 2`), which is a `#line` directive that can be
+    /// followed by flags. The line number isn't part of the token.
+    PreLineMarker,
+    /// `#assert` (an obsolete System V extension that is ignored).
+    PreAssert,
+    /// `#unassert` (an obsolete System V extension that is ignored).
+    PreUnassert,
     // == End Preprocessors
 
     // == Begin Symbols
@@ -82,6 +98,10 @@ pub enum TokenKind {
     RBracket {
         alt: bool,
     },
+    /// `[[` (only when the two brackets are adjacent)
+    LDoubleBracket,
+    /// `]]` (only when the two brackets are adjacent and end a `[[`)
+    RDoubleBracket,
     /// `(`
     LParen,
     /// `)`
@@ -228,9 +248,10 @@ impl TokenKind {
     pub fn is_number_joinable_with(&self, other: &TokenKind) -> bool {
         use TokenKind::*;
         match *self {
-            Dot => matches!(*other, Number { .. }),
+            Dot => matches!(*other, Number { .. } | Integer(..)),
+            Integer(..) => matches!(*other, Number { .. } | Integer(..) | Identifier(..) | Dot),
             Number(ref digits) => match *other {
-                Number { .. } | Identifier(..) | Dot => true,
+                Number { .. } | Integer(..) | Identifier(..) | Dot => true,
                 Plus | Minus => matches!(
                     digits.string().as_bytes().last(),
                     Some(b'e' | b'E' | b'p' | b'P')
@@ -248,7 +269,10 @@ impl TokenKind {
     pub fn is_id_joinable_with(&self, other: &TokenKind) -> bool {
         use TokenKind::*;
         matches!(self, Identifier(..) | Keyword(..))
-            & matches!(other, Identifier(..) | Keyword(..) | Number { .. })
+            & matches!(
+                other,
+                Identifier(..) | Keyword(..) | Number { .. } | Integer(..)
+            )
     }
 
     /// Gets the token's simple textual form.
@@ -261,7 +285,7 @@ impl TokenKind {
         match *self {
             Message(ref message) => message,
             Identifier(ref id) => id.string(),
-            Keyword(keyword, ..) => keyword.text(),
+            Keyword(_, ref spelling) => spelling.string(),
             Number(ref digits) => digits.string(),
             PreIf { .. } => "#if",
             PreIfDef { .. } => "#ifdef",
@@ -272,14 +296,19 @@ impl TokenKind {
             PreDefine => "#define",
             PreUndef => "#undefine",
             PreLine => "#line",
+            PreLineMarker => "#",
             PreError => "#error",
             PrePragma => "#pragma",
             PreBlank => "#",
             PreInclude => "#include",
             PreIncludeNext => "#include_next",
             PreWarning => "#warning",
+            PreAssert => "#assert",
+            PreUnassert => "#unassert",
             LBracket { alt } => (if alt { "<:" } else { "[" }),
             RBracket { alt } => (if alt { ":>" } else { "]" }),
+            LDoubleBracket => "[[",
+            RDoubleBracket => "]]",
             LParen => "(",
             RParen => ")",
             LBrace { alt } => (if alt { "<%" } else { "{" }),
@@ -348,7 +377,8 @@ impl TokenKind {
             // Comments are to make rustfmt happy.
             PreIf { .. } | PreIfDef { .. } | PreIfNDef { .. } | PreElif { .. } | PreElse { .. } // 1
             | PreEndIf | PreDefine | PreUndef | PreLine | PreError | PrePragma | PreInclude // 2
-            | PreUnknown(..) | PreIncludeNext | PreWarning // 3
+            | PreUnknown(..) | PreIncludeNext | PreWarning | PreLineMarker | PreAssert // 3
+            | PreUnassert // 4
         )
     }
 }
@@ -372,6 +402,7 @@ impl fmt::Display for TokenKind {
                     write!(f, r#"{}"{}""#, prefix, str_data)
                 }
             },
+            Integer(value) => write!(f, "{}", value),
             PreUnknown(ref instr) => write!(f, "#{}", instr),
             LexerError(..) | Eof | PreEnd => Ok(()),
             _ => write!(f, "{}", self.text()),
@@ -1,6 +1,9 @@
 // Copyright 2021. remilia-dev
 // This source code is licensed under GPLv3 or any later version.
-use std::fmt;
+use std::{
+    borrow::Cow,
+    fmt,
+};
 
 pub use self::{
     include_type::IncludeType,
@@ -50,6 +53,31 @@ impl Token {
     pub fn kind_mut(&mut self) -> &mut TokenKind {
         &mut self.kind
     }
+    /// Returns how the token was spelled in the source bytes of its file.
+    ///
+    /// Unlike [TokenKind::text], this keeps the exact spelling (like `<:` instead of `[`).
+    /// Escaped new lines within the token are removed, so the source is only copied if
+    /// the token had one. Bytes that aren't valid UTF-8 are replaced.
+    pub fn spelling<'a>(&self, source: &'a [u8]) -> Cow<'a, str> {
+        let bytes = source.get(self.loc.range()).unwrap_or_default();
+        if !bytes.windows(2).any(|pair| pair == b"\\\n" || pair == b"\\\r") {
+            return String::from_utf8_lossy(bytes);
+        }
+
+        let mut joined = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i..] {
+                [b'\\', b'\n', ..] => i += 2,
+                [b'\\', b'\r', b'\n', ..] => i += 3,
+                _ => {
+                    joined.push(bytes[i]);
+                    i += 1;
+                },
+            }
+        }
+        Cow::Owned(String::from_utf8_lossy(&joined).into_owned())
+    }
 }
 
 impl fmt::Display for Token {
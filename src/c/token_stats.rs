@@ -0,0 +1,73 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use std::ops::AddAssign;
+
+use crate::{
+    c::{
+        FileTokens,
+        TokenKind,
+    },
+    sync::OnceArray,
+    util::FileId,
+};
+
+/// Counts of the tokens in one or more files (see [FileTokens::stats]).
+///
+/// Only tokens that come from the source are counted, so the EOF token, the PreEnd tokens
+/// that end directives, and lexer error tokens are not included in any count (including
+/// [total](Self::total)). Every counted token falls into exactly one category.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct TokenStats {
+    pub identifiers: usize,
+    pub keywords: usize,
+    pub numbers: usize,
+    /// String and character literals.
+    pub strings: usize,
+    pub punctuators: usize,
+    /// Directives (like `#define`) as well as include paths and `#error`/`#warning` messages.
+    pub preprocessor: usize,
+    pub total: usize,
+    /// The number of lexer errors.
+    pub errors: usize,
+}
+
+impl TokenStats {
+    /// Sums the stats of every file in the array.
+    pub fn from_files(files: &OnceArray<FileTokens, FileId>) -> Self {
+        let mut stats = TokenStats::default();
+        for (_, tokens) in files.iter() {
+            stats += tokens.stats();
+        }
+        stats
+    }
+
+    /// Counts the token in its category.
+    pub(super) fn add(&mut self, kind: &TokenKind) {
+        use TokenKind::*;
+        let category = match *kind {
+            Eof | PreEnd | LexerError(..) => return,
+            Identifier(..) => &mut self.identifiers,
+            Keyword(..) => &mut self.keywords,
+            Number(..) | Integer(..) => &mut self.numbers,
+            String { .. } => &mut self.strings,
+            IncludePath { .. } | Message(..) | PreBlank => &mut self.preprocessor,
+            _ if kind.is_preprocessor() => &mut self.preprocessor,
+            _ => &mut self.punctuators,
+        };
+        *category += 1;
+        self.total += 1;
+    }
+}
+
+impl AddAssign for TokenStats {
+    fn add_assign(&mut self, other: Self) {
+        self.identifiers += other.identifiers;
+        self.keywords += other.keywords;
+        self.numbers += other.numbers;
+        self.strings += other.strings;
+        self.punctuators += other.punctuators;
+        self.preprocessor += other.preprocessor;
+        self.total += other.total;
+        self.errors += other.errors;
+    }
+}
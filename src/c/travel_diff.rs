@@ -0,0 +1,123 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use std::cell::RefCell;
+
+use crate::{
+    c::{
+        CompileEnv,
+        FileTokens,
+        IncludeCallback,
+        Lexer,
+        Token,
+        TokenKind,
+        Traveler,
+        TravelerError,
+        TravelerErrorKind,
+    },
+    error::{
+        ErrorReceiver,
+        MayUnwind,
+        Unwind,
+    },
+    sync::Arc,
+};
+
+/// The first point at which two travels produced different tokens.
+#[derive(Clone, Debug)]
+pub struct TravelDivergence {
+    /// How many tokens both travels produced before they diverged.
+    pub index: usize,
+    /// The token the left travel produced (this will be an Eof if it ended early).
+    pub left: Token,
+    /// The token the right travel produced (this will be an Eof if it ended early).
+    pub right: Token,
+}
+
+/// Travels two token stacks side by side and returns where they first differ (if they do).
+///
+/// Tokens are only compared by their kinds since their locations are expected to differ.
+/// Errors from both travels are reported to the given receiver.
+pub fn diff_travels<E>(
+    env: &CompileEnv,
+    left: Arc<FileTokens>,
+    right: Arc<FileTokens>,
+    errors: E,
+) -> MayUnwind<Option<TravelDivergence>>
+where
+    E: ErrorReceiver<TravelerError>,
+{
+    let errors = Arc::new(RefCell::new(errors));
+    let mut left_traveler = Traveler::new(env, errors.clone());
+    let mut right_traveler = Traveler::new(env, errors);
+    left_traveler.load_start(left)?;
+    right_traveler.load_start(right)?;
+
+    let mut index = 0;
+    loop {
+        let left = left_traveler.head();
+        let right = right_traveler.head();
+        if left.kind() != right.kind() {
+            return Ok(Some(TravelDivergence {
+                index,
+                left: left.clone(),
+                right: right.clone(),
+            }));
+        }
+        if let TokenKind::Eof = *left.kind() {
+            return Ok(None);
+        }
+
+        left_traveler.move_forward()?;
+        right_traveler.move_forward()?;
+        index += 1;
+    }
+}
+
+/// Lexes the source twice (once with each include callback) and then compares
+/// the results of traveling through them. See [diff_travels] for more information.
+///
+/// Any files the callbacks resolve to must already be in the environment's
+/// [file_id_to_tokens](CompileEnv::file_id_to_tokens). If there are no file ids left for the
+/// lexed sources, a fatal [OutOfFileIds](TravelerErrorKind::OutOfFileIds) is reported.
+pub fn diff_include_configs<A, B, E>(
+    env: &CompileEnv,
+    source: &[u8],
+    left_callback: A,
+    right_callback: B,
+    errors: E,
+) -> MayUnwind<Option<TravelDivergence>>
+where
+    A: IncludeCallback,
+    B: IncludeCallback,
+    E: ErrorReceiver<TravelerError>,
+{
+    let mut errors = Arc::new(RefCell::new(errors));
+    let left = lex_into_env(env, source, Lexer::new(env, left_callback), &mut errors)?;
+    let right = lex_into_env(env, source, Lexer::new(env, right_callback), &mut errors)?;
+    diff_travels(env, left, right, errors)
+}
+
+fn lex_into_env<C, E>(
+    env: &CompileEnv,
+    source: &[u8],
+    mut lexer: Lexer<C>,
+    errors: &mut E,
+) -> MayUnwind<Arc<FileTokens>>
+where
+    C: IncludeCallback,
+    E: ErrorReceiver<TravelerError>,
+{
+    let file_id = match env.file_id_to_tokens.reserve() {
+        Some(file_id) => file_id,
+        None => {
+            // Nothing has been traveled yet, so the error is given the state of a new traveler.
+            let state = Traveler::new(env, |_: TravelerError| false).save_state();
+            let kind = TravelerErrorKind::OutOfFileIds;
+            errors.report(TravelerError { state, kind })?;
+            return Err(Unwind::Fatal);
+        },
+    };
+    let tokens = Arc::new(lexer.lex_bytes(file_id, source));
+    env.file_id_to_tokens.set_or_panic(file_id, tokens.clone());
+    Ok(tokens)
+}
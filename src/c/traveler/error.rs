@@ -1,5 +1,7 @@
 // Copyright 2021. remilia-dev
 // This source code is licensed under GPLv3 or any later version.
+use std::time::Duration;
+
 use crate::{
     c::{
         ast::{
@@ -49,6 +51,18 @@ impl CodedError for TravelerError {
     fn message(&self) -> String {
         self.kind.message()
     }
+
+    fn loc(&self) -> Option<SourceLoc> {
+        let loc = self.state.loc()?;
+        match self.kind {
+            TravelerErrorKind::Number(ref error) => Some(error.loc_in(loc)),
+            _ => Some(loc),
+        }
+    }
+
+    fn notes(&self) -> Vec<(SourceLoc, String)> {
+        self.kind.notes()
+    }
 }
 
 enum_with_properties! {
@@ -67,6 +81,12 @@ enum_with_properties! {
         // == Fatals
         #[values(Fatal, 800)]
         ErrorPreprocessor(Option<Arc<Box<str>>>),
+        #[values(Fatal, 810)]
+        MacroExpansionDepthExceeded(usize),
+        #[values(Fatal, 811)]
+        TimeLimitExceeded(Duration),
+        #[values(Fatal, 812)]
+        OutOfFileIds,
         #[values(Fatal, 850)]
         IncludeNotFound(Option<FileId>, IncludeType, CachedString),
         // == Errors
@@ -90,10 +110,24 @@ enum_with_properties! {
         IfDiv0(Token, Sign, Box<BinaryExpr>),
         #[values(Error, 517)]
         IfReal(Token, Token),
+        #[values(Error, 518)]
+        IfHasIncludeExpectedParens(Token, Token),
+        #[values(Error, 519)]
+        IfHasIncludeExpectedPath(Token, Token),
         #[values(Error, 520)]
         ElseExtraTokens,
         #[values(Error, 521)]
         EndIfExtraTokens,
+        #[values(Error, 522)]
+        IfHasAttributeExpectedParens(Token, Token),
+        #[values(Error, 523)]
+        IfHasAttributeExpectedName(Token, Token),
+        #[values(Error, 524)]
+        UnbalancedConditional(Token),
+        #[values(Error, 525)]
+        IfHasBuiltinExpectedParens(Token, Token),
+        #[values(Error, 526)]
+        IfHasBuiltinExpectedName(Token, Token),
         #[values(Error, 530)]
         DefineExpectedId(Token),
         #[values(Error, 531)]
@@ -108,10 +142,20 @@ enum_with_properties! {
         UndefExpectedId(Token),
         #[values(Error, 541)]
         UndefExtraTokens,
+        #[values(Error, 545)]
+        LineExpectedNumber(Token),
+        #[values(Error, 546)]
+        LineInvalidNumber(Token),
+        #[values(Error, 547)]
+        LineExtraTokens(Token),
         #[values(Error, 550)]
         IncludeExpectedPath(Token),
         #[values(Error, 551)]
         IncludeExtraTokens,
+        #[values(Error, 552)]
+        IncludeDepthExceeded(usize),
+        #[values(Error, 553)]
+        CircularInclude(FileId),
         #[values(Error, 560)]
         FuncInvokeMissingArgs(usize),
         #[values(Error, 561)]
@@ -124,6 +168,10 @@ enum_with_properties! {
         StringifyExpectsId(Token),
         #[values(Error, 565)]
         StringifyNonParameter(Token),
+        #[values(Error, 566)]
+        VaOptExpectedLParen(Token),
+        #[values(Error, 567)]
+        VaOptExpectedRParen(Token),
         #[values(Error, 570)]
         InvalidJoin(Token, SourceLoc, Token),
         #[values(Error, 580)]
@@ -136,7 +184,17 @@ enum_with_properties! {
         StrayBackslash,
         #[values(Error, 590)]
         UnknownPreprocessor(CachedString),
+        #[values(Error, 595)]
+        PragmaExpectedLParen(Token),
+        #[values(Error, 596)]
+        PragmaExpectedString(Token),
+        #[values(Error, 597)]
+        PragmaExpectedRParen(Token),
         // == Warning
+        #[values(Warning, 200)]
+        MacroRedefinitionMismatch(Token, SourceLoc),
+        #[values(Warning, 201)]
+        UnusedMacroParameter(CachedString, CachedString),
         #[values(Warning, 210)]
         CommaInIfCondition,
         #[values(Warning, 211)]
@@ -149,8 +207,15 @@ enum_with_properties! {
         ShiftedToMuch(Sign, Sign, Box<BinaryExpr>),
         #[values(Warning, 280)]
         WarningPreprocessor(Option<Arc<Box<str>>>),
-        #[values(Warning, 299)]
-        UnsupportableLinePreprocessor,
+        #[values(Warning, 281)]
+        MessagePragmaExpectedString,
+        #[values(Warning, 282)]
+        PopMacroUnderflow(CachedString),
+        #[values(Warning, 283)]
+        AssertPreprocessorIgnored(Token),
+        // == Notes
+        #[values(Note, 100)]
+        MessagePragma(Arc<Box<str>>),
     }
 
     impl CodedError for TravelerErrorKind {
@@ -169,6 +234,25 @@ enum_with_properties! {
             }
         }
 
+        fn notes(&self) -> Vec<(SourceLoc, String)> {
+            use TravelerErrorKind::*;
+            match *self {
+                Lexer(ref error) => error.notes(),
+                Number(ref error) => error.notes(),
+                IfDefExpectedId(ref ifdef, _) => {
+                    vec![(ifdef.loc(), format!("The {} is here.", ifdef))]
+                },
+                InvalidJoin(ref left, _, ref right) => vec![
+                    (left.loc(), format!("The left side of ## is {}.", left)),
+                    (right.loc(), format!("The right side of ## is {}.", right)),
+                ],
+                MacroRedefinitionMismatch(ref id, previous) => {
+                    vec![(previous, format!("The previous definition of {} is here.", id))]
+                },
+                _ => Vec::new(),
+            }
+        }
+
         fn message(&self) -> String {
             // NOTE: See the end of this file for the messages
             Self::message(self)
@@ -214,6 +298,14 @@ impl TravelerErrorKind {
                 "#error: {}",
                 message.as_ref().map_or("", |message| &*message)
             ),
+            MacroExpansionDepthExceeded(max_depth) => format!(
+                "Function macros were expanded more than {} deep (see max_macro_depth).",
+                max_depth
+            ),
+            TimeLimitExceeded(deadline) => {
+                format!("Preprocessing took longer than the deadline ({:?}).", deadline)
+            },
+            OutOfFileIds => "Ran out of file ids to give the source that was lexed.".to_owned(),
             IncludeNotFound(_,kind, ref path) => format!(
                 "A {} of the path {} could not be found.",
                 kind, path
@@ -224,7 +316,7 @@ impl TravelerErrorKind {
                     "{} expects an identifier to follow on the same line. None was found.",
                     ifdef
                 ),
-                TokenKind::Number(..) => format!(
+                TokenKind::Number(..) | TokenKind::Integer(..) => format!(
                     "{} would not expect a number to follow since numbers cannot be macros.",
                     ifdef
                 ),
@@ -251,7 +343,7 @@ impl TravelerErrorKind {
                     "The defined preprocessor operator should be followed by an identifier."
                         .to_owned()
                 },
-                TokenKind::Number(..) => {
+                TokenKind::Number(..) | TokenKind::Integer(..) => {
                     "The defined preprocessor operator would not expect a number \
                     since numbers cannot be macros."
                         .to_owned()
@@ -304,18 +396,46 @@ impl TravelerErrorKind {
                 "Real numbers are not allowed in {} conditions. Only integers can be used.",
                 if_token
             ),
+            IfHasIncludeExpectedParens(_, ref token) => format!(
+                "The path of __has_include should be between a ( and a ) (found {}).",
+                token
+            ),
+            IfHasIncludeExpectedPath(_, ref token) => format!(
+                "__has_include expects a \"path\" or <path> (not a {}).",
+                token
+            ),
             ElseExtraTokens => {
                 "#else should not be followed by anything on the same line.".to_owned()
             },
             EndIfExtraTokens => {
                 "#endif should not be followed by anything on the same line.".to_owned()
             },
+            IfHasAttributeExpectedParens(_, ref token) => format!(
+                "The attribute name of __has_c_attribute or __has_attribute should be between a ( and a ) (found {}).",
+                token
+            ),
+            IfHasAttributeExpectedName(_, ref token) => format!(
+                "__has_c_attribute and __has_attribute expect the name of an attribute (not a {}).",
+                token
+            ),
+            UnbalancedConditional(ref token) => format!(
+                "{} is not within an #if, #ifdef, or #ifndef. It was ignored.",
+                token.kind()
+            ),
+            IfHasBuiltinExpectedParens(_, ref token) => format!(
+                "The builtin name of __has_builtin should be between a ( and a ) (found {}).",
+                token
+            ),
+            IfHasBuiltinExpectedName(_, ref token) => format!(
+                "__has_builtin expects the name of a builtin function (not a {}).",
+                token
+            ),
             DefineExpectedId(ref token) => match *token.kind() {
                 TokenKind::PreEnd => {
                     "#define expects an identifier to follow on the same line. None was found."
                         .to_owned()
                 },
-                TokenKind::Number(..) => {
+                TokenKind::Number(..) | TokenKind::Integer(..) => {
                     "A macro can't be defined starting with a number."
                         .to_owned()
                 },
@@ -328,7 +448,7 @@ impl TravelerErrorKind {
                 "An end-of-line occured before the end of a func-macro's arguments.".to_owned()
             },
             DefineFuncExpectedArg(ref token) => {
-                if let TokenKind::Number(..) = *token.kind() {
+                if let TokenKind::Number(..) | TokenKind::Integer(..) = *token.kind() {
                     "A function macro argument can't be a number. It must start with a non-digit."
                         .to_owned()
                 } else {
@@ -368,7 +488,7 @@ impl TravelerErrorKind {
                     on the same line. None was found."
                         .to_owned()
                 },
-                TokenKind::Number(..) => {
+                TokenKind::Number(..) | TokenKind::Integer(..) => {
                     "#undef would not expect a number to follow it since numbers cannot be macros."
                         .to_owned()
                 },
@@ -380,6 +500,18 @@ impl TravelerErrorKind {
             UndefExtraTokens => {
                 "#undef expects only a single identifier to follow it on the same line.".to_owned()
             },
+            LineExpectedNumber(ref token) => format!(
+                "#line should be followed by a line number (not a {}).",
+                token
+            ),
+            LineInvalidNumber(ref token) => format!(
+                "#line's line number should be a decimal number no larger than 2147483647 (not {}).",
+                token
+            ),
+            LineExtraTokens(ref token) => format!(
+                "#line expects only a line number and an optional file name (found {}).",
+                token
+            ),
             IncludeExpectedPath(ref path) => match *path.kind() {
                 TokenKind::PreEnd => {
                     "Expected an include path before the end of the line.".to_owned()
@@ -392,6 +524,13 @@ impl TravelerErrorKind {
             IncludeExtraTokens => {
                 "Only a single include path should follow an include directive.".to_owned()
             },
+            IncludeDepthExceeded(max_depth) => format!(
+                "Files were included more than {} deep (see max_include_depth).",
+                max_depth
+            ),
+            CircularInclude(_) => {
+                "A file kept including itself (see max_include_depth).".to_owned()
+            },
             FuncInvokeMissingArgs(count) => format!(
                 "This func-macro invocation is missing {} parameters.",
                 count
@@ -416,6 +555,14 @@ impl TravelerErrorKind {
                 "Can't stringify {} as it isn't a parameter to this function macro.",
                 token
             ),
+            VaOptExpectedLParen(ref token) => format!(
+                "Expected a ( after __VA_OPT__ (not a {}).",
+                token
+            ),
+            VaOptExpectedRParen(ref token) => format!(
+                "Expected a ) to end the __VA_OPT__ (not a {}).",
+                token
+            ),
             InvalidJoin(ref left, _, ref right) => format!(
                 "{} ## {} does not produce a valid token ({0}{1} has to be a single token).",
                 left, right
@@ -437,7 +584,27 @@ impl TravelerErrorKind {
                 "'#{}' is an unknown preprocessor instruction.",
                 instruction
             ),
+            PragmaExpectedLParen(ref token) => format!(
+                "_Pragma should be followed by a ( (not a {}).",
+                token
+            ),
+            PragmaExpectedString(ref token) => format!(
+                "_Pragma's operand should be a string literal (not a {}).",
+                token
+            ),
+            PragmaExpectedRParen(ref token) => format!(
+                "Expected a ) to end the _Pragma (not a {}).",
+                token
+            ),
             // == Warnings
+            MacroRedefinitionMismatch(ref id, _) => format!(
+                "{} was redefined with a different replacement list.",
+                id
+            ),
+            UnusedMacroParameter(ref id, ref param) => format!(
+                "The parameter {} is never used in the body of the function macro {}.",
+                param, id
+            ),
             CommaInIfCondition => {
                 "The comma operator discards everything before it in the conditional.".to_owned()
             },
@@ -461,9 +628,19 @@ impl TravelerErrorKind {
                 "#warning: {}",
                 message.as_ref().map_or("", |message| &*message)
             ),
-            UnsupportableLinePreprocessor => {
-                "Due to this compiler's design, #line is unsupportable.".to_owned()
+            MessagePragmaExpectedString => {
+                "#pragma message expects a string literal (that may be in parentheses).".to_owned()
             },
+            PopMacroUnderflow(ref id) => format!(
+                "#pragma pop_macro(\"{}\") has no pushed definition to restore.",
+                id
+            ),
+            AssertPreprocessorIgnored(ref token) => format!(
+                "{} is an obsolete extension and is ignored.",
+                token
+            ),
+            // == Notes
+            MessagePragma(ref message) => format!("#pragma message: {}", message),
         }
     }
 }
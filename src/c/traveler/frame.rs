@@ -28,6 +28,8 @@ pub(super) enum Frame {
         ///
         /// For include frames, this will *exclude* the [Eof](crate::c::CTokenKind::Eof) token.
         end: usize,
+        /// How many conditionals (`#if`, `#ifdef`, or `#ifndef`) travel is within in this file.
+        open_conditionals: usize,
     },
     /// A frame that represents a single token.
     ///
@@ -70,6 +72,15 @@ pub(super) enum Frame {
         /// The id of the macro.
         id: CachedString,
     },
+    /// A frame that represents tokens that are not from a file or macro.
+    ///
+    /// This is used to travel through arbitrary tokens (such as an expression being evaluated).
+    Tokens {
+        /// The tokens of this frame.
+        tokens: Arc<Vec<Token>>,
+        /// The current index of token we are at.
+        index: usize,
+    },
     /// A frame that is used to collect the tokens for a function macro.
     TokenCollector {
         /// The id of the file to get tokens from.
@@ -82,6 +93,8 @@ pub(super) enum Frame {
         end: usize,
         /// A map from a unique id to the tokens the parameter makes up.
         params: HashMap<CachedString, Vec<Token>>,
+        /// The id of the variable argument parameter (if the function macro has one).
+        var_arg: Option<CachedString>,
     },
     /// A frame that represents a token collector's parameter.
     ///
@@ -101,7 +114,9 @@ impl Frame {
         use Frame::*;
         match *self {
             File { file_id, .. } | ObjectMacro { file_id, .. } => file_id,
-            FuncMacro { ref tokens, index, .. } => tokens[index].loc().file_id(),
+            FuncMacro { ref tokens, index, .. } | Tokens { ref tokens, index } => {
+                tokens[index].loc().file_id()
+            },
             SingleToken { ref token, .. } => token.loc().file_id(),
             TokenCollector { .. } | TokenCollectorParameter { .. } => panic!(
                 "Can't get the file id on token collector frames! No analysis should be performed within these frames."
@@ -123,7 +138,7 @@ impl Frame {
                 *index = index.wrapping_add(1);
                 *index < end
             },
-            FuncMacro { ref mut index, ref tokens, .. } => {
+            FuncMacro { ref mut index, ref tokens, .. } | Tokens { ref mut index, ref tokens } => {
                 *index = index.wrapping_add(1);
                 *index < tokens.len()
             },
@@ -135,7 +150,7 @@ impl Frame {
             Frame::TokenCollector { ref params, .. } => {
                 let param_vec = params.get(id)?;
                 Some(if param_vec.is_empty() {
-                    MacroHandle::Empty
+                    MacroHandle::Empty(None)
                 } else {
                     MacroHandle::Simple(Frame::TokenCollectorParameter {
                         param_id: id.clone(),
@@ -148,6 +163,20 @@ impl Frame {
         }
     }
 
+    /// Returns true if this is a token collector whose variable argument parameter isn't empty.
+    ///
+    /// This decides whether the tokens of a `__VA_OPT__` are kept.
+    pub fn has_var_args(&self) -> bool {
+        match *self {
+            Frame::TokenCollector {
+                ref params, var_arg: Some(ref id), ..
+            } => {
+                matches!(params.get(id), Some(tokens) if !tokens.is_empty())
+            },
+            _ => false,
+        }
+    }
+
     pub fn stringify(&self, id: &CachedString) -> Option<String> {
         use std::fmt::Write;
         match *self {
@@ -1,21 +1,35 @@
 // Copyright 2021. remilia-dev
 // This source code is licensed under GPLv3 or any later version.
-use std::collections::{
-    HashMap,
-    VecDeque,
+use std::{
+    collections::{
+        HashMap,
+        HashSet,
+        VecDeque,
+    },
+    sync::atomic::{
+        AtomicU32,
+        Ordering,
+    },
 };
 
 use crate::{
     c::{
         traveler::{
+            BuiltinMacro,
             Frame,
+            MacroDefinition,
             MacroHandle,
             MacroKind,
             TravelerError,
             TravelerState,
         },
         CompileEnv,
+        CompileSettings,
+        EnvConfig,
         FileTokens,
+        IncludeType,
+        LangVersion,
+        StringEnc,
         Token,
         TokenKind,
         TokenKind::*,
@@ -28,28 +42,77 @@ use crate::{
     util::{
         CachedString,
         FileId,
+        MappedSourceLoc,
+        SourceLoc,
     },
 };
 
 type Error = crate::c::TravelerErrorKind;
 type Receiver<'a> = &'a mut dyn ErrorReceiver<TravelerError>;
 
+/// The line number (and file name) a `#line` directive gives the lines after it.
+#[derive(Clone, Debug)]
+pub(super) struct LineOverride {
+    /// The physical line (starting at 1) the directive ended on.
+    directive_line: u32,
+    /// The line number of the line after the directive.
+    line: u32,
+    file_name: Option<CachedString>,
+}
+
 /// A manager struct for where [Traveler](super::Traveler) is in a token stack.
 ///
 /// This includes reading tokens from macros and includes. It is important to note
 /// that FrameStack *never* handles preprocessor instructions (CTraveler does).
 pub(super) struct FrameStack<'a> {
     env: &'a CompileEnv,
+    /// The env's config when the stack was last [started](Self::load_start).
+    config: Arc<EnvConfig>,
     /// A map from file ids to the token stacks. Token stacks are loaded into here as needed.
     file_refs: HashMap<FileId, Arc<FileTokens>>,
     /// A vec-deque of frames. The frame that is currently being worked on will always be at index 0.
     frames: VecDeque<Frame>,
     /// A list of all the files that have been read so far during travel.
     dependencies: Vec<FileId>,
-    /// A map from a macro's unique id to the kind of macro it is.
+    /// The files that used `#pragma once` (so they shouldn't be included again).
+    once_files: HashSet<FileId>,
+    /// The `#line` directives of each file (in the order they appear in the file).
+    line_overrides: HashMap<FileId, Vec<LineOverride>>,
+    /// A map from a macro's unique id to its definition.
     ///
     /// A macro's unique id is the uniq_id() of its identifier.
-    macros: HashMap<CachedString, MacroKind>,
+    macros: HashMap<CachedString, MacroDefinition>,
+    /// A map from the ids of the built-in macros to which built-in they are.
+    ///
+    /// This is empty if [builtin_macros](crate::c::CompileSettings::builtin_macros) is disabled.
+    builtins: HashMap<CachedString, BuiltinMacro>,
+    /// The definitions saved by `#pragma push_macro` for each macro id.
+    ///
+    /// None means the macro was undefined when it was pushed.
+    macro_save_stacks: HashMap<CachedString, Vec<Option<MacroDefinition>>>,
+    /// The ids of the defined macros that have been expanded (built-ins aren't included).
+    used_macros: HashSet<CachedString>,
+    /// The number `__COUNTER__` will expand to next (shared with the env's
+    /// [counter](CompileEnv::counter)).
+    counter: Arc<AtomicU32>,
+    /// The values this stack took from the counter (in the order `__COUNTER__` was expanded).
+    ///
+    /// These aren't part of saved states. Loading a state only rewinds counter_uses, so the
+    /// same values are expanded again without the shared counter ever being rewound.
+    counter_values: Vec<u32>,
+    /// How many times `__COUNTER__` has been expanded.
+    counter_uses: usize,
+    /// The maximum alignment of struct members set by `#pragma pack` (None for the default).
+    pack: Option<u32>,
+    /// The pack values saved by `#pragma pack(push)`.
+    pack_stack: Vec<Option<u32>>,
+    /// How many function macros are being expanded within each other right now.
+    ///
+    /// This is the number of function macro frames on the stack (a macro's token collector
+    /// frame counts while its arguments are being expanded, then its FuncMacro frame does).
+    ///
+    /// See [max_macro_depth](crate::c::CompileSettings::max_macro_depth).
+    macro_depth: usize,
     /// Whether CTraveler should skip-ahead on PreElseIf/PreElse tokens.
     ///
     /// This is set to true every time the stack is moved. The only way it is false
@@ -65,24 +128,60 @@ pub(super) struct FrameStack<'a> {
 impl<'a> FrameStack<'a> {
     /// Creates a new frame stack from the given compile environment.
     pub fn new(env: &'a CompileEnv) -> Self {
+        let config = env.config();
         // OPTIMIZATION: A different hasher may be more performant
         FrameStack {
             env,
+            builtins: builtin_macros(env, config.settings()),
+            config,
             file_refs: HashMap::default(),
             frames: VecDeque::default(),
             dependencies: Vec::new(),
+            once_files: HashSet::new(),
+            line_overrides: HashMap::new(),
             macros: HashMap::default(),
+            macro_save_stacks: HashMap::new(),
+            used_macros: HashSet::new(),
+            counter: env.counter().clone(),
+            counter_values: Vec::new(),
+            counter_uses: 0,
+            pack: None,
+            pack_stack: Vec::new(),
+            macro_depth: 0,
             should_chain_skip: true,
             index: 0,
         }
     }
+    /// Creates an empty frame stack that knows this stack's files, so this stack's saved states
+    /// can be loaded into it. It also expands `__COUNTER__` with the values this stack did.
+    pub fn new_scratch(&self) -> Self {
+        let mut scratch = FrameStack::new(self.env);
+        scratch.config = self.config.clone();
+        scratch.builtins = self.builtins.clone();
+        scratch.file_refs = self.file_refs.clone();
+        scratch.counter_values = self.counter_values.clone();
+        scratch
+    }
     /// Sets up the frame stack up to start processing the given token stack.
     ///
-    /// This removes all previous macros/frames.
+    /// This removes all previous macros/frames. The env's current config is taken again and the
+    /// built-in macros are set up from it (in case the env was
+    /// [reconfigured](CompileEnv::reconfigure)).
     pub fn load_start(&mut self, tokens: Arc<FileTokens>) {
         self.frames.clear();
         self.macros.clear();
+        self.config = self.env.config();
+        self.builtins = builtin_macros(self.env, self.config.settings());
         self.dependencies.clear();
+        self.once_files.clear();
+        self.line_overrides.clear();
+        self.macro_save_stacks.clear();
+        self.used_macros.clear();
+        self.counter_values.clear();
+        self.counter_uses = 0;
+        self.pack = None;
+        self.pack_stack.clear();
+        self.macro_depth = 0;
         self.should_chain_skip = true;
         self.index = 0;
 
@@ -90,6 +189,20 @@ impl<'a> FrameStack<'a> {
             file_id: tokens.file_id(),
             end: tokens.len(),
             index: usize::MAX,
+            open_conditionals: 0,
+        });
+        self.file_refs.insert(tokens.file_id(), tokens);
+    }
+    /// Pushes a file of predefined macros that will be traveled before the start file.
+    ///
+    /// This should only be used right after [load_start](Self::load_start).
+    pub fn push_predefined(&mut self, tokens: Arc<FileTokens>) {
+        self.frames.push_front(Frame::File {
+            file_id: tokens.file_id(),
+            // The -1 is to exclude the EOF token.
+            end: tokens.len() - 1,
+            index: usize::MAX,
+            open_conditionals: 0,
         });
         self.file_refs.insert(tokens.file_id(), tokens);
     }
@@ -107,8 +220,21 @@ impl<'a> FrameStack<'a> {
             frames: self.frames.clone(),
             macros: self.macros.clone(),
             dependencies: self.dependencies.clone(),
+            once_files: self.once_files.clone(),
+            line_overrides: self.line_overrides.clone(),
+            macro_save_stacks: self.macro_save_stacks.clone(),
+            used_macros: self.used_macros.clone(),
+            counter_uses: self.counter_uses,
+            pack: self.pack,
+            pack_stack: self.pack_stack.clone(),
+            macro_depth: self.macro_depth,
             should_chain_skip: self.should_chain_skip,
             index: self.index,
+            loc: if self.frames.is_empty() {
+                None
+            } else {
+                Some(self.map_loc(self.head().loc()))
+            },
         }
     }
     /// Loads the given saved state.
@@ -120,9 +246,34 @@ impl<'a> FrameStack<'a> {
         self.frames = state.frames;
         self.macros = state.macros;
         self.dependencies = state.dependencies;
+        self.once_files = state.once_files;
+        self.line_overrides = state.line_overrides;
+        self.macro_save_stacks = state.macro_save_stacks;
+        self.used_macros = state.used_macros;
+        self.counter_uses = state.counter_uses;
+        self.pack = state.pack;
+        self.pack_stack = state.pack_stack;
+        self.macro_depth = state.macro_depth;
         self.should_chain_skip = state.should_chain_skip;
         self.index = state.index;
     }
+    /// The maximum alignment of struct members set by `#pragma pack` (None for the default).
+    pub fn pack(&self) -> Option<u32> {
+        self.pack
+    }
+    pub fn set_pack(&mut self, pack: Option<u32>) {
+        self.pack = pack;
+    }
+    /// Saves the current pack value (for `#pragma pack(push)`).
+    pub fn push_pack(&mut self) {
+        self.pack_stack.push(self.pack);
+    }
+    /// Restores the last pushed pack value (for `#pragma pack(pop)`).
+    ///
+    /// The pack value is reset to the default if there were no pushed values.
+    pub fn pop_pack(&mut self) {
+        self.pack = self.pack_stack.pop().flatten();
+    }
     /// Returns a reference to the current token the frame stack is at.
     pub fn head(&self) -> &Token {
         match self.frames[0] {
@@ -130,7 +281,9 @@ impl<'a> FrameStack<'a> {
             | Frame::ObjectMacro { file_id, index, .. }
             | Frame::TokenCollector { file_id, index, .. } => &self.file_refs[&file_id][index],
             Frame::SingleToken { ref token, .. } => token,
-            Frame::FuncMacro { index, ref tokens, .. } => &tokens[index],
+            Frame::FuncMacro { index, ref tokens, .. } | Frame::Tokens { index, ref tokens } => {
+                &tokens[index]
+            },
             Frame::TokenCollectorParameter { index, ref param_id, .. } => {
                 if let Frame::TokenCollector { ref params, .. } = self.frames[1] {
                     &params[param_id][index]
@@ -170,7 +323,8 @@ impl<'a> FrameStack<'a> {
                         return None;
                     }
                 },
-                Frame::FuncMacro { index, ref tokens, .. } => {
+                Frame::FuncMacro { index, ref tokens, .. }
+                | Frame::Tokens { index, ref tokens } => {
                     if index + 1 > tokens.len() {
                         if exit_macros {
                             continue;
@@ -209,7 +363,9 @@ impl<'a> FrameStack<'a> {
     pub fn move_forward(&mut self) -> &Token {
         self.should_chain_skip = true;
         while !self.frames[0].increment_index() {
-            self.frames.pop_front();
+            if let Some(Frame::FuncMacro { .. }) = self.frames.pop_front() {
+                self.macro_depth -= 1;
+            }
         }
         self.head()
     }
@@ -217,18 +373,29 @@ impl<'a> FrameStack<'a> {
     pub fn get_current_file(&self) -> &FileTokens {
         &self.file_refs[&self.frames[0].get_file_id()]
     }
-    /// Gets the file id of the given include string.
+    /// Gets the file id of the given include string (with the given include type).
     ///
     /// This will only examine the top file frame of the stack.
-    pub fn get_include_ref(&mut self, inc_str: &CachedString) -> Option<FileId> {
+    pub fn get_include_ref(
+        &mut self,
+        inc_type: IncludeType,
+        inc_str: &CachedString,
+    ) -> Option<FileId> {
         for frame in &self.frames {
             if let Frame::File { file_id, .. } = *frame {
-                return self.file_refs[&file_id].get_file_ref(inc_str);
+                return self.file_refs[&file_id].get_file_ref(inc_type, inc_str);
             }
         }
 
         None
     }
+    /// Returns whether the include string (with the given include type) resolved to a file.
+    ///
+    /// Like [get_include_ref](Self::get_include_ref), this only examines the top file frame
+    /// and doesn't push a frame for the file.
+    pub fn can_include(&mut self, path: &CachedString, inc_type: IncludeType) -> bool {
+        self.get_include_ref(inc_type, path).is_some()
+    }
     /// Gets the file id and index of the current frame.
     /// # Panics
     /// Panics if the frame stack is currently not on a file frame.
@@ -239,7 +406,13 @@ impl<'a> FrameStack<'a> {
         }
     }
     /// Returns true if a token joiner is the 'next' token.
+    #[inline]
     pub fn is_token_joiner_next(&self) -> bool {
+        // OPTIMIZATION: Token joiners only matter inside of macros. Since this is checked
+        // for every token, file frames (the common case) return as early as possible.
+        if let Frame::File { .. } = self.frames[0] {
+            return false;
+        }
         let frame = match self.frames[0] {
             Frame::SingleToken { .. } => &self.frames[1],
             ref frame => frame,
@@ -271,12 +444,17 @@ impl<'a> FrameStack<'a> {
     }
     /// Attempts to push a file frame to include another token stack (by its file id).
     ///
+    /// Files marked with `#pragma once` (see [FrameStack::mark_file_once]) can't be included
+    /// again, so Ok(false) is returned without pushing a frame for them.
     /// This will return Err only if no token stack by that file id could be loaded.
-    pub fn push_include(&mut self, file_id: FileId) -> Result<(), ()> {
+    pub fn push_include(&mut self, file_id: FileId) -> Result<bool, ()> {
+        if self.is_file_once(file_id) {
+            return Ok(false);
+        }
         self.dependencies.push(file_id);
         let (file_id, length) = match self.file_refs.get(&file_id) {
             Some(file) => (file_id, file.len()),
-            None => match self.env.file_id_to_tokens.get_arc(file_id) {
+            None => match self.env.get_or_lex_tokens(file_id) {
                 Some(tokens) => {
                     let length = tokens.len();
                     self.file_refs.insert(file_id, tokens);
@@ -291,8 +469,77 @@ impl<'a> FrameStack<'a> {
             index: 0,
             // The -1 is to exclude the EOF token.
             end: length - 1,
+            open_conditionals: 0,
         });
-        Ok(())
+        Ok(true)
+    }
+    /// Returns whether the file is being traveled right now (either it's the start file or it's
+    /// being included).
+    pub fn is_file_open(&self, file_id: FileId) -> bool {
+        let is_file =
+            |frame: &Frame| matches!(*frame, Frame::File { file_id: id, .. } if id == file_id);
+        self.frames.iter().any(is_file)
+    }
+    /// The env's config when the stack was last [started](Self::load_start).
+    pub fn config(&self) -> &EnvConfig {
+        &self.config
+    }
+    /// Returns how deeply the current file is included (0 for the start file).
+    pub fn include_depth(&self) -> usize {
+        let files = self
+            .frames
+            .iter()
+            .filter(|frame| matches!(**frame, Frame::File { .. }));
+        // The start file is the only file frame when it isn't including anything.
+        files.count().saturating_sub(1)
+    }
+    /// Returns whether the file was marked with `#pragma once` (see [FrameStack::mark_file_once]).
+    pub fn is_file_once(&self, file_id: FileId) -> bool {
+        self.once_files.contains(&file_id)
+    }
+    /// Marks the innermost file being traveled so it won't be included again.
+    pub fn mark_file_once(&mut self) {
+        for frame in &self.frames {
+            if let Frame::File { file_id, .. } = *frame {
+                self.once_files.insert(file_id);
+                return;
+            }
+        }
+    }
+    /// Makes the lines after the current line of the innermost file start at the given line.
+    ///
+    /// If no file name is given, the file name from the file's last `#line` directive is kept.
+    pub fn set_line_override(&mut self, line: u32, file_name: Option<CachedString>) {
+        let (file, loc) = self.current_file_loc();
+        let file_id = file.file_id();
+        let directive_line = self.env.source_map.line_col(&loc).map_or(0, |(line, _)| line);
+
+        let overrides = self.line_overrides.entry(file_id).or_default();
+        // A directive can be traveled again after loading an earlier state.
+        overrides.retain(|line_override| line_override.directive_line < directive_line);
+        let file_name = file_name.or_else(|| overrides.last()?.file_name.clone());
+        overrides.push(LineOverride { directive_line, line, file_name });
+    }
+    /// Returns the location with the line (and file name) the `#line` directives before it give it.
+    pub fn map_loc(&self, loc: SourceLoc) -> MappedSourceLoc {
+        let overrides = match self.line_overrides.get(&loc.file_id()) {
+            Some(overrides) => overrides,
+            None => return loc.into(),
+        };
+
+        let physical_line = match self.env.source_map.line_col(&loc) {
+            Some((line, _)) => line,
+            None => return loc.into(),
+        };
+        let line_override = overrides
+            .iter()
+            .rev()
+            .find(|line_override| line_override.directive_line < physical_line)
+            .map(|line_override| {
+                let line = line_override.line + (physical_line - line_override.directive_line - 1);
+                (line, line_override.file_name.clone())
+            });
+        MappedSourceLoc { loc, line_override }
     }
     /// Pushes a single-token frame onto the stack.
     ///
@@ -301,6 +548,11 @@ impl<'a> FrameStack<'a> {
         let frame = Frame::SingleToken { id: None, token };
         self.frames.push_front(frame);
     }
+    /// Pushes a frame of arbitrary tokens onto the stack. The first token will be the head.
+    pub fn push_tokens(&mut self, tokens: Vec<Token>) {
+        let frame = Frame::Tokens { tokens: Arc::new(tokens), index: 0 };
+        self.frames.push_front(frame);
+    }
     /// Skips the file frame to the given link. You can also set whether the skip should
     /// chain (keep jumping till past any PreElseIf/PreElse tokens).
     /// # Panics
@@ -313,23 +565,130 @@ impl<'a> FrameStack<'a> {
             _ => panic!("Can only skip to link when the last frame is an file frame."),
         }
     }
+    /// Records that a conditional (`#if`, `#ifdef`, or `#ifndef`) was opened in the current file.
+    pub fn open_conditional(&mut self) {
+        if let Frame::File { ref mut open_conditionals, .. } = self.frames[0] {
+            *open_conditionals += 1;
+        }
+    }
+    /// Records that a conditional was closed in the current file.
+    ///
+    /// Returns false if there wasn't an open conditional to close.
+    pub fn close_conditional(&mut self) -> bool {
+        match self.frames[0] {
+            Frame::File { ref mut open_conditionals, .. } if *open_conditionals > 0 => {
+                *open_conditionals -= 1;
+                true
+            },
+            _ => false,
+        }
+    }
+    /// Returns whether travel is within a conditional in the current file.
+    pub fn in_conditional(&self) -> bool {
+        matches!(self.frames[0], Frame::File { open_conditionals, .. } if open_conditionals > 0)
+    }
 }
 
 // Macro Utilities
 impl<'a> FrameStack<'a> {
     /// Returns whether the given macro unique-id has been defined.
     pub fn has_macro(&self, id: &CachedString) -> bool {
-        self.macros.contains_key(id)
+        self.macros.contains_key(id) || self.builtins.contains_key(id)
+    }
+    /// Returns the definition of the given macro unique-id (if it is defined).
+    pub fn get_macro(&self, id: &CachedString) -> Option<&MacroDefinition> {
+        self.macros.get(id)
     }
     /// Sets that a unique id represents the given macro.
     /// This does not check if any previous macros were the same.
-    pub fn add_macro(&mut self, id: CachedString, mcr: MacroKind) {
-        self.macros.insert(id, mcr);
+    /// See [macros_are_equivalent](Self::macros_are_equivalent) for that.
+    pub fn add_macro(&mut self, id: CachedString, loc: SourceLoc, mcr: MacroKind) {
+        self.macros.insert(id, MacroDefinition { loc, kind: mcr });
+    }
+    /// Returns whether two macros have the same replacement list (and parameters).
+    ///
+    /// Replacement lists are the same if their tokens are the same and the
+    /// whitespace between their tokens is the same.
+    pub fn macros_are_equivalent(&self, a: &MacroKind, b: &MacroKind) -> bool {
+        use MacroKind::*;
+        #[allow(clippy::pattern_type_mismatch)]
+        match (a, b) {
+            (Empty, Empty) => true,
+            (SingleToken { token: a }, SingleToken { token: b }) => a.kind() == b.kind(),
+            (ObjectMacro { .. }, ObjectMacro { .. }) => self.replacements_are_equivalent(a, b),
+            (
+                FuncMacro {
+                    param_ids: a_params,
+                    var_arg: a_var_arg,
+                    ..
+                },
+                FuncMacro {
+                    param_ids: b_params,
+                    var_arg: b_var_arg,
+                    ..
+                },
+            ) => {
+                a_params == b_params
+                    && a_var_arg == b_var_arg
+                    && self.replacements_are_equivalent(a, b)
+            },
+            _ => false,
+        }
+    }
+    /// Compares the replacement lists of two object/function macros.
+    fn replacements_are_equivalent(&self, a: &MacroKind, b: &MacroKind) -> bool {
+        let a_tokens = self.replacement_tokens(a);
+        let b_tokens = self.replacement_tokens(b);
+        if a_tokens.len() != b_tokens.len() {
+            return false;
+        }
+        // The whitespace before the first token does not matter.
+        a_tokens.iter().zip(b_tokens).enumerate().all(|(i, (a, b))| {
+            a.kind() == b.kind() && (i == 0 || a.whitespace_before() == b.whitespace_before())
+        })
+    }
+    /// Returns the replacement list of an object/function macro.
+    /// Other macros don't have a replacement list in a file, so they return an empty slice.
+    fn replacement_tokens(&self, mcr: &MacroKind) -> &[Token] {
+        match *mcr {
+            MacroKind::ObjectMacro { file_id, index, end }
+            | MacroKind::FuncMacro { file_id, index, end, .. } => {
+                &self.file_refs[&file_id][index..end]
+            },
+            _ => &[],
+        }
+    }
+    /// The ids of the defined macros that have been expanded so far.
+    pub fn used_macros(&self) -> &HashSet<CachedString> {
+        &self.used_macros
     }
     /// Removes the given macro unique-id as being defined.
     pub fn remove_macro(&mut self, id: &CachedString) {
         self.macros.remove(id);
     }
+    /// Saves the current definition of the macro (for `#pragma push_macro`).
+    pub fn push_macro(&mut self, id: CachedString) {
+        let definition = self.macros.get(&id).cloned();
+        self.macro_save_stacks.entry(id).or_default().push(definition);
+    }
+    /// Restores the last saved definition of the macro (for `#pragma pop_macro`).
+    ///
+    /// Returns false if no definition of the macro was saved.
+    pub fn pop_macro(&mut self, id: CachedString) -> bool {
+        let definition = match self.macro_save_stacks.get_mut(&id).and_then(Vec::pop) {
+            Some(definition) => definition,
+            None => return false,
+        };
+        match definition {
+            Some(definition) => {
+                self.macros.insert(id, definition);
+            },
+            None => {
+                self.macros.remove(&id);
+            },
+        }
+        true
+    }
     /// Checks if the given unique id should be handled as a macro.
     /// This will return None should any of the following occur:
     /// * The unique id is not the unique id of a macro.
@@ -337,14 +696,20 @@ impl<'a> FrameStack<'a> {
     ///
     /// Should some value be returned, the value contains the strategy [FrameStack::handle_macro] should use.
     pub fn should_handle_macro(&self, id: &CachedString) -> Option<MacroHandle> {
-        let mcr = self.macros.get(id)?;
+        let mcr = match self.macros.get(id) {
+            Some(definition) => &definition.kind,
+            None => {
+                let kind = *self.builtins.get(id)?;
+                return Some(MacroHandle::Builtin { id: id.clone(), kind });
+            },
+        };
 
         if self.in_macro(id) {
             return None;
         }
 
         match *mcr {
-            MacroKind::Empty => Some(MacroHandle::Empty),
+            MacroKind::Empty => Some(MacroHandle::Empty(Some(id.clone()))),
             MacroKind::SingleToken { ref token } => {
                 let frame = Frame::SingleToken {
                     token: token.clone(),
@@ -368,8 +733,11 @@ impl<'a> FrameStack<'a> {
     }
 
     pub fn handle_macro(&mut self, handle: MacroHandle, errors: Receiver) -> MayUnwind<()> {
+        if let Some(id) = handle.macro_id() {
+            self.used_macros.insert(id.clone());
+        }
         match handle {
-            MacroHandle::Empty => {
+            MacroHandle::Empty(..) => {
                 // Move past the empty token.
                 self.move_forward();
             },
@@ -380,16 +748,97 @@ impl<'a> FrameStack<'a> {
             MacroHandle::FuncMacro { id, param_count } => {
                 self.handle_function_macro(id, param_count, errors)?;
             },
+            MacroHandle::Builtin { id, kind } => {
+                let token = self.builtin_token(kind);
+                self.frames.push_front(Frame::SingleToken { token, id: Some(id) });
+            },
         }
         Ok(())
     }
 
+    /// Creates the token a built-in macro expands to (where the head currently is).
+    fn builtin_token(&mut self, kind: BuiltinMacro) -> Token {
+        // Built-ins refer to where they're used in the file (even if they're used in a macro).
+        let (file, file_loc) = self.current_file_loc();
+        let mapped = self.map_loc(file_loc);
+        let kind = match kind {
+            BuiltinMacro::File => {
+                let path = match (mapped.file_name(), file.path().as_ref()) {
+                    (Some(file_name), _) => file_name.string().to_owned(),
+                    (None, Some(path)) => path.to_string_lossy().into_owned(),
+                    (None, None) => "<unknown>".to_owned(),
+                };
+                let str_data = path.replace('\\', "\\\\").replace('"', "\\\"");
+                String {
+                    encoding: StringEnc::Default,
+                    has_escapes: str_data.len() != path.len(),
+                    is_char: false,
+                    str_data: Arc::new(str_data.into_boxed_str()),
+                }
+            },
+            BuiltinMacro::Line => {
+                let line = match mapped.line() {
+                    Some(line) => line,
+                    None => self.env.source_map.line_col(&file_loc).map_or(0, |(line, _)| line),
+                };
+                Number(self.env.cache().get_or_cache(&line.to_string()))
+            },
+            BuiltinMacro::Counter => {
+                let value = match self.counter_values.get(self.counter_uses) {
+                    Some(&value) => value,
+                    None => {
+                        let value = self.counter.fetch_add(1, Ordering::SeqCst);
+                        self.counter_values.push(value);
+                        value
+                    },
+                };
+                self.counter_uses += 1;
+                Number(self.env.cache().get_or_cache(&value.to_string()))
+            },
+            BuiltinMacro::IncludeLevel => {
+                let level = self.include_depth();
+                Number(self.env.cache().get_or_cache(&level.to_string()))
+            },
+            BuiltinMacro::Date => String {
+                encoding: StringEnc::Default,
+                has_escapes: false,
+                is_char: false,
+                str_data: self.env.start_date().clone(),
+            },
+            BuiltinMacro::Time => String {
+                encoding: StringEnc::Default,
+                has_escapes: false,
+                is_char: false,
+                str_data: self.env.start_time().clone(),
+            },
+            BuiltinMacro::Stdc => Number(self.env.cache().get_or_cache("1")),
+            BuiltinMacro::StdcVersion(version) => Number(self.env.cache().get_or_cache(version)),
+        };
+        Token::new(self.head().loc(), self.head().whitespace_before(), kind)
+    }
+
+    /// Returns the innermost file being traveled and the location of its current token.
+    fn current_file_loc(&self) -> (&FileTokens, SourceLoc) {
+        for frame in &self.frames {
+            if let Frame::File { file_id, index, .. } = *frame {
+                let file = &self.file_refs[&file_id];
+                return (file, file[index].loc());
+            }
+        }
+        panic!("A frame stack should always have a file frame.");
+    }
+
     fn handle_function_macro(
         &mut self,
         id: CachedString,
         param_count: usize,
         errors: Receiver,
     ) -> MayUnwind<()> {
+        let max_depth = self.config.settings().max_macro_depth;
+        if self.macro_depth >= max_depth {
+            self.report_error(Error::MacroExpansionDepthExceeded(max_depth), errors)?;
+        }
+
         // Pass the ID of the macro
         self.move_forward();
 
@@ -401,7 +850,7 @@ impl<'a> FrameStack<'a> {
             end,
             ref param_ids,
             ref var_arg,
-        } = self.macros[&id]
+        } = self.macros[&id].kind
         {
             let id_count = param_ids.len();
             let param_count = param_tokens.len();
@@ -437,7 +886,16 @@ impl<'a> FrameStack<'a> {
                 (None, None) => {},
             }
 
-            self.create_func_macro_frame(file_id, index, end, id, param_map, errors)
+            // This frame is to read the tokens in a function macro.
+            let collector = Frame::TokenCollector {
+                file_id,
+                index,
+                // We want to include the PreEnd token to signal to
+                end: end + 1,
+                params: param_map,
+                var_arg: var_arg.clone(),
+            };
+            self.create_func_macro_frame(id, collector, errors)
         } else {
             panic!("Can't handle a function macro on a non-function macro.");
         }
@@ -445,76 +903,58 @@ impl<'a> FrameStack<'a> {
 
     fn create_func_macro_frame(
         &mut self,
-        file_id: FileId,
-        index: usize,
-        end: usize,
         id: CachedString,
-        params: HashMap<CachedString, Vec<Token>>,
+        collector: Frame,
         errors: Receiver,
     ) -> MayUnwind<()> {
         // By assuming each parameter will show up at least once, we get a good initial capacity estimation.
-        let sum_parameter_lengths = params.iter().fold(0, |accum, value| accum + value.1.len());
-
-        // This frame is to read the tokens in a function macro.
-        self.frames.push_front(Frame::TokenCollector {
-            file_id,
-            index,
-            // We want to include the PreEnd token to signal to
-            end: end + 1,
-            params,
-        });
+        let sum_parameter_lengths = match collector {
+            Frame::TokenCollector { ref params, .. } => params.values().map(Vec::len).sum(),
+            _ => panic!("A function macro frame can only be created with a token collector."),
+        };
+        self.frames.push_front(collector);
+        self.macro_depth += 1;
 
         let function_frame = self.frames.len();
 
         let mut tokens = Vec::with_capacity(sum_parameter_lengths);
+        // How many parentheses deep we are in a kept __VA_OPT__ (if we are in one).
+        let mut va_opt_depth = None;
         loop {
             let head = self.head();
+            if self.frames.len() == function_frame && ends_va_opt(head.kind(), &mut va_opt_depth) {
+                self.move_forward();
+                continue;
+            }
             match *head.kind() {
                 PreEnd if self.frames.len() == function_frame => {
+                    if va_opt_depth.is_some() {
+                        let error = Error::VaOptExpectedRParen(head.clone());
+                        self.report_error(error, errors)?;
+                    }
                     break;
                 },
                 Hash { .. } if self.frames.len() == function_frame => {
                     let loc = head.loc();
-
                     self.move_forward();
-                    let define = match self.head().kind() {
-                        token if token.is_definable() => self.env.get_definable_id(token),
-                        _ => {
-                            let error = Error::StringifyExpectsId(self.head().clone());
-                            self.report_error(error, errors)?;
-                            continue;
-                        },
-                    };
-
-                    let str_data = if let Some(string) = self.frames[0].stringify(define) {
-                        Arc::new(string.into_boxed_str())
-                    } else {
-                        let id_token = self.head().clone();
-                        let id = match *id_token.kind() {
-                            Identifier(ref id) => id.clone(),
-                            _ => self.env.cache().get_or_cache(id_token.kind().text()),
-                        };
-                        let error = Error::StringifyNonParameter(id_token);
-                        self.report_error(error, errors)?;
-                        Arc::new(Box::from(id.string()))
-                    };
-                    tokens.push(Token::new(loc, true, String {
-                        encoding: crate::c::StringEnc::Default,
-                        has_escapes: false,
-                        is_char: false,
-                        str_data,
-                    }));
+                    match self.stringify_parameter(loc, errors)? {
+                        Some(token) => tokens.push(token),
+                        None => continue,
+                    }
+                },
+                ref def
+                    if def.is_definable()
+                        && self.frames.len() == function_frame
+                        && self.env.get_definable_id(def) == self.env.va_opt_id() =>
+                {
+                    va_opt_depth = self.start_va_opt(errors)?;
+                    continue;
                 },
                 ref def if def.is_definable() && self.frames.len() == function_frame => {
                     let param_id = self.env.get_definable_id(def);
                     match self.frames[0].has_parameter(param_id) {
                         Some(handle) if handle.is_empty() => {
-                            let last_kind = tokens.last().map(|token| token.kind());
-                            if let Some(&HashHash { .. }) = last_kind {
-                                tokens.pop();
-                            } else if self.is_token_joiner_next() {
-                                self.move_forward();
-                            }
+                            self.remove_placemarker_joiner(&mut tokens);
                             self.handle_macro(handle, errors)?;
                             continue;
                         },
@@ -542,6 +982,7 @@ impl<'a> FrameStack<'a> {
 
         self.frames.pop_front();
         if tokens.is_empty() {
+            self.macro_depth -= 1;
             self.move_forward();
         } else {
             self.frames.push_front(Frame::FuncMacro {
@@ -553,6 +994,97 @@ impl<'a> FrameStack<'a> {
         Ok(())
     }
 
+    /// Removes the `##` that joins the empty parameter at the head (if there is one).
+    ///
+    /// An empty argument is a placemarker, so joining it with another operand gives that
+    /// operand unchanged (and joining two placemarkers gives nothing). The `##` before the
+    /// parameter is removed from the tokens so far. Otherwise, the `##` after it is skipped.
+    ///
+    /// This is used instead of a `paste_or_propagate(lhs, rhs)` helper since two real operands
+    /// are only joined once the traveler reaches the `##` (see `Traveler::handle_joiner`). The
+    /// operands of the `##` aren't known here yet (parameters expand in their own frames), so
+    /// only the placemarker cases can be handled by the frame stack.
+    fn remove_placemarker_joiner(&mut self, tokens: &mut Vec<Token>) {
+        if matches!(tokens.last().map(Token::kind), Some(&HashHash { .. })) {
+            tokens.pop();
+        } else if self.is_token_joiner_next() {
+            self.move_forward();
+        }
+    }
+
+    /// Stringifies the parameter at the head (the token after a `#` in a function macro).
+    ///
+    /// Returns None if the head isn't definable (so it shouldn't be skipped).
+    fn stringify_parameter(
+        &mut self,
+        loc: SourceLoc,
+        errors: Receiver,
+    ) -> MayUnwind<Option<Token>> {
+        let define = match self.head().kind() {
+            token if token.is_definable() => self.env.get_definable_id(token),
+            _ => {
+                let error = Error::StringifyExpectsId(self.head().clone());
+                self.report_error(error, errors)?;
+                return Ok(None);
+            },
+        };
+
+        let str_data = if let Some(string) = self.frames[0].stringify(define) {
+            Arc::new(string.into_boxed_str())
+        } else {
+            let id_token = self.head().clone();
+            let id = match *id_token.kind() {
+                Identifier(ref id) => id.clone(),
+                _ => self.env.cache().get_or_cache(id_token.kind().text()),
+            };
+            let error = Error::StringifyNonParameter(id_token);
+            self.report_error(error, errors)?;
+            Arc::new(Box::from(id.string()))
+        };
+        Ok(Some(Token::new(loc, true, String {
+            encoding: crate::c::StringEnc::Default,
+            has_escapes: false,
+            is_char: false,
+            str_data,
+        })))
+    }
+
+    /// Handles a `__VA_OPT__` in the body of a function macro. The head should be the `__VA_OPT__`.
+    ///
+    /// If the variable arguments are empty, the `__VA_OPT__` and its parenthesized tokens are
+    /// skipped. Otherwise, only the `__VA_OPT__(` is skipped and `Some(0)` is returned so the
+    /// matching `)` can be skipped later (see [ends_va_opt]).
+    fn start_va_opt(&mut self, errors: Receiver) -> MayUnwind<Option<usize>> {
+        if *self.move_forward().kind() != LParen {
+            let error = Error::VaOptExpectedLParen(self.head().clone());
+            self.report_error(error, errors)?;
+            return Ok(None);
+        }
+
+        self.move_forward();
+        if self.frames[0].has_var_args() {
+            return Ok(Some(0));
+        }
+
+        let mut depth = 0usize;
+        loop {
+            match *self.head().kind() {
+                LParen => depth += 1,
+                RParen if depth == 0 => break,
+                RParen => depth -= 1,
+                PreEnd => {
+                    let error = Error::VaOptExpectedRParen(self.head().clone());
+                    self.report_error(error, errors)?;
+                    return Ok(None);
+                },
+                _ => {},
+            }
+            self.move_forward();
+        }
+        self.move_forward();
+        Ok(None)
+    }
+
     fn collect_func_macro_invocation(
         &mut self,
         param_count: usize,
@@ -640,3 +1172,55 @@ impl<'a> FrameStack<'a> {
         false
     }
 }
+
+/// Returns a map from the ids of the built-in macros to which built-in they are (see
+/// [FrameStack::builtins]). It's empty if [builtin_macros](crate::c::CompileSettings::builtin_macros)
+/// is disabled.
+fn builtin_macros(
+    env: &CompileEnv,
+    settings: &CompileSettings,
+) -> HashMap<CachedString, BuiltinMacro> {
+    let mut builtins = HashMap::new();
+    if !settings.builtin_macros {
+        return builtins;
+    }
+    let cache = env.cache();
+    builtins.insert(cache.get_or_cache("__FILE__"), BuiltinMacro::File);
+    builtins.insert(cache.get_or_cache("__LINE__"), BuiltinMacro::Line);
+    builtins.insert(cache.get_or_cache("__COUNTER__"), BuiltinMacro::Counter);
+    builtins.insert(
+        cache.get_or_cache("__INCLUDE_LEVEL__"),
+        BuiltinMacro::IncludeLevel,
+    );
+    builtins.insert(cache.get_or_cache("__DATE__"), BuiltinMacro::Date);
+    builtins.insert(cache.get_or_cache("__TIME__"), BuiltinMacro::Time);
+    builtins.insert(cache.get_or_cache("__STDC__"), BuiltinMacro::Stdc);
+    let version = match settings.version {
+        LangVersion::C89 => None,
+        LangVersion::C99 => Some("199901L"),
+        LangVersion::C11 => Some("201112L"),
+        LangVersion::C17 => Some("201710L"),
+        LangVersion::C23 => Some("202311L"),
+    };
+    if let Some(version) = version {
+        let id = cache.get_or_cache("__STDC_VERSION__");
+        builtins.insert(id, BuiltinMacro::StdcVersion(version));
+    }
+    builtins
+}
+
+/// Tracks the parentheses of a kept `__VA_OPT__` (see [FrameStack::start_va_opt]).
+///
+/// Returns true if the token is the `)` that ends the `__VA_OPT__`.
+fn ends_va_opt(kind: &TokenKind, depth: &mut Option<usize>) -> bool {
+    match (kind, depth.as_mut()) {
+        (&LParen, Some(depth)) => *depth += 1,
+        (&RParen, Some(&mut 0)) => {
+            *depth = None;
+            return true;
+        },
+        (&RParen, Some(depth)) => *depth -= 1,
+        _ => {},
+    }
+    false
+}
@@ -155,9 +155,13 @@ impl<E: OnError> ExprVisitor for IfEvaluator<E> {
 
 impl<E: OnError> IfEvaluator<E> {
     pub fn calc(e: &mut Expr, if_token: Token, on_error: E) -> MayUnwind<bool> {
+        Ok(!Self::calc_value(e, if_token, on_error)?.is_zero())
+    }
+    /// Calculates the value of the expression (rather than whether it is true).
+    pub fn calc_value(e: &mut Expr, if_token: Token, on_error: E) -> MayUnwind<Sign> {
         let mut visitor = IfEvaluator { accum: None, if_token, on_error };
         visitor.on_expr(e)?;
-        Ok(visitor.accum.map_or(false, |v| !v.is_zero()))
+        Ok(visitor.accum.unwrap_or(Sign::Signed(0)))
     }
 
     fn as_unsigned(&mut self, s: Sign, rhs: bool, expr: &BinaryExpr) -> MayUnwind<u64> {
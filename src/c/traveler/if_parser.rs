@@ -3,6 +3,7 @@
 use crate::{
     c::{
         ast::{
+            normalize_attribute_name,
             BinaryExpr,
             BinaryOp,
             Expr,
@@ -15,6 +16,7 @@ use crate::{
             PrefixOp,
             TernaryExpr,
         },
+        IncludeType,
         StringEnc,
         Token,
         TokenKind::*,
@@ -39,6 +41,11 @@ pub struct IfParser<'a, 'b, E: ErrorReceiver<TravelerError>> {
     traveler: &'a mut Traveler<'b, E>,
     if_token: &'a Token,
     defined_id: usize,
+    has_include_id: usize,
+    has_include_next_id: usize,
+    has_c_attribute_id: usize,
+    has_attribute_id: usize,
+    has_builtin_id: usize,
 }
 
 impl<'a, 'b, E: ErrorReceiver<TravelerError>> IfParser<'a, 'b, E> {
@@ -46,8 +53,24 @@ impl<'a, 'b, E: ErrorReceiver<TravelerError>> IfParser<'a, 'b, E> {
         traveler: &'a mut Traveler<'b, E>,
         if_token: &'a Token,
     ) -> MayUnwind<Box<Expr>> {
-        let defined_id = traveler.env.cache().get_or_cache("defined").uniq_id();
-        Self { traveler, if_token, defined_id }.parse_expression()
+        let cache = traveler.env.cache();
+        let defined_id = cache.get_or_cache("defined").uniq_id();
+        let has_include_id = cache.get_or_cache("__has_include").uniq_id();
+        let has_include_next_id = cache.get_or_cache("__has_include_next").uniq_id();
+        let has_c_attribute_id = cache.get_or_cache("__has_c_attribute").uniq_id();
+        let has_attribute_id = cache.get_or_cache("__has_attribute").uniq_id();
+        let has_builtin_id = cache.get_or_cache("__has_builtin").uniq_id();
+        Self {
+            traveler,
+            if_token,
+            defined_id,
+            has_include_id,
+            has_include_next_id,
+            has_c_attribute_id,
+            has_attribute_id,
+            has_builtin_id,
+        }
+        .parse_expression()
     }
 
     fn parse_expression(&mut self) -> MayUnwind<Box<Expr>> {
@@ -117,17 +140,45 @@ impl<'a, 'b, E: ErrorReceiver<TravelerError>> IfParser<'a, 'b, E> {
                 let index = self.traveler.index();
                 self.parse_defined(index)
             },
+            // '__has_include("path")' or '__has_include(<path>)'
+            Identifier(ref id) if id.uniq_id() == self.has_include_id => {
+                let index = self.traveler.index();
+                self.parse_has_include(index, false)
+            },
+            Identifier(ref id) if id.uniq_id() == self.has_include_next_id => {
+                let index = self.traveler.index();
+                self.parse_has_include(index, true)
+            },
+            // '__has_c_attribute(name)' or '__has_attribute(name)'
+            Identifier(ref id) if id.uniq_id() == self.has_c_attribute_id => {
+                let index = self.traveler.index();
+                self.parse_has_attribute(index, false)
+            },
+            Identifier(ref id) if id.uniq_id() == self.has_attribute_id => {
+                let index = self.traveler.index();
+                self.parse_has_attribute(index, true)
+            },
+            // '__has_builtin(name)'
+            Identifier(ref id) if id.uniq_id() == self.has_builtin_id => {
+                let index = self.traveler.index();
+                self.parse_has_builtin(index)
+            },
             // Undefined identifiers are replaced with 0s
             Identifier(..) => {
                 let index = self.traveler.index();
                 self.move_forward()?;
-                Ok(Box::new(Number { kind: 0i64.into(), index }.into()))
+                Ok(Box::new(Number::new(0i64.into(), index).into()))
             },
             Number(ref digits) => {
                 let digits = digits.clone();
                 let index = self.traveler.index();
                 self.parse_number(index, digits)
             },
+            Integer(value) => {
+                let index = self.traveler.index();
+                self.move_forward()?;
+                Ok(Box::new(Number::new((value as i64).into(), index).into()))
+            },
             Plus | Minus | Tilde | Bang => {
                 let op: PrefixOp = head.kind().try_into().unwrap();
                 let op_index = self.traveler.index();
@@ -154,7 +205,7 @@ impl<'a, 'b, E: ErrorReceiver<TravelerError>> IfParser<'a, 'b, E> {
                 let index = self.traveler.index();
                 let error = Error::IfExpectedAtom(self.if_token.clone(), head.clone());
                 self.report_error(error)?;
-                Ok(Box::new(Number { index, kind: 0i64.into() }.into()))
+                Ok(Box::new(Number::new(0i64.into(), index).into()))
             },
             _ => {
                 let error = Error::IfExpectedAtom(self.if_token.clone(), head.clone());
@@ -176,6 +227,9 @@ impl<'a, 'b, E: ErrorReceiver<TravelerError>> IfParser<'a, 'b, E> {
         };
 
         let value = match *head.kind() {
+            // __has_include (and the other predicates) can be checked for like a macro before
+            // they're used.
+            Identifier(ref id) if self.is_predicate(id) => 1,
             ref kind if kind.is_definable() => {
                 let id = self.traveler.env.get_definable_id(kind);
                 self.traveler.frames.has_macro(id) as i64
@@ -188,7 +242,7 @@ impl<'a, 'b, E: ErrorReceiver<TravelerError>> IfParser<'a, 'b, E> {
                 );
                 self.report_error(error)?;
                 self.move_forward()?;
-                return Ok(Box::new(Number { index, kind: 0i64.into() }.into()));
+                return Ok(Box::new(Number::new(0i64.into(), index).into()));
             },
             PreEnd => {
                 let error = Error::IfDefinedNotDefinable(
@@ -197,7 +251,7 @@ impl<'a, 'b, E: ErrorReceiver<TravelerError>> IfParser<'a, 'b, E> {
                     self.clone_head(),
                 );
                 self.report_error(error)?;
-                return Ok(Box::new(Number { index, kind: 0i64.into() }.into()));
+                return Ok(Box::new(Number::new(0i64.into(), index).into()));
             },
             _ => {
                 let error = Error::IfDefinedNotDefinable(
@@ -222,7 +276,127 @@ impl<'a, 'b, E: ErrorReceiver<TravelerError>> IfParser<'a, 'b, E> {
             _ => {},
         }
 
-        Ok(Box::new(Number { index, kind: value.into() }.into()))
+        Ok(Box::new(Number::new(value.into(), index).into()))
+    }
+
+    fn parse_has_include(&mut self, index: TravelIndex, next: bool) -> MayUnwind<Box<Expr>> {
+        if !matches!(*self.move_frame_forward().kind(), LParen) {
+            let error = Error::IfHasIncludeExpectedParens(self.if_token.clone(), self.clone_head());
+            self.report_error(error)?;
+            return Ok(Box::new(Number::new(0i64.into(), index).into()));
+        }
+
+        let value = match *self.move_frame_forward().kind() {
+            IncludePath { ref path, inc_type } => {
+                let inc_type = if next {
+                    IncludeType::IncludeNext
+                } else {
+                    inc_type
+                };
+                let path = path.clone();
+                self.traveler.frames.can_include(&path, inc_type) as i64
+            },
+            _ => {
+                let error =
+                    Error::IfHasIncludeExpectedPath(self.if_token.clone(), self.clone_head());
+                self.report_error(error)?;
+                return Ok(Box::new(Number::new(0i64.into(), index).into()));
+            },
+        };
+
+        if matches!(*self.move_frame_forward().kind(), RParen) {
+            self.move_forward()?;
+        } else {
+            let error = Error::IfHasIncludeExpectedParens(self.if_token.clone(), self.clone_head());
+            self.report_error(error)?;
+        }
+
+        Ok(Box::new(Number::new(value.into(), index).into()))
+    }
+
+    /// Parses `__has_c_attribute(name)` (or `__has_attribute(name)` if gnu is true) into a number.
+    ///
+    /// Known standard attributes give 200809 for `__has_c_attribute` and known attributes give 1
+    /// for `__has_attribute`. Unknown attributes give 0.
+    fn parse_has_attribute(&mut self, index: TravelIndex, gnu: bool) -> MayUnwind<Box<Expr>> {
+        let name = self.parse_predicate_name(
+            Error::IfHasAttributeExpectedParens,
+            Error::IfHasAttributeExpectedName,
+        )?;
+        let name = match name {
+            Some(name) => name,
+            None => return Ok(Box::new(Number::new(0i64.into(), index).into())),
+        };
+
+        let env = self.traveler.env;
+        let name = env.cache().get_or_cache(normalize_attribute_name(name.string()));
+        let value = if gnu {
+            let is_known = env.known_gnu_attributes().contains(&name)
+                || env.known_c_attributes().contains(&name);
+            is_known as i64
+        } else if env.known_c_attributes().contains(&name) {
+            200809
+        } else {
+            0
+        };
+        Ok(Box::new(Number::new(value.into(), index).into()))
+    }
+
+    /// Parses `__has_builtin(name)` into 1 if the builtin is known (or 0 if it isn't).
+    fn parse_has_builtin(&mut self, index: TravelIndex) -> MayUnwind<Box<Expr>> {
+        let name = self.parse_predicate_name(
+            Error::IfHasBuiltinExpectedParens,
+            Error::IfHasBuiltinExpectedName,
+        )?;
+        let is_known = match name {
+            Some(name) => self.traveler.frames.config().is_known_builtin(&name),
+            None => false,
+        };
+        let kind = (is_known as i64).into();
+        Ok(Box::new(Number::new(kind, index).into()))
+    }
+
+    /// Parses the `(name)` that follows a predicate (like `__has_attribute`) at the head.
+    ///
+    /// None is returned if there was no name (after reporting the given error).
+    fn parse_predicate_name(
+        &mut self,
+        expected_parens: fn(Token, Token) -> Error,
+        expected_name: fn(Token, Token) -> Error,
+    ) -> MayUnwind<Option<CachedString>> {
+        if !matches!(*self.move_frame_forward().kind(), LParen) {
+            let error = expected_parens(self.if_token.clone(), self.clone_head());
+            self.report_error(error)?;
+            return Ok(None);
+        }
+
+        let name = match *self.move_frame_forward().kind() {
+            Identifier(ref name) | Keyword(_, ref name) => name.clone(),
+            _ => {
+                let error = expected_name(self.if_token.clone(), self.clone_head());
+                self.report_error(error)?;
+                return Ok(None);
+            },
+        };
+
+        if matches!(*self.move_frame_forward().kind(), RParen) {
+            self.move_forward()?;
+        } else {
+            let error = expected_parens(self.if_token.clone(), self.clone_head());
+            self.report_error(error)?;
+        }
+        Ok(Some(name))
+    }
+
+    /// Returns whether the identifier is one of the predicates that are only defined in
+    /// `#if` conditions (like `__has_include`).
+    fn is_predicate(&self, id: &CachedString) -> bool {
+        let id = id.uniq_id();
+        id == self.has_include_id
+            || id == self.has_include_next_id
+            || id == self.has_c_attribute_id
+            || id == self.has_attribute_id
+            || id == self.has_builtin_id
     }
 
     fn parse_parens(&mut self, lparen_index: TravelIndex) -> MayUnwind<Box<Expr>> {
@@ -259,7 +433,7 @@ impl<'a, 'b, E: ErrorReceiver<TravelerError>> IfParser<'a, 'b, E> {
             l => l,
         };
         self.move_forward()?;
-        Ok(Box::new(Number { kind, index }.into()))
+        Ok(Box::new(Number::new(kind, index).into()))
     }
 
     fn parse_char(
@@ -280,7 +454,7 @@ impl<'a, 'b, E: ErrorReceiver<TravelerError>> IfParser<'a, 'b, E> {
             l => l,
         };
         self.move_forward()?;
-        Ok(Box::new(Number { kind, index }.into()))
+        Ok(Box::new(Number::new(kind, index).into()))
     }
 
     fn move_frame_forward(&mut self) -> &Token {
@@ -1,19 +1,33 @@
 // Copyright 2021. remilia-dev
 // This source code is licensed under GPLv3 or any later version.
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    convert::TryFrom,
+    path::Path,
+    time::Instant,
+};
+
 use crate::{
     c::{
         traveler::{
             FrameStack,
             IfEvaluator,
             IfParser,
+            MacroHandle,
             MacroKind,
             TravelIndex,
             TravelerError,
             TravelerState,
         },
         CompileEnv,
+        CompileSettings,
         FileTokens,
+        IncludeCallback,
+        IncludeType,
         Keyword,
+        Lexer,
+        LexerErrorKind,
         StringEnc,
         Token,
         TokenKind,
@@ -27,17 +41,37 @@ use crate::{
     sync::Arc,
     util::{
         CachedString,
+        FileId,
+        SourceLoc,
         StringBuilder,
     },
 };
 
 type Error = crate::c::traveler::TravelerErrorKind;
 
+/// How many steps [Traveler::move_forward] takes between checks of the
+/// [deadline](crate::c::CompileSettings::deadline).
+const DEADLINE_CHECK_INTERVAL: u32 = 1024;
+
 pub struct Traveler<'a, E: ErrorReceiver<TravelerError>> {
     pub(super) env: &'a CompileEnv,
     pub(super) frames: FrameStack<'a>,
     str_builder: StringBuilder,
     errors: E,
+    /// When [load_start](Self::load_start) was last called.
+    started: Instant,
+    /// How many steps are left before the [deadline](crate::c::CompileSettings::deadline)
+    /// is checked again.
+    steps_until_deadline_check: u32,
+    /// Whether the branch of each conditional directive was taken (in the order they were
+    /// first reached). See [branch_coverage](Self::branch_coverage).
+    branches: Vec<(SourceLoc, bool)>,
+    /// A map from the location of a conditional directive to its index in branches.
+    branch_indexes: HashMap<SourceLoc, usize>,
+    /// See [set_include_callback](Self::set_include_callback).
+    include_callback: Option<Box<dyn 'a + IncludeCallback>>,
+    /// The tokens of each `_Pragma` content that was lexed (see [lex_pragma](Self::lex_pragma)).
+    pragma_tokens: HashMap<Box<str>, Arc<FileTokens>>,
 }
 
 impl<'a, E: ErrorReceiver<TravelerError>> Traveler<'a, E> {
@@ -49,17 +83,58 @@ impl<'a, E: ErrorReceiver<TravelerError>> Traveler<'a, E> {
             frames,
             str_builder: StringBuilder::new(),
             errors,
+            started: Instant::now(),
+            steps_until_deadline_check: DEADLINE_CHECK_INTERVAL,
+            branches: Vec::new(),
+            branch_indexes: HashMap::new(),
+            include_callback: None,
+            pragma_tokens: HashMap::new(),
         }
     }
 
     pub fn load_start(&mut self, tokens: Arc<FileTokens>) -> MayUnwind<()> {
+        self.started = Instant::now();
+        self.steps_until_deadline_check = DEADLINE_CHECK_INTERVAL;
+        self.branches.clear();
+        self.branch_indexes.clear();
         self.frames.load_start(tokens);
+        // The predefined macros are fetched on every start, so they match the env's current
+        // defines (even if it was reconfigured after this traveler was created).
+        match self.env.predefined_tokens() {
+            // The predefined macros act as if they were at the top of the file.
+            Ok(Some(predefined)) => self.frames.push_predefined(predefined),
+            Ok(None) => {},
+            Err(error) => {
+                self.report_error(error)?;
+                return Err(Unwind::Fatal);
+            },
+        }
         // self.frames starts before the first token in the file.
         // This allows handling any preprocessor instructions at the start of the file.
         self.move_forward()?;
         Ok(())
     }
 
+    pub fn env(&self) -> &'a CompileEnv {
+        self.env
+    }
+
+    /// The settings of the env when the traveler was last [started](Self::load_start).
+    pub fn settings(&self) -> &CompileSettings {
+        self.frames.config().settings()
+    }
+
+    /// Sets the callback that resolves the includes whose paths come from macros (like
+    /// `#include HEADER`). Without one, only the includes the lexer resolved can be included.
+    ///
+    /// It's given the paths like a [Lexer]'s include callback is (after they're rewritten by the
+    /// [include rewriter](crate::c::CompileSettings::include_rewriter)). Unlike the lexer's,
+    /// the tokens of the file it returns should be in
+    /// [file_id_to_tokens](CompileEnv::file_id_to_tokens) once it returns.
+    pub fn set_include_callback(&mut self, callback: impl 'a + IncludeCallback) {
+        self.include_callback = Some(Box::new(callback));
+    }
+
     pub fn save_state(&self) -> TravelerState {
         self.frames.save_state()
     }
@@ -67,19 +142,106 @@ impl<'a, E: ErrorReceiver<TravelerError>> Traveler<'a, E> {
     pub fn load_state(&mut self, state: TravelerState) {
         self.frames.load_state(state);
     }
+    /// Evaluates the tokens as if they were an #if condition (using the currently defined macros).
+    ///
+    /// The traveler will be back where it was after the evaluation (even if it fails).
+    /// Unsigned results are wrapped into an i64.
+    pub fn eval_pp_expr(&mut self, tokens: &[Token]) -> MayUnwind<i64> {
+        let state = self.save_state();
+        let result = self.eval_pp_tokens(tokens);
+        self.load_state(state);
+        result
+    }
+
+    /// Expands the object macro with the given name (as if it was used right now) and
+    /// returns the tokens it expanded to as text.
+    ///
+    /// Returns None if the name isn't a defined object macro or if the expansion failed.
+    /// The traveler will be back where it was after the expansion.
+    pub fn expand_macro_to_string(&mut self, name: &CachedString) -> Option<std::string::String> {
+        let definition = self.frames.get_macro(name)?;
+        if let MacroKind::FuncMacro { .. } = definition.kind {
+            return None;
+        }
+        let loc = definition.loc;
+
+        let state = self.save_state();
+        let result = self.expand_to_string(name, loc);
+        self.load_state(state);
+        result.ok()
+    }
+
+    /// Returns the tokens the macro invocation at the head of the given state expands to
+    /// (including the expansions of any macros within it).
+    ///
+    /// The state's head should be the macro's name (before it was expanded), like the states
+    /// [save_state_before_next](Self::save_state_before_next) returns. Returns None if the head
+    /// doesn't invoke a macro or if the expansion reports any error.
+    ///
+    /// This is a dry run in a scratch traveler, so this traveler isn't moved and the errors of
+    /// the expansion aren't reported.
+    pub fn preview_expansion_at(&self, state: &TravelerState) -> Option<Vec<Token>> {
+        let mut scratch = Traveler::new(self.env, |_: TravelerError| true);
+        scratch.frames = self.frames.new_scratch();
+        scratch.load_state(state.clone());
+        scratch.preview_head_expansion().ok().flatten()
+    }
+
+    /// Returns a state whose head is the raw token after the head (so a macro there isn't
+    /// expanded yet). This is the state [preview_expansion_at](Self::preview_expansion_at)
+    /// needs to preview the macro invocation after the head.
+    ///
+    /// Loading the state and moving forward continues after the token without expanding it.
+    pub fn save_state_before_next(&self) -> TravelerState {
+        let mut frames = self.frames.new_scratch();
+        frames.load_state(self.save_state());
+        frames.move_forward();
+        frames.index += 1;
+        frames.save_state()
+    }
+
+    /// The names of the macros that have been expanded so far (each is given once).
+    ///
+    /// Built-in macros (like `__LINE__`) aren't included.
+    pub fn used_macro_names(&self) -> impl Iterator<Item = CachedString> + '_ {
+        self.frames.used_macros().iter().cloned()
+    }
+
+    /// Returns the location of each `#if`, `#ifdef`, `#ifndef`, `#elif`, and `#else` that has
+    /// been reached along with whether its branch was taken.
+    ///
+    /// The directives are in the order they were first reached. A directive that was reached
+    /// multiple times (like in a file that was included twice) is taken if it was ever taken.
+    /// Directives within skipped branches are never reached, so they aren't included.
+    pub fn branch_coverage(&self) -> Vec<(SourceLoc, bool)> {
+        self.branches.clone()
+    }
 
     pub fn index(&self) -> TravelIndex {
         TravelIndex::new(self.frames.index).unwrap()
     }
 
+    /// The maximum alignment of struct members set by `#pragma pack` (None for the default).
+    pub fn pack(&self) -> Option<u32> {
+        self.frames.pack()
+    }
+
     pub fn head(&self) -> &Token {
         self.frames.head()
     }
 
+    /// Returns the kind of the token after the head without moving forward.
+    ///
+    /// The preview is of the raw token, so macros and preprocessor instructions aren't handled.
+    pub fn preview_next_kind(&self) -> Option<&TokenKind> {
+        self.frames.preview_next_kind(true)
+    }
+
     pub fn move_forward(&mut self) -> MayUnwind<&Token> {
         self.frames.index += 1;
         self.frames.move_forward();
         loop {
+            self.check_deadline()?;
             if self.frames.is_token_joiner_next() {
                 self.handle_joiner()?;
                 continue;
@@ -89,14 +251,21 @@ impl<'a, E: ErrorReceiver<TravelerError>> Traveler<'a, E> {
             match *head.kind() {
                 PreIf { link } => {
                     let head = head.clone();
+                    self.frames.open_conditional();
                     self.handle_if(head, link)?;
                 },
                 PreIfDef { link } | PreIfNDef { link } => {
                     let head = head.clone();
+                    self.frames.open_conditional();
                     self.handle_if_def(head, link)?;
                 },
+                PreElif { .. } | PreElse { .. } if !self.frames.in_conditional() => {
+                    let head = head.clone();
+                    self.handle_unbalanced_conditional(head)?;
+                },
                 PreElif { link } => {
                     if self.frames.should_chain_skip() {
+                        self.record_branch(head.loc(), false);
                         self.frames.skip_to(link, true);
                     } else {
                         let head = head.clone();
@@ -105,6 +274,7 @@ impl<'a, E: ErrorReceiver<TravelerError>> Traveler<'a, E> {
                 },
                 PreElse { link } => {
                     let should_chain_skip = self.frames.should_chain_skip();
+                    self.record_branch(head.loc(), !should_chain_skip);
                     self.ensure_end_of_preprocessor(Error::ElseExtraTokens)?;
                     if should_chain_skip {
                         self.frames.skip_to(link, true);
@@ -114,30 +284,27 @@ impl<'a, E: ErrorReceiver<TravelerError>> Traveler<'a, E> {
                     // Pre blank doesn't have a corresponding PreEnd
                     self.frames.move_forward();
                 },
-                PreEndIf => self.ensure_end_of_preprocessor(Error::EndIfExtraTokens)?,
+                PreEndIf => self.handle_end_if()?,
                 PreDefine => self.handle_define()?,
                 PreUndef => self.handle_undef()?,
-                PreLine => {
-                    self.report_error(Error::UnsupportableLinePreprocessor)?;
-                    self.skip_past_preprocessor();
-                },
+                PreLine => self.handle_line(false)?,
+                PreLineMarker => self.handle_line(true)?,
                 PreInclude => self.handle_include(false)?,
                 PreIncludeNext => self.handle_include(true)?,
                 PreError => self.handle_message(true)?,
                 PreWarning => self.handle_message(false)?,
+                PreAssert | PreUnassert => {
+                    let error = Error::AssertPreprocessorIgnored(self.head().clone());
+                    self.report_error(error)?;
+                    self.skip_past_preprocessor();
+                },
                 PreUnknown(ref str) => {
                     let error = Error::UnknownPreprocessor(str.clone());
                     self.report_error(error)?;
                     self.skip_past_preprocessor();
                 },
-                PrePragma => {
-                    self.report_error(Error::Unimplemented("#pragma"))?;
-                    unreachable!();
-                },
-                Keyword(Keyword::Pragma, ..) => {
-                    self.report_error(Error::Unimplemented("_Pragma"))?;
-                    unreachable!();
-                },
+                PrePragma => self.handle_pre_pragma()?,
+                Keyword(Keyword::Pragma, ..) => self.handle_pragma_operator()?,
                 ref token if token.is_definable() => {
                     let definable_id = self.env.get_definable_id(token);
                     if let Some(handle) = self.frames.should_handle_macro(definable_id) {
@@ -147,8 +314,7 @@ impl<'a, E: ErrorReceiver<TravelerError>> Traveler<'a, E> {
                     }
                 },
                 LexerError(index) => {
-                    let error = self.frames.get_current_file().errors()[index].clone();
-                    self.report_error(error.into())?;
+                    self.report_lexer_error(index)?;
                     self.frames.move_forward();
                 },
                 Hash { .. } => {
@@ -175,21 +341,54 @@ impl<'a, E: ErrorReceiver<TravelerError>> Traveler<'a, E> {
         Ok(self.frames.head())
     }
 
+    /// Reports a [TimeLimitExceeded](Error::TimeLimitExceeded) error if traveling has taken
+    /// longer than the [deadline](crate::c::CompileSettings::deadline).
+    ///
+    /// The time is only actually checked every [DEADLINE_CHECK_INTERVAL] calls.
+    fn check_deadline(&mut self) -> MayUnwind<()> {
+        let deadline = match self.frames.config().settings().deadline {
+            Some(deadline) => deadline,
+            None => return Ok(()),
+        };
+        if self.steps_until_deadline_check > 0 {
+            self.steps_until_deadline_check -= 1;
+            return Ok(());
+        }
+
+        self.steps_until_deadline_check = DEADLINE_CHECK_INTERVAL;
+        if self.started.elapsed() > deadline {
+            self.report_error(Error::TimeLimitExceeded(deadline))?;
+        }
+        Ok(())
+    }
+
     fn move_slightly_forward(&mut self) -> MayUnwind<&Token> {
         while let LexerError(index) = *self.frames.move_forward().kind() {
-            let error = self.frames.get_current_file().errors()[index].clone();
-            self.report_error(error.into())?;
+            self.report_lexer_error(index)?;
         }
 
         Ok(self.frames.head())
     }
 
+    /// Reports the current file's lexer error at the index. The lexer's errors for directives
+    /// without an #if are skipped since they're reported as unbalanced conditionals (with the
+    /// current conditional state) when they're traveled over.
+    fn report_lexer_error(&mut self, index: usize) -> MayUnwind<()> {
+        let error = &self.frames.get_current_file().errors()[index];
+        if matches!(error.kind, LexerErrorKind::MissingCorrespondingIf(..)) {
+            return Ok(());
+        }
+        let error = error.clone();
+        self.report_error(error.into())
+    }
+
     fn handle_if(&mut self, if_token: Token, link: usize) -> MayUnwind<()> {
         self.move_forward()?;
         let mut expr = match IfParser::create_and_parse(self, &if_token) {
             Ok(expr) => expr,
             Err(Unwind::Block) => {
                 // We failed to parse the if condition, so we assume it's false.
+                self.record_branch(if_token.loc(), false);
                 self.frames.skip_to(link, false);
                 return Ok(());
             },
@@ -197,14 +396,146 @@ impl<'a, E: ErrorReceiver<TravelerError>> Traveler<'a, E> {
         };
         // Move past the PreEnd token.
         self.frames.move_forward();
-        match IfEvaluator::calc(&mut expr, if_token, |err| self.report_error(err)) {
-            Ok(true) => Ok(()),
-            Ok(false) | Err(Unwind::Block) => {
-                self.frames.skip_to(link, false);
-                Ok(())
-            },
-            Err(Unwind::Fatal) => Err(Unwind::Fatal),
+        let loc = if_token.loc();
+        let taken = match IfEvaluator::calc(&mut expr, if_token, |err| self.report_error(err)) {
+            Ok(taken) => taken,
+            Err(Unwind::Block) => false,
+            Err(Unwind::Fatal) => return Err(Unwind::Fatal),
+        };
+        self.record_branch(loc, taken);
+        if !taken {
+            self.frames.skip_to(link, false);
+        }
+        Ok(())
+    }
+
+    /// Records whether the branch of the conditional directive at the location was taken.
+    fn record_branch(&mut self, loc: SourceLoc, taken: bool) {
+        let branches = &mut self.branches;
+        let index = *self.branch_indexes.entry(loc).or_insert_with(|| {
+            branches.push((loc, false));
+            branches.len() - 1
+        });
+        self.branches[index].1 |= taken;
+    }
+
+    /// Handles an `#elif` or `#else` that isn't within a conditional. It's reported and
+    /// ignored, but it still opens a conditional so the `#endif` after it is balanced.
+    fn handle_unbalanced_conditional(&mut self, token: Token) -> MayUnwind<()> {
+        self.frames.open_conditional();
+        let result = self.report_error(Error::UnbalancedConditional(token));
+        self.skip_past_preprocessor();
+        result
+    }
+
+    fn handle_end_if(&mut self) -> MayUnwind<()> {
+        if !self.frames.close_conditional() {
+            let error = Error::UnbalancedConditional(self.head().clone());
+            self.report_error(error)?;
         }
+        self.ensure_end_of_preprocessor(Error::EndIfExtraTokens)
+    }
+
+    fn eval_pp_tokens(&mut self, tokens: &[Token]) -> MayUnwind<i64> {
+        let loc = tokens
+            .first()
+            .map_or_else(|| SourceLoc::new_first_byte(FileId::MAX), Token::loc);
+        let if_token = Token::new(loc, false, PreIf { link: 0 });
+        let end_loc = tokens.last().map_or(loc, Token::loc);
+
+        let mut frame_tokens = Vec::with_capacity(tokens.len() + 2);
+        // The if token is a placeholder so moving forward onto the expression handles macros.
+        frame_tokens.push(if_token.clone());
+        frame_tokens.extend_from_slice(tokens);
+        frame_tokens.push(Token::new(end_loc, false, PreEnd));
+        self.frames.push_tokens(frame_tokens);
+        self.move_forward()?;
+
+        let mut expr = IfParser::create_and_parse(self, &if_token)?;
+        if !matches!(*self.head().kind(), PreEnd) {
+            let error = Error::IfExpectedOp(if_token, self.head().clone());
+            self.report_error(error)?;
+            return Err(Unwind::Block);
+        }
+        let value = IfEvaluator::calc_value(&mut expr, if_token, |err| self.report_error(err))?;
+        Ok(value.wrapped_signed().0)
+    }
+
+    fn preview_head_expansion(&mut self) -> MayUnwind<Option<Vec<Token>>> {
+        // The invocation's tokens are read as-is and then traveled again within a
+        // token frame (like in expand_to_string) so the expansion's end is known.
+        let name = self.frames.head().clone();
+        if !name.kind().is_definable() {
+            return Ok(None);
+        }
+        let macro_id = self.env.get_definable_id(name.kind());
+        let is_func_macro = match self.frames.should_handle_macro(macro_id) {
+            Some(MacroHandle::FuncMacro { .. }) => true,
+            Some(_) => false,
+            None => return Ok(None),
+        };
+
+        let loc = name.loc();
+        let mut invocation = vec![Token::new(loc, false, PreEnd), name];
+        if is_func_macro {
+            let l_paren = self.frames.move_forward().clone();
+            if !matches!(*l_paren.kind(), LParen) {
+                return Ok(None);
+            }
+            invocation.push(l_paren);
+            // The depth of the parentheses within the arguments.
+            let mut depth = 0usize;
+            loop {
+                let token = self.frames.move_forward().clone();
+                match *token.kind() {
+                    LParen => depth += 1,
+                    RParen if depth == 0 => {
+                        invocation.push(token);
+                        break;
+                    },
+                    RParen => depth -= 1,
+                    Eof => return Ok(None),
+                    _ => {},
+                }
+                invocation.push(token);
+            }
+        }
+        invocation.push(Token::new(loc, false, PreEnd));
+        self.frames.push_tokens(invocation);
+
+        let mut expansion = Vec::new();
+        while !matches!(*self.move_forward()?.kind(), PreEnd) {
+            expansion.push(self.head().clone());
+        }
+        Ok(Some(expansion))
+    }
+
+    fn expand_to_string(
+        &mut self,
+        name: &CachedString,
+        loc: SourceLoc,
+    ) -> MayUnwind<std::string::String> {
+        let kind = match self.frames.config().get_keyword(name) {
+            Some(keyword) => Keyword(keyword, name.clone()),
+            None => Identifier(name.clone()),
+        };
+        // Like in eval_pp_tokens, the first token is a placeholder so moving forward
+        // onto the macro's name expands it.
+        self.frames.push_tokens(vec![
+            Token::new(loc, false, PreEnd),
+            Token::new(loc, false, kind),
+            Token::new(loc, false, PreEnd),
+        ]);
+
+        let mut text = std::string::String::new();
+        while !matches!(*self.move_forward()?.kind(), PreEnd) {
+            let head = self.head();
+            if !text.is_empty() && head.whitespace_before() {
+                text.push(' ');
+            }
+            text.push_str(&head.to_string());
+        }
+        Ok(text)
     }
 
     fn handle_if_def(&mut self, if_def: Token, link: usize) -> MayUnwind<()> {
@@ -217,6 +548,7 @@ impl<'a, E: ErrorReceiver<TravelerError>> Traveler<'a, E> {
                 self.frames.has_macro(macro_id)
             },
             _ => {
+                self.record_branch(if_def.loc(), false);
                 let error = Error::IfDefExpectedId(if_def, self.frames.head().clone());
                 let result = self.report_error(error);
                 self.frames.skip_to(link, false);
@@ -224,6 +556,7 @@ impl<'a, E: ErrorReceiver<TravelerError>> Traveler<'a, E> {
             },
         };
 
+        self.record_branch(if_def.loc(), defined == is_ifdef);
         self.ensure_end_of_preprocessor(Error::IfDefExtraTokens(if_def))?;
         if defined != is_ifdef {
             self.frames.skip_to(link, false);
@@ -243,20 +576,20 @@ impl<'a, E: ErrorReceiver<TravelerError>> Traveler<'a, E> {
             },
         };
 
+        let id_token = self.head().clone();
         let head = self.move_slightly_forward()?;
         match *head.kind() {
             PreEnd => {
-                // TODO: Ensure the previous macro was empty (otherwise report warning)
-                self.frames.add_macro(id, MacroKind::Empty);
+                self.define_macro(id, id_token, MacroKind::Empty)?;
                 self.frames.move_forward();
                 Ok(())
             },
-            LParen if !head.whitespace_before() => self.handle_function_macro(id),
-            _ => self.handle_object_macro(id),
+            LParen if !head.whitespace_before() => self.handle_function_macro(id, id_token),
+            _ => self.handle_object_macro(id, id_token),
         }
     }
 
-    fn handle_function_macro(&mut self, id: CachedString) -> MayUnwind<()> {
+    fn handle_function_macro(&mut self, id: CachedString, id_token: Token) -> MayUnwind<()> {
         let mut params = Vec::new();
         let mut var_arg = None;
         loop {
@@ -264,11 +597,16 @@ impl<'a, E: ErrorReceiver<TravelerError>> Traveler<'a, E> {
             match *self.head().kind() {
                 ref token if token.is_definable() => {
                     let id = self.env.get_definable_id(token);
+                    if id == self.env.va_opt_id() {
+                        let error = Error::DefineFuncExpectedArg(self.frames.head().clone());
+                        let result = self.report_error(error);
+                        self.skip_past_preprocessor();
+                        return result;
+                    }
                     params.push(id.clone());
                 },
                 DotDotDot => {
-                    // OPTIMIZATION: The cached value should always be the same.
-                    var_arg = Some(self.env.cache().get_or_cache("__VA_ARGS__"));
+                    var_arg = Some(self.env.va_args_id().clone());
                     self.frames.move_forward();
                     break;
                 },
@@ -321,26 +659,54 @@ impl<'a, E: ErrorReceiver<TravelerError>> Traveler<'a, E> {
         }
 
         let (file_id, index) = self.frames.get_file_index();
+        // The unused parameters are found before the macro takes ownership of them.
+        // System headers commonly ignore parameters on purpose, so they aren't warned about.
+        let unused = if self.frames.config().settings().warn_unused_macro_params
+            && !self.frames.get_current_file().is_system_header()
+        {
+            self.unused_params(&params, index)
+        } else {
+            Vec::new()
+        };
         let length = self.skip_past_preprocessor();
-        self.frames.add_macro(id, MacroKind::FuncMacro {
+        let mcr = MacroKind::FuncMacro {
             file_id,
             index,
             end: index + length,
             param_ids: params,
             var_arg,
-        });
-
+        };
+        self.define_macro(id.clone(), id_token, mcr)?;
+        for param in unused {
+            self.report_error(Error::UnusedMacroParameter(id.clone(), param))?;
+        }
         Ok(())
     }
 
-    fn handle_object_macro(&mut self, id: CachedString) -> MayUnwind<()> {
+    /// Returns the parameters that aren't used in the function macro's body.
+    ///
+    /// The body should start at the given index of the current file.
+    fn unused_params(&self, params: &[CachedString], body_index: usize) -> Vec<CachedString> {
+        let body = self.frames.get_current_file()[body_index..]
+            .iter()
+            .take_while(|token| !matches!(*token.kind(), PreEnd));
+        let mut unused = params.to_vec();
+        for token in body {
+            if token.kind().is_definable() {
+                let id = self.env.get_definable_id(token.kind());
+                unused.retain(|param| param != id);
+            }
+        }
+        unused
+    }
+
+    fn handle_object_macro(&mut self, id: CachedString, id_token: Token) -> MayUnwind<()> {
         if matches!(
             self.frames.preview_next_kind(false),
             Some(&TokenKind::PreEnd)
         ) {
-            // TODO: Ensure the previous macro is the same (otherwise report warning)
             let token = self.frames.head().clone();
-            self.frames.add_macro(id, MacroKind::SingleToken { token });
+            self.define_macro(id, id_token, MacroKind::SingleToken { token })?;
             // Move onto the PreEnd token
             self.frames.move_forward();
             // Move past the PreEnd token
@@ -348,29 +714,40 @@ impl<'a, E: ErrorReceiver<TravelerError>> Traveler<'a, E> {
         } else {
             let (file_id, index) = self.frames.get_file_index();
             let length = self.skip_past_preprocessor();
-            // TODO: Ensure the previous macro was the same (otherwise report warning)
-            self.frames.add_macro(id, MacroKind::ObjectMacro {
-                index,
-                file_id,
-                end: index + length,
-            });
+            let mcr = MacroKind::ObjectMacro { index, file_id, end: index + length };
+            self.define_macro(id, id_token, mcr)?;
         }
 
         Ok(())
     }
 
+    /// Defines the macro and reports a warning if it was previously defined differently
+    /// (unless the macro is defined in a system header).
+    fn define_macro(&mut self, id: CachedString, id_token: Token, mcr: MacroKind) -> MayUnwind<()> {
+        let mismatch = match self.frames.get_macro(&id) {
+            _ if self.frames.get_current_file().is_system_header() => None,
+            Some(previous) if !self.frames.macros_are_equivalent(&previous.kind, &mcr) => {
+                Some(previous.loc)
+            },
+            _ => None,
+        };
+        let loc = id_token.loc();
+        self.frames.add_macro(id, loc, mcr);
+        match mismatch {
+            Some(previous) => {
+                self.report_error(Error::MacroRedefinitionMismatch(id_token, previous))
+            },
+            None => Ok(()),
+        }
+    }
+
     fn handle_undef(&mut self) -> MayUnwind<()> {
         self.move_slightly_forward()?;
         match *self.head().kind() {
-            Identifier(ref id) => {
+            Identifier(ref id) | Keyword(_, ref id) => {
                 let id = id.clone();
                 self.frames.remove_macro(&id)
             },
-            Keyword(keyword) => {
-                if let Some(id) = self.env.get_keyword_string(keyword) {
-                    self.frames.remove_macro(id);
-                }
-            },
             _ => {
                 let error = Error::UndefExpectedId(self.frames.head().clone());
                 let result = self.report_error(error);
@@ -383,29 +760,25 @@ impl<'a, E: ErrorReceiver<TravelerError>> Traveler<'a, E> {
         Ok(())
     }
 
-    fn handle_include(&mut self, _include_next: bool) -> MayUnwind<()> {
+    fn handle_include(&mut self, include_next: bool) -> MayUnwind<()> {
         // We use self.move_forward to allow for macros to be used.
-        let (inc_file, inc_type, path) = match *self.move_forward()?.kind() {
+        let include = match *self.move_forward()?.kind() {
             IncludePath { ref path, inc_type } => {
                 let path = path.clone();
-                if let Some(inc_file) = self.frames.get_include_ref(&path) {
-                    (inc_file, inc_type, path.clone())
-                } else {
-                    let error = Error::IncludeNotFound(None, inc_type, path);
-                    let result = self.report_error(error);
-                    self.skip_past_preprocessor();
-                    return result;
-                }
-            },
-            String { is_char: false, .. } => {
-                self.report_error(Error::Unimplemented("Include indirection with quotes"))?;
-                unreachable!()
+                let inc_file = self.frames.get_include_ref(inc_type, &path);
+                Some((inc_file, inc_type, path))
             },
-            LAngle => {
-                self.report_error(Error::Unimplemented("Include indirection with <>"))?;
-                unreachable!()
+            _ => self.read_computed_include(include_next)?,
+        };
+        let (inc_file, inc_type, path) = match include {
+            Some((Some(inc_file), inc_type, path)) => (inc_file, inc_type, path),
+            Some((None, inc_type, path)) => {
+                let error = Error::IncludeNotFound(None, inc_type, path);
+                let result = self.report_error(error);
+                self.skip_past_preprocessor();
+                return result;
             },
-            _ => {
+            None => {
                 let error = Error::IncludeExpectedPath(self.head().clone());
                 let result = self.report_error(error);
                 self.skip_past_preprocessor();
@@ -418,10 +791,157 @@ impl<'a, E: ErrorReceiver<TravelerError>> Traveler<'a, E> {
             while !matches!(*self.frames.move_forward().kind(), PreEnd) {}
         }
 
-        if self.frames.push_include(inc_file).is_err() {
-            self.report_error(Error::IncludeNotFound(Some(inc_file), inc_type, path))
+        if self.frames.include_depth() >= self.settings().max_include_depth {
+            // Recursive includes are allowed (as long as they end), so a file only counts as
+            // circular once it hits the limit while it's already being included.
+            let error = if self.frames.is_file_open(inc_file) {
+                Error::CircularInclude(inc_file)
+            } else {
+                Error::IncludeDepthExceeded(self.settings().max_include_depth)
+            };
+            let result = self.report_error(error);
+            // The include is skipped, so we just move past the PreEnd token.
+            self.frames.move_forward();
+            result
         } else {
-            Ok(())
+            match self.frames.push_include(inc_file) {
+                Ok(true) => Ok(()),
+                Ok(false) => {
+                    // The file can't be included again, so we just move past the PreEnd token.
+                    self.frames.move_forward();
+                    Ok(())
+                },
+                Err(()) => {
+                    self.report_error(Error::IncludeNotFound(Some(inc_file), inc_type, path))
+                },
+            }
+        }
+    }
+
+    /// Reads the include path that the (macro expanded) tokens at the head form. This is either
+    /// a `"path"` string or the text of the tokens between a `<` and a `>`.
+    ///
+    /// None is returned if the tokens don't form a path (with the head on the token that
+    /// doesn't fit). Otherwise, the head is left on the last token of the path.
+    ///
+    /// The lexer only resolves the include paths it sees, so the path is resolved using the
+    /// includes of the current file or (failing that) the
+    /// [include callback](Self::set_include_callback).
+    fn read_computed_include(
+        &mut self,
+        include_next: bool,
+    ) -> MayUnwind<Option<(Option<FileId>, IncludeType, CachedString)>> {
+        let (inc_type, path) = match *self.head().kind() {
+            String {
+                is_char: false,
+                encoding: StringEnc::Default,
+                ref str_data,
+                ..
+            } => {
+                let path = self.env.cache().get_or_cache(str_data);
+                (IncludeType::IncludeLocal, path)
+            },
+            LAngle => {
+                let mut path = std::string::String::new();
+                loop {
+                    let token = self.move_forward()?;
+                    match *token.kind() {
+                        RAngle => break,
+                        PreEnd => return Ok(None),
+                        ref kind => {
+                            if token.whitespace_before() && !path.is_empty() {
+                                path.push(' ');
+                            }
+                            path += &kind.to_string();
+                        },
+                    }
+                }
+                let path = self.env.cache().get_or_cache(&path);
+                (IncludeType::IncludeSystem, path)
+            },
+            _ => return Ok(None),
+        };
+        let inc_type = if include_next {
+            IncludeType::IncludeNext
+        } else {
+            inc_type
+        };
+
+        let inc_file = match self.frames.get_include_ref(inc_type, &path) {
+            Some(inc_file) => Some(inc_file),
+            None => {
+                let current_path = self.frames.get_current_file().path().clone();
+                let (rewritten_type, rewritten) = self.env.rewrite_include(inc_type, &path);
+                let callback = self.include_callback.as_mut();
+                callback.and_then(|callback| callback(rewritten_type, &rewritten, &current_path))
+            },
+        };
+        Ok(Some((inc_file, inc_type, path)))
+    }
+
+    /// Handles a `#line number "file name"` directive. The file name is optional.
+    ///
+    /// If the directive is a GNU line marker (`# number "file name" flags`), the flags after
+    /// the file name are skipped. The tokens after #line are macro expanded, but the tokens of a
+    /// line marker aren't.
+    fn handle_line(&mut self, is_marker: bool) -> MayUnwind<()> {
+        let line = match *self.move_to_line_operand(is_marker)?.kind() {
+            Number(ref digits) => parse_line_number(digits.string()),
+            Integer(line) => u32::try_from(line).ok(),
+            _ => None,
+        };
+        let line = match line {
+            Some(line) => line,
+            None => {
+                let head = self.head().clone();
+                let error = match *head.kind() {
+                    Number(..) | Integer(..) => Error::LineInvalidNumber(head),
+                    _ => Error::LineExpectedNumber(head),
+                };
+                let result = self.report_error(error);
+                self.skip_past_preprocessor();
+                return result;
+            },
+        };
+
+        let file_name = match *self.move_to_line_operand(is_marker)?.kind() {
+            String {
+                is_char: false,
+                encoding: StringEnc::Default,
+                ref str_data,
+                ..
+            } => Some(str_data.clone()),
+            _ => None,
+        };
+        let file_name = match file_name {
+            Some(str_data) => {
+                self.move_to_line_operand(is_marker)?;
+                Some(self.env.cache().get_or_cache(&str_data))
+            },
+            None => None,
+        };
+        // The flags of a line marker (like 3 for a system header) don't change anything.
+        while is_marker && file_name.is_some() && is_line_marker_flag(self.head().kind()) {
+            self.move_slightly_forward()?;
+        }
+
+        if !matches!(*self.head().kind(), PreEnd) {
+            let error = Error::LineExtraTokens(self.head().clone());
+            self.report_error(error)?;
+            while !matches!(*self.frames.move_forward().kind(), PreEnd) {}
+        }
+        // The override starts on the line after the PreEnd token.
+        self.frames.set_line_override(line, file_name);
+        self.frames.move_forward();
+        Ok(())
+    }
+
+    /// Moves to the next operand of a #line directive (with macros expanded) or a line marker.
+    fn move_to_line_operand(&mut self, is_marker: bool) -> MayUnwind<&Token> {
+        if is_marker {
+            self.move_slightly_forward()
+        } else {
+            self.move_forward()
         }
     }
 
@@ -430,8 +950,8 @@ impl<'a, E: ErrorReceiver<TravelerError>> Traveler<'a, E> {
         let message = match *self.move_slightly_forward()?.kind() {
             Message(ref text) => {
                 let text = text.clone();
-                // The next token *should* be a PreEnd token.
-                self.skip_past_preprocessor();
+                // The lexer doesn't add a PreEnd token after a message.
+                self.frames.move_forward();
                 Some(text)
             },
             PreEnd => {
@@ -456,6 +976,180 @@ impl<'a, E: ErrorReceiver<TravelerError>> Traveler<'a, E> {
         self.report_error_with_state(error_kind, state)
     }
 
+    fn handle_pre_pragma(&mut self) -> MayUnwind<()> {
+        let mut content = std::string::String::new();
+        let mut tokens = Vec::new();
+        while !matches!(*self.move_slightly_forward()?.kind(), PreEnd) {
+            let head = self.frames.head();
+            if !content.is_empty() && head.whitespace_before() {
+                content.push(' ');
+            }
+            content.push_str(&head.to_string());
+            tokens.push(head.clone());
+        }
+        let result = self.handle_pragma(&content, Some(&tokens));
+        // Move past the PreEnd token.
+        self.frames.move_forward();
+        result
+    }
+
+    /// Handles a `_Pragma("...")` operator as if it was a #pragma of the un-escaped string.
+    ///
+    /// If the operator is malformed, the traveler stops at the unexpected token.
+    fn handle_pragma_operator(&mut self) -> MayUnwind<()> {
+        if !matches!(*self.move_slightly_forward()?.kind(), LParen) {
+            let error = Error::PragmaExpectedLParen(self.head().clone());
+            return self.report_error(error);
+        }
+        let content = match *self.move_slightly_forward()?.kind() {
+            String { is_char: false, ref str_data, .. } => unescape_pragma(str_data),
+            _ => {
+                let error = Error::PragmaExpectedString(self.head().clone());
+                return self.report_error(error);
+            },
+        };
+        if !matches!(*self.move_slightly_forward()?.kind(), RParen) {
+            let error = Error::PragmaExpectedRParen(self.head().clone());
+            return self.report_error(error);
+        }
+        // Move past the ).
+        self.frames.move_forward();
+        self.handle_pragma(&content, None)
+    }
+
+    /// Performs the pragma with the given content (the text after #pragma).
+    ///
+    /// The tokens are the tokens of the content. They're None for a `_Pragma` operator, so its
+    /// content is lexed if a pragma needs the tokens. Unknown pragmas are ignored.
+    fn handle_pragma(&mut self, content: &str, tokens: Option<&[Token]>) -> MayUnwind<()> {
+        let mut words = content.split_whitespace();
+        if let (Some("once"), None) = (words.next(), words.next()) {
+            self.frames.mark_file_once();
+        } else if let Some(args) = content
+            .trim()
+            .strip_prefix("pack")
+            .and_then(|rest| rest.trim_start().strip_prefix('('))
+            .and_then(|rest| rest.strip_suffix(')'))
+        {
+            self.handle_pragma_pack(args);
+        } else if pragma_name(content) == "message" {
+            return match tokens {
+                Some(tokens) => self.handle_pragma_message(tokens),
+                None => {
+                    let tokens = self.lex_pragma(content)?;
+                    self.handle_pragma_message(&tokens)
+                },
+            };
+        } else if let Some(id) = self.pragma_macro_name(content, "push_macro") {
+            self.frames.push_macro(id);
+        } else if let Some(id) = self.pragma_macro_name(content, "pop_macro") {
+            if !self.frames.pop_macro(id.clone()) {
+                return self.report_error(Error::PopMacroUnderflow(id));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the macro name of a `#pragma push_macro("name")` or `#pragma pop_macro("name")`
+    /// if the content is the given pragma.
+    ///
+    /// Like unknown pragmas, malformed names are ignored (and None is returned).
+    fn pragma_macro_name(&self, content: &str, pragma: &str) -> Option<CachedString> {
+        let name = content
+            .trim()
+            .strip_prefix(pragma)?
+            .trim_start()
+            .strip_prefix('(')?
+            .strip_suffix(')')?
+            .trim()
+            .strip_prefix('"')?
+            .strip_suffix('"')?;
+        Some(self.env.cache().get_or_cache(name))
+    }
+
+    /// Lexes the content of a `_Pragma` operator as if it was the tokens after a #pragma.
+    ///
+    /// The content is added to [file_id_to_tokens](CompileEnv::file_id_to_tokens) with its own
+    /// file id. The same content is only lexed once, so each later `_Pragma` with it shares that
+    /// file id. Like with a #pragma, its lexer errors are reported and their tokens are skipped
+    /// (as is the Eof token).
+    fn lex_pragma(&mut self, content: &str) -> MayUnwind<Vec<Token>> {
+        let tokens = match self.pragma_tokens.get(content) {
+            Some(tokens) => tokens.clone(),
+            None => {
+                let file_id = match self.env.file_id_to_tokens.reserve() {
+                    Some(file_id) => file_id,
+                    None => {
+                        self.report_error(Error::OutOfFileIds)?;
+                        return Err(Unwind::Fatal);
+                    },
+                };
+                let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+                let mut lexer = Lexer::new(self.env, callback);
+                let tokens = Arc::new(lexer.lex_bytes(file_id, content.as_bytes()));
+                self.env.file_id_to_tokens.set_or_panic(file_id, tokens.clone());
+                self.pragma_tokens.insert(content.into(), tokens.clone());
+                tokens
+            },
+        };
+
+        for error in tokens.errors() {
+            self.report_error(error.clone().into())?;
+        }
+        let is_kept = |token: &&Token| !matches!(*token.kind(), LexerError(..) | Eof);
+        Ok(tokens.iter().filter(is_kept).cloned().collect())
+    }
+
+    /// Performs a `#pragma message` with the tokens of the pragma (starting with `message`).
+    ///
+    /// The message should be string literals (that may be in parentheses). Like in C, adjacent
+    /// literals are joined. The message is reported as a note with its escapes as written.
+    fn handle_pragma_message(&mut self, tokens: &[Token]) -> MayUnwind<()> {
+        let mut kinds = tokens.iter().skip(1).map(Token::kind).peekable();
+        let in_parens = kinds.next_if(|&kind| matches!(*kind, LParen)).is_some();
+        let is_string = |kind: &&TokenKind| matches!(**kind, String { is_char: false, .. });
+        let mut message: Option<std::string::String> = None;
+        while let Some(kind) = kinds.next_if(is_string) {
+            if let String { ref str_data, .. } = *kind {
+                message.get_or_insert_with(Default::default).push_str(str_data);
+            }
+        }
+        let closed = !in_parens || kinds.next_if(|&kind| matches!(*kind, RParen)).is_some();
+
+        match message {
+            Some(message) if closed && kinds.next().is_none() => {
+                let message = Arc::new(message.into_boxed_str());
+                self.report_error(Error::MessagePragma(message))
+            },
+            _ => self.report_error(Error::MessagePragmaExpectedString),
+        }
+    }
+
+    /// Performs a `#pragma pack` with the given arguments (the text between its parentheses).
+    ///
+    /// Like unknown pragmas, malformed arguments are ignored.
+    fn handle_pragma_pack(&mut self, args: &str) {
+        let parse_pack = |arg: &str| arg.parse::<u32>().ok().filter(|pack| pack.is_power_of_two());
+        let mut args = args.split(',').map(str::trim);
+        match (args.next(), args.next(), args.next()) {
+            (Some(""), None, None) => self.frames.set_pack(None),
+            (Some("push"), None, None) => self.frames.push_pack(),
+            (Some("push"), Some(arg), None) => {
+                if let Some(pack) = parse_pack(arg) {
+                    self.frames.push_pack();
+                    self.frames.set_pack(Some(pack));
+                }
+            },
+            (Some("pop"), None, None) => self.frames.pop_pack(),
+            (Some(arg), None, None) => {
+                if let Some(pack) = parse_pack(arg) {
+                    self.frames.set_pack(Some(pack));
+                }
+            },
+            _ => {},
+        }
+    }
+
     fn handle_joiner(&mut self) -> MayUnwind<()> {
         self.str_builder.clear();
         let first_token = self.head().clone();
@@ -516,7 +1210,7 @@ impl<'a, E: ErrorReceiver<TravelerError>> Traveler<'a, E> {
                     str_data,
                 },
             ) => {
-                if let Some(encoding) = self.env.get_string_prefix(id) {
+                if let Some(encoding) = self.frames.config().get_string_prefix(id) {
                     String {
                         encoding,
                         is_char: *is_char,
@@ -528,13 +1222,13 @@ impl<'a, E: ErrorReceiver<TravelerError>> Traveler<'a, E> {
                 }
             },
             (part1, part2) if part1.is_number_joinable_with(part2) => {
-                let digits = self.join_and_cache(part1.text(), part2.text());
+                let digits = self.join_and_cache(&number_text(part1), &number_text(part2));
                 Number(digits)
             },
             (id1, id2) if id1.is_id_joinable_with(id2) => {
-                let cached = self.join_and_cache(id1.text(), id2.text());
-                if let Some(keyword) = self.env.get_keyword(&cached) {
-                    Keyword(keyword)
+                let cached = self.join_and_cache(id1.text(), &number_text(id2));
+                if let Some(keyword) = self.frames.config().get_keyword(&cached) {
+                    Keyword(keyword, cached)
                 } else {
                     Identifier(cached)
                 }
@@ -586,3 +1280,58 @@ impl<'a, E: ErrorReceiver<TravelerError>> Traveler<'a, E> {
         count
     }
 }
+
+/// Returns the name of a pragma (the identifier its content starts with).
+fn pragma_name(content: &str) -> &str {
+    let content = content.trim_start();
+    let end = content
+        .find(|c: char| !c.is_alphanumeric() && c != '_')
+        .unwrap_or(content.len());
+    &content[..end]
+}
+
+/// Removes the escapes from a _Pragma's string literal (`\"` becomes `"` and `\\` becomes `\`).
+fn unescape_pragma(str_data: &str) -> std::string::String {
+    let mut content = std::string::String::with_capacity(str_data.len());
+    let mut chars = str_data.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(escaped @ ('"' | '\\')) => content.push(escaped),
+                Some(other) => {
+                    content.push('\\');
+                    content.push(other);
+                },
+                None => content.push('\\'),
+            },
+            c => content.push(c),
+        }
+    }
+    content
+}
+
+/// Returns the text of a token that can be joined with ##. Unlike [TokenKind::text],
+/// [Integer] tokens are supported.
+fn number_text(kind: &TokenKind) -> Cow<'_, str> {
+    match *kind {
+        Integer(value) => Cow::Owned(value.to_string()),
+        _ => Cow::Borrowed(kind.text()),
+    }
+}
+
+/// Returns whether the token is one of the flags (1 to 4) that can follow a line marker.
+fn is_line_marker_flag(kind: &TokenKind) -> bool {
+    match *kind {
+        Integer(flag) => (1..=4).contains(&flag),
+        Number(ref digits) => matches!(digits.string(), "1" | "2" | "3" | "4"),
+        _ => false,
+    }
+}
+
+/// Parses the line number of a #line directive (which must be a decimal digit sequence).
+fn parse_line_number(digits: &str) -> Option<u32> {
+    if !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok().filter(|&line| line <= i32::MAX as u32)
+}
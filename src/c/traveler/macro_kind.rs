@@ -8,9 +8,18 @@ use crate::{
     util::{
         CachedString,
         FileId,
+        SourceLoc,
     },
 };
 
+/// A macro along with where it was defined.
+#[derive(Clone, Debug)]
+pub(super) struct MacroDefinition {
+    /// The location of the macro's identifier in its #define.
+    pub loc: SourceLoc,
+    pub kind: MacroKind,
+}
+
 /// A enum representing the different types of macros.
 #[derive(Clone, Debug)]
 pub(super) enum MacroKind {
@@ -53,7 +62,9 @@ pub(super) enum MacroKind {
 /// should handle.
 pub(super) enum MacroHandle {
     /// An empty macro that should be handled. The FrameStack should move past the current token.
-    Empty,
+    ///
+    /// The id is None if this is an empty function-macro argument.
+    Empty(Option<CachedString>),
     /// A macro that can be handled by pushing a pre-calculated frame.
     ///
     /// The macro should be a single-token macro, an object-macro, or a function-macro's argument.
@@ -65,10 +76,50 @@ pub(super) enum MacroHandle {
         id: CachedString,
         param_count: usize,
     },
+    /// A built-in macro whose token is created when it is used.
+    Builtin {
+        id: CachedString,
+        kind: BuiltinMacro,
+    },
+}
+
+/// The built-in macros whose tokens depend on where (or when) they are used.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(super) enum BuiltinMacro {
+    /// `__FILE__` expands to a string of the current file's path.
+    File,
+    /// `__LINE__` expands to the number of the current line.
+    Line,
+    /// `__COUNTER__` expands to a number that increases every time it is used.
+    Counter,
+    /// `__INCLUDE_LEVEL__` expands to how deeply the current file is included
+    /// (0 for the start file).
+    IncludeLevel,
+    /// `__DATE__` expands to a string of the date the compilation started.
+    Date,
+    /// `__TIME__` expands to a string of the time the compilation started.
+    Time,
+    /// `__STDC__` expands to 1.
+    Stdc,
+    /// `__STDC_VERSION__` expands to the version of the C standard being used (like `201112L`).
+    /// The version is the one the settings had when the built-ins were set up.
+    ///
+    /// It isn't defined for C89 (since it was added after it).
+    StdcVersion(&'static str),
 }
 
 impl MacroHandle {
     pub(super) fn is_empty(&self) -> bool {
-        matches!(*self, MacroHandle::Empty)
+        matches!(*self, MacroHandle::Empty(..))
+    }
+    /// The id of the defined macro this handles (None for function-macro arguments and built-ins).
+    pub(super) fn macro_id(&self) -> Option<&CachedString> {
+        match *self {
+            MacroHandle::Empty(ref id) => id.as_ref(),
+            MacroHandle::Simple(Frame::SingleToken { ref id, .. }) => id.as_ref(),
+            MacroHandle::Simple(Frame::ObjectMacro { ref id, .. })
+            | MacroHandle::FuncMacro { ref id, .. } => Some(id),
+            MacroHandle::Simple(..) | MacroHandle::Builtin { .. } => None,
+        }
     }
 }
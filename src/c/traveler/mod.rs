@@ -28,5 +28,8 @@ mod implementation;
 mod macro_kind;
 mod state;
 
-pub type TravelIndex = crate::math::NonMaxU32;
+crate::math::non_max_id! {
+    /// The index of a token produced by a [Traveler] (after preprocessing).
+    pub struct TravelIndex;
+}
 pub type TravelRange = std::ops::Range<TravelIndex>;
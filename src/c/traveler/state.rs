@@ -2,17 +2,21 @@
 // This source code is licensed under GPLv3 or any later version.
 use std::collections::{
     HashMap,
+    HashSet,
     VecDeque,
 };
 
 use crate::{
     c::traveler::{
         Frame,
-        MacroKind,
+        LineOverride,
+        MacroDefinition,
     },
     util::{
         CachedString,
         FileId,
+        MappedSourceLoc,
+        SourceLoc,
     },
 };
 
@@ -24,8 +28,36 @@ use crate::{
 #[derive(Clone, Debug)]
 pub struct TravelerState {
     pub(super) frames: VecDeque<Frame>,
-    pub(super) macros: HashMap<CachedString, MacroKind>,
+    pub(super) macros: HashMap<CachedString, MacroDefinition>,
     pub(super) dependencies: Vec<FileId>,
+    pub(super) once_files: HashSet<FileId>,
+    pub(super) line_overrides: HashMap<FileId, Vec<LineOverride>>,
+    pub(super) macro_save_stacks: HashMap<CachedString, Vec<Option<MacroDefinition>>>,
+    pub(super) used_macros: HashSet<CachedString>,
+    /// How many times `__COUNTER__` was expanded (so loading the state expands it the same
+    /// way again).
+    pub(super) counter_uses: usize,
+    /// The `#pragma pack` value and the values saved by `#pragma pack(push)`.
+    pub(super) pack: Option<u32>,
+    pub(super) pack_stack: Vec<Option<u32>>,
+    pub(super) macro_depth: usize,
     pub(super) index: u32,
     pub(super) should_chain_skip: bool,
+    pub(super) loc: Option<MappedSourceLoc>,
+}
+
+impl TravelerState {
+    /// The location of the token the traveler was at when this state was saved.
+    ///
+    /// This is None if the traveler had not started traveling yet.
+    pub fn loc(&self) -> Option<SourceLoc> {
+        Some(self.loc.as_ref()?.loc)
+    }
+    /// The location of the token the traveler was at along with the line (and file name)
+    /// `#line` directives gave it.
+    ///
+    /// This is None if the traveler had not started traveling yet.
+    pub fn mapped_loc(&self) -> Option<&MappedSourceLoc> {
+        self.loc.as_ref()
+    }
 }
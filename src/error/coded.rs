@@ -1,6 +1,10 @@
 // Copyright 2021. remilia-dev
 // This source code is licensed under GPLv3 or any later version.
-use crate::error::Severity;
+use crate::{
+    error::Severity,
+    util::SourceLoc,
+};
+
 /// An error type with a severity and code.
 pub trait CodedError {
     /// The severity of the error.
@@ -31,4 +35,15 @@ pub trait CodedError {
     }
     /// Constructs the message of error.
     fn message(&self) -> String;
+    /// The location in source code the error occured at (if it is known).
+    fn loc(&self) -> Option<SourceLoc> {
+        None
+    }
+    /// Secondary locations that are related to the error with a message for each.
+    ///
+    /// For example, a redefinition error could point to the previous definition.
+    /// These should be reported with the [Note](Severity::Note) severity.
+    fn notes(&self) -> Vec<(SourceLoc, String)> {
+        Vec::new()
+    }
 }
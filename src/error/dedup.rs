@@ -0,0 +1,131 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use std::collections::HashMap;
+
+use crate::{
+    error::{
+        CodedError,
+        ErrorReceiver,
+    },
+    util::SourceLoc,
+};
+
+/// An [ErrorReceiver] wrapper that suppresses exact duplicate errors.
+///
+/// Macro expansions and includes can cause the same error to be reported
+/// many times. An error is considered a duplicate if its code prefix, code number,
+/// and [SourceLoc] are the same as an error that was already reported. Only the
+/// first occurrence is forwarded to the inner receiver.
+///
+/// Errors without a location are always forwarded.
+/// # Translation Units
+/// Duplicates are tracked until [clear](DedupReceiver::clear) is called. If the receiver
+/// is reused for another translation unit, it should be cleared first.
+pub struct DedupReceiver<R> {
+    inner: R,
+    /// A map from an error's identity to whether the inner receiver considered it fatal.
+    seen: HashMap<(&'static str, u32, SourceLoc), bool>,
+}
+
+impl<R> DedupReceiver<R> {
+    /// Creates a new DedupReceiver that forwards unique errors to the given receiver.
+    pub fn new(inner: R) -> Self {
+        DedupReceiver { inner, seen: HashMap::new() }
+    }
+    /// Forgets all the errors that have been reported so far.
+    pub fn clear(&mut self) {
+        self.seen.clear();
+    }
+    /// Returns a reference to the inner receiver.
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+    /// Returns a mutable reference to the inner receiver.
+    pub fn inner_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+    /// Consumes this DedupReceiver and returns the inner receiver.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<E, R> ErrorReceiver<E> for DedupReceiver<R>
+where
+    E: CodedError,
+    R: ErrorReceiver<E>,
+{
+    fn report_error(&mut self, error: E) -> bool {
+        let key = match error.loc() {
+            Some(loc) => (error.code_prefix(), error.code_number(), loc),
+            None => return self.inner.report_error(error),
+        };
+        // A suppressed duplicate is as fatal as its first occurrence was.
+        if let Some(&fatal) = self.seen.get(&key) {
+            return fatal;
+        }
+        let fatal = self.inner.report_error(error);
+        self.seen.insert(key, fatal);
+        fatal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        c::{
+            LexerError,
+            LexerErrorKind,
+        },
+        util::FileId,
+    };
+
+    fn unended_comment(byte: u32) -> LexerError {
+        LexerError {
+            kind: LexerErrorKind::UnendedComment,
+            loc: SourceLoc::new(FileId::new(0).unwrap(), byte, 2),
+        }
+    }
+
+    #[test]
+    fn duplicates_are_reported_once() {
+        let mut count = 0;
+        let mut receiver = DedupReceiver::new(|_: LexerError| {
+            count += 1;
+            false
+        });
+        for _ in 0..3 {
+            assert!(receiver.report(unended_comment(10)).is_ok());
+        }
+        drop(receiver);
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn different_locations_are_not_duplicates() {
+        let mut count = 0;
+        let mut receiver = DedupReceiver::new(|_: LexerError| {
+            count += 1;
+            false
+        });
+        receiver.report_error(unended_comment(10));
+        receiver.report_error(unended_comment(20));
+        drop(receiver);
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn clear_forgets_reported_errors() {
+        let mut count = 0;
+        let mut receiver = DedupReceiver::new(|_: LexerError| {
+            count += 1;
+            false
+        });
+        receiver.report_error(unended_comment(10));
+        receiver.clear();
+        receiver.report_error(unended_comment(10));
+        drop(receiver);
+        assert_eq!(count, 2);
+    }
+}
@@ -0,0 +1,124 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use std::io::Write;
+
+use crate::{
+    error::{
+        render::severity_name,
+        CodedError,
+        ErrorReceiver,
+        Severity,
+    },
+    util::{
+        SourceLoc,
+        SourceMap,
+    },
+};
+
+/// An [ErrorReceiver] that writes each error as a single line in the format GCC uses
+/// (`path:line:column: severity: [code] message`).
+///
+/// This is the format editors and `make` look for to jump to the location of an error.
+/// Each of the error's [notes](CodedError::notes) is written on its own line afterwards
+/// with the `note` severity.
+///
+/// Locations are found with the given [SourceMap]. If an error has no location (or its file
+/// isn't in the map), the line starts with the severity instead.
+///
+/// The receiver never considers an error fatal by itself. Failures to write are ignored.
+pub struct GnuFormatReceiver<'a, W> {
+    source_map: &'a SourceMap,
+    output: W,
+}
+
+impl<'a, W: Write> GnuFormatReceiver<'a, W> {
+    /// Creates a new GnuFormatReceiver that writes errors to the given output.
+    pub fn new(source_map: &'a SourceMap, output: W) -> Self {
+        GnuFormatReceiver { source_map, output }
+    }
+    /// Consumes this GnuFormatReceiver and returns the output.
+    pub fn into_inner(self) -> W {
+        self.output
+    }
+
+    fn write_line(&mut self, loc: Option<SourceLoc>, severity: Severity, message: &str) {
+        if let Some(loc) = loc {
+            if let Some((line, column)) = self.source_map.line_col(&loc) {
+                let path = match self.source_map.path(loc.file_id()) {
+                    Some(path) => path.display().to_string(),
+                    None => "<unknown>".to_owned(),
+                };
+                let _ = write!(self.output, "{}:{}:{}: ", path, line, column);
+            }
+        }
+        let _ = writeln!(self.output, "{}: {}", severity_name(severity), message);
+    }
+}
+
+impl<'a, E, W> ErrorReceiver<E> for GnuFormatReceiver<'a, W>
+where
+    E: CodedError,
+    W: Write,
+{
+    fn report_error(&mut self, error: E) -> bool {
+        let message = format!("[{}] {}", error.code(), error.message());
+        self.write_line(error.loc(), error.severity(), &message);
+        for (loc, note) in error.notes() {
+            self.write_line(Some(loc), Severity::Note, &note);
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::{
+        c::{
+            LexerError,
+            LexerErrorKind,
+        },
+        sync::Arc,
+        util::FileId,
+    };
+
+    fn format(source_map: &SourceMap, loc: SourceLoc) -> String {
+        let mut receiver = GnuFormatReceiver::new(source_map, Vec::new());
+        receiver.report_error(LexerError {
+            kind: LexerErrorKind::UnendedComment,
+            loc,
+        });
+        String::from_utf8(receiver.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn errors_are_written_on_one_line() {
+        let source_map = SourceMap::new();
+        let path: Arc<Path> = Arc::from(Path::new("foo.c"));
+        source_map.insert(
+            FileId::new(0).unwrap(),
+            Some(path),
+            b"int x;\nint y; /* y\n",
+        );
+        let output = format(
+            &source_map,
+            SourceLoc::new(FileId::new(0).unwrap(), 14, 2),
+        );
+        assert_eq!(
+            output,
+            "foo.c:2:8: error: [C-L510] The multiline comment was not properly ended before \
+             the end of the file.\n"
+        );
+    }
+
+    #[test]
+    fn unknown_files_have_no_location() {
+        let output = format(
+            &SourceMap::new(),
+            SourceLoc::new(FileId::new(3).unwrap(), 0, 1),
+        );
+        assert!(output.starts_with("error: [C-L510] "));
+    }
+}
@@ -1,14 +1,21 @@
 // Copyright 2021. remilia-dev
 // This source code is licensed under GPLv3 or any later version.
 pub use coded::CodedError;
+pub use dedup::DedupReceiver;
+pub use gnu_format::GnuFormatReceiver;
 pub use receiver::ErrorReceiver;
+pub use render::render_diagnostic;
 pub use severity::Severity;
 pub use unwind::{
     MayUnwind,
+    Recover,
     Unwind,
 };
 
 mod coded;
+mod dedup;
+mod gnu_format;
 mod receiver;
+mod render;
 mod severity;
 mod unwind;
@@ -0,0 +1,152 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use std::fmt::Write;
+
+use crate::{
+    c::CompileEnv,
+    error::{
+        CodedError,
+        Severity,
+    },
+    util::{
+        MappedSourceLoc,
+        SourceLoc,
+    },
+};
+
+/// Renders the error as a human-readable diagnostic pointing at the given location.
+///
+/// The diagnostic has a header (`path:line:column: severity[code]: message`) followed by
+/// the line the location starts on and a `^~~~` underline spanning the location.
+/// If the location spans multiple lines, only the first line is underlined and a note is added.
+///
+/// The source line comes from the env's [SourceMap](crate::util::SourceMap). If the location's
+/// file isn't in the map, only the header (without a line and column) is rendered.
+///
+/// If the location is [mapped](MappedSourceLoc) by a `#line` directive, the header uses the
+/// directive's line and file name (but the snippet is still the physical line).
+pub fn render_diagnostic(
+    env: &CompileEnv,
+    err: &impl CodedError,
+    loc: impl Into<MappedSourceLoc>,
+) -> String {
+    let mapped = loc.into();
+    let loc: SourceLoc = mapped.loc;
+    let mut output = String::new();
+    let severity = severity_name(err.severity());
+    let (path, line, column) = match env.resolve_location(mapped) {
+        Some(resolved) => resolved,
+        None => {
+            let _ = writeln!(output, "{}[{}]: {}", severity, err.code(), err.message());
+            return output;
+        },
+    };
+    let _ = writeln!(
+        output,
+        "{}:{}:{}: {}[{}]: {}",
+        path.as_deref().unwrap_or("<unknown>"),
+        line,
+        column,
+        severity,
+        err.code(),
+        err.message()
+    );
+
+    let snippet = match env.source_map.snippet(&loc) {
+        Some(snippet) => snippet,
+        None => return output,
+    };
+    let start = (column - 1) as usize;
+    let end = start + loc.byte_length as usize;
+    let _ = writeln!(output, "{}", snippet);
+    output.push_str(&underline(&snippet, start, end.min(snippet.len())));
+    output.push('\n');
+    if end > snippet.len() {
+        output.push_str("note: the location continues past this line\n");
+    }
+    output
+}
+
+/// Creates the `^~~~` underline for the bytes start..end of the line.
+///
+/// Tabs before the underline are kept so it lines up with the line when printed.
+fn underline(line: &str, start: usize, end: usize) -> String {
+    let before = line.get(..start).unwrap_or(line);
+    let mut underline: String =
+        before.chars().map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+    underline.push('^');
+
+    let underlined = line.get(start..end).unwrap_or("");
+    for _ in underlined.chars().skip(1) {
+        underline.push('~');
+    }
+    underline
+}
+
+pub(super) fn severity_name(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Internal => "internal error",
+        Severity::Fatal => "fatal error",
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Deprecation => "deprecation",
+        Severity::Note => "note",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        c::{
+            LexerError,
+            LexerErrorKind,
+            TokenKind,
+        },
+        util::FileId,
+    };
+
+    fn render(source: &str, byte: u32, byte_length: u16) -> String {
+        let env = CompileEnv::default();
+        env.source_map
+            .insert(FileId::new(0).unwrap(), None, source.as_bytes());
+        let loc = SourceLoc::new(FileId::new(0).unwrap(), byte, byte_length);
+        let err = LexerError {
+            kind: LexerErrorKind::MissingCorrespondingIf(TokenKind::PreEndIf),
+            loc,
+        };
+        render_diagnostic(&env, &err, loc)
+    }
+
+    #[test]
+    fn diagnostics_underline_the_location() {
+        let output = render("int x;\n\tint yy = 1;\n", 12, 2);
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[0].starts_with("<unknown>:2:6: error[C-L500]: "));
+        assert_eq!(lines[1], "\tint yy = 1;");
+        assert_eq!(lines[2], "\t    ^~");
+        assert_eq!(lines.len(), 3);
+    }
+
+    #[test]
+    fn multi_line_locations_only_underline_the_first_line() {
+        let output = render("x = \"a\\\nb\";", 4, 7);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[1], "x = \"a\\");
+        assert_eq!(lines[2], "    ^~~");
+        assert!(lines[3].starts_with("note: "));
+    }
+
+    #[test]
+    fn unknown_files_only_have_a_header() {
+        let env = CompileEnv::default();
+        let loc = SourceLoc::new(FileId::new(3).unwrap(), 0, 1);
+        let err = LexerError {
+            kind: LexerErrorKind::MissingCorrespondingIf(TokenKind::PreEndIf),
+            loc,
+        };
+        let output = render_diagnostic(&env, &err, loc);
+        assert!(output.starts_with("error[C-L500]: "));
+        assert_eq!(output.lines().count(), 1);
+    }
+}
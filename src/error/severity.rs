@@ -18,6 +18,8 @@ pub enum Severity {
     Warning,
     /// A warning to signal that a piece of code/behavior is deprecated.
     Deprecation,
+    /// Extra information attached to another error (such as where something was previously defined).
+    Note,
 }
 
 impl Severity {
@@ -39,6 +41,7 @@ impl From<Severity> for crd::Severity {
             Severity::Error => crd::Severity::Error,
             Severity::Warning => crd::Severity::Warning,
             Severity::Deprecation => crd::Severity::Warning,
+            Severity::Note => crd::Severity::Note,
         }
     }
 }
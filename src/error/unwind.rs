@@ -1,5 +1,9 @@
 // Copyright 2021. remilia-dev
 // This source code is licensed under GPLv3 or any later version.
+use crate::error::{
+    CodedError,
+    Severity,
+};
 
 /// A value representing how far back an error condition should unwind.
 ///
@@ -13,8 +17,92 @@ pub enum Unwind {
     /// to the current compilation step.
     Fatal,
 }
+
+impl Unwind {
+    /// Returns how far an error of the given severity should unwind.
+    ///
+    /// Fatal severities unwind fatally while every other severity unwinds to the outer block.
+    pub fn for_severity(severity: Severity) -> Unwind {
+        if severity.is_fatal() {
+            Unwind::Fatal
+        } else {
+            Unwind::Block
+        }
+    }
+
+    /// Returns how far the error should unwind (see [Unwind::for_severity]).
+    pub fn from_error<E: CodedError>(error: &E) -> Unwind {
+        Unwind::for_severity(error.severity())
+    }
+
+    /// Returns whether this unwinding should go all the way up to the compilation step.
+    pub fn is_fatal(self) -> bool {
+        matches!(self, Unwind::Fatal)
+    }
+}
+
 /// A result that represents a successful result or a requested unwinding.
 ///
 /// Functions that don't plan on recovering on the unwind request, should
 /// just use the `?` operator to return the unwind value if it occurs.
 pub type MayUnwind<T> = Result<T, Unwind>;
+
+/// A trait to recover from unwinds at a recovery point.
+pub trait Recover<T> {
+    /// Replaces a [Unwind::Block] with the value the function returns.
+    ///
+    /// A [Unwind::Fatal] is not recovered from and is returned as is.
+    fn or_recover<F>(self, f: F) -> MayUnwind<T>
+    where F: FnOnce() -> T;
+}
+
+impl<T> Recover<T> for MayUnwind<T> {
+    fn or_recover<F>(self, f: F) -> MayUnwind<T>
+    where F: FnOnce() -> T {
+        match self {
+            Err(Unwind::Block) => Ok(f()),
+            result => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::c::{
+        LexerError,
+        LexerErrorKind,
+    };
+
+    #[test]
+    fn or_recover_recovers_from_block() {
+        let result: MayUnwind<u32> = Err(Unwind::Block);
+        assert_eq!(result.or_recover(|| 5), Ok(5));
+        assert_eq!(Ok(1).or_recover(|| 5), Ok(1));
+    }
+
+    #[test]
+    fn or_recover_propagates_fatal() {
+        let result: MayUnwind<u32> = Err(Unwind::Fatal);
+        assert_eq!(
+            result.or_recover(|| panic!("Fatal unwinds should not recover.")),
+            Err(Unwind::Fatal)
+        );
+    }
+
+    #[test]
+    fn errors_unwind_by_severity() {
+        let loc = crate::util::SourceLoc::new(crate::util::FileId::new(0).unwrap(), 0, 1);
+        let fatal = LexerError {
+            kind: LexerErrorKind::Io(std::io::Error::from(std::io::ErrorKind::NotFound).into()),
+            loc,
+        };
+        let error = LexerError {
+            kind: LexerErrorKind::UnendedComment,
+            loc,
+        };
+        assert_eq!(Unwind::from_error(&fatal), Unwind::Fatal);
+        assert_eq!(Unwind::from_error(&error), Unwind::Block);
+        assert_eq!(Unwind::for_severity(Severity::Warning), Unwind::Block);
+    }
+}
@@ -5,6 +5,7 @@ use std::path::Path;
 use vase::{
     c::*,
     sync::Arc,
+    util::FileId,
 };
 
 fn main() {
@@ -18,7 +19,10 @@ fn main() {
         panic!("{:?}", error);
         //false
     });
-    let tokens = env.file_id_to_tokens.get_arc(0.into()).unwrap();
+    parser.set_include_callback(|inc_type, path: &_, curr_file: &_| {
+        lexer.lex_include(inc_type, path, curr_file)
+    });
+    let tokens = env.file_id_to_tokens.get_arc(FileId::MIN).unwrap();
     println!("Tokens In File: {}", tokens.len());
     let parsed = parser.parse(tokens).unwrap();
     println!("{:#?}", parsed)
@@ -1,6 +1,7 @@
 // Copyright 2021. remilia-dev
 // This source code is licensed under GPLv3 or any later version.
 pub use base::NumBase;
+pub(crate) use non_max::non_max_id;
 pub use non_max::NonMaxU32;
 pub use num::{
     Integer,
@@ -14,6 +14,8 @@ pub struct NonMaxU32(NonZeroU32);
 impl NonMaxU32 {
     /// The maximum possible NonMaxU32.
     pub const MAX: NonMaxU32 = unsafe { NonMaxU32::new_unchecked(u32::MAX - 1) };
+    /// The minimum possible NonMaxU32 (0).
+    pub const MIN: NonMaxU32 = unsafe { NonMaxU32::new_unchecked(0) };
     /// Creates a non-max if the given value is not [u32::MAX].
     pub fn new(n: u32) -> Option<NonMaxU32> {
         Some(NonMaxU32(NonZeroU32::new(!n)?))
@@ -60,6 +62,74 @@ impl From<u16> for NonMaxU32 {
     }
 }
 
+/// Declares a newtype around a [NonMaxU32] that is used as an id or index.
+///
+/// Each newtype is distinct, so ids of different kinds can't be mixed up without
+/// explicitly converting through a [NonMaxU32].
+macro_rules! non_max_id {
+    ($(#[$meta:meta])* $vis:vis struct $name:ident;) => {
+        $(#[$meta])*
+        #[derive(Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+        $vis struct $name($crate::math::NonMaxU32);
+
+        impl $name {
+            /// The maximum possible id.
+            pub const MAX: $name = $name($crate::math::NonMaxU32::MAX);
+            /// The minimum possible id (0).
+            pub const MIN: $name = $name($crate::math::NonMaxU32::MIN);
+            /// Creates an id from the given value if it is not [u32::MAX].
+            pub fn new(n: u32) -> Option<Self> {
+                Some($name($crate::math::NonMaxU32::new(n)?))
+            }
+            /// Creates an id from the given value if it is less than [u32::MAX].
+            pub fn new_usize(n: usize) -> Option<Self> {
+                Some($name($crate::math::NonMaxU32::new_usize(n)?))
+            }
+            /// Creates an id from the given [NonMaxU32](crate::math::NonMaxU32).
+            pub fn from_non_max(n: $crate::math::NonMaxU32) -> Self {
+                $name(n)
+            }
+            /// Returns the id as a [NonMaxU32](crate::math::NonMaxU32).
+            pub fn non_max(self) -> $crate::math::NonMaxU32 {
+                self.0
+            }
+            /// Returns the value of the id as a primitive type.
+            pub fn get(self) -> u32 {
+                self.0.get()
+            }
+            /// Increments the value of the id.
+            /// # Panics
+            /// Panics if the value is incremented to [u32::MAX].
+            pub fn increment(&mut self) {
+                self.0.increment();
+            }
+        }
+
+        impl std::fmt::Debug for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}({})", stringify!($name), self.0)
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+
+        impl $crate::sync::OnceIndex for $name {
+            fn from_non_max(index: $crate::math::NonMaxU32) -> Self {
+                $name::from_non_max(index)
+            }
+
+            fn non_max(self) -> $crate::math::NonMaxU32 {
+                $name::non_max(self)
+            }
+        }
+    };
+}
+pub(crate) use non_max_id;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -27,7 +27,7 @@ use crate::sync::{
 /// * setting the value if it is None
 ///
 /// Other operations (such as setting the Arc even if it's not None) requires
-/// exclusive mutable access.
+/// exclusive mutable access (or the unsafe [compare_exchange](Self::compare_exchange)).
 pub struct AtomicArc<T> {
     ptr: AtomicPtr<T>,
     _phantom: PhantomData<Arc<T>>,
@@ -185,6 +185,43 @@ impl<T> AtomicArc<T> {
             Err(ptr) => Err(unsafe { &*ptr }),
         }
     }
+    /// Sets the value to new if the current value is the same Arc as current (or both are None).
+    ///
+    /// Like [AtomicPtr::compare_exchange], this returns a result that indicates whether the
+    /// exchange occurred (Ok) or not (Err). If it occurred, the result contains the value that
+    /// was replaced (which is the same Arc as current). If it didn't, the result contains the
+    /// current value and new is dropped.
+    /// # Safety
+    /// Replacing a value with this function drops this AtomicArc's reference count of it.
+    /// The value may then be freed while references from [load](Self::load),
+    /// [set_if_none](Self::set_if_none), and [try_set_if_none](Self::try_set_if_none) are
+    /// still in use. Concurrent [load_arc](Self::load_arc), [load_or_else](Self::load_or_else),
+    /// and compare_exchange calls may also try to clone the Arc of a freed value.
+    ///
+    /// The caller must ensure that none of these happen to values this replaces
+    /// (such as by only replacing None or by otherwise keeping replaced values alive).
+    pub unsafe fn compare_exchange(
+        &self,
+        current: Option<&Arc<T>>,
+        new: Option<Arc<T>>,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<Option<Arc<T>>, Option<Arc<T>>> {
+        let current_ptr = current.map_or(null_mut(), |arc| Arc::as_ptr(arc) as *mut T);
+        let new_ptr = new.as_ref().map_or(null_mut(), |arc| Arc::as_ptr(arc) as *mut T);
+        match self.ptr.compare_exchange(current_ptr, new_ptr, success, failure) {
+            Ok(old) => {
+                // NOTE: We have to forget the new Arc since its ptr is now in self.
+                std::mem::forget(new);
+                // SAFETY: This type owned a reference count of the old ptr, which is given
+                // to the returned Arc.
+                Ok(NonNull::new(old).map(|old| Arc::from_raw(old.as_ptr())))
+            },
+            // SAFETY: The ptr is still in self (and the caller ensures it hasn't been freed).
+            // The Arc they tried to set will drop itself.
+            Err(actual) => Err(NonNull::new(actual).map(|ptr| Self::increment_and_make_arc(ptr))),
+        }
+    }
     /// Loads the internal pointer that represents the Arc.
     /// This pointer should be from [Arc::into_raw].
     fn load_ptr(&self) -> Option<NonNull<T>> {
@@ -230,7 +267,13 @@ impl<T: fmt::Debug> fmt::Debug for AtomicArc<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::test_utils::DropTester;
+    use crate::{
+        sync::AtomicUsize,
+        test_utils::{
+            DropCounter,
+            DropTester,
+        },
+    };
 
     #[test]
     fn atomic_arc_same_size_as_arc() {
@@ -272,4 +315,82 @@ mod tests {
         let aa = AtomicArc::<usize>::default();
         assert!(aa.try_set_if_none(Arc::new(1)).is_ok());
     }
+
+    #[test]
+    fn compare_exchange_sets_and_clears_values() {
+        let aa = AtomicArc::<usize>::default();
+        let first = Arc::new(1);
+        let second = Arc::new(2);
+        let cas = |current, new| unsafe {
+            aa.compare_exchange(current, new, Ordering::SeqCst, Ordering::SeqCst)
+        };
+
+        // None to a value.
+        assert_eq!(cas(None, Some(first.clone())), Ok(None));
+        assert_eq!(aa.load(), Some(&1));
+        // A value to another value.
+        let replaced = cas(Some(&first), Some(second.clone())).unwrap().unwrap();
+        assert!(Arc::ptr_eq(&replaced, &first));
+        assert_eq!(aa.load(), Some(&2));
+        // A value to None.
+        let replaced = cas(Some(&second), None).unwrap().unwrap();
+        assert!(Arc::ptr_eq(&replaced, &second));
+        assert_eq!(aa.load(), None);
+    }
+
+    #[test]
+    fn compare_exchange_fails_when_current_is_different() {
+        let aa = AtomicArc::new_arc(1usize);
+        let other = Arc::new(1);
+        let cas = |current| unsafe {
+            aa.compare_exchange(current, Some(Arc::new(3)), Ordering::SeqCst, Ordering::SeqCst)
+        };
+        // Arcs are compared by pointer (not by value).
+        let actual = cas(Some(&other)).unwrap_err().unwrap();
+        assert_eq!(*actual, 1);
+        let actual = cas(None).unwrap_err().unwrap();
+        assert_eq!(*actual, 1);
+        assert_eq!(aa.load(), Some(&1));
+    }
+
+    #[test]
+    fn compare_exchange_does_not_leak() {
+        const THREADS: usize = 8;
+        const SWAPS: usize = 1000;
+        let drops = Arc::new(AtomicUsize::new(0));
+        let aa = Arc::new(AtomicArc::<DropCounter>::default());
+
+        let threads: Vec<_> = (0..THREADS)
+            .map(|_| {
+                let aa = aa.clone();
+                let drops = drops.clone();
+                std::thread::spawn(move || {
+                    // The values are kept alive until every thread is done (so none are freed
+                    // while another thread may be loading them).
+                    let mut kept = Vec::new();
+                    for _ in 0..SWAPS {
+                        let current = aa.load_arc();
+                        let new = Arc::new(DropCounter::new(&drops));
+                        let result = unsafe {
+                            aa.compare_exchange(
+                                current.as_ref(),
+                                Some(new),
+                                Ordering::SeqCst,
+                                Ordering::SeqCst,
+                            )
+                        };
+                        kept.push((current, result));
+                    }
+                    kept
+                })
+            })
+            .collect();
+        let kept: Vec<_> = threads.into_iter().map(|thread| thread.join().unwrap()).collect();
+        drop(kept);
+
+        // Every value except the last one set should have been dropped.
+        assert_eq!(drops.load(Ordering::SeqCst), THREADS * SWAPS - 1);
+        drop(aa);
+        assert_eq!(drops.load(Ordering::SeqCst), THREADS * SWAPS);
+    }
 }
@@ -32,7 +32,11 @@ pub use self::work_queue::WorkQueue;
 pub use self::{
     atomic_arc::AtomicArc,
     atomic_box::AtomicBox,
-    once_array::OnceArray,
+    once_array::{
+        AlreadySet,
+        OnceArray,
+        OnceIndex,
+    },
 };
 
 mod atomic_arc;
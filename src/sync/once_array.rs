@@ -1,10 +1,11 @@
 // Copyright 2021. remilia-dev
 // This source code is licensed under GPLv3 or any later version.
+use std::marker::PhantomData;
+
 use crate::{
     math::NonMaxU32,
     sync::{
         Arc,
-        AtomicArc,
         AtomicBox,
         AtomicU32,
         Ordering,
@@ -22,7 +23,7 @@ const MAX_VALUES: usize = NODE_SIZE * NODE_COUNT;
 
 /// A grow-only array that can initialize indexes individually.
 ///
-/// This array uses [AtomicArc]s to initialize individual elements.
+/// This array uses [AtomicBox]es of Arcs to initialize individual elements.
 /// As such, each element of a OnceArray must be contained in an Arc.
 /// A thread can reserve an index and fill it later (or have another thread
 /// fill it).
@@ -30,54 +31,127 @@ const MAX_VALUES: usize = NODE_SIZE * NODE_COUNT;
 /// A thread could also reserve an index and not fill it in. However,
 /// this is discouraged.
 ///
+/// The array is indexed by `I`, which can be any id that wraps a [NonMaxU32].
+///
 /// # Max Size
 /// Currently, the array can not grow beyond 102,400 indices.
 /// Growing beyond that will result in a panic when the slot is set.
-pub struct OnceArray<T> {
+pub struct OnceArray<T, I = NonMaxU32> {
     nodes: [AtomicBox<OnceArrayNode<T>>; NODE_COUNT],
     accum: AtomicU32,
+    _index: PhantomData<I>,
+}
+
+/// A type that can index a [OnceArray].
+pub trait OnceIndex: Copy {
+    /// Creates the index of the given position in the array.
+    fn from_non_max(index: NonMaxU32) -> Self;
+    /// Returns the position in the array the index refers to.
+    fn non_max(self) -> NonMaxU32;
+}
+
+impl OnceIndex for NonMaxU32 {
+    fn from_non_max(index: NonMaxU32) -> Self {
+        index
+    }
+
+    fn non_max(self) -> NonMaxU32 {
+        self
+    }
 }
 
-impl<T> OnceArray<T> {
+/// The error [OnceArray::set] returns when the index already has a value.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct AlreadySet;
+
+impl<T, I: OnceIndex> OnceArray<T, I> {
     pub fn new() -> Self {
         OnceArray {
             nodes: make_static_array::<_, NODE_COUNT>(&|| AtomicBox::default()),
             accum: 0.into(),
+            _index: PhantomData,
         }
     }
     /// Reserves an index to be set later. This index is guaranteed to be unique.
-    pub fn reserve(&self) -> Option<NonMaxU32> {
+    pub fn reserve(&self) -> Option<I> {
         // NOTE: We use a u32 because the maximum number of indices can fit within u32.
         // Also, we know outside code will use u32 more than usize.
         let index = self.accum.fetch_add(1, Ordering::SeqCst) as u32;
         if index < MAX_VALUES as u32 {
             // SAFETY: MAX_VALUES is less than the maximum of a u32.
-            Some(unsafe { NonMaxU32::new_unchecked(index) })
+            Some(I::from_non_max(unsafe {
+                NonMaxU32::new_unchecked(index)
+            }))
         } else {
             // Subtract 1 to ensure that index accumulator never wraps.
             self.accum.fetch_sub(1, Ordering::SeqCst);
             None
         }
     }
+    /// Reserves the given number of consecutive indexes to be set later (possibly by other
+    /// threads). These indexes are guaranteed to be unique.
+    ///
+    /// If there isn't room for all the indexes, none of them are reserved and None is returned.
+    pub fn reserve_many(&self, count: usize) -> Option<impl ExactSizeIterator<Item = I>> {
+        if count > MAX_VALUES {
+            return None;
+        }
+        // NOTE: count fits in a u32 since MAX_VALUES does.
+        let count = count as u32;
+        let start = self.accum.fetch_add(count, Ordering::SeqCst);
+        if start.saturating_add(count) <= MAX_VALUES as u32 {
+            // SAFETY: The indexes are less than MAX_VALUES, which is less than a u32's maximum.
+            let indexes = start..start + count;
+            Some(indexes.map(|index| I::from_non_max(unsafe { NonMaxU32::new_unchecked(index) })))
+        } else {
+            // Subtract the count to ensure that index accumulator never wraps.
+            self.accum.fetch_sub(count, Ordering::SeqCst);
+            None
+        }
+    }
+    /// The number of indexes that have been reserved (whether they have been set or not).
+    pub fn len(&self) -> usize {
+        self.accum.load(Ordering::SeqCst).min(MAX_VALUES as u32) as usize
+    }
+    /// Returns true if no indexes have been reserved.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
     /// Tries to get the value at a specific index. If that index has not been initialized,
     /// it will return None.
-    pub fn get(&self, index: NonMaxU32) -> Option<&T> {
+    pub fn get(&self, index: I) -> Option<&T> {
+        let index = index.non_max();
         self.get_node(index)?.get(index)
     }
     /// Tries to get the Arc of the value at a specific index. If that index has not been
     /// initialized, it will return None.
-    pub fn get_arc(&self, index: NonMaxU32) -> Option<Arc<T>> {
+    pub fn get_arc(&self, index: I) -> Option<Arc<T>> {
+        let index = index.non_max();
         self.get_node(index)?.get_arc(index)
     }
+    /// Returns an iterator over the values that have been set (and their indices).
+    ///
+    /// Indices that were reserved but never set are skipped. Values that are set while
+    /// iterating may or may not be yielded, so this is best used after all values are set.
+    pub fn iter(&self) -> impl Iterator<Item = (I, &Arc<T>)> {
+        let reserved = self.accum.load(Ordering::SeqCst).min(MAX_VALUES as u32);
+        (0..reserved).filter_map(move |index| {
+            // SAFETY: reserved is at most MAX_VALUES, which is less than the maximum of a u32.
+            let index = unsafe { NonMaxU32::new_unchecked(index) };
+            let value = self.get_node(index)?.get_arc_ref(index)?;
+            Some((I::from_non_max(index), value))
+        })
+    }
     /// Adds a value onto the array and returns the index the value is at.
-    pub fn push(&self, val: Arc<T>) -> NonMaxU32 {
+    pub fn push(&self, val: Arc<T>) -> I {
         let index = self.reserve().unwrap();
         self.set_or_panic(index, val);
         index
     }
     /// Sets the value at the given index. Since this method has exclusive mutability,
     /// it can also set a value to None.
-    pub fn set_mut(&mut self, index: NonMaxU32, val: Option<Arc<T>>) {
+    pub fn set_mut(&mut self, index: I, val: Option<Arc<T>>) {
+        let index = index.non_max();
         // OPTIMIZATION: Could we use Ordering::Acquire here?
         if self.accum.load(Ordering::SeqCst) <= index.get() {
             panic!("Cannot set a value in a non-reserved index.")
@@ -87,14 +161,26 @@ impl<T> OnceArray<T> {
     /// Sets the value at the given index.
     /// # Panics
     /// Panics if this index has already been set.
-    pub fn set_or_panic(&self, index: NonMaxU32, val: Arc<T>) {
+    pub fn set_or_panic(&self, index: I, val: Arc<T>) {
         if !self.set_if_none(index, val) {
             panic!("Cannot set a value in a once-array that has already been initialized.");
         }
     }
+    /// Sets the value at the given index. If that index has already been set, the value is
+    /// discarded and [AlreadySet] is returned.
+    /// # Panics
+    /// Panics if the index hasn't been reserved.
+    pub fn set(&self, index: I, val: Arc<T>) -> Result<(), AlreadySet> {
+        if self.set_if_none(index, val) {
+            Ok(())
+        } else {
+            Err(AlreadySet)
+        }
+    }
     /// Tries to set the value at the given index. If that value has been already set,
     /// the value is discarded. Returns whether the value was set or not.
-    pub fn set_if_none(&self, index: NonMaxU32, val: Arc<T>) -> bool {
+    pub fn set_if_none(&self, index: I, val: Arc<T>) -> bool {
+        let index = index.non_max();
         // OPTIMIZATION: Could we use Ordering::Acquire here?
         if self.accum.load(Ordering::SeqCst) <= index.get() {
             panic!("Cannot set a value in a non-reserved index.")
@@ -119,43 +205,48 @@ impl<T> OnceArray<T> {
     }
 }
 
-impl<T> std::ops::Index<NonMaxU32> for OnceArray<T> {
+impl<T, I: OnceIndex> std::ops::Index<I> for OnceArray<T, I> {
     type Output = T;
 
     /// Gets the value at the given index.
     /// # Panics
     /// Panics if the index is out of bounds or if that index has not been set.
-    fn index(&self, index: NonMaxU32) -> &T {
+    fn index(&self, index: I) -> &T {
         return self.get(index).unwrap();
     }
 }
 
-impl<T> Default for OnceArray<T> {
+impl<T, I: OnceIndex> Default for OnceArray<T, I> {
     fn default() -> Self {
         OnceArray::new()
     }
 }
 
+// NOTE: The Arcs are boxed (instead of using AtomicArcs) so references to them can be returned.
 /// Contains a limited number of values for a once array.
 struct OnceArrayNode<T> {
-    values: [AtomicArc<T>; NODE_SIZE],
+    values: [AtomicBox<Arc<T>>; NODE_SIZE],
 }
 impl<T> OnceArrayNode<T> {
     fn get(&self, index: NonMaxU32) -> Option<&T> {
-        self.values[self.val_index(index)].load()
+        Some(&**self.get_arc_ref(index)?)
     }
 
     fn get_arc(&self, index: NonMaxU32) -> Option<Arc<T>> {
-        self.values[self.val_index(index)].load_arc()
+        self.get_arc_ref(index).cloned()
+    }
+
+    fn get_arc_ref(&self, index: NonMaxU32) -> Option<&Arc<T>> {
+        self.values[self.val_index(index)].load()
     }
 
     fn try_set(&self, index: NonMaxU32, v: Arc<T>) -> bool {
         let slot = &self.values[self.val_index(index)];
-        return matches!(slot.try_set_if_none(v), Ok(_));
+        slot.try_set_if_none(Box::new(v)).is_ok()
     }
 
     fn set_mut(&mut self, index: NonMaxU32, v: Option<Arc<T>>) {
-        self.values[self.val_index(index)].set(v);
+        self.values[self.val_index(index)].set(v.map(Box::new));
     }
 
     fn val_index(&self, index: NonMaxU32) -> usize {
@@ -166,7 +257,7 @@ impl<T> OnceArrayNode<T> {
 impl<T> Default for OnceArrayNode<T> {
     fn default() -> Self {
         OnceArrayNode::<T> {
-            values: make_static_array::<_, NODE_SIZE>(&|| AtomicArc::default()),
+            values: make_static_array::<_, NODE_SIZE>(&|| AtomicBox::default()),
         }
     }
 }
@@ -238,6 +329,69 @@ mod tests {
         assert_eq!(arr.get(index), Some(&10));
     }
 
+    #[test]
+    fn iter_skips_unset_indexes() {
+        let arr = OnceArray::<usize>::default();
+        let first = arr.push(10.into());
+        let _gap = arr.reserve().unwrap();
+        let second = arr.push(11.into());
+        let third = arr.push(12.into());
+
+        let values: Vec<_> = arr.iter().collect();
+        assert_eq!(values, vec![
+            (first, &Arc::new(10)),
+            (second, &Arc::new(11)),
+            (third, &Arc::new(12)),
+        ]);
+    }
+
+    #[test]
+    fn reserve_many_reserves_consecutive_indexes() {
+        let arr = OnceArray::<usize>::default();
+        arr.reserve().unwrap();
+        let indexes: Vec<NonMaxU32> = arr.reserve_many(3).unwrap().collect();
+        assert_eq!(indexes, vec![1.into(), 2.into(), 3.into()]);
+        assert_eq!(arr.len(), 4);
+        assert!(arr.reserve_many(MAX_VALUES).is_none());
+        assert_eq!(arr.len(), 4);
+    }
+
+    #[test]
+    fn set_fails_when_already_set() {
+        let arr = OnceArray::<usize>::default();
+        let index = arr.reserve().unwrap();
+        assert_eq!(arr.set(index, 10.into()), Ok(()));
+        assert_eq!(arr.set(index, 11.into()), Err(AlreadySet));
+        assert_eq!(arr[index], 10);
+    }
+
+    #[test]
+    fn threads_can_set_reserved_indexes() {
+        const THREADS: usize = 8;
+        const PER_THREAD: usize = 500;
+        let arr = Arc::new(OnceArray::<usize>::default());
+        let indexes: Vec<NonMaxU32> = arr.reserve_many(THREADS * PER_THREAD).unwrap().collect();
+
+        let threads: Vec<_> = indexes
+            .chunks(PER_THREAD)
+            .map(|chunk| {
+                let arr = arr.clone();
+                let chunk = chunk.to_vec();
+                std::thread::spawn(move || {
+                    for index in chunk {
+                        arr.set(index, Arc::new(index.get() as usize)).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let values: Vec<usize> = arr.iter().map(|(_, value)| **value).collect();
+        assert_eq!(values, (0..THREADS * PER_THREAD).collect::<Vec<_>>());
+    }
+
     #[test]
     #[should_panic]
     fn index_panics_on_empty_index() {
@@ -1,5 +1,10 @@
 // Copyright 2021. remilia-dev
 // This source code is licensed under GPLv3 or any later version.
+use crate::sync::{
+    Arc,
+    AtomicUsize,
+    Ordering,
+};
 
 pub struct DropTester<'a> {
     flag: &'a mut bool,
@@ -16,3 +21,21 @@ impl Drop for DropTester<'_> {
         *self.flag = true;
     }
 }
+
+/// Counts how many times it (and its clones) have been dropped.
+#[derive(Clone)]
+pub struct DropCounter {
+    count: Arc<AtomicUsize>,
+}
+
+impl DropCounter {
+    pub fn new(count: &Arc<AtomicUsize>) -> Self {
+        DropCounter { count: count.clone() }
+    }
+}
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        self.count.fetch_add(1, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,138 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use std::{
+    collections::HashMap,
+    hash::{
+        BuildHasherDefault,
+        Hasher,
+    },
+};
+
+use crate::util::CachedString;
+
+/// A set of [CachedStrings](CachedString) that is faster than a `HashSet<CachedString>`.
+///
+/// Cached strings compare by pointer, so the set is keyed by each string's
+/// [uniq_id](crate::util::CachedStringData::uniq_id) and hashes it with a cheap
+/// multiplicative hash instead of SipHash.
+#[derive(Clone, Debug, Default)]
+pub struct CachedStringSet {
+    strings: HashMap<usize, CachedString, BuildHasherDefault<UniqIdHasher>>,
+}
+impl CachedStringSet {
+    pub fn new() -> Self {
+        CachedStringSet::default()
+    }
+    /// Adds the string to the set. Returns whether the string was not already in the set.
+    pub fn insert(&mut self, string: CachedString) -> bool {
+        self.strings.insert(string.uniq_id(), string).is_none()
+    }
+    /// Returns whether the string is in the set.
+    pub fn contains(&self, string: &CachedString) -> bool {
+        self.strings.contains_key(&string.uniq_id())
+    }
+    /// Removes the string from the set. Returns whether the string was in the set.
+    pub fn remove(&mut self, string: &CachedString) -> bool {
+        self.strings.remove(&string.uniq_id()).is_some()
+    }
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+    pub fn clear(&mut self) {
+        self.strings.clear();
+    }
+    /// Returns an iterator over the strings in the set (in no particular order).
+    pub fn iter(&self) -> impl Iterator<Item = &CachedString> {
+        self.strings.values()
+    }
+}
+impl std::iter::FromIterator<CachedString> for CachedStringSet {
+    fn from_iter<T: IntoIterator<Item = CachedString>>(iter: T) -> Self {
+        let mut set = CachedStringSet::new();
+        set.extend(iter);
+        set
+    }
+}
+impl Extend<CachedString> for CachedStringSet {
+    fn extend<T: IntoIterator<Item = CachedString>>(&mut self, iter: T) {
+        for string in iter {
+            self.insert(string);
+        }
+    }
+}
+
+/// A hasher for the uniq_id of cached strings.
+///
+/// The ids are pointers, so their low bits are mostly zeros because of alignment.
+/// The id is multiplied to spread its bits and then the high bits are folded
+/// back into the low bits (which the hash map uses to pick a bucket).
+#[derive(Default)]
+struct UniqIdHasher {
+    hash: u64,
+}
+impl Hasher for UniqIdHasher {
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.write_u64(u64::from(byte));
+        }
+    }
+    fn write_usize(&mut self, id: usize) {
+        self.write_u64(id as u64);
+    }
+    fn write_u64(&mut self, id: u64) {
+        const SEED: u64 = 0x517c_c1b7_2722_0a95;
+        let hash = (self.hash.rotate_left(5) ^ id).wrapping_mul(SEED);
+        self.hash = hash ^ (hash >> 32);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::StringCache;
+
+    #[test]
+    fn set_matches_hash_set() {
+        let cache = StringCache::new();
+        let strings: Vec<CachedString> =
+            (0..500).map(|i| cache.get_or_cache(&format!("s{}", i))).collect();
+
+        let mut set = CachedStringSet::new();
+        let mut expected = std::collections::HashSet::new();
+        for (i, string) in strings.iter().enumerate() {
+            if i % 3 != 0 {
+                assert_eq!(
+                    set.insert(string.clone()),
+                    expected.insert(string.clone())
+                );
+            }
+        }
+        assert!(!set.insert(strings[1].clone()));
+        for (i, string) in strings.iter().enumerate() {
+            if i % 5 == 0 {
+                assert_eq!(set.remove(string), expected.remove(string));
+            }
+        }
+
+        assert_eq!(set.len(), expected.len());
+        for string in &strings {
+            assert_eq!(set.contains(string), expected.contains(string));
+        }
+        assert!(set.iter().all(|string| expected.contains(string)));
+    }
+
+    #[test]
+    fn strings_from_different_caches_are_different() {
+        let first = StringCache::new().get_or_cache("name");
+        let second = StringCache::new().get_or_cache("name");
+        let set: CachedStringSet = std::iter::once(first.clone()).collect();
+        assert!(set.contains(&first));
+        assert!(!set.contains(&second));
+    }
+}
@@ -171,6 +171,18 @@ pub enum Utf8DecodeError {
         missing_byte_count: usize,
     },
 }
+impl Utf8DecodeError {
+    /// Returns the byte offset that decoding failed at.
+    pub fn byte_position(&self) -> usize {
+        match *self {
+            Self::InvalidByte { byte_position, .. }
+            | Self::InvalidCharacter { byte_position, .. }
+            | Self::OverlongEncoding { byte_position, .. }
+            | Self::MisalignedRead { byte_position }
+            | Self::MissingBytes { byte_position, .. } => byte_position,
+        }
+    }
+}
 impl std::fmt::Display for Utf8DecodeError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match *self {
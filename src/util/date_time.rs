@@ -0,0 +1,96 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use std::time::{
+    SystemTime,
+    UNIX_EPOCH,
+};
+
+/// A UTC date and time (to the second).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct DateTime {
+    pub year: i64,
+    /// The month of the year (1 to 12).
+    pub month: u32,
+    /// The day of the month (1 to 31).
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+}
+impl DateTime {
+    /// Returns the current date and time. A system clock set before 1970 is treated as 1970.
+    pub fn now() -> Self {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        DateTime::from_unix_secs(secs)
+    }
+
+    /// Converts the number of seconds since the Unix epoch to a date and time.
+    pub fn from_unix_secs(secs: u64) -> Self {
+        let days = (secs / 86400) as i64;
+        let secs_of_day = (secs % 86400) as u32;
+
+        // Converts the days to a civil date (see Howard Hinnant's days_from_civil algorithms).
+        // Years are shifted to start in March so the leap day is the last day of the year.
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        } as u32;
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+        DateTime {
+            year,
+            month,
+            day,
+            hour: secs_of_day / 3600,
+            minute: secs_of_day % 3600 / 60,
+            second: secs_of_day % 60,
+        }
+    }
+
+    /// Formats the date like `__DATE__` (`Mmm dd yyyy`). Days before the 10th are padded with a space.
+    pub fn date_macro_string(&self) -> String {
+        const MONTHS: [&str; 12] = [
+            "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+        ];
+        let month = MONTHS[(self.month - 1) as usize];
+        format!("{} {:>2} {}", month, self.day, self.year)
+    }
+
+    /// Formats the time like `__TIME__` (`hh:mm:ss`).
+    pub fn time_macro_string(&self) -> String {
+        format!("{:02}:{:02}:{:02}", self.hour, self.minute, self.second)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn unix_secs_convert_to_dates() {
+        let epoch = DateTime::from_unix_secs(0);
+        assert_eq!(epoch.date_macro_string(), "Jan  1 1970");
+        assert_eq!(epoch.time_macro_string(), "00:00:00");
+
+        // 2000-02-29 13:05:09 (a leap day)
+        let leap_day = DateTime::from_unix_secs(951_829_509);
+        assert_eq!(leap_day.date_macro_string(), "Feb 29 2000");
+        assert_eq!(leap_day.time_macro_string(), "13:05:09");
+
+        // 2021-12-31 23:59:59
+        let year_end = DateTime::from_unix_secs(1_640_995_199);
+        assert_eq!(year_end.date_macro_string(), "Dec 31 2021");
+        assert_eq!(year_end.time_macro_string(), "23:59:59");
+    }
+}
@@ -1,11 +1,13 @@
 // Copyright 2021. remilia-dev
 // This source code is licensed under GPLv3 or any later version.
+pub use cached_string_set::CachedStringSet;
 pub use char_ext::{
     CharExt,
     DecodedChar,
     Utf8DecodeError,
 };
 pub use convert::Conversions;
+pub use date_time::DateTime;
 pub use ptr_equality::PtrEquality;
 pub use redecl_map::{
     RedeclMap,
@@ -13,8 +15,10 @@ pub use redecl_map::{
 };
 pub use source_loc::{
     FileId,
+    MappedSourceLoc,
     SourceLoc,
 };
+pub use source_map::SourceMap;
 pub use string_builder::StringBuilder;
 pub use string_cache::{
     CachedString,
@@ -31,11 +35,14 @@ pub use vec32::Vec32;
 /// Memory utilities.
 pub mod mem;
 
+mod cached_string_set;
 mod char_ext;
 mod convert;
+mod date_time;
 mod ptr_equality;
 mod redecl_map;
 mod source_loc;
+mod source_map;
 mod string_builder;
 mod string_cache;
 mod vec32;
@@ -104,6 +104,12 @@ impl<K: Hash + Eq, V> RedeclMap<K, V> {
     pub fn keys(&self) -> Keys<K, NonMaxU32> {
         self.by_name.keys()
     }
+    /// Returns an iterator over all the values (including redeclared and unkeyed values).
+    ///
+    /// Values are iterated in the order their keys were first added.
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.items.iter().flatten()
+    }
 }
 
 impl<K: Hash + Eq, V> Default for RedeclMap<K, V> {
@@ -2,9 +2,12 @@
 // This source code is licensed under GPLv3 or any later version.
 use std::convert::TryInto;
 
-use crate::math::NonMaxU32;
+use crate::{
+    math::non_max_id,
+    util::CachedString,
+};
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub struct SourceLoc {
     file_id: FileId,
     /// The byte in the file this location starts at.
@@ -51,12 +54,50 @@ impl SourceLoc {
         }
     }
 }
-/// A type alias representing the numeric type that represents the id of a file.
-/// # Warning
-/// While this type is currently a NonMaxU32, it may change in the future to another
-/// numeric type or a structure type. However, this type should never be
-/// larger than 4 bytes.
-pub type FileId = NonMaxU32;
+/// A [SourceLoc] along with the line (and file name) a `#line` directive gave it.
+///
+/// Diagnostics should show the overridden line instead of the physical one when there is one.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MappedSourceLoc {
+    pub loc: SourceLoc,
+    /// The line (starting at 1) and file name given by a `#line` directive.
+    ///
+    /// This is None if no directive applies to the location. The file name is None if
+    /// the directives never changed it (so the location's file should be used).
+    pub line_override: Option<(u32, Option<CachedString>)>,
+}
+
+impl MappedSourceLoc {
+    /// Returns the overridden line (if there is one).
+    pub fn line(&self) -> Option<u32> {
+        self.line_override.as_ref().map(|&(line, _)| line)
+    }
+    /// Returns the overridden file name (if there is one).
+    pub fn file_name(&self) -> Option<&CachedString> {
+        self.line_override.as_ref()?.1.as_ref()
+    }
+}
+
+impl From<SourceLoc> for MappedSourceLoc {
+    fn from(loc: SourceLoc) -> Self {
+        MappedSourceLoc { loc, line_override: None }
+    }
+}
+
+non_max_id! {
+    /// The id of a file (which is its index in [CompileEnv::file_id_to_tokens](crate::c::CompileEnv::file_id_to_tokens)).
+    ///
+    /// It is a distinct type so it can't be mixed up with other ids:
+    /// ```compile_fail
+    /// use vase::{c::ast::ScopeId, util::FileId};
+    ///
+    /// fn takes_scope_id(_: ScopeId) {}
+    /// takes_scope_id(FileId::new(0).unwrap());
+    /// ```
+    /// # Warning
+    /// This type should never be larger than 4 bytes.
+    pub struct FileId;
+}
 
 #[cfg(test)]
 mod test {
@@ -66,29 +107,35 @@ mod test {
     fn range_matches_expected() {
         const START: usize = 23;
         const LENGTH: usize = 45;
-        let test_case = SourceLoc::new(0.into(), START as u32, LENGTH as u16);
+        let test_case = SourceLoc::new(FileId::new(0).unwrap(), START as u32, LENGTH as u16);
         assert_eq!(test_case.range().start, START);
         assert_eq!(test_case.range().len(), LENGTH);
     }
 
     #[test]
     fn through_matches_expected() {
-        let start = SourceLoc::new(0.into(), 3, 4);
-        let end = SourceLoc::new(0.into(), 20, 3);
-        assert_eq!(start.through(&end), Some(SourceLoc::new(0.into(), 3, 20)));
+        let start = SourceLoc::new(FileId::new(0).unwrap(), 3, 4);
+        let end = SourceLoc::new(FileId::new(0).unwrap(), 20, 3);
+        assert_eq!(
+            start.through(&end),
+            Some(SourceLoc::new(FileId::new(0).unwrap(), 3, 20))
+        );
     }
 
     #[test]
     fn through_reversed_matches_expected() {
-        let start = SourceLoc::new(0.into(), 20, 3);
-        let end = SourceLoc::new(0.into(), 3, 4);
-        assert_eq!(start.through(&end), Some(SourceLoc::new(0.into(), 3, 20)));
+        let start = SourceLoc::new(FileId::new(0).unwrap(), 20, 3);
+        let end = SourceLoc::new(FileId::new(0).unwrap(), 3, 4);
+        assert_eq!(
+            start.through(&end),
+            Some(SourceLoc::new(FileId::new(0).unwrap(), 3, 20))
+        );
     }
 
     #[test]
     fn through_returns_none_when_different_files() {
-        let start = SourceLoc::new(0.into(), 0, 10);
-        let end = SourceLoc::new(1.into(), 10, 10);
+        let start = SourceLoc::new(FileId::new(0).unwrap(), 0, 10);
+        let end = SourceLoc::new(FileId::new(1).unwrap(), 10, 10);
         assert!(start.through(&end).is_none());
     }
 }
@@ -0,0 +1,202 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    path::Path,
+};
+
+use crate::{
+    sync::{
+        Arc,
+        RwLock,
+    },
+    util::{
+        FileId,
+        SourceLoc,
+    },
+};
+
+/// A map from file ids to the path and original bytes of each file.
+///
+/// This allows anything with a [SourceLoc] (like diagnostics) to find where
+/// it came from without holding onto the files itself.
+#[derive(Default)]
+pub struct SourceMap {
+    files: RwLock<HashMap<FileId, Arc<MappedFile>>>,
+}
+
+/// The contents of a file in a [SourceMap].
+struct MappedFile {
+    path: Option<Arc<Path>>,
+    bytes: Arc<[u8]>,
+    /// The byte each line starts at. The first line always starts at 0.
+    line_starts: Vec<u32>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        SourceMap::default()
+    }
+    /// Adds the file's path and bytes to the map. Files without a path
+    /// (like ones lexed directly from bytes) have a path of None.
+    ///
+    /// Only the first file added with an id is kept. Returns whether the file was added.
+    pub fn insert(&self, file_id: FileId, path: Option<Arc<Path>>, bytes: &[u8]) -> bool {
+        let mut files = self.files.write();
+        if files.contains_key(&file_id) {
+            return false;
+        }
+
+        let mut line_starts = vec![0];
+        for (i, &byte) in bytes.iter().enumerate() {
+            if byte == b'\n' {
+                line_starts.push(u32::try_from(i + 1).unwrap_or(u32::MAX));
+            }
+        }
+
+        let file = MappedFile {
+            path,
+            bytes: Arc::from(bytes),
+            line_starts,
+        };
+        files.insert(file_id, Arc::new(file));
+        true
+    }
+    /// Returns whether the file with the given id has been added.
+    pub fn contains(&self, file_id: FileId) -> bool {
+        self.files.read().contains_key(&file_id)
+    }
+    /// Returns the path of the given file (if it was added and has a path).
+    pub fn path(&self, file_id: FileId) -> Option<Arc<Path>> {
+        self.get(file_id)?.path.clone()
+    }
+    /// Returns the original bytes of the given file (if it was added).
+    pub fn bytes(&self, file_id: FileId) -> Option<Arc<[u8]>> {
+        Some(self.get(file_id)?.bytes.clone())
+    }
+    /// Returns the line and column the location starts at. Both start at 1.
+    ///
+    /// Columns are counted in bytes from the start of the line.
+    pub fn line_col(&self, loc: &SourceLoc) -> Option<(u32, u32)> {
+        let file = self.get(loc.file_id())?;
+        let line = file.line_index(loc.byte);
+        let column = loc.byte - file.line_starts[line] + 1;
+        Some((line as u32 + 1, column))
+    }
+    /// Returns the full line the location starts on (without the line's newline).
+    ///
+    /// Any invalid UTF-8 is replaced (see [String::from_utf8_lossy]).
+    pub fn snippet(&self, loc: &SourceLoc) -> Option<String> {
+        let file = self.get(loc.file_id())?;
+        let line = file.line_index(loc.byte);
+        let start = file.line_starts[line] as usize;
+        let end = match file.line_starts.get(line + 1) {
+            // The -1 excludes the newline.
+            Some(&next_start) => next_start as usize - 1,
+            None => file.bytes.len(),
+        };
+
+        let mut text = &file.bytes[start..end];
+        if text.ends_with(b"\r") {
+            text = &text[..text.len() - 1];
+        }
+        Some(String::from_utf8_lossy(text).into_owned())
+    }
+
+    fn get(&self, file_id: FileId) -> Option<Arc<MappedFile>> {
+        self.files.read().get(&file_id).cloned()
+    }
+}
+
+impl MappedFile {
+    /// Returns the index of the line that contains the given byte.
+    fn line_index(&self, byte: u32) -> usize {
+        match self.line_starts.binary_search(&byte) {
+            Ok(line) => line,
+            // The first line starts at 0, so the byte can't be before every line.
+            Err(next_line) => next_line - 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn two_file_map() -> SourceMap {
+        let map = SourceMap::new();
+        map.insert(
+            FileId::new(0).unwrap(),
+            Some(Arc::from(Path::new("a.c"))),
+            b"int a;\nint b;\n",
+        );
+        map.insert(FileId::new(1).unwrap(), None, b"first\r\nsecond");
+        map
+    }
+
+    #[test]
+    fn paths_and_bytes_are_kept() {
+        let map = two_file_map();
+        assert_eq!(
+            map.path(FileId::new(0).unwrap()).as_deref(),
+            Some(Path::new("a.c"))
+        );
+        assert_eq!(map.path(FileId::new(1).unwrap()), None);
+        assert_eq!(
+            &*map.bytes(FileId::new(1).unwrap()).unwrap(),
+            b"first\r\nsecond"
+        );
+        assert!(map.bytes(FileId::new(2).unwrap()).is_none());
+    }
+
+    #[test]
+    fn only_the_first_file_is_kept() {
+        let map = two_file_map();
+        assert!(!map.insert(FileId::new(0).unwrap(), None, b"replaced"));
+        assert_eq!(
+            &*map.bytes(FileId::new(0).unwrap()).unwrap(),
+            b"int a;\nint b;\n"
+        );
+    }
+
+    #[test]
+    fn line_col_starts_at_one() {
+        let map = two_file_map();
+        assert_eq!(
+            map.line_col(&SourceLoc::new(FileId::new(0).unwrap(), 0, 3)),
+            Some((1, 1))
+        );
+        assert_eq!(
+            map.line_col(&SourceLoc::new(FileId::new(0).unwrap(), 11, 1)),
+            Some((2, 5))
+        );
+        assert_eq!(
+            map.line_col(&SourceLoc::new(FileId::new(1).unwrap(), 7, 6)),
+            Some((2, 1))
+        );
+    }
+
+    #[test]
+    fn snippets_are_full_lines() {
+        let map = two_file_map();
+        let snippet = |file_id: FileId, byte| map.snippet(&SourceLoc::new(file_id, byte, 1));
+        assert_eq!(
+            snippet(FileId::new(0).unwrap(), 4).as_deref(),
+            Some("int a;")
+        );
+        assert_eq!(
+            snippet(FileId::new(0).unwrap(), 7).as_deref(),
+            Some("int b;")
+        );
+        assert_eq!(
+            snippet(FileId::new(1).unwrap(), 2).as_deref(),
+            Some("first")
+        );
+        assert_eq!(
+            snippet(FileId::new(1).unwrap(), 12).as_deref(),
+            Some("second")
+        );
+        assert_eq!(snippet(FileId::new(2).unwrap(), 0), None);
+    }
+}
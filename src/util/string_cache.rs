@@ -1,6 +1,7 @@
 // Copyright 2021. remilia-dev
 // This source code is licensed under GPLv3 or any later version.
 use std::{
+    collections::HashSet,
     marker::PhantomPinned,
     mem::swap,
     ptr::{
@@ -63,6 +64,36 @@ impl StringCache {
             }
         }
     }
+
+    /// Returns an iterator over every string in the cache (in no particular order).
+    /// # Snapshot
+    /// Other threads may cache strings while the trie is being walked, so the iterator
+    /// is a best-effort snapshot. Strings cached concurrently may be missed.
+    pub fn iter(&self) -> impl Iterator<Item = CachedString> {
+        let mut strings = Vec::new();
+        // NOTE: The nodes are walked with a stack (rather than recursion) to prevent stack overflows.
+        let mut stack: Vec<&dyn TrieNode> = vec![&self.root];
+        while let Some(node) = stack.pop() {
+            node.collect_strings(&mut strings, &mut stack);
+        }
+
+        // A value that is being moved to a child node may have been found in both nodes.
+        let mut seen = HashSet::new();
+        strings.retain(|string| seen.insert(string.uniq_id()));
+        strings.into_iter()
+    }
+
+    /// Returns the number of strings in the cache.
+    ///
+    /// This walks the entire cache (see [iter](StringCache::iter)).
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns whether no strings have been cached.
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
 }
 impl Default for StringCache {
     fn default() -> Self {
@@ -352,11 +383,40 @@ impl<const NODE_COUNT: usize> TrieNode for TrieNodeLimited<NODE_COUNT> {
             self.chain.get_or_create_chain(data.depth).find_next_node(data)
         }
     }
+
+    fn collect_strings<'a>(
+        &'a self,
+        strings: &mut Vec<CachedString>,
+        stack: &mut Vec<&'a dyn TrieNode>,
+    ) {
+        if let Some(value) = self.node_value.load_arc() {
+            strings.push(value);
+        }
+        // Once a node stops being an end node, its end value has been moved to a child node.
+        // OPTIMIZATION: Could we use Ordering::Acquire here?
+        if self.is_end_node.load(Ordering::SeqCst) {
+            if let Some(value) = self.end_value.load_arc() {
+                strings.push(value);
+            }
+        }
+
+        for node in self.nodes.iter().chain(std::iter::once(&self.chain)) {
+            if let Some(node) = node.load_trait() {
+                stack.push(node);
+            }
+        }
+    }
 }
 
 trait TrieNode {
     fn get_or_cache_string(&self, data: &mut CacheRequest) -> Result<CachedString, &dyn TrieNode>;
     fn find_next_node(&self, data: &mut CacheRequest) -> Result<CachedString, &dyn TrieNode>;
+    /// Adds the values stored in this node to strings and its child (and chain) nodes to stack.
+    fn collect_strings<'a>(
+        &'a self,
+        strings: &mut Vec<CachedString>,
+        stack: &mut Vec<&'a dyn TrieNode>,
+    );
 }
 
 struct TrieNodePtr {
@@ -459,6 +519,17 @@ impl TrieNodePtr {
         TrieNodePtr::get_trait(node_ptr)
     }
 
+    /// Returns the node this points to (or None if it's null).
+    fn load_trait(&self) -> Option<&dyn TrieNode> {
+        // OPTIMIZATION: Could we use Ordering::Acquire here?
+        let node_ptr = self.ptr.load(Ordering::SeqCst);
+        if node_ptr.is_null() {
+            None
+        } else {
+            Some(TrieNodePtr::get_trait(node_ptr))
+        }
+    }
+
     fn get_trait<'a>(ptr: *mut u8) -> &'a dyn TrieNode {
         let raw = match NonNull::new(ptr) {
             Some(raw) => raw,
@@ -609,6 +680,48 @@ mod tests {
         assert_ne!(cache_inline1, cache_int1);
     }
 
+    #[test]
+    fn string_cache_iterates_every_string() {
+        let cache = StringCache::new();
+        assert!(cache.is_empty());
+        // These cause end values to move, chains to form, and conflicting slots (see above).
+        let values = [
+            "foobar",
+            "foobaz",
+            "if",
+            "int",
+            "inline",
+            "AA0",
+            "AAp",
+            "",
+            "🏳️‍🌈",
+        ];
+        for value in &values {
+            cache.get_or_cache(value);
+        }
+        cache.get_or_cache("int");
+
+        let mut strings: Vec<String> = cache.iter().map(|s| s.string().to_owned()).collect();
+        strings.sort();
+        let mut expected: Vec<String> = values.iter().map(|s| (*s).to_owned()).collect();
+        expected.sort();
+        assert_eq!(strings, expected);
+        assert_eq!(cache.len(), values.len());
+    }
+
+    #[test]
+    fn string_cache_iterates_strings_in_chain_nodes() {
+        let cache = StringCache::new();
+        // The x node has 128 slots, so 158 different second bytes require a chain node.
+        let ascii = (0..128).map(|c| c as u8 as char);
+        // Each of these starts with a different UTF-8 lead byte.
+        let two_byte = (0..30).map(|i| std::char::from_u32(0x80 + i * 0x40).unwrap());
+        for second in ascii.chain(two_byte) {
+            cache.get_or_cache(&format!("x{}", second));
+        }
+        assert_eq!(cache.len(), 158);
+    }
+
     #[test]
     fn cached_strings_are_only_equal_as_pointers() {
         let cache1 = CachedString::new(CachedStringData::new("test"));
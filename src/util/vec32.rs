@@ -80,6 +80,10 @@ impl<T> Vec32<T> {
         self.0.get_mut(index as usize)
     }
 
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T> {
+        self.0.iter_mut()
+    }
+
     fn check_size(&mut self) {
         if self.0.len() >= u32::MAX as usize {
             self.clear();
@@ -1,9 +1,21 @@
 // Copyright 2021. remilia-dev
 // This source code is licensed under GPLv3 or any later version.
+use std::path::Path;
+
 use indoc::indoc;
-use vase::c::{
-    CompileEnv,
-    TokenKind::*,
+use vase::{
+    c::{
+        CompileEnv,
+        CompileSettings,
+        Lexer,
+        LexerErrorKind,
+        TokenKind::*,
+    },
+    sync::Arc,
+    util::{
+        CachedString,
+        FileId,
+    },
 };
 
 use super::run_test;
@@ -39,3 +51,21 @@ fn comments_lex_properly() {
         false,
     );
 }
+
+#[test]
+fn nested_comments_can_be_warned_about() {
+    let env = CompileEnv::new(CompileSettings {
+        warn_nested_comments: true,
+        ..CompileSettings::default()
+    });
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| panic!("No includes should occur!");
+    let mut lexer = Lexer::new(&env, callback);
+    let tokens = lexer.lex_bytes(FileId::new(0).unwrap(), "/* a /* b */ +".as_bytes());
+
+    assert_eq!(tokens[0].kind(), &LexerError(0));
+    assert_eq!(tokens[1].kind(), &Plus);
+    assert_eq!(tokens.errors().len(), 1);
+    let error = &tokens.errors()[0];
+    assert!(matches!(error.kind, LexerErrorKind::NestedComment));
+    assert_eq!((error.loc.byte, error.loc.byte_length), (5, 2));
+}
@@ -0,0 +1,93 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use std::path::Path;
+
+use vase::{
+    c::{
+        CompileEnv,
+        CompileSettings,
+        FileTokens,
+        InvalidUtf8Policy,
+        Lexer,
+        LexerErrorKind,
+        StringEnc,
+        TokenKind,
+    },
+    sync::Arc,
+    util::{
+        CachedString,
+        FileId,
+        Utf8DecodeError,
+    },
+};
+
+fn lex_with_policy(env: &CompileEnv, bytes: &[u8]) -> FileTokens {
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| panic!("No includes should occur!");
+    let mut lexer = Lexer::new(env, callback);
+    lexer.lex_bytes(FileId::new(0).unwrap(), bytes)
+}
+
+fn env_with_policy(on_invalid_utf8: InvalidUtf8Policy) -> CompileEnv {
+    CompileEnv::new(CompileSettings {
+        on_invalid_utf8,
+        ..CompileSettings::default()
+    })
+}
+
+fn string_kind(str_data: &str) -> TokenKind {
+    TokenKind::String {
+        encoding: StringEnc::Default,
+        has_escapes: false,
+        is_char: false,
+        str_data: Arc::new(Box::from(str_data)),
+    }
+}
+
+#[test]
+fn invalid_bytes_are_reported_and_skipped() {
+    let env = env_with_policy(InvalidUtf8Policy::Error);
+    let tokens = lex_with_policy(&env, b"a \xFF b");
+
+    let cache = env.cache();
+    let expected = [
+        TokenKind::Identifier(cache.get_or_cache("a")),
+        TokenKind::LexerError(0),
+        TokenKind::Identifier(cache.get_or_cache("b")),
+        TokenKind::Eof,
+    ];
+    for (i, kind) in expected.iter().enumerate() {
+        assert_eq!(tokens[i].kind(), kind, "Index: {}", i);
+    }
+    let error = &tokens.errors()[0];
+    assert_eq!(error.loc.byte, 2);
+    assert!(matches!(
+        error.kind,
+        LexerErrorKind::Utf8Decode(Utf8DecodeError::InvalidByte { bad_byte: 0xFF, .. })
+    ));
+}
+
+#[test]
+fn invalid_bytes_can_be_replaced() {
+    let env = env_with_policy(InvalidUtf8Policy::Replace);
+    let tokens = lex_with_policy(&env, b"\"a\xFFb\"");
+    assert_eq!(tokens[0].kind(), &string_kind("a\u{FFFD}b"));
+    assert!(tokens.errors().is_empty());
+}
+
+#[test]
+fn invalid_bytes_can_be_read_as_latin1() {
+    let env = env_with_policy(InvalidUtf8Policy::Latin1);
+    // Valid UTF-8 (the \xC3\xA9 is an e-acute) is still read as UTF-8.
+    let tokens = lex_with_policy(&env, b"\"\xC3\xA9\xFF\"");
+    assert_eq!(tokens[0].kind(), &string_kind("\u{e9}\u{ff}"));
+    assert!(tokens.errors().is_empty());
+}
+
+#[test]
+fn leading_byte_order_mark_is_skipped() {
+    let env = CompileEnv::default();
+    let tokens = lex_with_policy(&env, b"\xEF\xBB\xBF#define A\nA");
+    assert_eq!(tokens[0].kind(), &TokenKind::PreDefine);
+    assert_eq!(tokens[0].loc().byte, 3);
+    assert!(tokens.errors().is_empty());
+}
@@ -1,21 +1,34 @@
 // Copyright 2021. remilia-dev
 // This source code is licensed under GPLv3 or any later version.
 mod comments;
+mod encoding;
+#[cfg(all(feature = "file-reading", feature = "multithreading"))]
+mod multi_lexer;
 mod preprocessor;
 mod symbols;
 
-use std::path::Path;
+use std::{
+    borrow::Cow,
+    path::Path,
+};
 
 use vase::{
     c::{
         CompileEnv,
+        CompileSettings,
+        Keyword,
+        LangVersion,
         Lexer,
+        LexerErrorKind,
+        StringEnc,
         TokenKind,
+        TokenStats,
     },
     sync::Arc,
     util::{
         CachedString,
         FileId,
+        SourceLoc,
     },
 };
 
@@ -28,7 +41,7 @@ fn run_test(env: &CompileEnv, source: &str, expected: &[TokenKind], allow_includ
         None
     };
     let mut lexer = Lexer::new(&env, callback);
-    let tokens = lexer.lex_bytes(0.into(), source.as_bytes());
+    let tokens = lexer.lex_bytes(FileId::new(0).unwrap(), source.as_bytes());
 
     for i in 0..expected.len() {
         assert_eq!(tokens[i].kind(), &expected[i], "Index: {}", i);
@@ -40,10 +53,538 @@ fn escape_new_line_adds_to_token_length() {
     let env = CompileEnv::default();
     let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| panic!("No includes should occur!");
     let mut lexer = Lexer::new(&env, callback);
-    let tokens = lexer.lex_bytes(0.into(), "+\\\n=\\\n+=+=\\\n".as_bytes());
+    let tokens = lexer.lex_bytes(FileId::new(0).unwrap(), "+\\\n=\\\n+=+=\\\n".as_bytes());
     // The escape-newline is included in the length of the token if it occurs in the center.
     assert_eq!(tokens[0].loc().byte_length, 4);
     // The escape-newline is not included in the length of the token if it is at the start or ending.
     assert_eq!(tokens[1].loc().byte_length, 2);
     assert_eq!(tokens[2].loc().byte_length, 2);
 }
+
+#[test]
+fn spellings_match_the_source() {
+    let env = CompileEnv::default();
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| panic!("No includes should occur!");
+    let mut lexer = Lexer::new(&env, callback);
+    let source = "a<:0x1Fu:> = \"s\\\"\"; long_\\\nname +\\\r\n=";
+    let tokens = lexer.lex_bytes(FileId::new(0).unwrap(), source.as_bytes());
+
+    let spellings: Vec<_> = tokens.iter().map(|token| token.spelling(source.as_bytes())).collect();
+    let expected = [
+        "a",
+        "<:",
+        "0x1Fu",
+        ":>",
+        "=",
+        "\"s\\\"\"",
+        ";",
+        "long_name",
+        "+=",
+        "",
+    ];
+    assert_eq!(spellings, expected);
+    // Only the tokens with escaped new lines are copied.
+    assert!(matches!(spellings[5], Cow::Borrowed(_)));
+    assert!(matches!(spellings[7], Cow::Owned(_)));
+}
+
+#[test]
+fn integer_tokens_are_only_plain_ints() {
+    let env = CompileEnv::new(CompileSettings {
+        integer_tokens: true,
+        ..CompileSettings::default()
+    });
+    let cache = env.cache();
+    run_test(
+        &env,
+        "0 42 2147483647 2147483648 017 0x1F 10u 1.5",
+        &[
+            TokenKind::Integer(0),
+            TokenKind::Integer(42),
+            TokenKind::Integer(i32::MAX),
+            TokenKind::Number(cache.get_or_cache("2147483648")),
+            TokenKind::Number(cache.get_or_cache("017")),
+            TokenKind::Number(cache.get_or_cache("0x1F")),
+            TokenKind::Number(cache.get_or_cache("10u")),
+            TokenKind::Number(cache.get_or_cache("1.5")),
+        ],
+        false,
+    );
+}
+
+fn trigraph_env() -> CompileEnv {
+    CompileEnv::new(CompileSettings {
+        trigraphs: true,
+        ..CompileSettings::default()
+    })
+}
+
+#[test]
+fn trigraphs_are_replaced_with_a_warning() {
+    let env = trigraph_env();
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| panic!("No includes should occur!");
+    let mut lexer = Lexer::new(&env, callback);
+    let tokens = lexer.lex_bytes(FileId::new(0).unwrap(), "??< ??> ??? ??-".as_bytes());
+    let kinds: Vec<_> = tokens.iter().map(|token| token.kind().clone()).collect();
+    // The warnings come after the tokens the trigraphs are in.
+    assert_eq!(kinds, [
+        TokenKind::LBrace { alt: false },
+        TokenKind::LexerError(0),
+        TokenKind::RBrace { alt: false },
+        TokenKind::LexerError(1),
+        TokenKind::QMark,
+        TokenKind::QMark,
+        TokenKind::QMark,
+        TokenKind::Tilde,
+        TokenKind::LexerError(2),
+        TokenKind::Eof,
+    ]);
+    let error = &tokens.errors()[0];
+    assert!(matches!(error.kind, LexerErrorKind::Trigraph('{')));
+    assert_eq!((error.loc.byte, error.loc.byte_length), (0, 3));
+}
+
+#[test]
+fn trigraph_backslashes_join_lines() {
+    let env = trigraph_env();
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| panic!("No includes should occur!");
+    let mut lexer = Lexer::new(&env, callback);
+    let tokens = lexer.lex_bytes(FileId::new(0).unwrap(), "+??/\n=".as_bytes());
+    assert_eq!(tokens[0].kind(), &TokenKind::PlusEqual);
+    assert_eq!(tokens[0].loc().byte_length, 6);
+    assert_eq!(tokens[1].kind(), &TokenKind::LexerError(0));
+}
+
+#[test]
+fn trigraphs_are_not_replaced_by_default() {
+    let env = CompileEnv::default();
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| panic!("No includes should occur!");
+    let mut lexer = Lexer::new(&env, callback);
+    let tokens = lexer.lex_bytes(FileId::new(0).unwrap(), "??=".as_bytes());
+    assert!(!tokens.has_errors());
+    assert_eq!(tokens[0].kind(), &TokenKind::QMark);
+}
+
+#[test]
+fn lexing_from_reader_matches_lexing_bytes() {
+    let env = CompileEnv::default();
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| panic!("No includes should occur!");
+    let mut lexer = Lexer::new(&env, callback);
+    let source = "int x = 10;";
+    let from_reader = lexer.lex_reader(FileId::new(0).unwrap(), std::io::Cursor::new(source));
+    let from_bytes = lexer.lex_bytes(FileId::new(0).unwrap(), source.as_bytes());
+
+    assert_eq!(from_reader.len(), from_bytes.len());
+    for (i, token) in from_bytes.iter().enumerate() {
+        assert_eq!(from_reader[i].kind(), token.kind(), "Index: {}", i);
+    }
+}
+
+#[test]
+fn failed_reads_give_an_io_error() {
+    struct FailingReader;
+    impl std::io::Read for FailingReader {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::BrokenPipe.into())
+        }
+    }
+
+    let env = CompileEnv::default();
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| panic!("No includes should occur!");
+    let mut lexer = Lexer::new(&env, callback);
+    let tokens = lexer.lex_reader(FileId::new(0).unwrap(), FailingReader);
+    assert!(
+        matches!(tokens.errors()[..], [ref error] if matches!(error.kind, LexerErrorKind::Io(..)))
+    );
+    assert_eq!(
+        tokens.last().map(|token| token.kind()),
+        Some(&TokenKind::Eof)
+    );
+}
+
+#[test]
+fn readers_larger_than_the_max_source_size_are_rejected() {
+    let env = CompileEnv::new(CompileSettings {
+        max_source_size: 8,
+        ..CompileSettings::default()
+    });
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| panic!("No includes should occur!");
+    let mut lexer = Lexer::new(&env, callback);
+    let tokens = lexer.lex_reader(
+        FileId::new(0).unwrap(),
+        std::io::Cursor::new("int x = 10;"),
+    );
+    assert!(matches!(
+        tokens.errors()[..],
+        [ref error] if matches!(error.kind, LexerErrorKind::SourceTooLarge(8))
+    ));
+    assert_eq!(tokens.len(), 2);
+
+    // A source that's exactly the max size is fine.
+    let tokens = lexer.lex_reader(FileId::new(0).unwrap(), std::io::Cursor::new("int x=1;"));
+    assert!(!tokens.has_errors());
+}
+
+#[test]
+fn lexed_bytes_are_added_to_source_map() {
+    let env = CompileEnv::default();
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| panic!("No includes should occur!");
+    let mut lexer = Lexer::new(&env, callback);
+    let tokens = lexer.lex_bytes(FileId::new(0).unwrap(), "int x;\nint y;".as_bytes());
+
+    let y_loc = tokens[4].loc();
+    assert_eq!(env.source_map.line_col(&y_loc), Some((2, 5)));
+    assert_eq!(env.source_map.snippet(&y_loc).as_deref(), Some("int y;"));
+}
+
+#[test]
+fn locations_resolve_to_lines_and_columns() {
+    let env = CompileEnv::default();
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| panic!("No includes should occur!");
+    let mut lexer = Lexer::new(&env, callback);
+    // The escaped new line joins int and y but still starts a new line in the source.
+    let tokens = lexer.lex_bytes(FileId::new(0).unwrap(), "int x;\n  int\\\ny;".as_bytes());
+    // The bytes were lexed without a path, so there isn't a file name.
+    assert_eq!(env.resolve_location(tokens[0].loc()), Some((None, 1, 1)));
+    assert_eq!(env.resolve_location(tokens[1].loc()), Some((None, 1, 5)));
+    assert_eq!(env.resolve_location(tokens[3].loc()), Some((None, 2, 3)));
+    assert_eq!(env.resolve_location(tokens[4].loc()), Some((None, 3, 2)));
+    // Files that were never lexed can't be resolved.
+    assert!(env
+        .resolve_location(SourceLoc::new(FileId::new(1).unwrap(), 0, 1))
+        .is_none());
+}
+
+#[test]
+fn token_stats_count_each_category() {
+    let env = CompileEnv::default();
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+    let mut lexer = Lexer::new(&env, callback);
+    let source = "#include <a.h>\n#define X 1\nint main(void) { return X + 'a'; }\n\"";
+    let tokens = lexer.lex_bytes(FileId::new(0).unwrap(), source.as_bytes());
+
+    let stats = tokens.stats();
+    // X main X
+    assert_eq!(stats.identifiers, 3);
+    // int void return
+    assert_eq!(stats.keywords, 3);
+    assert_eq!(stats.numbers, 1);
+    // 'a' and the unended string (which is also an error)
+    assert_eq!(stats.strings, 2);
+    // ( ) { + ; }
+    assert_eq!(stats.punctuators, 6);
+    // #include <a.h> #define
+    assert_eq!(stats.preprocessor, 3);
+    assert_eq!(stats.total, 18);
+    assert_eq!(stats.errors, 1);
+
+    env.file_id_to_tokens.push(Arc::new(tokens));
+    let tokens = lexer.lex_bytes(FileId::new(1).unwrap(), b"x;");
+    env.file_id_to_tokens.push(Arc::new(tokens));
+    let project = TokenStats::from_files(&env.file_id_to_tokens);
+    assert_eq!(project.identifiers, 4);
+    assert_eq!(project.total, 20);
+}
+
+#[test]
+fn layout_records_whitespace_before_tokens() {
+    let env = CompileEnv::new(CompileSettings {
+        record_layout: true,
+        ..CompileSettings::default()
+    });
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| panic!("No includes should occur!");
+    let mut lexer = Lexer::new(&env, callback);
+    let tokens = lexer.lex_bytes(FileId::new(0).unwrap(), "int\n    x;".as_bytes());
+
+    let first = tokens.layout_before(0).unwrap();
+    assert_eq!((first.byte, first.byte_length), (0, 0));
+    // The new line and the 4 spaces of indentation come before x.
+    let indent = tokens.layout_before(1).unwrap();
+    assert_eq!((indent.byte, indent.byte_length), (3, 5));
+    let semicolon = tokens.layout_before(2).unwrap();
+    assert_eq!((semicolon.byte, semicolon.byte_length), (9, 0));
+}
+
+#[test]
+fn layout_is_not_recorded_by_default() {
+    let env = CompileEnv::default();
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| panic!("No includes should occur!");
+    let mut lexer = Lexer::new(&env, callback);
+    let tokens = lexer.lex_bytes(FileId::new(0).unwrap(), "int x;".as_bytes());
+    assert!(tokens.layout_before(0).is_none());
+}
+
+#[test]
+fn reconfiguring_env_changes_keywords() {
+    let env = CompileEnv::new(CompileSettings {
+        version: LangVersion::C11,
+        ..CompileSettings::default()
+    });
+    let keyword = TokenKind::Keyword(Keyword::Restrict, env.cache().get_or_cache("restrict"));
+    run_test(&env, "restrict", &[keyword], false);
+
+    env.reconfigure(CompileSettings {
+        version: LangVersion::C89,
+        ..CompileSettings::default()
+    });
+    let identifier = TokenKind::Identifier(env.cache().get_or_cache("restrict"));
+    run_test(&env, "restrict", &[identifier], false);
+}
+
+#[test]
+fn reconfiguring_env_drops_old_settings() {
+    let env = CompileEnv::default();
+    let old = env.settings();
+    env.reconfigure(CompileSettings {
+        version: LangVersion::C23,
+        ..CompileSettings::default()
+    });
+    // The held settings don't change, but the env no longer keeps them alive.
+    assert!(old.version != LangVersion::C23);
+    assert_eq!(Arc::strong_count(&old), 1);
+    assert!(env.settings().version == LangVersion::C23);
+}
+
+#[test]
+fn gnu_alternate_spellings_are_keywords() {
+    let env = CompileEnv::default();
+    let cache = env.cache();
+    let restrict = cache.get_or_cache("__restrict__");
+    run_test(
+        &env,
+        "int __restrict__ * p;",
+        &[
+            TokenKind::Keyword(Keyword::Int, cache.get_or_cache("int")),
+            TokenKind::Keyword(Keyword::Restrict, restrict),
+            TokenKind::Star,
+            TokenKind::Identifier(cache.get_or_cache("p")),
+            TokenKind::Semicolon,
+        ],
+        false,
+    );
+}
+
+#[test]
+fn gnu_alternate_spellings_need_gnu_extensions() {
+    let env = CompileEnv::new(CompileSettings {
+        enable_gnu_extensions: false,
+        ..CompileSettings::default()
+    });
+    let identifier = TokenKind::Identifier(env.cache().get_or_cache("__restrict__"));
+    run_test(&env, "__restrict__", &[identifier], false);
+}
+
+#[test]
+fn reserved_keywords_need_their_version_without_gnu_extensions() {
+    let lex = |version, enable_gnu_extensions| {
+        let env = CompileEnv::new(CompileSettings {
+            version,
+            enable_gnu_extensions,
+            ..CompileSettings::default()
+        });
+        let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+        let mut lexer = Lexer::new(&env, callback);
+        let tokens = lexer.lex_bytes(FileId::new(0).unwrap(), b"_Bool _Generic");
+        let is_keyword = |i: usize| matches!(*tokens[i].kind(), TokenKind::Keyword(..));
+        (is_keyword(0), is_keyword(1))
+    };
+    assert_eq!(lex(LangVersion::C89, false), (false, false));
+    assert_eq!(lex(LangVersion::C99, false), (true, false));
+    assert_eq!(lex(LangVersion::C11, false), (true, true));
+    // GNU extensions accept them in every version.
+    assert_eq!(lex(LangVersion::C89, true), (true, true));
+}
+
+#[test]
+fn stray_control_characters_are_reported_and_skipped() {
+    let env = CompileEnv::default();
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| panic!("No includes should occur!");
+    let mut lexer = Lexer::new(&env, callback);
+    let tokens = lexer.lex_bytes(FileId::new(0).unwrap(), "a\0b".as_bytes());
+
+    let cache = env.cache();
+    let expected = [
+        TokenKind::Identifier(cache.get_or_cache("a")),
+        TokenKind::LexerError(0),
+        TokenKind::Identifier(cache.get_or_cache("b")),
+    ];
+    for (i, kind) in expected.iter().enumerate() {
+        assert_eq!(tokens[i].kind(), kind, "Index: {}", i);
+    }
+    assert!(matches!(
+        tokens.errors()[0].kind,
+        LexerErrorKind::StrayControlCharacter('\0')
+    ));
+}
+
+#[test]
+fn form_feeds_and_vertical_tabs_are_whitespace() {
+    let env = CompileEnv::default();
+    let cache = env.cache();
+    let expected = [
+        TokenKind::Identifier(cache.get_or_cache("a")),
+        TokenKind::Identifier(cache.get_or_cache("b")),
+        TokenKind::Identifier(cache.get_or_cache("c")),
+        TokenKind::Eof,
+    ];
+    run_test(&env, "a\x0cb\x0bc", &expected, false);
+}
+
+#[test]
+fn double_brackets_are_only_lexed_when_adjacent() {
+    let env = CompileEnv::default();
+    let cache = env.cache();
+    run_test(
+        &env,
+        "[[a]] b[c[0]] [ [",
+        &[
+            TokenKind::LDoubleBracket,
+            TokenKind::Identifier(cache.get_or_cache("a")),
+            TokenKind::RDoubleBracket,
+            TokenKind::Identifier(cache.get_or_cache("b")),
+            TokenKind::LBracket { alt: false },
+            TokenKind::Identifier(cache.get_or_cache("c")),
+            TokenKind::LBracket { alt: false },
+            TokenKind::Number(cache.get_or_cache("0")),
+            TokenKind::RBracket { alt: false },
+            TokenKind::RBracket { alt: false },
+            TokenKind::LBracket { alt: false },
+            TokenKind::LBracket { alt: false },
+            TokenKind::Eof,
+        ],
+        false,
+    );
+}
+
+#[test]
+fn universal_character_names_are_decoded_in_identifiers() {
+    let env = CompileEnv::default();
+    let cache = env.cache();
+    for &(source, identifier) in &[
+        (r"\u00e9t\u00E9", "été"),
+        (r"caf\U000000e9", "café"),
+        ("é", "é"),
+    ] {
+        let expected = [
+            TokenKind::Identifier(cache.get_or_cache(identifier)),
+            TokenKind::Eof,
+        ];
+        run_test(&env, source, &expected, false);
+    }
+}
+
+#[test]
+fn invalid_universal_character_names_are_reported() {
+    let env = CompileEnv::default();
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| panic!("No includes should occur!");
+    let mut lexer = Lexer::new(&env, callback);
+    let tokens = lexer.lex_bytes(
+        FileId::new(0).unwrap(),
+        r"a\u12 b \uD800c \u0041d".as_bytes(),
+    );
+
+    let cache = env.cache();
+    let expected = [
+        TokenKind::Identifier(cache.get_or_cache("a")),
+        TokenKind::LexerError(0),
+        TokenKind::Identifier(cache.get_or_cache("b")),
+        TokenKind::Identifier(cache.get_or_cache("c")),
+        TokenKind::LexerError(1),
+        TokenKind::Identifier(cache.get_or_cache("d")),
+        TokenKind::LexerError(2),
+    ];
+    for (i, kind) in expected.iter().enumerate() {
+        assert_eq!(tokens[i].kind(), kind, "Index: {}", i);
+    }
+    let errors = tokens.errors();
+    assert!(matches!(errors[0].kind, LexerErrorKind::IncompleteUcn));
+    assert_eq!(errors[0].loc.byte_length, 4);
+    assert!(matches!(
+        errors[1].kind,
+        LexerErrorKind::InvalidUcnInIdentifier(0xD800)
+    ));
+    assert!(matches!(
+        errors[2].kind,
+        LexerErrorKind::InvalidUcnInIdentifier(0x41)
+    ));
+
+    // An identifier of only invalid names doesn't become an empty identifier.
+    let tokens = lexer.lex_bytes(FileId::new(0).unwrap(), r"\uD800 e".as_bytes());
+    assert_eq!(tokens[0].kind(), &TokenKind::LexerError(0));
+    assert_eq!(
+        tokens[1].kind(),
+        &TokenKind::Identifier(cache.get_or_cache("e"))
+    );
+    assert_eq!(tokens[2].kind(), &TokenKind::Eof);
+    assert!(matches!(
+        tokens.errors()[0].kind,
+        LexerErrorKind::InvalidUcnInIdentifier(0xD800)
+    ));
+}
+
+#[test]
+fn numbers_keep_their_prefixes_and_exponents() {
+    let env = CompileEnv::default();
+    let cache = env.cache();
+    let expected = [
+        TokenKind::Number(cache.get_or_cache("0b1010")),
+        TokenKind::Number(cache.get_or_cache("0x1p+5")),
+        TokenKind::Number(cache.get_or_cache("1e-5")),
+        // e is a hexadecimal digit so it doesn't take the sign after it.
+        TokenKind::Number(cache.get_or_cache("0x1e")),
+        TokenKind::Plus,
+        TokenKind::Number(cache.get_or_cache("5")),
+        TokenKind::Eof,
+    ];
+    run_test(&env, "0b1010 0x1p+5 1e-5 0x1e+5", &expected, false);
+}
+
+#[test]
+fn digit_separators_need_to_be_enabled() {
+    let env = CompileEnv::default();
+    let cache = env.cache();
+    let expected = [
+        TokenKind::Number(cache.get_or_cache("1")),
+        TokenKind::String {
+            encoding: StringEnc::Default,
+            has_escapes: false,
+            is_char: true,
+            str_data: Arc::new(Box::from("2")),
+        },
+        TokenKind::Eof,
+    ];
+    run_test(&env, "1'2'", &expected, false);
+
+    let env = CompileEnv::new(CompileSettings {
+        digit_separators: true,
+        ..CompileSettings::default()
+    });
+    let cache = env.cache();
+    let expected = [
+        TokenKind::Number(cache.get_or_cache("1000000")),
+        TokenKind::Number(cache.get_or_cache("0xFFFF")),
+        TokenKind::Eof,
+    ];
+    run_test(&env, "1'000'000 0xFF'FF", &expected, false);
+}
+
+#[test]
+fn digit_separators_only_join_hex_digits_in_hex_numbers() {
+    let env = CompileEnv::new(CompileSettings {
+        digit_separators: true,
+        ..CompileSettings::default()
+    });
+    let cache = env.cache();
+    let expected = [
+        TokenKind::Number(cache.get_or_cache("1")),
+        TokenKind::String {
+            encoding: StringEnc::Default,
+            has_escapes: false,
+            is_char: true,
+            str_data: Arc::new(Box::from("e5")),
+        },
+        TokenKind::Number(cache.get_or_cache("0x1e5")),
+        TokenKind::Eof,
+    ];
+    run_test(&env, "1'e5' 0x1'e5", &expected, false);
+}
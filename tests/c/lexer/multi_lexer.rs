@@ -0,0 +1,60 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use std::{
+    fs,
+    path::Path,
+};
+
+use vase::{
+    c::{
+        CompileEnv,
+        FileTokens,
+        MultiLexer,
+    },
+    sync::{
+        Arc,
+        Mutex,
+    },
+    util::FileId,
+};
+
+#[test]
+fn each_lexed_file_is_reported_once_its_tokens_are_set() {
+    let dir = std::env::temp_dir().join(format!("vase_multi_lexer_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let write = |name: &str, source: &str| -> Arc<Path> {
+        let path = dir.join(name);
+        fs::write(&path, source).unwrap();
+        Arc::from(path.as_path())
+    };
+    let header = write("header.h", "int from_header;\n");
+    let first = write("first.c", "#include \"header.h\"\nint first;\n");
+    let second = write("second.c", "#include \"header.h\"\nint second;\n");
+
+    let env = Arc::new(CompileEnv::default());
+    let reported = Mutex::new(Vec::new());
+    let mut lexer = MultiLexer::new(env.clone());
+    lexer.lex_multi_threaded_with(
+        &[first, second],
+        |file_id: FileId, tokens: &FileTokens| {
+            let set = env.file_id_to_tokens.get(file_id);
+            assert!(
+                matches!(set, Some(set) if std::ptr::eq(set, tokens)),
+                "The tokens of {:?} should be set before it's reported.",
+                file_id
+            );
+            reported.lock().push(file_id);
+        },
+    );
+    fs::remove_dir_all(&dir).unwrap();
+
+    // The header is included twice but only lexed (and reported) once.
+    let mut reported = reported.into_inner();
+    reported.sort();
+    let mut lexed: Vec<FileId> = env.file_id_to_tokens.iter().map(|(id, _)| id).collect();
+    lexed.sort();
+    assert_eq!(reported, lexed);
+    assert_eq!(reported.len(), 3);
+    let mut lexed_paths = env.file_id_to_tokens.iter().map(|(_, tokens)| tokens.path());
+    assert!(lexed_paths.any(|path| path.as_ref() == Some(&header)));
+}
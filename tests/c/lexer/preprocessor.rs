@@ -103,3 +103,24 @@ fn preprocessor_tokens_lex_properly() {
         true,
     );
 }
+
+#[test]
+fn continued_directives_are_one_line() {
+    let env = CompileEnv::default();
+    let cache = env.cache();
+    run_test(
+        &env,
+        "#define SUM \\\n    1 + \\\n    2\nSUM\n",
+        &[
+            PreDefine,
+            Identifier(cache.get_or_cache("SUM")),
+            Number(cache.get_or_cache("1")),
+            Plus,
+            Number(cache.get_or_cache("2")),
+            PreEnd,
+            Identifier(cache.get_or_cache("SUM")),
+            Eof,
+        ],
+        false,
+    );
+}
@@ -1,4 +1,5 @@
 // Copyright 2021. remilia-dev
 // This source code is licensed under GPLv3 or any later version.
 mod lexer;
+mod parser;
 mod traveler;
@@ -0,0 +1,53 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use vase::c::{
+    ast::*,
+    CompileEnv,
+};
+
+use super::{
+    first_func_body,
+    global_type,
+    parse,
+};
+
+fn alignas(type_: &Type) -> &ModifierSegment {
+    let alignas = type_.segments.iter().find_map(|segment| match *segment {
+        TypeSegment::Modifier(ref modifier) if modifier.name() == "_Alignas" => Some(modifier),
+        _ => None,
+    });
+    alignas.expect("The type should have an _Alignas specifier.")
+}
+
+#[test]
+fn alignas_accepts_constant_expressions() {
+    let env = CompileEnv::default();
+    let file = parse(&env, "_Alignas(16) int x;");
+    let type_ = global_type(&env, &file, "x");
+    assert!(matches!(type_.root, TypeRoot::Int));
+    match *alignas(type_) {
+        ModifierSegment::AlignasExpr(ref expr) => assert!(matches!(**expr, Expr::Number(..))),
+        ref segment => panic!("Expected an expression alignment, found {:?}", segment),
+    }
+}
+
+#[test]
+fn alignas_accepts_type_names() {
+    let env = CompileEnv::default();
+    let file = parse(&env, "_Alignas(double) char buf[8];");
+    let type_ = global_type(&env, &file, "buf");
+    assert!(matches!(type_.root, TypeRoot::Char));
+    match *alignas(type_) {
+        ModifierSegment::AlignasType(ref aligned_as) => {
+            assert!(matches!(aligned_as.root, TypeRoot::Double))
+        },
+        ref segment => panic!("Expected a type alignment, found {:?}", segment),
+    }
+}
+
+#[test]
+fn alignas_can_start_local_declarations() {
+    let env = CompileEnv::default();
+    let file = parse(&env, "void f() { _Alignas(long) int x; }");
+    assert!(matches!(first_func_body(&file)[0], Stmt::Decl(..)));
+}
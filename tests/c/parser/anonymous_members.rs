@@ -0,0 +1,56 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use vase::c::{
+    ast::*,
+    CompileEnv,
+    ParseErrorKind,
+};
+
+use super::{
+    global_type,
+    parse,
+    parse_with_errors,
+};
+
+/// Finds the field of the struct or union that the global `s` has as its type.
+fn field_of_s<'a>(env: &CompileEnv, file: &'a SourceFile, field: &str) -> Option<&'a Decl> {
+    let type_index = match global_type(env, file, "s").root {
+        TypeRoot::Type(type_index) => type_index,
+        ref root => panic!("Expected s to be a struct or union, found {:?}", root),
+    };
+    file.find_field(type_index, &env.cache().get_or_cache(field))
+}
+
+#[test]
+fn fields_of_anonymous_unions_are_found() {
+    let env = CompileEnv::default();
+    let file = parse(&env, "struct S { int a; union { int b; float c; }; } s;");
+    let c = field_of_s(&env, &file, "c").expect("c should be found through the union.");
+    assert!(matches!(c.type_.root, TypeRoot::Float));
+    assert!(field_of_s(&env, &file, "a").is_some());
+    assert!(field_of_s(&env, &file, "d").is_none());
+}
+
+#[test]
+fn nested_anonymous_members_are_found() {
+    let env = CompileEnv::default();
+    let file = parse(
+        &env,
+        "struct S { union { struct { char deep; }; int b; }; } s;",
+    );
+    let deep = field_of_s(&env, &file, "deep").expect("deep should be found two levels down.");
+    assert!(matches!(deep.type_.root, TypeRoot::Char));
+    assert!(field_of_s(&env, &file, "b").is_some());
+}
+
+#[test]
+fn duplicate_anonymous_fields_are_ambiguous() {
+    let env = CompileEnv::default();
+    let (_, errors) = parse_with_errors(
+        &env,
+        "struct S { union { int x; }; struct { float x; }; } s;",
+    );
+    assert!(matches!(errors[..], [
+        ParseErrorKind::AmbiguousAnonymousMember(..)
+    ]));
+}
@@ -0,0 +1,81 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use vase::c::{
+    ast::*,
+    CompileEnv,
+    CompileSettings,
+};
+
+use super::{
+    first_func_body,
+    parse,
+    parse_with_errors,
+};
+
+fn asm_stmt(stmt: &Stmt) -> &AsmStmt {
+    match *stmt {
+        Stmt::Asm(ref asm) => asm,
+        ref stmt => panic!("Expected an asm statement, found {:?}", stmt),
+    }
+}
+
+#[test]
+fn asm_can_have_only_a_template() {
+    let env = CompileEnv::default();
+    let file = parse(&env, "void f() { __asm__ volatile(\"nop\"); }");
+    let asm = asm_stmt(&first_func_body(&file)[0]);
+    assert!(asm.volatile);
+    assert_eq!(&**asm.template.segments[0], "nop");
+    assert!(asm.outputs.is_empty() && asm.inputs.is_empty() && asm.clobbers.is_empty());
+}
+
+#[test]
+fn asm_can_have_outputs_inputs_and_clobbers() {
+    let env = CompileEnv::default();
+    let (file, errors) = parse_with_errors(
+        &env,
+        "int f(int a, int b) {
+            int r;
+            asm(\"add %1, %[rhs]\" : \"=r\"(r) : \"r\"(a), [rhs] \"r\"(b + 1) : \"cc\", \"memory\");
+            return r;
+        }",
+    );
+    assert!(errors.is_empty(), "{:?}", errors);
+    let asm = asm_stmt(&first_func_body(&file)[1]);
+    assert!(!asm.volatile);
+    assert_eq!(asm.outputs.len(), 1);
+    assert_eq!(&**asm.outputs[0].constraint.segments[0], "=r");
+    assert_eq!(asm.inputs.len(), 2);
+    assert!(asm.inputs[0].name.is_none());
+    assert_eq!(
+        asm.inputs[1].name.as_ref().map(|name| name.string()),
+        Some("rhs")
+    );
+    assert!(matches!(*asm.inputs[1].expr, Expr::Binary(..)));
+    let clobbers: Vec<&str> = asm.clobbers.iter().map(|clobber| clobber.string()).collect();
+    assert_eq!(clobbers, ["cc", "memory"]);
+}
+
+#[test]
+fn asm_sections_can_be_empty() {
+    let env = CompileEnv::default();
+    let (file, errors) = parse_with_errors(
+        &env,
+        "void f(int x) { __asm__(\"\" : : \"r\"(x) : \"memory\"); }",
+    );
+    assert!(errors.is_empty(), "{:?}", errors);
+    let asm = asm_stmt(&first_func_body(&file)[0]);
+    assert!(asm.outputs.is_empty());
+    assert_eq!(asm.inputs.len(), 1);
+    assert_eq!(asm.clobbers.len(), 1);
+}
+
+#[test]
+fn asm_is_an_identifier_without_gnu_extensions() {
+    let env = CompileEnv::new(CompileSettings {
+        enable_gnu_extensions: false,
+        ..CompileSettings::default()
+    });
+    let (_, errors) = parse_with_errors(&env, "int asm = 1;");
+    assert!(errors.is_empty(), "{:?}", errors);
+}
@@ -0,0 +1,43 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use vase::c::{
+    CompileEnv,
+    ParseErrorKind,
+};
+
+use super::parse_with_errors;
+
+fn assignment_errors(assignment: &str) -> Vec<ParseErrorKind> {
+    let env = CompileEnv::default();
+    let source = format!("int f(int *p) {{ int x; {} return x; }}", assignment);
+    let (_, errors) = parse_with_errors(&env, &source);
+    errors
+}
+
+#[test]
+fn unspaced_unary_after_assign_is_ambiguous() {
+    for assignment in &["x =- 1;", "x =+ 1;", "x =*p;"] {
+        let errors = assignment_errors(assignment);
+        assert_eq!(errors.len(), 1, "{}: {:?}", assignment, errors);
+        assert!(matches!(
+            errors[0],
+            ParseErrorKind::AmbiguousAssignment(..)
+        ));
+    }
+}
+
+#[test]
+fn spaced_unary_after_assign_is_not_ambiguous() {
+    for assignment in &["x = -1;", "x = +1;", "x = *p;", "x -= 1;"] {
+        let errors = assignment_errors(assignment);
+        assert!(errors.is_empty(), "{}: {:?}", assignment, errors);
+    }
+}
+
+#[test]
+fn unspaced_assignments_are_not_ambiguous() {
+    for assignment in &["x=-1;", "x=+1;", "x=*p;"] {
+        let errors = assignment_errors(assignment);
+        assert!(errors.is_empty(), "{}: {:?}", assignment, errors);
+    }
+}
@@ -0,0 +1,125 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use vase::c::{
+    ast::*,
+    CompileEnv,
+};
+
+use super::{
+    first_func_body,
+    parse,
+};
+
+#[test]
+fn fallthrough_attribute_is_attached() {
+    let env = CompileEnv::default();
+    let file = parse(
+        &env,
+        r#"
+        void f(int x) {
+            switch (x) {
+                case 1:
+                    x = 2;
+                    [[fallthrough]];
+                case 2:
+                    break;
+            }
+        }
+        "#,
+    );
+
+    let switch_block = match first_func_body(&file)[0] {
+        Stmt::Switch(ref switch) => match *switch.block {
+            Stmt::Block(ref block) => &file.get_scope(block.scope_id).stmts,
+            ref stmt => panic!("Expected the switch to have a block, found {:?}", stmt),
+        },
+        ref stmt => panic!("Expected a switch statement, found {:?}", stmt),
+    };
+
+    let attributed = match switch_block[1] {
+        Stmt::Attributed(ref attributed) => attributed,
+        ref stmt => panic!("Expected an attributed statement, found {:?}", stmt),
+    };
+    assert!(switch_block[1].is_fallthrough());
+    assert_eq!(attributed.attributes.len(), 1);
+    assert!(matches!(*attributed.stmt, Stmt::Empty(..)));
+}
+
+#[test]
+fn prefixed_attributes_are_not_standard() {
+    let env = CompileEnv::default();
+    let file = parse(
+        &env,
+        "void f(void) { [[gnu::fallthrough, __maybe_unused__, deprecated(\"(\")]]; }",
+    );
+
+    let attributed = match first_func_body(&file)[0] {
+        Stmt::Attributed(ref attributed) => attributed,
+        ref stmt => panic!("Expected an attributed statement, found {:?}", stmt),
+    };
+    let attributes = &attributed.attributes;
+    assert_eq!(attributes.len(), 3);
+    assert_eq!(attributes[0].prefix.as_ref().unwrap().string(), "gnu");
+    assert!(!attributes[0].is_standard("fallthrough"));
+    assert!(attributes[1].is_standard("maybe_unused"));
+    assert!(attributes[2].is_standard("deprecated"));
+    assert!(attributes[2].args.is_some());
+}
+
+#[test]
+fn keywords_are_attribute_names() {
+    let env = CompileEnv::default();
+    let file = parse(
+        &env,
+        "[[gnu::always_inline, gnu::const]] int f(void); [[const]] int g(void);",
+    );
+
+    let root = file.root_scope();
+    let attribute_names = |name: &str| -> Vec<_> {
+        let index = root.decls.get_index(&env.cache().get_or_cache(name)).unwrap();
+        let attributes = &root.decls[index].leading_attributes;
+        attributes.iter().map(|attr| attr.name.string().to_owned()).collect()
+    };
+    assert_eq!(attribute_names("f"), ["always_inline", "const"]);
+    assert_eq!(attribute_names("g"), ["const"]);
+}
+
+#[test]
+fn attributes_apply_to_every_declaration() {
+    let env = CompileEnv::default();
+    let file = parse(&env, "[[deprecated]] [[maybe_unused]] int a, b;");
+
+    let root = file.root_scope();
+    for name in &["a", "b"] {
+        let index = root.decls.get_index(&env.cache().get_or_cache(name));
+        let decl = &root.decls[index.expect("The global should have been declared.")];
+        assert!(decl.has_attribute("deprecated"));
+        assert!(decl.has_attribute("maybe_unused"));
+    }
+}
+
+#[test]
+fn attributes_before_local_declarations_are_not_statement_attributes() {
+    let env = CompileEnv::default();
+    let file = parse(&env, "void f(void) { [[maybe_unused]] int x = 1; }");
+
+    let decl_stmt = match first_func_body(&file)[0] {
+        Stmt::Decl(ref decl_stmt) => decl_stmt,
+        ref stmt => panic!("Expected a declaration, found {:?}", stmt),
+    };
+    let scope = file.get_scope(decl_stmt.scope_id);
+    assert!(scope.decls[decl_stmt.decl_ids[0]].has_attribute("maybe_unused"));
+}
+
+#[test]
+fn brackets_in_attribute_arguments_are_balanced() {
+    let env = CompileEnv::default();
+    let file = parse(&env, "void f(void) { [[vendor::check(x[y[0]])]]; }");
+
+    let attributed = match first_func_body(&file)[0] {
+        Stmt::Attributed(ref attributed) => attributed,
+        ref stmt => panic!("Expected an attributed statement, found {:?}", stmt),
+    };
+    assert_eq!(attributed.attributes.len(), 1);
+    assert!(attributed.attributes[0].args.is_some());
+}
@@ -0,0 +1,95 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use vase::c::{
+    ast::*,
+    CompileEnv,
+};
+
+use super::{
+    first_func_body,
+    global_initializer,
+    parse_with_errors,
+};
+
+fn compound_literal(expr: &Expr) -> &CompoundLiteralExpr {
+    match *expr {
+        Expr::CompoundLiteral(ref literal) => literal,
+        ref expr => panic!("Expected a compound literal, found {:?}", expr),
+    }
+}
+
+fn array_kind(type_: &Type) -> &ArrayKind {
+    match type_.segments.last() {
+        Some(TypeSegment::Array(ref array)) => &array.kind,
+        segment => panic!("Expected an array segment, found {:?}", segment),
+    }
+}
+
+#[test]
+fn compound_literal_arrays_infer_their_length() {
+    let env = CompileEnv::default();
+    let (file, errors) = parse_with_errors(&env, "int *x = (int[]){ 1, 2, 3 };");
+    assert!(errors.is_empty(), "{:?}", errors);
+    let literal = compound_literal(global_initializer(&env, &file, "x"));
+    assert_eq!(literal.init.values.len(), 3);
+    assert!(matches!(
+        *array_kind(&literal.type_),
+        ArrayKind::Inferred(3)
+    ));
+}
+
+#[test]
+fn compound_literals_can_use_designators() {
+    let env = CompileEnv::default();
+    let (file, errors) = parse_with_errors(
+        &env,
+        "struct Foo { int x; }; struct Foo y = (struct Foo){ .x = 1 };",
+    );
+    assert!(errors.is_empty(), "{:?}", errors);
+    let literal = compound_literal(global_initializer(&env, &file, "y"));
+    assert!(matches!(literal.init.values[..], [InitMember::Named(..)]));
+}
+
+#[test]
+fn compound_literals_are_lvalues() {
+    let env = CompileEnv::default();
+    let (file, errors) = parse_with_errors(&env, "int *x = (int[]){ 1 };");
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert!(global_initializer(&env, &file, "x").is_lvalue());
+}
+
+#[test]
+fn compound_literals_work_inside_other_expressions() {
+    let env = CompileEnv::default();
+    let (file, errors) = parse_with_errors(
+        &env,
+        "int f(int *p); int g() { return f((int[]){ 1 }) + ((int *[]){ (int[]){ 2 } })[0][0]; }",
+    );
+    assert!(errors.is_empty(), "{:?}", errors);
+    let value = match first_func_body(&file)[0] {
+        Stmt::Return(ref return_) => return_.expr.as_ref().expect("The return has a value."),
+        ref stmt => panic!("Expected a return statement, found {:?}", stmt),
+    };
+    let (call, array) = match **value {
+        Expr::Binary(ref binary) => (&binary.lhs, &binary.rhs),
+        ref expr => panic!("Expected a binary expression, found {:?}", expr),
+    };
+    match **call {
+        Expr::Call(ref call) => {
+            compound_literal(&call.args[0]);
+        },
+        ref expr => panic!("Expected a call, found {:?}", expr),
+    }
+    assert!(array.is_lvalue());
+}
+
+#[test]
+fn casts_are_not_compound_literals() {
+    let env = CompileEnv::default();
+    let (file, errors) = parse_with_errors(&env, "long x = (long)(1 + 2);");
+    assert!(errors.is_empty(), "{:?}", errors);
+    match *global_initializer(&env, &file, "x") {
+        Expr::Cast(ref cast) => assert!(matches!(*cast.expr, Expr::Parens(..))),
+        ref expr => panic!("Expected a cast, found {:?}", expr),
+    }
+}
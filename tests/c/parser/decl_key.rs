@@ -0,0 +1,58 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use vase::c::{
+    ast::*,
+    CompileEnv,
+};
+
+use super::parse;
+
+fn global_key(env: &CompileEnv, file: &SourceFile, name: &str) -> DeclKey {
+    let index = file
+        .root_scope()
+        .decls
+        .get_index(&env.cache().get_or_cache(name))
+        .expect("The global should have been declared.");
+    file.decl_key(DeclIndex::new(ScopeId::ROOT, index))
+}
+
+#[test]
+fn unchanged_function_keeps_its_key_after_an_edit() {
+    let env = CompileEnv::default();
+    let before = parse(
+        &env,
+        "int add(int a, int b) { return a + b; }\nint twice(int x) { return x * 2; }",
+    );
+    let after = parse(
+        &env,
+        "int add(int a, int b) { int sum = a + b; return sum; }\nint twice(int x) { return x * 2; }",
+    );
+
+    assert_eq!(
+        global_key(&env, &before, "twice"),
+        global_key(&env, &after, "twice")
+    );
+}
+
+#[test]
+fn changed_signature_changes_the_key() {
+    let env = CompileEnv::default();
+    let before = parse(&env, "int twice(int x) { return x * 2; }");
+    let after = parse(&env, "long twice(long x) { return x * 2; }");
+    assert_ne!(
+        global_key(&env, &before, "twice"),
+        global_key(&env, &after, "twice")
+    );
+}
+
+#[test]
+fn local_keys_include_the_function_name() {
+    let env = CompileEnv::default();
+    let file = parse(&env, "void f() { int x = 0; }");
+    let decl = match super::first_func_body(&file)[0] {
+        Stmt::Decl(ref decl) => decl,
+        ref stmt => panic!("Expected a declaration, found {:?}", stmt),
+    };
+    let key = file.decl_key(DeclIndex::new(decl.scope_id, decl.decl_ids[0]));
+    assert_eq!(key.path, vec![env.cache().get_or_cache("f")]);
+}
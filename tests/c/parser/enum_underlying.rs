@@ -0,0 +1,91 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use vase::c::{
+    ast::*,
+    CompileEnv,
+    CompileSettings,
+    LangVersion,
+    ParseErrorKind,
+};
+
+use super::{
+    parse,
+    parse_with_errors,
+};
+
+fn c23_env() -> CompileEnv {
+    CompileEnv::new(CompileSettings {
+        version: LangVersion::C23,
+        ..CompileSettings::default()
+    })
+}
+
+fn enum_decl<'a>(env: &CompileEnv, file: &'a SourceFile, name: &str) -> &'a TypeDecl {
+    let root = file.root_scope();
+    let index = root.types.get_index(&env.cache().get_or_cache(name));
+    &root.types[index.expect("The enum should have been declared.")]
+}
+
+#[test]
+fn enums_can_have_an_underlying_type() {
+    let env = c23_env();
+    let file = parse(&env, "enum E : int { A };");
+    let type_decl = enum_decl(&env, &file, "E");
+    let underlying = type_decl.underlying.as_ref().unwrap();
+    assert!(matches!(underlying.root, TypeRoot::Int));
+    assert!(type_decl.body.is_some());
+}
+
+#[test]
+fn forward_declarations_can_have_an_underlying_type() {
+    let env = c23_env();
+    let file = parse(&env, "enum E : short;\nenum E : short { A, B };");
+    let type_decl = enum_decl(&env, &file, "E");
+    assert_eq!(type_decl.tags.len(), 2);
+    assert!(type_decl.underlying.is_some());
+}
+
+#[test]
+fn underlying_types_must_be_integers() {
+    let env = c23_env();
+    let (_, errors) = parse_with_errors(&env, "enum E : float { A };");
+    assert!(matches!(errors[..], [
+        ParseErrorKind::EnumUnderlyingNotInteger(..)
+    ]));
+}
+
+#[test]
+fn underlying_types_warn_before_c23() {
+    let env = CompileEnv::default();
+    let (_, errors) = parse_with_errors(&env, "enum E : unsigned char { A };");
+    assert!(matches!(errors[..], [
+        ParseErrorKind::EnumUnderlyingTypeBeforeC23
+    ]));
+}
+
+#[test]
+fn enum_values_must_fit_in_an_int_before_c23() {
+    let env = CompileEnv::default();
+    let (_, errors) = parse_with_errors(&env, "enum E { A = 2147483647, B, C = -2147483648 };");
+    assert!(matches!(
+        errors[..],
+        [ParseErrorKind::EnumValueOutOfRange(_, ref name, 2147483648)] if name.string() == "B"
+    ));
+
+    let (_, errors) = parse_with_errors(&env, "enum E { A = 1 << 40 };");
+    assert!(matches!(errors[..], [
+        ParseErrorKind::EnumValueOutOfRange(..)
+    ]));
+}
+
+#[test]
+fn enum_values_fit_in_their_underlying_type() {
+    let env = c23_env();
+    let (_, errors) = parse_with_errors(&env, "enum E : long long { A = 4294967296, B };");
+    assert!(errors.is_empty(), "{:?}", errors);
+
+    let (_, errors) = parse_with_errors(&env, "enum E : unsigned char { A = 255, B };");
+    assert!(matches!(errors[..], [
+        ParseErrorKind::EnumValueOutOfRange(..)
+    ]));
+}
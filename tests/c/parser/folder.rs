@@ -0,0 +1,87 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use vase::c::{
+    ast::*,
+    CompileEnv,
+};
+
+use super::{
+    global_initializer,
+    parse,
+};
+
+/// Replaces additions of two integer literals with their sum.
+#[derive(Default)]
+struct AdditionFolder {
+    folded: usize,
+}
+
+impl Folder for AdditionFolder {
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        let (lhs, rhs) = match expr {
+            Expr::Binary(ref binary) if binary.op == BinaryOp::Addition => {
+                (&binary.lhs, &binary.rhs)
+            },
+            _ => return expr,
+        };
+        match (&**lhs, &**rhs) {
+            (
+                &Expr::Number(Number {
+                    kind: NumberKind::I32(lhs), index, ..
+                }),
+                &Expr::Number(Number { kind: NumberKind::I32(rhs), .. }),
+            ) => {
+                self.folded += 1;
+                let kind = NumberKind::I32(lhs + rhs);
+                Expr::Number(Number::new(kind, index))
+            },
+            _ => expr,
+        }
+    }
+}
+
+fn print(file: &SourceFile) -> String {
+    let mut printer = Printer::new(file, String::new());
+    printer.print_file().unwrap();
+    printer.into_inner()
+}
+
+#[test]
+fn folders_replace_nodes_from_the_inside_out() {
+    let env = CompileEnv::default();
+    let mut file = parse(
+        &env,
+        "int x = 1 + 2 + 3;\nint f(int a) {\n    return a + (4 + 5);\n}\n",
+    );
+    let mut folder = AdditionFolder::default();
+    fold_file(&mut file, &mut folder);
+
+    // 1 + 2 is folded first, which makes the outer addition (3 + 3) foldable too.
+    assert_eq!(folder.folded, 3);
+    assert!(matches!(
+        *global_initializer(&env, &file, "x"),
+        Expr::Number(Number { kind: NumberKind::I32(6), .. })
+    ));
+    assert_eq!(
+        print(&file),
+        "int x = 6;\nint f(int a) {\n    return a + (9);\n}\n"
+    );
+}
+
+#[test]
+fn folding_an_expression_folds_its_children() {
+    let env = CompileEnv::default();
+    let mut file = parse(&env, "int x = (1 + 2) * 3;");
+    let mut expr = global_initializer(&env, &file, "x").clone();
+    fold_expr(&mut file, &mut expr, &mut AdditionFolder::default());
+
+    let lhs = match expr {
+        Expr::Binary(ref binary) if binary.op == BinaryOp::Multiplication => &binary.lhs,
+        ref expr => panic!("Expected a multiplication, found {:?}", expr),
+    };
+    assert!(matches!(
+        **lhs,
+        Expr::Parens(ParenExpr { ref expr, .. })
+            if matches!(**expr, Expr::Number(Number { kind: NumberKind::I32(3), .. }))
+    ));
+}
@@ -0,0 +1,115 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use vase::c::{
+    ast::*,
+    CompileEnv,
+    ParseErrorKind,
+};
+
+use super::{
+    global_initializer,
+    parse,
+    parse_with_errors,
+};
+
+fn generic_of<'a>(env: &CompileEnv, file: &'a SourceFile) -> &'a GenericExpr {
+    match *global_initializer(env, file, "y") {
+        Expr::Generic(ref generic) => generic,
+        ref expr => panic!("Expected a _Generic expression, found {:?}", expr),
+    }
+}
+
+#[test]
+fn generic_without_associations_is_an_error() {
+    let env = CompileEnv::default();
+    let (file, errors) = parse_with_errors(&env, "int x; int y = _Generic(x);");
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(matches!(
+        errors[0],
+        ParseErrorKind::EmptyGenericAssociations
+    ));
+    assert!(generic_of(&env, &file).associations.is_empty());
+}
+
+#[test]
+fn generic_trailing_comma_is_handled() {
+    let env = CompileEnv::default();
+    let (file, errors) = parse_with_errors(&env, "int x; int y = _Generic(x, int: 1,);");
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(matches!(errors[0], ParseErrorKind::GenericTrailingComma));
+    assert_eq!(generic_of(&env, &file).associations.len(), 1);
+}
+
+#[test]
+fn generic_selects_by_type() {
+    let env = CompileEnv::default();
+    let file = parse(&env, "int x; int y = _Generic(x, int: 1);");
+    let generic = generic_of(&env, &file);
+    assert!(matches!(*generic.controller, Expr::DeclRef(..)));
+    assert_eq!(generic.associations.len(), 1);
+    let (ref type_, ref expr) = generic.associations[0];
+    assert!(matches!(type_.as_ref().unwrap().root, TypeRoot::Int));
+    assert!(matches!(**expr, Expr::Number(..)));
+}
+
+#[test]
+fn generic_can_have_many_associations() {
+    let env = CompileEnv::default();
+    let file = parse(
+        &env,
+        "int x; int y = _Generic(x, char: 1, double: 2, default: 3, float: 4);",
+    );
+    let associations = &generic_of(&env, &file).associations;
+    assert_eq!(associations.len(), 4);
+    assert!(matches!(
+        associations[1].0.as_ref().unwrap().root,
+        TypeRoot::Double
+    ));
+    assert!(associations[2].0.is_none());
+}
+
+#[test]
+fn generic_default_is_optional() {
+    let env = CompileEnv::default();
+    let file = parse(&env, "int x; int y = _Generic(x, char: 1, int: 2);");
+    let associations = &generic_of(&env, &file).associations;
+    assert!(associations.iter().all(|(type_, _)| type_.is_some()));
+}
+
+#[test]
+fn generics_can_nest() {
+    let env = CompileEnv::default();
+    let file = parse(
+        &env,
+        "int x; int y = _Generic(_Generic(x, default: x), int: _Generic(x, default: 1));",
+    );
+    let generic = generic_of(&env, &file);
+    assert!(matches!(*generic.controller, Expr::Generic(..)));
+    assert!(matches!(*generic.associations[0].1, Expr::Generic(..)));
+}
+
+#[test]
+fn generic_multiple_defaults_is_an_error() {
+    let env = CompileEnv::default();
+    let (file, errors) = parse_with_errors(
+        &env,
+        "int x; int y = _Generic(x, default: 1, default: 2);",
+    );
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(matches!(
+        errors[0],
+        ParseErrorKind::GenericMultipleDefault(..)
+    ));
+    assert_eq!(generic_of(&env, &file).associations.len(), 2);
+}
+
+#[test]
+fn generic_recovers_from_missing_commas() {
+    let env = CompileEnv::default();
+    let (file, errors) = parse_with_errors(&env, "int x; int y = _Generic(x int: 1 default: 2);");
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+    assert!(errors
+        .iter()
+        .all(|error| matches!(*error, ParseErrorKind::GenericExpectedSeparator(..))));
+    assert_eq!(generic_of(&env, &file).associations.len(), 2);
+}
@@ -0,0 +1,102 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use vase::c::{
+    ast::*,
+    CompileEnv,
+};
+
+use super::{
+    global_type,
+    parse,
+    parse_with_errors,
+};
+
+fn global_decl<'a>(env: &CompileEnv, file: &'a SourceFile, name: &str) -> &'a Decl {
+    let root = file.root_scope();
+    let index = root.decls.get_index(&env.cache().get_or_cache(name));
+    &root.decls[index.expect("The global should have been declared.")]
+}
+
+fn has_attribute(lists: &[AttributeList], name: &str) -> bool {
+    lists.iter().any(|list| list.get(name).is_some())
+}
+
+#[test]
+fn attributes_can_be_in_the_type_specifiers() {
+    let env = CompileEnv::default();
+    let file = parse(
+        &env,
+        "__attribute__((deprecated)) int x;\nint __attribute__((unused)) *y;",
+    );
+    assert!(has_attribute(
+        &global_type(&env, &file, "x").attributes,
+        "deprecated"
+    ));
+    assert!(has_attribute(
+        &global_type(&env, &file, "y").attributes,
+        "unused"
+    ));
+}
+
+#[test]
+fn attributes_can_follow_the_declarator() {
+    let env = CompileEnv::default();
+    let file = parse(
+        &env,
+        "void f(void) __attribute__((noreturn));\nint x __attribute__((__unused__)) = 1;",
+    );
+    assert!(has_attribute(
+        &global_decl(&env, &file, "f").attributes,
+        "noreturn"
+    ));
+    assert!(has_attribute(
+        &global_decl(&env, &file, "x").attributes,
+        "unused"
+    ));
+}
+
+#[test]
+fn attribute_arguments_are_expressions() {
+    let env = CompileEnv::default();
+    let file = parse(
+        &env,
+        "int x __attribute__((aligned(8), , section(\"data\"), const));",
+    );
+    let lists = &global_decl(&env, &file, "x").attributes;
+    assert_eq!(lists.len(), 1);
+    let attributes = &lists[0].attributes;
+    assert_eq!(attributes.len(), 3);
+    assert!(matches!(
+        attributes[0].args.as_deref(),
+        Some([Expr::Number(..)])
+    ));
+    assert!(matches!(
+        attributes[1].args.as_deref(),
+        Some([Expr::String(..)])
+    ));
+    assert!(attributes[2].is("const"));
+    assert!(attributes[2].args.is_none());
+}
+
+#[test]
+fn unknown_attributes_are_accepted() {
+    let env = CompileEnv::default();
+    let (_, errors) = parse_with_errors(&env, "int x __attribute__((not_an_attribute(1, 2)));");
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+#[test]
+fn attributes_can_follow_a_tag_keyword() {
+    let env = CompileEnv::default();
+    let file = parse(
+        &env,
+        "struct __attribute__((packed)) S { int x; };\n\
+        enum __attribute__((packed)) E { A };\n\
+        struct S s;",
+    );
+    let type_decl = match global_type(&env, &file, "s").root {
+        TypeRoot::Type(index) => file.get_type_decl(index),
+        ref root => panic!("Expected a struct type, found {:?}", root),
+    };
+    assert!(has_attribute(&type_decl.tags[0].attributes, "packed"));
+}
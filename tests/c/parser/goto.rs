@@ -0,0 +1,154 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use vase::c::{
+    ast::*,
+    CompileEnv,
+    CompileSettings,
+    LangVersion,
+    ParseErrorKind,
+};
+
+use super::{
+    first_func_body,
+    parse,
+    parse_with_errors,
+};
+
+#[test]
+fn computed_goto_has_target_expression() {
+    let env = CompileEnv::default();
+    let file = parse(&env, "void f(void *p) { goto *p; }");
+    let goto = match first_func_body(&file)[0] {
+        Stmt::Goto(ref goto) => goto,
+        ref stmt => panic!("Expected a goto statement, found {:?}", stmt),
+    };
+    match goto.target {
+        GotoTarget::Computed(ref expr) => {
+            assert!(matches!(**expr, Expr::DeclRef(..)), "{:?}", expr);
+        },
+        ref target => panic!("Expected a computed goto, found {:?}", target),
+    }
+}
+
+#[test]
+fn label_address_is_parsed() {
+    let env = CompileEnv::default();
+    let file = parse(&env, "void f() { void *p = &&lbl; lbl: return; }");
+    let decl = match first_func_body(&file)[0] {
+        Stmt::Decl(ref decl) => decl,
+        ref stmt => panic!("Expected a declaration, found {:?}", stmt),
+    };
+    let scope = file.get_scope(decl.scope_id);
+    let label_address = match scope.decls[decl.decl_ids[0]].postfix {
+        DeclPostfix::Initializer(ref expr) => match **expr {
+            Expr::LabelAddress(ref label_address) => label_address,
+            ref expr => panic!("Expected an address-of-label, found {:?}", expr),
+        },
+        ref postfix => panic!("Expected an initializer, found {:?}", postfix),
+    };
+    assert_eq!(label_address.label, env.cache().get_or_cache("lbl"));
+    assert!(label_address.label_scope_id.is_some());
+}
+
+#[test]
+fn labels_are_targets_of_gotos() {
+    let env = CompileEnv::default();
+    let (file, errors) = parse_with_errors(&env, "void f() { goto end; end: return; }");
+    assert!(errors.is_empty(), "{:?}", errors);
+    let labeled = match first_func_body(&file)[1] {
+        Stmt::Labeled(ref labeled) => labeled,
+        ref stmt => panic!("Expected a labeled statement, found {:?}", stmt),
+    };
+    assert_eq!(labeled.name, env.cache().get_or_cache("end"));
+    assert!(
+        matches!(*labeled.stmt, Stmt::Return(..)),
+        "{:?}",
+        labeled.stmt
+    );
+}
+
+#[test]
+fn duplicate_labels_are_reported() {
+    let env = CompileEnv::default();
+    let (_, errors) = parse_with_errors(&env, "void f() { a: ; { a: ; } }");
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(matches!(errors[0], ParseErrorKind::DuplicateLabel(..)));
+}
+
+#[test]
+fn gotos_to_undeclared_labels_are_reported() {
+    let env = CompileEnv::default();
+    let (_, errors) = parse_with_errors(&env, "void f() { goto missing; } void g() { missing: ; }");
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(matches!(errors[0], ParseErrorKind::UndeclaredLabel(..)));
+}
+
+#[test]
+fn label_addresses_of_undeclared_labels_are_reported() {
+    let env = CompileEnv::default();
+    let (_, errors) = parse_with_errors(&env, "void f() { void *p = &&nope; }");
+    assert!(
+        matches!(errors[..], [ParseErrorKind::UndeclaredLabel(..)]),
+        "{:?}",
+        errors
+    );
+
+    let (_, errors) = parse_with_errors(&env, "void f() { void *p = &&later; later: return; }");
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+#[test]
+fn computed_gotos_and_label_addresses_are_gnu_extensions() {
+    let env = CompileEnv::new(CompileSettings {
+        enable_gnu_extensions: false,
+        ..CompileSettings::default()
+    });
+    let (_, errors) = parse_with_errors(&env, "void f() { void *p = &&end; goto *p; end: ; }");
+    assert!(
+        matches!(errors[..], [
+            ParseErrorKind::GnuExtension(..),
+            ParseErrorKind::GnuExtension(..)
+        ]),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn labels_in_nested_blocks_belong_to_the_function() {
+    let env = CompileEnv::default();
+    let (_, errors) = parse_with_errors(
+        &env,
+        "void f(int x) { goto inner; if (x) { while (x) { inner: x--; } } }",
+    );
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+#[test]
+fn backward_gotos_are_not_reported() {
+    let env = CompileEnv::default();
+    let (_, errors) = parse_with_errors(&env, "void f(int x) { again: x--; if (x) goto again; }");
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+#[test]
+fn labels_at_the_end_of_a_block_are_a_c23_feature() {
+    let env = CompileEnv::default();
+    let (file, errors) = parse_with_errors(&env, "void f() { goto end; end: }");
+    assert!(
+        matches!(errors[..], [ParseErrorKind::LabelAtEndOfBlock]),
+        "{:?}",
+        errors
+    );
+    assert!(matches!(
+        first_func_body(&file)[1],
+        Stmt::Labeled(ref labeled) if matches!(*labeled.stmt, Stmt::Empty(..))
+    ));
+
+    let env = CompileEnv::new(CompileSettings {
+        version: LangVersion::C23,
+        ..CompileSettings::default()
+    });
+    let (_, errors) = parse_with_errors(&env, "void f() { goto end; end: }");
+    assert!(errors.is_empty(), "{:?}", errors);
+}
@@ -0,0 +1,58 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use vase::c::{
+    CompileEnv,
+    CompileSettings,
+    LangVersion,
+    ParseErrorKind,
+};
+
+use super::{
+    global_type,
+    parse,
+    parse_with_errors,
+};
+
+fn c23_env() -> CompileEnv {
+    CompileEnv::new(CompileSettings {
+        version: LangVersion::C23,
+        ..CompileSettings::default()
+    })
+}
+
+#[test]
+fn auto_without_type_specifiers_is_inferred() {
+    let env = c23_env();
+    let file = parse(&env, "auto x = 1;");
+    let type_ = global_type(&env, &file, "x");
+    assert!(type_.is_inferred(), "{:?}", type_);
+}
+
+#[test]
+fn gnu_auto_type_is_inferred() {
+    let env = CompileEnv::default();
+    let file = parse(&env, "__auto_type x = 1;");
+    let type_ = global_type(&env, &file, "x");
+    assert!(type_.is_inferred(), "{:?}", type_);
+}
+
+#[test]
+fn auto_is_implicit_int_before_c23() {
+    let env = CompileEnv::default();
+    let file = parse(&env, "auto x = 1;");
+    let type_ = global_type(&env, &file, "x");
+    assert!(type_.is_implicit(), "{:?}", type_);
+}
+
+#[test]
+fn inferred_type_requires_initializer() {
+    let env = c23_env();
+    let (_, errors) = parse_with_errors(&env, "auto y;");
+    assert!(
+        matches!(errors[..], [
+            ParseErrorKind::InferredTypeWithoutInitializer(..)
+        ]),
+        "{:?}",
+        errors
+    );
+}
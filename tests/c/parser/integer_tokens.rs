@@ -0,0 +1,54 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use vase::c::{
+    ast::Expr,
+    CompileEnv,
+    CompileSettings,
+};
+
+use super::{
+    global_initializer,
+    parse,
+};
+
+const SOURCE: &str = r#"
+#define JOIN(a, b) a ## b
+#if 100 > 99 && JOIN(1, 0) == 10
+int a = 0, b = 42, c = 2147483647, d = 2147483648, e = 017, f = 0x1F, g = 10u, h = 1.5;
+#endif
+int i = JOIN(1, 2), j = JOIN(3, u), k = JOIN(4, .5), l = -7;
+"#;
+
+/// Returns the debug form of the number kind in the expression (with any prefix operators).
+///
+/// The kinds are compared since the tokens the numbers were parsed from differ (and NumberKind
+/// isn't comparable).
+fn number_kind(expr: &Expr) -> String {
+    match *expr {
+        Expr::Number(ref number) => format!("{:?}", number.kind),
+        Expr::Prefix(ref prefix) => format!("{:?} {}", prefix.op, number_kind(&prefix.expr)),
+        ref expr => panic!("Expected a number, found {:?}", expr),
+    }
+}
+
+#[test]
+fn integer_tokens_give_the_same_numbers() {
+    let full_env = CompileEnv::default();
+    let integer_env = CompileEnv::new(CompileSettings {
+        integer_tokens: true,
+        ..CompileSettings::default()
+    });
+    let full_file = parse(&full_env, SOURCE);
+    let integer_file = parse(&integer_env, SOURCE);
+
+    for name in &["a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l"] {
+        let full = global_initializer(&full_env, &full_file, name);
+        let integer = global_initializer(&integer_env, &integer_file, name);
+        assert_eq!(
+            number_kind(full),
+            number_kind(integer),
+            "Variable: {}",
+            name
+        );
+    }
+}
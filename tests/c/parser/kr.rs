@@ -0,0 +1,58 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use vase::c::{
+    ast::*,
+    CompileEnv,
+    ParseErrorKind,
+};
+
+use super::{
+    parse,
+    parse_with_errors,
+};
+
+/// Returns the parameters of the first function declared in the file.
+fn first_func_params(file: &SourceFile) -> &Scope {
+    for decl in file.root_scope().decls.values() {
+        if let Some(scope_id) = decl.type_.get_func_scope_id() {
+            return file.get_scope(scope_id);
+        }
+    }
+    panic!("No function was found in the file.");
+}
+
+fn param_root<'a>(env: &CompileEnv, params: &'a Scope, name: &str) -> &'a TypeRoot {
+    let index = params.decls.get_index(&env.cache().get_or_cache(name));
+    &params.decls[index.expect("The parameter should have been declared.")]
+        .type_
+        .root
+}
+
+#[test]
+fn kr_parameters_take_their_declared_types() {
+    let env = CompileEnv::default();
+    let file = parse(&env, "int foo(a, b) int a; double b; { return a; }");
+    let params = first_func_params(&file);
+    assert!(matches!(*param_root(&env, params, "a"), TypeRoot::Int));
+    assert!(matches!(*param_root(&env, params, "b"), TypeRoot::Double));
+}
+
+#[test]
+fn undeclared_kr_parameters_are_implicit_ints() {
+    let env = CompileEnv::default();
+    let file = parse(&env, "int foo(a, b) double b; { return a; }");
+    let params = first_func_params(&file);
+    assert!(matches!(
+        *param_root(&env, params, "a"),
+        TypeRoot::AutoInt
+    ));
+    assert!(matches!(*param_root(&env, params, "b"), TypeRoot::Double));
+}
+
+#[test]
+fn kr_and_typed_parameters_cannot_mix() {
+    let env = CompileEnv::default();
+    let (_, errors) = parse_with_errors(&env, "int foo(a, int b) { return b; }");
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(matches!(errors[0], ParseErrorKind::KRMixedParameters));
+}
@@ -0,0 +1,113 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+mod alignas;
+mod anonymous_members;
+mod asm;
+mod assignment;
+mod attributes;
+mod compound_literal;
+mod decl_key;
+mod enum_underlying;
+mod folder;
+mod generic;
+mod gnu_attributes;
+mod goto;
+mod inferred;
+mod integer_tokens;
+mod kr;
+mod nullptr;
+mod pragma_pack;
+mod printer;
+mod qualifiers;
+mod recovery;
+mod restrict;
+mod shadowing;
+mod static_assert;
+mod stmt_expr;
+mod type_name;
+mod typeof_specifier;
+mod visitor;
+
+use std::path::Path;
+
+use vase::{
+    c::{
+        ast::*,
+        CompileEnv,
+        Lexer,
+        ParseError,
+        ParseErrorKind,
+        Parser,
+    },
+    error::CodedError,
+    sync::Arc,
+    util::{
+        CachedString,
+        FileId,
+    },
+};
+
+fn parse(env: &CompileEnv, source: &str) -> SourceFile {
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+    let mut lexer = Lexer::new(env, callback);
+    let tokens = Arc::new(lexer.lex_bytes(FileId::new(0).unwrap(), source.as_bytes()));
+    env.file_id_to_tokens.push(tokens.clone());
+
+    let mut parser = Parser::new(env, |err: ParseError| {
+        panic!(
+            "An error should not have occured: {:?}\n{}",
+            err,
+            err.message()
+        );
+    });
+    parser.parse(tokens).unwrap()
+}
+
+/// Parses the source while collecting the kinds of all the errors that were reported.
+fn parse_with_errors(env: &CompileEnv, source: &str) -> (SourceFile, Vec<ParseErrorKind>) {
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+    let mut lexer = Lexer::new(env, callback);
+    let tokens = Arc::new(lexer.lex_bytes(FileId::new(0).unwrap(), source.as_bytes()));
+    env.file_id_to_tokens.push(tokens.clone());
+
+    let mut errors = Vec::new();
+    let mut parser = Parser::new(env, |err: ParseError| {
+        errors.push(err.kind);
+        false
+    });
+    let file = parser.parse(tokens).unwrap();
+    drop(parser);
+    (file, errors)
+}
+
+/// Returns the initializer of a variable declared in the root scope of the file.
+fn global_initializer<'a>(env: &CompileEnv, file: &'a SourceFile, name: &str) -> &'a Expr {
+    let root = file.root_scope();
+    let index = root.decls.get_index(&env.cache().get_or_cache(name));
+    match root.decls[index.expect("The global should have been declared.")].postfix {
+        DeclPostfix::Initializer(ref expr) => expr,
+        ref postfix => panic!("Expected an initializer, found {:?}", postfix),
+    }
+}
+
+/// Returns the type of a variable declared in the root scope of the file.
+fn global_type<'a>(env: &CompileEnv, file: &'a SourceFile, name: &str) -> &'a Type {
+    let root = file.root_scope();
+    let index = root.decls.get_index(&env.cache().get_or_cache(name));
+    &root.decls[index.expect("The global should have been declared.")].type_
+}
+
+/// Returns the statements in the body of the first function declared in the file.
+fn first_func_body(file: &SourceFile) -> &[Stmt] {
+    let root = file.root_scope();
+    for stmt in &root.stmts {
+        if let Stmt::Decl(ref decl_stmt) = *stmt {
+            for &decl_id in &decl_stmt.decl_ids {
+                if let DeclPostfix::Block(ref block) = root.decls[decl_id].postfix {
+                    return &file.get_scope(block.scope_id).stmts;
+                }
+            }
+        }
+    }
+    panic!("No function body was found in the file.");
+}
@@ -0,0 +1,64 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use vase::c::{
+    ast::*,
+    CompileEnv,
+    CompileSettings,
+    LangVersion,
+};
+
+use super::{
+    global_initializer,
+    global_type,
+    parse,
+};
+
+fn env_for(version: LangVersion) -> CompileEnv {
+    CompileEnv::new(CompileSettings {
+        version,
+        ..CompileSettings::default()
+    })
+}
+
+#[test]
+fn nullptr_is_a_constant_in_c23() {
+    let env = env_for(LangVersion::C23);
+    let file = parse(&env, "void *p = nullptr; nullptr_t n = nullptr;");
+    assert!(matches!(
+        *global_initializer(&env, &file, "p"),
+        Expr::Nullptr(..)
+    ));
+    assert!(matches!(
+        global_type(&env, &file, "n").root,
+        TypeRoot::NullptrT
+    ));
+}
+
+#[test]
+fn nullptr_t_is_a_typedef_name_instead_of_a_keyword() {
+    let env = env_for(LangVersion::C23);
+    let file = parse(&env, "int s = sizeof(nullptr_t);");
+    assert!(matches!(
+        *global_initializer(&env, &file, "s"),
+        Expr::Type(TypeExpr {
+            of: TypeOrExpr::Type(Type { root: TypeRoot::NullptrT, .. }),
+            ..
+        })
+    ));
+
+    let file = parse(&env, "int nullptr_t = 0; int x = nullptr_t;");
+    assert!(matches!(
+        *global_initializer(&env, &file, "x"),
+        Expr::DeclRef(DeclRefExpr { ref id, .. }) if id.text.string() == "nullptr_t"
+    ));
+}
+
+#[test]
+fn nullptr_is_an_identifier_before_c23() {
+    let env = env_for(LangVersion::C17);
+    let file = parse(&env, "int nullptr = 0; int x = nullptr;");
+    assert!(matches!(
+        *global_initializer(&env, &file, "x"),
+        Expr::DeclRef(DeclRefExpr { ref id, .. }) if id.text.string() == "nullptr"
+    ));
+}
@@ -0,0 +1,42 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use vase::c::{
+    ast::*,
+    CompileEnv,
+};
+
+use super::parse;
+
+fn struct_pack(env: &CompileEnv, file: &SourceFile, name: &str) -> Option<u32> {
+    let root = file.root_scope();
+    let index = root.types.get_index(&env.cache().get_or_cache(name));
+    let type_decl = &root.types[index.expect("The struct should have been declared.")];
+    type_decl.body.as_ref().expect("The struct should have a body.").pack
+}
+
+#[test]
+fn structs_record_the_pack_value() {
+    let env = CompileEnv::default();
+    let source = r#"
+        struct Default { int i; };
+        #pragma pack(1)
+        struct Packed { char c; int i; };
+        #pragma pack(push, 4)
+        struct Pushed { char c; int i; };
+        _Pragma("pack(push)")
+        #pragma pack(2)
+        struct Nested { char c; int i; };
+        #pragma pack(pop)
+        #pragma pack(pop)
+        struct Popped { char c; int i; };
+        #pragma pack()
+        struct Reset { char c; int i; };
+        "#;
+    let file = parse(&env, source);
+    assert_eq!(struct_pack(&env, &file, "Default"), None);
+    assert_eq!(struct_pack(&env, &file, "Packed"), Some(1));
+    assert_eq!(struct_pack(&env, &file, "Pushed"), Some(4));
+    assert_eq!(struct_pack(&env, &file, "Nested"), Some(2));
+    assert_eq!(struct_pack(&env, &file, "Popped"), Some(1));
+    assert_eq!(struct_pack(&env, &file, "Reset"), None);
+}
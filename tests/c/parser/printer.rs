@@ -0,0 +1,102 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use std::path::Path;
+
+use vase::{
+    c::{
+        ast::*,
+        CompileEnv,
+        Lexer,
+        TokenKind,
+    },
+    sync::Arc,
+    util::{
+        CachedString,
+        FileId,
+    },
+};
+
+use super::parse;
+
+fn lex_kinds(env: &CompileEnv, source: &str) -> Vec<TokenKind> {
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+    let mut lexer = Lexer::new(env, callback);
+    let tokens = lexer.lex_bytes(FileId::new(0).unwrap(), source.as_bytes());
+    tokens.iter().map(|token| token.kind().clone()).collect()
+}
+
+fn print(file: &SourceFile) -> String {
+    let mut printer = Printer::new(file, String::new());
+    printer.print_file().unwrap();
+    printer.into_inner()
+}
+
+const CORPUS: &[&str] = &[
+    "int x; static const unsigned long y = 3;",
+    "int *p, **pp, a[4], *ap[2], (*pa)[3];",
+    "int (*fp)(int, char *); int apply(int (*op)(int), int x);",
+    "int f(void); int g(int a, int b) { return a + b * 2; }",
+    "int h(int a) { return (a + 1) * (a - 1) / -a % 3; }",
+    "int m(int a, int b) { a = b = 3; a += b -= 1; return a ? b : a ? 1 : 2; }",
+    "int n(int *p) { return *p++ + ++*p - - -*p + sizeof(int) + sizeof p[0]; }",
+    "struct S { int a, b; char *c; struct S *next; }; struct S s = {1, 2, 0, &s};",
+    "union U { int i; float f; } u = {.f = 1.5};",
+    "enum E { A, B = 4, C }; enum E e = C;",
+    "typedef struct { int x; } T; T t; T *tp = &t; int get(void) { return tp->x + t.x; }",
+    "int a[3] = {[0] = 1, [2] = 3};",
+    "double d = (double)1 / 2.5; char *s = \"text\" \"more\";",
+    "int l(int a) {
+        int b = 0;
+        if (a) b = 1; else if (a > 2) { b = 2; } else b = 3;
+        while (a--) b++;
+        do { b--; } while (b > 10);
+        for (int i = 0; i < a; i++) { b += i; }
+        for (;;) break;
+        switch (a) { case 1: b = 0; break; default: b = 1; }
+        goto end;
+        end:
+        return b;
+    }",
+    "int p(int a) { return (a & 1) == 0 || (a | 2) != 0 && a << 1 > 3 ^ ~a; }",
+    "int q(void) { int x = ({ int y = 1; y + 1; }); return _Generic(x, int: 1, default: 0); }",
+    "int *r(void) { return (int []){ 1, 2 }; }",
+    "_Static_assert(1, \"message\");",
+    "unsigned x = 0x10 + 0xffU + 017 + 3L + 4ul + 5LL; double y = 1e10 + 2.5e-3f + .5 + 0x1p4;",
+    "int c = 'a' + L'b' + '\\n' + u'\\x41';",
+];
+
+#[test]
+fn printing_gives_the_same_tokens() {
+    for source in CORPUS {
+        let env = CompileEnv::default();
+        let file = parse(&env, source);
+        let printed = print(&file);
+        assert_eq!(
+            lex_kinds(&env, source),
+            lex_kinds(&env, &printed),
+            "Source:\n{}\nPrinted:\n{}",
+            source,
+            printed
+        );
+    }
+}
+
+#[test]
+fn printing_lays_out_statements() {
+    let env = CompileEnv::default();
+    let file = parse(
+        &env,
+        "int f(int a){if(a)return a;while(a)a--;for(;a<3;)a++;return-a;}",
+    );
+    let expected = "int f(int a) {
+    if (a)
+        return a;
+    while (a)
+        a--;
+    for (; a < 3;)
+        a++;
+    return -a;
+}
+";
+    assert_eq!(print(&file), expected);
+}
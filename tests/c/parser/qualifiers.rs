@@ -0,0 +1,58 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use vase::c::{
+    ast::*,
+    CompileEnv,
+    ParseErrorKind,
+};
+
+use super::{
+    global_type,
+    parse_with_errors,
+};
+
+#[test]
+fn duplicate_qualifiers_are_reported_and_collapsed() {
+    let env = CompileEnv::default();
+    let (file, errors) = parse_with_errors(&env, "const const int x;");
+    assert!(
+        matches!(errors[..], [ParseErrorKind::DuplicateQualifier(..)]),
+        "{:?}",
+        errors
+    );
+    let consts = global_type(&env, &file, "x")
+        .segments
+        .iter()
+        .filter(|segment| {
+            matches!(
+                **segment,
+                TypeSegment::Modifier(ModifierSegment::Const(..))
+            )
+        })
+        .count();
+    assert_eq!(consts, 1);
+}
+
+#[test]
+fn different_qualifiers_are_not_duplicates() {
+    let env = CompileEnv::default();
+    let (_, errors) = parse_with_errors(&env, "const volatile int y;");
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+#[test]
+fn gnu_thread_is_thread_local() {
+    let env = CompileEnv::default();
+    let (file, errors) = parse_with_errors(&env, "__thread int tls;\nstatic __thread int x;");
+    assert!(errors.is_empty(), "{:?}", errors);
+    for name in &["tls", "x"] {
+        assert!(
+            global_type(&env, &file, name).segments.iter().any(|segment| {
+                matches!(
+                    *segment,
+                    TypeSegment::Modifier(ModifierSegment::ThreadLocal(..))
+                )
+            })
+        );
+    }
+}
@@ -0,0 +1,147 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use vase::c::{
+    ast::*,
+    CompileEnv,
+    ParseErrorKind,
+};
+
+use super::{
+    first_func_body,
+    global_initializer,
+    parse,
+    parse_with_errors,
+};
+
+fn init_members(expr: &Expr) -> &[InitMember] {
+    match *expr {
+        Expr::Init(ref init) => &init.values,
+        ref expr => panic!("Expected an initializer, found {:?}", expr),
+    }
+}
+
+#[test]
+fn unended_array_subscript_is_reported() {
+    let env = CompileEnv::default();
+    let (_, errors) = parse_with_errors(&env, "void f(int *a) { int b = a[0; }");
+    assert!(
+        matches!(errors[..], [ParseErrorKind::ArrayExpectedRBracket(..)]),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn unended_array_type_is_reported() {
+    let env = CompileEnv::default();
+    let (_, errors) = parse_with_errors(&env, "int a[2;");
+    assert!(
+        matches!(errors[..], [ParseErrorKind::ArrayExpectedRBracket(..)]),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn trailing_comma_in_call_is_reported() {
+    let env = CompileEnv::default();
+    let (file, errors) = parse_with_errors(&env, "int g(int a); void f() { g(1,); }");
+    assert!(
+        matches!(errors[..], [ParseErrorKind::CallTrailingComma]),
+        "{:?}",
+        errors
+    );
+    let call = match first_func_body(&file)[0] {
+        Stmt::Expr(ref expr) => match *expr {
+            Expr::Call(ref call) => call,
+            ref expr => panic!("Expected a call, found {:?}", expr),
+        },
+        ref stmt => panic!("Expected an expression statement, found {:?}", stmt),
+    };
+    assert_eq!(call.args.len(), 1);
+}
+
+#[test]
+fn trailing_comma_in_params_is_reported() {
+    let env = CompileEnv::default();
+    let (_, errors) = parse_with_errors(&env, "void f(int a,);");
+    assert!(
+        matches!(errors[..], [ParseErrorKind::ParamsTrailingComma]),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn designators_are_parsed() {
+    let env = CompileEnv::default();
+    let file = parse(&env, "int a[4] = { [1] = 5, [3] = 1 };");
+    let members = init_members(global_initializer(&env, &file, "a"));
+    assert!(
+        matches!(members, [InitMember::Array(ref first, _), InitMember::Array(ref second, _)]
+            if first.len() == 1 && second.len() == 1),
+        "{:?}",
+        members
+    );
+}
+
+#[test]
+fn designators_without_equal_are_reported() {
+    let env = CompileEnv::default();
+    let source = "struct S { int x; } s = { .x 1 }; int a[2] = { [1] 5 };";
+    let (file, errors) = parse_with_errors(&env, source);
+    assert!(
+        matches!(errors[..], [
+            ParseErrorKind::DesignatorExpectedEqual(..),
+            ParseErrorKind::DesignatorExpectedEqual(..)
+        ]),
+        "{:?}",
+        errors
+    );
+    let members = init_members(global_initializer(&env, &file, "s"));
+    assert!(
+        matches!(members, [InitMember::Named(..)]),
+        "{:?}",
+        members
+    );
+    let members = init_members(global_initializer(&env, &file, "a"));
+    assert!(
+        matches!(members, [InitMember::Array(..)]),
+        "{:?}",
+        members
+    );
+}
+
+#[test]
+fn unended_calls_are_reported() {
+    let env = CompileEnv::default();
+    let (_, errors) = parse_with_errors(&env, "int g(int a, int b); int x = g(1,");
+    assert!(
+        matches!(errors[..], [ParseErrorKind::CallExpectedRParen(..), ..]),
+        "{:?}",
+        errors
+    );
+
+    let env = CompileEnv::default();
+    let (_, errors) = parse_with_errors(&env, "int g(int a, int b); int x = g(1 2);");
+    assert!(
+        matches!(errors[..], [ParseErrorKind::CallExpectedRParen(..)]),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn missing_call_arguments_are_reported() {
+    let env = CompileEnv::default();
+    let (file, errors) = parse_with_errors(&env, "int g(int a, int b); int x = g(, 1);");
+    assert!(
+        matches!(errors[..], [ParseErrorKind::CallExpectedArg(..)]),
+        "{:?}",
+        errors
+    );
+    assert!(matches!(
+        *global_initializer(&env, &file, "x"),
+        Expr::Call(ref call) if call.args.len() == 1
+    ));
+}
@@ -0,0 +1,51 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use vase::c::{
+    CompileEnv,
+    CompileSettings,
+    LangVersion,
+    ParseErrorKind,
+};
+
+use super::parse_with_errors;
+
+fn c99_env() -> CompileEnv {
+    CompileEnv::new(CompileSettings {
+        version: LangVersion::C99,
+        ..CompileSettings::default()
+    })
+}
+
+fn restrict_errors(source: &str) -> Vec<ParseErrorKind> {
+    let env = c99_env();
+    let (_, errors) = parse_with_errors(&env, source);
+    errors
+}
+
+#[test]
+fn restrict_can_qualify_pointers() {
+    let errors = restrict_errors("int * restrict p;");
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+#[test]
+fn restrict_can_qualify_pointer_return_types() {
+    let errors = restrict_errors("int * restrict f(void);");
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+#[test]
+fn restrict_can_qualify_pointer_typedefs() {
+    let errors = restrict_errors("typedef int *ptr; restrict ptr p;");
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+#[test]
+fn restrict_on_non_pointer_is_an_error() {
+    let errors = restrict_errors("restrict int x;");
+    assert!(
+        matches!(errors[..], [ParseErrorKind::RestrictOnNonPointer(..)]),
+        "{:?}",
+        errors
+    );
+}
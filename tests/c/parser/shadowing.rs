@@ -0,0 +1,47 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use vase::c::{
+    ast::*,
+    CompileEnv,
+};
+
+use super::parse;
+
+/// Returns the root of the type of the variable declared in the body of the file's function.
+fn body_decl_root<'a>(env: &CompileEnv, file: &'a SourceFile, name: &str) -> &'a TypeRoot {
+    for decl in file.root_scope().decls.values() {
+        if let DeclPostfix::Block(ref block) = decl.postfix {
+            let body = file.get_scope(block.scope_id);
+            let index = body.decls.get_index(&env.cache().get_or_cache(name));
+            return &body.decls[index.expect("The variable should have been declared.")]
+                .type_
+                .root;
+        }
+    }
+    panic!("No function body was found in the file.");
+}
+
+#[test]
+fn variables_can_be_redeclared_in_inner_scopes() {
+    let env = CompileEnv::default();
+    // The inner x is the declaration's name (not the root of its type) since its type already
+    // has a root.
+    let file = parse(&env, "int x;\nint f(void) { double x; return x; }");
+    assert!(matches!(
+        *body_decl_root(&env, &file, "x"),
+        TypeRoot::Double
+    ));
+}
+
+#[test]
+fn typedef_names_can_be_redeclared_as_variables() {
+    let env = CompileEnv::default();
+    let file = parse(
+        &env,
+        "typedef int T;\nint f(void) { double T; return T; }",
+    );
+    assert!(matches!(
+        *body_decl_root(&env, &file, "T"),
+        TypeRoot::Double
+    ));
+}
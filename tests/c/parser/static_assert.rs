@@ -0,0 +1,118 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use vase::c::{
+    ast::*,
+    CompileEnv,
+    CompileSettings,
+    LangVersion,
+    ParseErrorKind,
+};
+
+use super::{
+    first_func_body,
+    parse,
+    parse_with_errors,
+};
+
+fn first_static_assert(stmts: &[Stmt]) -> &StaticAssertStmt {
+    for stmt in stmts {
+        if let Stmt::StaticAssert(ref assert) = *stmt {
+            return assert;
+        }
+    }
+    panic!("No _Static_assert was found in the statements.");
+}
+
+#[test]
+fn passing_static_assert_parses() {
+    let env = CompileEnv::default();
+    let file = parse(&env, r#"_Static_assert(1 + 1 == 2, "math works");"#);
+    let assert = first_static_assert(&file.root_scope().stmts);
+    assert!(matches!(*assert.condition, Expr::Binary(..)));
+    let message = assert.message.as_ref().expect("The message should have been parsed.");
+    assert_eq!(&**message.segments[0], "math works");
+}
+
+#[test]
+fn failing_static_assert_parses() {
+    // Parsing doesn't evaluate the condition, so failing assertions parse the same way.
+    let env = CompileEnv::default();
+    let file = parse(
+        &env,
+        r#"int f(void) { _Static_assert(0, "always fails"); return 0; }"#,
+    );
+    let body = first_func_body(&file);
+    let assert = first_static_assert(body);
+    assert!(matches!(*assert.condition, Expr::Number(..)));
+    assert!(assert.message.is_some());
+    assert!(matches!(body[1], Stmt::Return(..)));
+}
+
+#[test]
+fn static_assert_message_is_optional_in_c23() {
+    let env = CompileEnv::new(CompileSettings {
+        version: LangVersion::C23,
+        ..CompileSettings::default()
+    });
+    let file = parse(&env, "_Static_assert(1);");
+    assert!(first_static_assert(&file.root_scope().stmts).message.is_none());
+
+    let (_, errors) = parse_with_errors(&CompileEnv::default(), "_Static_assert(1);");
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(
+        errors[0],
+        ParseErrorKind::StaticAssertWithoutMessage
+    ));
+}
+
+#[test]
+fn static_assert_recovers_from_missing_rparen() {
+    let env = CompileEnv::default();
+    let (file, errors) = parse_with_errors(&env, r#"_Static_assert(1, "unended"; int x;"#);
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(matches!(
+        errors[0],
+        ParseErrorKind::StaticAssertExpectedRParen(..)
+    ));
+    let root = file.root_scope();
+    assert!(first_static_assert(&root.stmts).message.is_some());
+    assert!(root.decls.get_index(&env.cache().get_or_cache("x")).is_some());
+}
+
+fn static_assert_errors(source: &str) -> Vec<ParseErrorKind> {
+    let env = CompileEnv::default();
+    let (file, errors) = parse_with_errors(&env, source);
+    // Every malformed assertion should still produce a statement.
+    first_static_assert(&file.root_scope().stmts);
+    errors
+}
+
+#[test]
+fn static_assert_reports_missing_lparen() {
+    let errors = static_assert_errors(r#"_Static_assert 1, "msg");"#);
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(matches!(
+        errors[0],
+        ParseErrorKind::StaticAssertExpectedLParen(..)
+    ));
+}
+
+#[test]
+fn static_assert_reports_missing_comma() {
+    let errors = static_assert_errors(r#"_Static_assert(1 "msg");"#);
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(matches!(
+        errors[0],
+        ParseErrorKind::StaticAssertExpectedComma(..)
+    ));
+}
+
+#[test]
+fn static_assert_reports_non_string_message() {
+    let errors = static_assert_errors("_Static_assert(1, 2 + 3);");
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(matches!(
+        errors[0],
+        ParseErrorKind::StaticAssertExpectedMessage(..)
+    ));
+}
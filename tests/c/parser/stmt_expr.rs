@@ -0,0 +1,99 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use std::path::Path;
+
+use vase::{
+    c::{
+        ast::*,
+        CompileEnv,
+        CompileSettings,
+        Lexer,
+        ParseError,
+        ParseErrorKind,
+        Parser,
+    },
+    error::Unwind,
+    sync::Arc,
+    util::{
+        CachedString,
+        FileId,
+    },
+};
+
+use super::{
+    first_func_body,
+    parse,
+    parse_with_errors,
+};
+
+fn returned_stmt_expr(file: &SourceFile) -> &StmtExpr {
+    let body = first_func_body(file);
+    let value = match *body.last().expect("The function should have a statement.") {
+        Stmt::Return(ref return_) => return_.expr.as_ref().expect("The return has a value."),
+        ref stmt => panic!("Expected a return statement, found {:?}", stmt),
+    };
+    match **value {
+        Expr::Stmt(ref expr) => expr,
+        ref expr => panic!("Expected a statement expression, found {:?}", expr),
+    }
+}
+
+#[test]
+fn stmt_exprs_are_valued_by_their_last_expression() {
+    let env = CompileEnv::default();
+    let file = parse(&env, "int f() { return ({ int x = f(); x + 1; }); }");
+    let expr = returned_stmt_expr(&file);
+    let stmts = &file.get_scope(expr.block.scope_id).stmts;
+    assert_eq!(stmts.len(), 2);
+    assert_eq!(expr.value_index, Some(1));
+    assert!(matches!(stmts[1], Stmt::Expr(Expr::Binary(..))));
+}
+
+#[test]
+fn stmt_exprs_without_a_last_expression_are_void() {
+    let env = CompileEnv::default();
+    let file = parse(&env, "int f() { return ({ int x = 1; }); }");
+    assert_eq!(returned_stmt_expr(&file).value_index, None);
+
+    let file = parse(&env, "int f() { return ({ }); }");
+    assert_eq!(returned_stmt_expr(&file).value_index, None);
+}
+
+#[test]
+fn stmt_exprs_are_rejected_without_gnu_extensions() {
+    let env = CompileEnv::new(CompileSettings {
+        enable_gnu_extensions: false,
+        ..CompileSettings::default()
+    });
+    let (file, errors) = parse_with_errors(&env, "int f() { return ({ 1; }); }");
+    assert!(
+        matches!(errors[..], [ParseErrorKind::GnuExtension(..)]),
+        "{:?}",
+        errors
+    );
+    assert_eq!(returned_stmt_expr(&file).value_index, Some(0));
+}
+
+#[test]
+fn stmt_exprs_without_a_closing_paren_are_reported() {
+    let env = CompileEnv::default();
+    let source = "int f() { int x = ({ 1; }; return x; }";
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+    let tokens = Lexer::new(&env, callback).lex_bytes(FileId::new(0).unwrap(), source.as_bytes());
+    let tokens = Arc::new(tokens);
+    env.file_id_to_tokens.push(tokens.clone());
+
+    let mut errors = Vec::new();
+    let mut parser = Parser::new(&env, |err: ParseError| {
+        errors.push(err.kind);
+        false
+    });
+    // The missing ) unwinds out of the statement expression.
+    assert_eq!(parser.parse(tokens).unwrap_err(), Unwind::Block);
+    drop(parser);
+    assert!(
+        matches!(errors[..], [ParseErrorKind::StmtExprExpectedRParen(..)]),
+        "{:?}",
+        errors
+    );
+}
@@ -0,0 +1,91 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use vase::{
+    c::{
+        ast::*,
+        CompileEnv,
+        ParseError,
+        ParseErrorKind,
+        Parser,
+    },
+    error::MayUnwind,
+};
+
+fn parse_type_name(env: &CompileEnv, source: &str) -> (MayUnwind<Type>, Vec<ParseErrorKind>) {
+    let mut errors = Vec::new();
+    let mut parser = Parser::new(env, |err: ParseError| {
+        errors.push(err.kind);
+        false
+    });
+    let type_ = parser.parse_type_name(source);
+    drop(parser);
+    (type_, errors)
+}
+
+#[test]
+fn type_names_are_parsed_without_declarations() {
+    let env = CompileEnv::default();
+
+    let (type_, errors) = parse_type_name(&env, "int *");
+    let type_ = type_.unwrap();
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert!(matches!(type_.root, TypeRoot::Int));
+    assert!(matches!(type_.segments.as_slice(), [
+        TypeSegment::Pointer(..)
+    ]));
+
+    let (type_, errors) = parse_type_name(&env, "char (*)(void)");
+    let type_ = type_.unwrap();
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert!(matches!(type_.root, TypeRoot::Char));
+    assert!(type_.name.is_none());
+    assert!(matches!(type_.segments.as_slice(), [
+        TypeSegment::Func(..),
+        TypeSegment::Pointer(..)
+    ]));
+}
+
+#[test]
+fn type_names_with_names_or_extra_tokens_are_errors() {
+    let env = CompileEnv::default();
+
+    let (type_, errors) = parse_type_name(&env, "int x");
+    assert!(type_.is_err());
+    assert!(matches!(errors[..], [ParseErrorKind::TypeNameHasName(
+        ..
+    )]));
+
+    let (type_, errors) = parse_type_name(&env, "unsigned long;");
+    assert!(type_.is_err());
+    assert!(matches!(errors[..], [
+        ParseErrorKind::TypeNameExpectedEnd(..)
+    ]));
+}
+
+#[test]
+fn invalid_declarators_are_errors() {
+    let env = CompileEnv::default();
+    let (type_, errors) = parse_type_name(&env, "int +");
+    assert!(type_.is_err());
+    assert!(matches!(errors[..], [
+        ParseErrorKind::DeclaratorUnexpectedToken(..)
+    ]));
+}
+
+#[test]
+fn type_names_are_lexed_in_their_own_files() {
+    let env = CompileEnv::default();
+    let mut errors = Vec::new();
+    let mut parser = Parser::new(&env, |err: ParseError| {
+        errors.push(err.state);
+        false
+    });
+    parser.parse_type_name("int").unwrap();
+    parser.parse_type_name("char * +").unwrap_err();
+    drop(parser);
+    assert_eq!(env.file_id_to_tokens.iter().count(), 2);
+
+    // The error resolves against the source of the second call (not the first).
+    let loc = errors[0].loc().unwrap();
+    assert_eq!(env.source_map.snippet(&loc).as_deref(), Some("char * +"));
+}
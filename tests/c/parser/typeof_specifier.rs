@@ -0,0 +1,80 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use vase::c::{
+    ast::*,
+    CompileEnv,
+    CompileSettings,
+    LangVersion,
+};
+
+use super::{
+    global_type,
+    parse,
+};
+
+fn c23_env() -> CompileEnv {
+    CompileEnv::new(CompileSettings {
+        version: LangVersion::C23,
+        ..CompileSettings::default()
+    })
+}
+
+fn typeof_type(type_: &Type) -> &Type {
+    match type_.root {
+        TypeRoot::TypeofType(ref inner) => inner,
+        ref root => panic!("Expected a typeof type, found {:?}", root),
+    }
+}
+
+fn has_const(type_: &Type) -> bool {
+    type_.segments.iter().any(|segment| {
+        matches!(
+            *segment,
+            TypeSegment::Modifier(ModifierSegment::Const(..))
+        )
+    })
+}
+
+#[test]
+fn typeof_accepts_expressions() {
+    let env = c23_env();
+    let file = parse(&env, "typeof(0 + 1) x;");
+    let type_ = global_type(&env, &file, "x");
+    assert!(
+        matches!(type_.root, TypeRoot::TypeofExpr(..)),
+        "{:?}",
+        type_
+    );
+}
+
+#[test]
+fn typeof_accepts_types() {
+    let env = c23_env();
+    let file = parse(&env, "typeof(int *) x;");
+    let inner = typeof_type(global_type(&env, &file, "x"));
+    assert!(matches!(inner.root, TypeRoot::Int));
+    assert!(matches!(
+        inner.segments.last(),
+        Some(&TypeSegment::Pointer(..))
+    ));
+}
+
+#[test]
+fn gnu_typeof_is_always_available() {
+    let env = CompileEnv::default();
+    let file = parse(&env, "int *ptr;\n__typeof__(ptr) x;");
+    let type_ = global_type(&env, &file, "x");
+    assert!(
+        matches!(type_.root, TypeRoot::TypeofExpr(..)),
+        "{:?}",
+        type_
+    );
+}
+
+#[test]
+fn typeof_unqual_removes_qualifiers() {
+    let env = c23_env();
+    let file = parse(&env, "typeof(const int) x;\ntypeof_unqual(const int) y;");
+    assert!(has_const(typeof_type(global_type(&env, &file, "x"))));
+    assert!(!has_const(typeof_type(global_type(&env, &file, "y"))));
+}
@@ -0,0 +1,148 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use vase::{
+    c::{
+        ast::*,
+        CompileEnv,
+    },
+    error::MayUnwind,
+};
+
+use super::{
+    first_func_body,
+    global_initializer,
+    parse,
+};
+
+/// Records a short name for everything it visits.
+#[derive(Default)]
+struct Recorder {
+    visited: Vec<String>,
+    skip_calls: bool,
+    stop_at_return: bool,
+}
+
+impl Visitor for Recorder {
+    fn visit_decl(&mut self, decl: &Decl) -> Flow {
+        let name = decl.type_.name.as_ref().map_or("?", |name| name.string());
+        self.visited.push(format!("decl {}", name));
+        Flow::Continue
+    }
+
+    fn visit_stmt(&mut self, stmt: &Stmt) -> Flow {
+        let name = match *stmt {
+            Stmt::Return(..) if self.stop_at_return => return Flow::Stop,
+            Stmt::Return(..) => "return",
+            Stmt::If(..) => "if",
+            Stmt::Expr(..) => "expr",
+            Stmt::Decl(..) => "decl-stmt",
+            _ => "stmt",
+        };
+        self.visited.push(name.to_owned());
+        Flow::Continue
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) -> Flow {
+        let name = match *expr {
+            Expr::DeclRef(ref expr) => expr.id.text.string().to_owned(),
+            Expr::Number(ref number) => format!("{:?}", number.kind),
+            Expr::Call(..) if self.skip_calls => return Flow::SkipChildren,
+            Expr::Call(..) => "call".to_owned(),
+            Expr::Binary(ref expr) => format!("{:?}", expr.op),
+            Expr::Assign(..) => "assign".to_owned(),
+            _ => "expr".to_owned(),
+        };
+        self.visited.push(name);
+        Flow::Continue
+    }
+}
+
+const SOURCE: &str = "int g(int a);
+int x = 1;
+int f(int b) {
+    if (b < x) b = g(b);
+    return b;
+}";
+
+#[test]
+fn walk_visits_in_source_order() {
+    let env = CompileEnv::default();
+    let file = parse(&env, SOURCE);
+    let mut recorder = Recorder::default();
+    assert_eq!(walk_file(&file, &mut recorder), Flow::Continue);
+    assert_eq!(recorder.visited, [
+        "decl-stmt",
+        "decl g",
+        // The ; after the function's declaration.
+        "stmt",
+        "decl-stmt",
+        "decl x",
+        "I32(1)",
+        "decl-stmt",
+        "decl f",
+        "if",
+        // The parentheses around the condition.
+        "expr",
+        "LessThan",
+        "b",
+        "x",
+        "expr",
+        "assign",
+        "b",
+        "call",
+        "g",
+        "b",
+        "return",
+        "b",
+    ]);
+}
+
+#[test]
+fn walk_can_skip_children_and_stop() {
+    let env = CompileEnv::default();
+    let file = parse(&env, SOURCE);
+    let mut recorder = Recorder {
+        skip_calls: true,
+        ..Recorder::default()
+    };
+    walk_file(&file, &mut recorder);
+    assert!(!recorder.visited.contains(&"g".to_owned()));
+
+    let mut recorder = Recorder {
+        stop_at_return: true,
+        ..Recorder::default()
+    };
+    let body = first_func_body(&file);
+    assert_eq!(walk_stmt(&file, &body[1], &mut recorder), Flow::Stop);
+    assert!(recorder.visited.is_empty());
+    assert_eq!(walk_stmt(&file, &body[0], &mut recorder), Flow::Continue);
+    assert_eq!(recorder.visited.len(), 11);
+}
+
+/// Counts the numbers an expression visitor reaches.
+#[derive(Default)]
+struct NumberCounter {
+    numbers: usize,
+}
+
+impl ExprVisitor for NumberCounter {
+    fn on_number(&mut self, _lit: &mut Number) -> MayUnwind<()> {
+        self.numbers += 1;
+        Ok(())
+    }
+}
+
+#[test]
+fn expr_visitors_visit_generic_selections_and_compound_literals() {
+    let env = CompileEnv::default();
+    let file = parse(
+        &env,
+        "int a = _Generic(1, int: 2, default: 3);\nint *b = (int[]){4, [5] = 6, {7}};",
+    );
+    for &(name, numbers) in &[("a", 3), ("b", 4)] {
+        let mut expr = global_initializer(&env, &file, name).clone();
+        let mut counter = NumberCounter::default();
+        counter.on_expr(&mut expr).unwrap();
+        assert_eq!(counter.numbers, numbers, "{}", name);
+    }
+}
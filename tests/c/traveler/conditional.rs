@@ -1,11 +1,34 @@
 // Copyright 2021. remilia-dev
 // This source code is licensed under GPLv3 or any later version.
-use vase::c::{
-    CompileEnv,
-    TokenKind::*,
+use std::path::Path;
+
+use vase::{
+    c::{
+        ast::NumberError,
+        CompileEnv,
+        CompileSettings,
+        LangVersion,
+        Lexer,
+        TokenKind::*,
+        Traveler,
+        TravelerError,
+        TravelerErrorKind,
+    },
+    error::{
+        CodedError,
+        Severity,
+    },
+    sync::Arc,
+    util::{
+        CachedString,
+        FileId,
+    },
 };
 
-use super::run_test;
+use super::{
+    collect_errors,
+    run_test,
+};
 
 #[test]
 fn preprocessor_ifdef_works() {
@@ -159,3 +182,243 @@ fn preprocessor_if_char_literals_work() {
         ],
     );
 }
+
+#[test]
+fn standalone_expressions_use_current_macros() {
+    let env = CompileEnv::default();
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+    let mut lexer = Lexer::new(&env, callback);
+    let source =
+        Arc::new(lexer.lex_bytes(FileId::new(0).unwrap(), b"#define A 3\n#define B 4\nend"));
+    let expr = lexer.lex_bytes(FileId::new(1).unwrap(), b"A + B * 2");
+    // Exclude the Eof token.
+    let expr = &expr[..expr.len() - 1];
+
+    let mut traveler = Traveler::new(&env, |err: TravelerError| -> bool {
+        panic!("An error should not have occured: {:?}", err);
+    });
+    traveler.load_start(source).unwrap();
+    assert_eq!(traveler.eval_pp_expr(expr).unwrap(), 11);
+    // The traveler should be where it was before the evaluation.
+    assert_eq!(
+        traveler.head().kind(),
+        &Identifier(env.cache().get_or_cache("end"))
+    );
+}
+
+#[test]
+fn has_attribute_checks_for_known_attributes() {
+    let env = CompileEnv::default();
+    let cache = env.cache();
+    run_test(
+        &env,
+        &[r#"
+        #if __has_c_attribute(nodiscard) == 200809 && __has_c_attribute(__deprecated__)
+            known_c_attribute
+        #endif
+        #if __has_c_attribute(unknown_attr) || __has_c_attribute(packed)
+            Should not occur
+        #endif
+        #if __has_attribute(packed) == 1 && __has_attribute(const) && !__has_attribute(unknown_attr)
+            known_gnu_attribute
+        #endif
+        #if defined(__has_c_attribute) && defined __has_attribute
+            predicates_are_defined
+        #endif
+        "#],
+        &[
+            Identifier(cache.get_or_cache("known_c_attribute")),
+            Identifier(cache.get_or_cache("known_gnu_attribute")),
+            Identifier(cache.get_or_cache("predicates_are_defined")),
+        ],
+    );
+}
+
+#[test]
+fn has_builtin_checks_for_known_builtins() {
+    let env = CompileEnv::new(CompileSettings {
+        extra_builtins: vec!["__builtin_custom".to_owned()],
+        ..CompileSettings::default()
+    });
+    let cache = env.cache();
+    run_test(
+        &env,
+        &[r#"
+        #if __has_builtin(__builtin_expect) && __has_builtin(__builtin_custom)
+            known_builtin
+        #endif
+        #if __has_builtin(__noncexistent)
+            Should not occur
+        #endif
+        #if defined(__has_builtin)
+            predicate_is_defined
+        #endif
+        "#],
+        &[
+            Identifier(cache.get_or_cache("known_builtin")),
+            Identifier(cache.get_or_cache("predicate_is_defined")),
+        ],
+    );
+}
+
+#[test]
+fn unbalanced_conditionals_are_reported_and_ignored() {
+    let env = CompileEnv::default();
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+    let mut lexer = Lexer::new(&env, callback);
+    let source = "before\n#else\nafter_else\n#endif\n#endif\nend\n";
+    let tokens = Arc::new(lexer.lex_bytes(FileId::new(0).unwrap(), source.as_bytes()));
+    env.file_id_to_tokens.push(tokens.clone());
+
+    let mut errors = Vec::new();
+    let mut traveler = Traveler::new(&env, |err: TravelerError| {
+        errors.push(err.kind);
+        false
+    });
+    traveler.load_start(tokens).unwrap();
+    let mut identifiers = Vec::new();
+    while !matches!(traveler.head().kind(), Eof) {
+        if let Identifier(ref id) = *traveler.head().kind() {
+            identifiers.push(id.string().to_owned());
+        }
+        traveler.move_forward().unwrap();
+    }
+    drop(traveler);
+
+    // The #else doesn't skip anything since there was no #if before it.
+    assert_eq!(identifiers, ["before", "after_else", "end"]);
+    // The first #endif closes the #else. The lexer's errors for the #else and #endif aren't
+    // reported too.
+    assert!(
+        matches!(errors[..], [
+            TravelerErrorKind::UnbalancedConditional(ref else_),
+            TravelerErrorKind::UnbalancedConditional(ref end_if),
+        ] if matches!(*else_.kind(), PreElse { .. }) && *end_if.kind() == PreEndIf),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn assert_directives_are_ignored_with_a_warning() {
+    let env = CompileEnv::default();
+    let source = "#assert machine(vax)\n#unassert machine\nint x;\n";
+    let errors = collect_errors(&env, source);
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+    for error in &errors {
+        assert_eq!(error.severity(), Severity::Warning);
+        assert!(matches!(
+            *error,
+            TravelerErrorKind::AssertPreprocessorIgnored(..)
+        ));
+    }
+}
+
+#[test]
+fn invalid_octal_digits_are_reported_at_the_digit() {
+    let env = CompileEnv::default();
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+    let mut lexer = Lexer::new(&env, callback);
+    let tokens = Arc::new(lexer.lex_bytes(FileId::new(0).unwrap(), b"#if 0778\n#endif\n"));
+    env.file_id_to_tokens.push(tokens.clone());
+
+    let mut errors = Vec::new();
+    let mut traveler = Traveler::new(&env, |err: TravelerError| {
+        errors.push(err);
+        false
+    });
+    traveler.load_start(tokens).unwrap();
+    while !matches!(traveler.head().kind(), Eof) {
+        traveler.move_forward().unwrap();
+    }
+    drop(traveler);
+
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(matches!(
+        errors[0].kind,
+        TravelerErrorKind::Number(NumberError::InvalidOctalDigit('8', 3))
+    ));
+    let loc = errors[0].loc().unwrap();
+    assert_eq!((loc.byte, loc.byte_length), (7, 1));
+}
+
+#[test]
+fn branch_coverage_records_which_branches_were_taken() {
+    let env = CompileEnv::default();
+    let source = "#if 1\ntaken\n#else\nskipped\n#endif\n\
+                  #ifdef UNDEFINED\n#if 1\n#endif\n#elif 0\n#elif 1\n#else\n#endif\n";
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+    let mut lexer = Lexer::new(&env, callback);
+    let tokens = Arc::new(lexer.lex_bytes(FileId::new(0).unwrap(), source.as_bytes()));
+
+    let mut traveler = Traveler::new(&env, |err: TravelerError| -> bool {
+        panic!("An error should not have occured: {:?}", err);
+    });
+    traveler.load_start(tokens).unwrap();
+    while traveler.head().kind() != &Eof {
+        traveler.move_forward().unwrap();
+    }
+
+    let coverage: Vec<(&str, bool)> = traveler
+        .branch_coverage()
+        .into_iter()
+        .map(|(loc, taken)| (&source[loc.range()], taken))
+        .collect();
+    // The #if within the skipped #ifdef is never reached.
+    assert_eq!(coverage, [
+        ("#if", true),
+        ("#else", false),
+        ("#ifdef", false),
+        ("#elif", false),
+        ("#elif", true),
+        ("#else", false),
+    ]);
+}
+
+#[test]
+fn stdc_version_is_the_version_being_used() {
+    let source = "#if __STDC__ && __STDC_VERSION__ >= 201112L\nnew\n#else\nold\n#endif\n";
+    let run = |version, expected: &str| {
+        let env = CompileEnv::new(CompileSettings {
+            version,
+            ..CompileSettings::default()
+        });
+        let expected = Identifier(env.cache().get_or_cache(expected));
+        run_test(&env, &[source], &[expected]);
+    };
+    run(LangVersion::C11, "new");
+    run(LangVersion::C23, "new");
+    run(LangVersion::C99, "old");
+    // __STDC_VERSION__ isn't defined in C89 (so it's 0).
+    run(LangVersion::C89, "old");
+}
+
+#[test]
+fn stdc_version_follows_a_reconfigured_env() {
+    let env = CompileEnv::new(CompileSettings {
+        version: LangVersion::C11,
+        ..CompileSettings::default()
+    });
+    let source = "#ifdef __STDC_VERSION__\n__STDC_VERSION__\n#else\nold\n#endif\n";
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+    let tokens = Lexer::new(&env, callback).lex_bytes(FileId::new(0).unwrap(), source.as_bytes());
+    let tokens = Arc::new(tokens);
+    env.file_id_to_tokens.push(tokens.clone());
+
+    let mut traveler = Traveler::new(&env, |err: TravelerError| {
+        panic!("An error should not have occured: {:?}", err);
+    });
+    // The traveler was created under C11, but each start uses the env's current version.
+    for &(version, expected) in &[(LangVersion::C89, "old"), (LangVersion::C17, "201710L")] {
+        env.reconfigure(CompileSettings {
+            version,
+            ..CompileSettings::default()
+        });
+        traveler.load_start(tokens.clone()).unwrap();
+        let expected = match version {
+            LangVersion::C89 => Identifier(env.cache().get_or_cache(expected)),
+            _ => Number(env.cache().get_or_cache(expected)),
+        };
+        assert_eq!(traveler.head().kind(), &expected);
+    }
+}
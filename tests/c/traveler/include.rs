@@ -1,11 +1,37 @@
 // Copyright 2021. remilia-dev
 // This source code is licensed under GPLv3 or any later version.
-use vase::c::{
-    CompileEnv,
-    TokenKind::*,
+use std::{
+    path::Path,
+    sync::atomic::{
+        AtomicUsize,
+        Ordering,
+    },
 };
 
-use super::run_test;
+use vase::{
+    c::{
+        diff_include_configs,
+        CompileEnv,
+        CompileSettings,
+        IncludeType,
+        Lexer,
+        TokenKind::*,
+        Traveler,
+        TravelerError,
+        TravelerErrorKind,
+    },
+    sync::Arc,
+    util::{
+        CachedString,
+        FileId,
+        StringCache,
+    },
+};
+
+use super::{
+    collect_errors,
+    run_test,
+};
 
 #[test]
 fn includes_work() {
@@ -31,3 +57,421 @@ fn includes_work() {
         &expected,
     );
 }
+
+#[test]
+fn include_config_divergence_is_found() {
+    let env = CompileEnv::default();
+    let cache = env.cache();
+    let no_includes = |_, _: &CachedString, _: &Option<Arc<Path>>| -> Option<FileId> { None };
+    let mut lexer = Lexer::new(&env, no_includes);
+    let mut add_header = |source: &str| {
+        let file_id = env.file_id_to_tokens.reserve().unwrap();
+        let tokens = lexer.lex_bytes(file_id, source.as_bytes());
+        env.file_id_to_tokens.set_or_panic(file_id, Arc::new(tokens));
+        file_id
+    };
+    let old_header = add_header("#define VALUE 1\n");
+    let new_header = add_header("#define VALUE 2\n");
+    let same_header = add_header("#define VALUE 1\n");
+
+    let source = "#include \"value.h\"\nint x = VALUE;\n";
+    let resolve_to = |file_id: FileId| {
+        move |_, _: &CachedString, _: &Option<Arc<Path>>| -> Option<FileId> { Some(file_id) }
+    };
+    let errors = |err: TravelerError| -> bool {
+        panic!("An error should not have occured: {:?}", err);
+    };
+
+    let divergence = diff_include_configs(
+        &env,
+        source.as_bytes(),
+        resolve_to(old_header),
+        resolve_to(new_header),
+        errors,
+    )
+    .unwrap()
+    .expect("The configurations should diverge.");
+    // int x = VALUE
+    assert_eq!(divergence.index, 3);
+    assert_eq!(divergence.left.loc().file_id(), old_header);
+    assert_eq!(divergence.right.loc().file_id(), new_header);
+    assert_eq!(divergence.left.kind(), &Number(cache.get_or_cache("1")));
+    assert_eq!(divergence.right.kind(), &Number(cache.get_or_cache("2")));
+
+    let divergence = diff_include_configs(
+        &env,
+        source.as_bytes(),
+        resolve_to(old_header),
+        resolve_to(same_header),
+        errors,
+    )
+    .unwrap();
+    assert!(divergence.is_none(), "{:?}", divergence);
+}
+
+#[test]
+fn local_and_system_includes_of_same_path_are_separate() {
+    let env = CompileEnv::default();
+    let cache = env.cache();
+    let no_includes = |_, _: &CachedString, _: &Option<Arc<Path>>| -> Option<FileId> { None };
+    let mut lexer = Lexer::new(&env, no_includes);
+    let mut add_header = |source: &str| {
+        let file_id = env.file_id_to_tokens.reserve().unwrap();
+        let tokens = lexer.lex_bytes(file_id, source.as_bytes());
+        env.file_id_to_tokens.set_or_panic(file_id, Arc::new(tokens));
+        file_id
+    };
+    let local_header = add_header("from_local\n");
+    let system_header = add_header("from_system\n");
+
+    let callback = |inc_type, _: &CachedString, _: &Option<Arc<Path>>| -> Option<FileId> {
+        match inc_type {
+            IncludeType::IncludeLocal => Some(local_header),
+            _ => Some(system_header),
+        }
+    };
+    let mut lexer = Lexer::new(&env, callback);
+    let source = "#include \"x.h\"\n#include <x.h>\n";
+    let tokens = Arc::new(lexer.lex_bytes(
+        env.file_id_to_tokens.reserve().unwrap(),
+        source.as_bytes(),
+    ));
+
+    let mut traveler = Traveler::new(&env, |err: TravelerError| -> bool {
+        panic!("An error should not have occured: {:?}", err);
+    });
+    traveler.load_start(tokens).unwrap();
+    assert_eq!(
+        traveler.head().kind(),
+        &Identifier(cache.get_or_cache("from_local"))
+    );
+    traveler.move_forward().unwrap();
+    assert_eq!(
+        traveler.head().kind(),
+        &Identifier(cache.get_or_cache("from_system"))
+    );
+    traveler.move_forward().unwrap();
+    assert_eq!(traveler.head().kind(), &Eof);
+}
+
+#[test]
+fn headers_can_be_lexed_when_first_included() {
+    let mut env = CompileEnv::default();
+    let header_id = env.file_id_to_tokens.reserve().unwrap();
+    let times_lexed = Arc::new(AtomicUsize::new(0));
+    let lazy_times_lexed = times_lexed.clone();
+    env.set_lazy_lexer(move |env: &CompileEnv, file_id: FileId| {
+        assert_eq!(file_id, header_id);
+        lazy_times_lexed.fetch_add(1, Ordering::SeqCst);
+        let no_includes = |_, _: &CachedString, _: &Option<Arc<Path>>| -> Option<FileId> { None };
+        let mut lexer = Lexer::new(env, no_includes);
+        Some(lexer.lex_bytes(file_id, b"from_header\n"))
+    });
+
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| Some(header_id);
+    let mut lexer = Lexer::new(&env, callback);
+    let source = "#include \"header.h\"\n#include \"header.h\"\n";
+    let tokens = Arc::new(lexer.lex_bytes(
+        env.file_id_to_tokens.reserve().unwrap(),
+        source.as_bytes(),
+    ));
+    assert!(env.file_id_to_tokens.get(header_id).is_none());
+
+    let mut traveler = Traveler::new(&env, |err: TravelerError| -> bool {
+        panic!("An error should not have occured: {:?}", err);
+    });
+    traveler.load_start(tokens).unwrap();
+    let from_header = Identifier(env.cache().get_or_cache("from_header"));
+    assert_eq!(traveler.head().kind(), &from_header);
+    traveler.move_forward().unwrap();
+    assert_eq!(traveler.head().kind(), &from_header);
+    traveler.move_forward().unwrap();
+    assert_eq!(traveler.head().kind(), &Eof);
+
+    assert!(env.file_id_to_tokens.get(header_id).is_some());
+    assert_eq!(times_lexed.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn includes_of_unreserved_ids_are_not_found() {
+    let mut env = CompileEnv::default();
+    env.set_lazy_lexer(|_: &CompileEnv, _: FileId| {
+        panic!("Only reserved ids should be lexed.");
+    });
+
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| FileId::new(100);
+    let mut lexer = Lexer::new(&env, callback);
+    let tokens = lexer.lex_bytes(FileId::new(0).unwrap(), b"#include \"unknown.h\"\n");
+    let tokens = Arc::new(tokens);
+    env.file_id_to_tokens.push(tokens.clone());
+
+    let mut errors = Vec::new();
+    let mut traveler = Traveler::new(&env, |err: TravelerError| {
+        errors.push(err.kind);
+        false
+    });
+    assert!(traveler.load_start(tokens).is_err());
+    drop(traveler);
+    assert!(
+        matches!(errors[..], [TravelerErrorKind::IncludeNotFound(..)]),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn has_include_checks_whether_paths_resolve() {
+    let env = CompileEnv::default();
+    let cache = env.cache();
+    let exists = cache.get_or_cache("exists.h");
+    let callback = |inc_type, path: &CachedString, _: &Option<Arc<Path>>| -> Option<FileId> {
+        match inc_type {
+            IncludeType::IncludeNext => None,
+            _ if *path == exists => Some(FileId::new(1).unwrap()),
+            _ => None,
+        }
+    };
+    let mut lexer = Lexer::new(&env, callback);
+    let source = r#"
+        #if __has_include("exists.h") && __has_include(<exists.h>)
+        found
+        #endif
+        #if __has_include("missing.h") || __has_include(<missing.h>)
+        missing
+        #endif
+        #if defined(__has_include) && !__has_include_next(<exists.h>)
+        not_next
+        #endif
+        #if __has_include("exists.h") && (1 < 2)
+        compared
+        #endif
+        "#;
+    let tokens = Arc::new(lexer.lex_bytes(FileId::new(0).unwrap(), source.as_bytes()));
+    env.file_id_to_tokens.push(tokens.clone());
+
+    let mut traveler = Traveler::new(&env, |err: TravelerError| -> bool {
+        panic!("An error should not have occured: {:?}", err);
+    });
+    traveler.load_start(tokens).unwrap();
+    for expected in &["found", "not_next", "compared"] {
+        assert_eq!(
+            traveler.head().kind(),
+            &Identifier(cache.get_or_cache(expected))
+        );
+        traveler.move_forward().unwrap();
+    }
+    assert_eq!(traveler.head().kind(), &Eof);
+}
+
+#[test]
+fn include_rewriter_redirects_includes() {
+    let env = CompileEnv::new(CompileSettings {
+        include_rewriter: Some(Box::new(
+            |inc_type, path: &CachedString, cache: &StringCache| match path.string() {
+                "config.h" => Some((inc_type, cache.get_or_cache("/generated/config.h"))),
+                _ => None,
+            },
+        )),
+        ..CompileSettings::default()
+    });
+    let cache = env.cache();
+    let generated = cache.get_or_cache("/generated/config.h");
+    let callback = |_, path: &CachedString, _: &Option<Arc<Path>>| -> Option<FileId> {
+        if *path == generated {
+            Some(FileId::new(1).unwrap())
+        } else {
+            None
+        }
+    };
+    let mut lexer = Lexer::new(&env, callback);
+    let source = lexer.lex_bytes(FileId::new(0).unwrap(), b"#include <config.h>\nCONFIG\n");
+    let header = lexer.lex_bytes(FileId::new(1).unwrap(), b"#define CONFIG redirected\n");
+    let source = Arc::new(source);
+    env.file_id_to_tokens.push(source.clone());
+    env.file_id_to_tokens.push(Arc::new(header));
+
+    let mut traveler = Traveler::new(&env, |err: TravelerError| -> bool {
+        panic!("An error should not have occured: {:?}", err);
+    });
+    traveler.load_start(source).unwrap();
+    assert_eq!(
+        traveler.head().kind(),
+        &Identifier(cache.get_or_cache("redirected"))
+    );
+    traveler.move_forward().unwrap();
+    assert_eq!(traveler.head().kind(), &Eof);
+}
+
+#[test]
+fn includes_can_be_macro_expanded() {
+    let env = CompileEnv::new(CompileSettings {
+        include_rewriter: Some(Box::new(
+            |inc_type, path: &CachedString, cache: &StringCache| match path.string() {
+                "old header" => Some((inc_type, cache.get_or_cache("the header"))),
+                _ => None,
+            },
+        )),
+        ..CompileSettings::default()
+    });
+    let cache = env.cache();
+    // The lexer only sees the macros' strings, so it doesn't resolve any of the includes.
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+    let mut lexer = Lexer::new(&env, callback);
+    let source = lexer.lex_bytes(
+        FileId::new(0).unwrap(),
+        b"#define HEADER \"the header\"\n#define SYS_HEADER <the header>\n\
+          #define INDIRECT HEADER\n#define OLD \"old header\"\n\
+          #include HEADER\n#include SYS_HEADER\n#include INDIRECT\n#include OLD\n",
+    );
+    let source = Arc::new(source);
+    env.file_id_to_tokens.push(source.clone());
+
+    let mut resolved = Vec::new();
+    let mut travelled = Vec::new();
+    let mut traveler = Traveler::new(&env, |err: TravelerError| {
+        panic!("An error should not have occured: {:?}", err);
+    });
+    // Each include is lexed on demand.
+    traveler.set_include_callback(|inc_type, path: &CachedString, _: &Option<Arc<Path>>| {
+        resolved.push((inc_type, path.string().to_owned()));
+        let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+        let file_id = env.file_id_to_tokens.reserve().unwrap();
+        let tokens = Lexer::new(&env, callback).lex_bytes(file_id, b"from_include\n");
+        env.file_id_to_tokens.set_or_panic(file_id, Arc::new(tokens));
+        Some(file_id)
+    });
+    traveler.load_start(source).unwrap();
+    while !matches!(traveler.head().kind(), Eof) {
+        travelled.push(traveler.head().kind().clone());
+        traveler.move_forward().unwrap();
+    }
+    drop(traveler);
+
+    let from_include = Identifier(cache.get_or_cache("from_include"));
+    assert_eq!(travelled, vec![from_include; 4]);
+    let local = |path: &str| (IncludeType::IncludeLocal, path.to_owned());
+    assert_eq!(resolved, [
+        local("the header"),
+        (IncludeType::IncludeSystem, "the header".to_owned()),
+        local("the header"),
+        // The rewriter applies to macro-expanded includes too.
+        local("the header"),
+    ]);
+}
+
+#[test]
+fn macro_expanded_includes_need_a_path() {
+    let env = CompileEnv::default();
+    let errors = collect_errors(
+        &env,
+        "#define NUMBER 42\n#define UNENDED <header\n#include NUMBER\n#include UNENDED\n",
+    );
+    assert!(
+        matches!(errors[..], [
+            TravelerErrorKind::IncludeExpectedPath(..),
+            TravelerErrorKind::IncludeExpectedPath(..),
+        ]),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn circular_includes_are_reported_at_the_depth_limit() {
+    let env = CompileEnv::new(CompileSettings {
+        max_include_depth: 4,
+        ..CompileSettings::default()
+    });
+    let cache = env.cache();
+    let a = env.file_id_to_tokens.reserve().unwrap();
+    let b = env.file_id_to_tokens.reserve().unwrap();
+    let callback = |_, path: &CachedString, _: &Option<Arc<Path>>| match path.string() {
+        "a.h" => Some(a),
+        _ => Some(b),
+    };
+    let mut lexer = Lexer::new(&env, callback);
+    let mut lex = |file_id, source: &str| {
+        let tokens = Arc::new(lexer.lex_bytes(file_id, source.as_bytes()));
+        env.file_id_to_tokens.set_or_panic(file_id, tokens.clone());
+        tokens
+    };
+    let tokens = lex(a, "#include \"b.h\"\nfrom_a\n");
+    lex(b, "#include \"a.h\"\nfrom_b\n");
+
+    let mut errors = Vec::new();
+    let mut traveler = Traveler::new(&env, |err: TravelerError| {
+        errors.push(err.kind);
+        false
+    });
+    traveler.load_start(tokens).unwrap();
+    let mut identifiers = Vec::new();
+    while traveler.head().kind() != &Eof {
+        identifiers.push(traveler.head().kind().clone());
+        traveler.move_forward().unwrap();
+    }
+    drop(traveler);
+
+    // The include in the fifth file (4 includes deep) is skipped since b.h is already open.
+    let from_a = Identifier(cache.get_or_cache("from_a"));
+    let from_b = Identifier(cache.get_or_cache("from_b"));
+    assert_eq!(identifiers, [
+        from_a.clone(),
+        from_b.clone(),
+        from_a.clone(),
+        from_b,
+        from_a,
+    ]);
+    assert!(
+        matches!(errors[..], [TravelerErrorKind::CircularInclude(file_id)] if file_id == b),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn deep_includes_of_new_files_exceed_the_depth_limit() {
+    let env = CompileEnv::new(CompileSettings {
+        max_include_depth: 1,
+        ..CompileSettings::default()
+    });
+    let cache = env.cache();
+    let b = env.file_id_to_tokens.reserve().unwrap();
+    let c = env.file_id_to_tokens.reserve().unwrap();
+    let callback = |_, path: &CachedString, _: &Option<Arc<Path>>| match path.string() {
+        "b.h" => Some(b),
+        _ => Some(c),
+    };
+    let mut lexer = Lexer::new(&env, callback);
+    let mut lex = |file_id, source: &str| {
+        let tokens = Arc::new(lexer.lex_bytes(file_id, source.as_bytes()));
+        env.file_id_to_tokens.set_or_panic(file_id, tokens.clone());
+        tokens
+    };
+    lex(b, "#include \"c.h\"\nfrom_b\n");
+    lex(c, "from_c\n");
+    let tokens = lex(
+        env.file_id_to_tokens.reserve().unwrap(),
+        "#include \"b.h\"\n",
+    );
+
+    let mut errors = Vec::new();
+    let mut traveler = Traveler::new(&env, |err: TravelerError| {
+        errors.push(err.kind);
+        false
+    });
+    traveler.load_start(tokens).unwrap();
+    let mut identifiers = Vec::new();
+    while traveler.head().kind() != &Eof {
+        identifiers.push(traveler.head().kind().clone());
+        traveler.move_forward().unwrap();
+    }
+    drop(traveler);
+
+    // c.h isn't being included yet, so skipping it is only a depth error.
+    assert_eq!(identifiers, [Identifier(cache.get_or_cache("from_b"))]);
+    assert!(
+        matches!(errors[..], [TravelerErrorKind::IncludeDepthExceeded(1)]),
+        "{:?}",
+        errors
+    );
+}
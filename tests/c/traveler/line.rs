@@ -0,0 +1,183 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use std::path::Path;
+
+use vase::{
+    c::{
+        CompileEnv,
+        CompileSettings,
+        Lexer,
+        TokenKind,
+        Traveler,
+        TravelerError,
+        TravelerErrorKind,
+    },
+    error::render_diagnostic,
+    sync::Arc,
+    util::{
+        CachedString,
+        FileId,
+    },
+};
+
+use super::{
+    collect_errors,
+    run_test,
+};
+
+/// Travels through the source and returns the full errors that were reported.
+fn travel_errors(env: &CompileEnv, source: &str) -> Vec<TravelerError> {
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+    let mut lexer = Lexer::new(env, callback);
+    let tokens = Arc::new(lexer.lex_bytes(FileId::new(0).unwrap(), source.as_bytes()));
+    env.file_id_to_tokens.push(tokens.clone());
+
+    let mut errors = Vec::new();
+    let mut traveler = Traveler::new(env, |err: TravelerError| {
+        errors.push(err);
+        false
+    });
+    traveler.load_start(tokens).unwrap();
+    while !matches!(traveler.head().kind(), TokenKind::Eof) {
+        traveler.move_forward().unwrap();
+    }
+    drop(traveler);
+    errors
+}
+
+fn render_first(env: &CompileEnv, errors: &[TravelerError]) -> String {
+    let loc = errors[0]
+        .state
+        .mapped_loc()
+        .expect("The error should have a location.");
+    render_diagnostic(env, &errors[0], loc.clone())
+}
+
+#[test]
+fn line_directives_remap_diagnostics() {
+    let env = CompileEnv::default();
+    let errors = travel_errors(&env, "#line 100 \"foo.c\"\n#warning remapped\n");
+    assert_eq!(errors.len(), 1);
+    let rendered = render_first(&env, &errors);
+    assert!(rendered.starts_with("foo.c:100:1: "), "{}", rendered);
+}
+
+#[test]
+fn line_directives_without_a_file_name_keep_the_last_one() {
+    let env = CompileEnv::default();
+    let errors = travel_errors(
+        &env,
+        "#line 100 \"foo.c\"\n#line 20\n\n#warning remapped\n",
+    );
+    let rendered = render_first(&env, &errors);
+    assert!(rendered.starts_with("foo.c:21:1: "), "{}", rendered);
+}
+
+#[test]
+fn resolved_locations_use_the_directive_line() {
+    let env = CompileEnv::default();
+    let errors = travel_errors(
+        &env,
+        "#line 100\n\n  #warning remapped\n# 7 \"bar.c\"\n#warning marked\n",
+    );
+    let lines: Vec<_> = errors
+        .iter()
+        .map(|error| env.resolve_location(error.state.mapped_loc().unwrap().clone()))
+        .collect();
+    assert_eq!(lines, [
+        Some((None, 101, 3)),
+        Some((Some("bar.c".to_owned()), 7, 1))
+    ]);
+
+    // Plain locations don't know about the directives, so they aren't remapped.
+    let loc = errors[1].state.mapped_loc().unwrap().loc;
+    assert_eq!(env.resolve_location(loc), Some((None, 5, 1)));
+}
+
+#[test]
+fn lines_before_the_directive_are_not_remapped() {
+    let env = CompileEnv::default();
+    let errors = travel_errors(&env, "#warning physical\n#line 100\n");
+    let mapped = errors[0].state.mapped_loc().unwrap();
+    assert!(mapped.line_override.is_none());
+}
+
+#[test]
+fn builtin_macros_use_the_remapped_line() {
+    let env = CompileEnv::default();
+    let cache = env.cache();
+    run_test(&env, &["#line 50 \"foo.c\"\n__LINE__ __FILE__"], &[
+        TokenKind::Number(cache.get_or_cache("50")),
+        TokenKind::String {
+            encoding: vase::c::StringEnc::Default,
+            has_escapes: false,
+            is_char: false,
+            str_data: Arc::new(Box::from("foo.c")),
+        },
+    ]);
+}
+
+#[test]
+fn line_directive_operands_are_macro_expanded() {
+    let env = CompileEnv::default();
+    let errors = travel_errors(
+        &env,
+        "#define LINE 100\n#define FILE \"foo.c\"\n#line LINE FILE\n#warning remapped\n",
+    );
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    let rendered = render_first(&env, &errors);
+    assert!(rendered.starts_with("foo.c:100:1: "), "{}", rendered);
+}
+
+#[test]
+fn malformed_line_directives_are_reported() {
+    let env = CompileEnv::default();
+    let errors = collect_errors(&env, "#line x\n#line 0x10\n#line 1 \"a.c\" extra\n");
+    assert!(
+        matches!(errors[..], [
+            TravelerErrorKind::LineExpectedNumber(..),
+            TravelerErrorKind::LineInvalidNumber(..),
+            TravelerErrorKind::LineExtraTokens(..),
+        ]),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn gnu_line_markers_remap_diagnostics() {
+    let env = CompileEnv::default();
+    let errors = travel_errors(&env, "# 100 \"foo.c\" 1 3\n#warning remapped\n");
+    assert_eq!(errors.len(), 1);
+    let rendered = render_first(&env, &errors);
+    assert!(rendered.starts_with("foo.c:100:1: "), "{}", rendered);
+}
+
+#[test]
+fn gnu_line_markers_need_gnu_extensions() {
+    let env = CompileEnv::new(CompileSettings {
+        enable_gnu_extensions: false,
+        ..CompileSettings::default()
+    });
+    let errors = collect_errors(&env, "# 100 \"foo.c\"\n");
+    assert!(
+        matches!(errors[..], [TravelerErrorKind::UnknownPreprocessor(..)]),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn line_directives_do_not_take_flags() {
+    let env = CompileEnv::default();
+    let errors = collect_errors(&env, "#line 1 \"a.c\" 3\n# 1 \"a.c\" 5\n# 1 3\n");
+    assert!(
+        matches!(errors[..], [
+            TravelerErrorKind::LineExtraTokens(..),
+            TravelerErrorKind::LineExtraTokens(..),
+            TravelerErrorKind::LineExtraTokens(..),
+        ]),
+        "{:?}",
+        errors
+    );
+}
@@ -1,12 +1,40 @@
 // Copyright 2021. remilia-dev
 // This source code is licensed under GPLv3 or any later version.
-use vase::c::{
-    CompileEnv,
-    Keyword,
-    TokenKind::*,
+use std::{
+    path::Path,
+    time::Duration,
 };
 
-use super::run_test;
+use vase::{
+    c::{
+        CompileEnv,
+        CompileSettings,
+        Keyword,
+        Lexer,
+        StringEnc,
+        TokenKind::{
+            self,
+            *,
+        },
+        Traveler,
+        TravelerError,
+        TravelerErrorKind,
+    },
+    error::{
+        CodedError,
+        Severity,
+    },
+    sync::Arc,
+    util::{
+        CachedString,
+        FileId,
+    },
+};
+
+use super::{
+    collect_errors,
+    run_test,
+};
 
 #[test]
 fn object_macros_work() {
@@ -38,13 +66,56 @@ fn object_macros_work() {
             Identifier(cache.get_or_cache("single_macro")),
             Identifier(cache.get_or_cache("multiple")),
             Identifier(cache.get_or_cache("tokens")),
-            Keyword(Keyword::Long),
+            Keyword(Keyword::Long, cache.get_or_cache("long")),
             Identifier(cache.get_or_cache("X3")),
             Identifier(cache.get_or_cache("RECURSE")),
         ],
     );
 }
 
+#[test]
+fn alternate_keyword_spellings_are_separate_macros() {
+    let env = CompileEnv::default();
+    let cache = env.cache();
+    run_test(
+        &env,
+        &["#define __restrict
+__restrict restrict
+"],
+        &[Identifier(cache.get_or_cache("restrict"))],
+    );
+}
+
+#[test]
+fn brackets_across_a_macro_boundary_are_not_double_brackets() {
+    let env = CompileEnv::default();
+    run_test(
+        &env,
+        &["#define OPEN [\n#define ATTR [[\nOPEN[ ATTR\n"],
+        &[
+            LBracket { alt: false },
+            LBracket { alt: false },
+            LDoubleBracket,
+        ],
+    );
+}
+
+#[test]
+fn continued_macro_bodies_are_expanded_fully() {
+    let env = CompileEnv::default();
+    let cache = env.cache();
+    run_test(
+        &env,
+        &["#define SUM \\\n    1 + \\\n    2\nSUM after\n"],
+        &[
+            Number(cache.get_or_cache("1")),
+            Plus,
+            Number(cache.get_or_cache("2")),
+            Identifier(cache.get_or_cache("after")),
+        ],
+    );
+}
+
 #[test]
 fn function_macro_var_args_work() {
     let env = CompileEnv::default();
@@ -80,6 +151,95 @@ fn function_macro_var_args_work() {
     );
 }
 
+#[test]
+fn trailing_commas_give_an_empty_last_argument() {
+    let env = CompileEnv::default();
+    let cache = env.cache();
+    run_test(
+        &env,
+        &[r#"
+        #define F(A, B) [A|B]
+        F(a,)
+
+        #define G(A, ...) [A|__VA_ARGS__]
+        G(a,)
+        G(a, b,)
+        "#],
+        &[
+            // F(a,) produces:
+            LBracket { alt: false },
+            Identifier(cache.get_or_cache("a")),
+            Bar,
+            RBracket { alt: false },
+            // G(a,) produces:
+            LBracket { alt: false },
+            Identifier(cache.get_or_cache("a")),
+            Bar,
+            RBracket { alt: false },
+            // G(a, b,) produces:
+            LBracket { alt: false },
+            Identifier(cache.get_or_cache("a")),
+            Bar,
+            Identifier(cache.get_or_cache("b")),
+            Comma,
+            RBracket { alt: false },
+        ],
+    );
+}
+
+#[test]
+fn va_opt_is_kept_only_with_var_args() {
+    let env = CompileEnv::default();
+    let cache = env.cache();
+    run_test(
+        &env,
+        &[r#"
+        #define F(a, ...) a __VA_OPT__(,) __VA_ARGS__
+        F(1)
+        F(1, 2, 3)
+
+        #define G(a, ...) [__VA_OPT__(a (a)) b]
+        G(x)
+        G(x, y)
+        "#],
+        &[
+            // F(1) produces:
+            Number(cache.get_or_cache("1")),
+            // F(1, 2, 3) produces:
+            Number(cache.get_or_cache("1")),
+            Comma,
+            Number(cache.get_or_cache("2")),
+            Comma,
+            Number(cache.get_or_cache("3")),
+            // G(x) produces:
+            LBracket { alt: false },
+            Identifier(cache.get_or_cache("b")),
+            RBracket { alt: false },
+            // G(x, y) produces:
+            LBracket { alt: false },
+            Identifier(cache.get_or_cache("x")),
+            LParen,
+            Identifier(cache.get_or_cache("x")),
+            RParen,
+            Identifier(cache.get_or_cache("b")),
+            RBracket { alt: false },
+        ],
+    );
+}
+
+#[test]
+fn va_opt_is_not_a_parameter_name() {
+    let env = CompileEnv::default();
+    let errors = collect_errors(&env, "#define F(__VA_OPT__) __VA_OPT__\n");
+    assert!(
+        matches!(*errors.as_slice(), [
+            TravelerErrorKind::DefineFuncExpectedArg(..)
+        ]),
+        "{:?}",
+        errors
+    );
+}
+
 #[test]
 fn partial_function_macro_invocations_work() {
     let env = CompileEnv::default();
@@ -145,7 +305,668 @@ fn can_undef_macros() {
             Identifier(cache.get_or_cache("FUNCTION")),
             LParen,
             RParen,
-            Keyword(Keyword::Int),
+            Keyword(Keyword::Int, cache.get_or_cache("int")),
+        ],
+    );
+}
+
+#[test]
+fn mismatched_redefinition_notes_original_definition() {
+    let env = CompileEnv::default();
+    let source = "#define FOO 1\n#define FOO 2\n";
+    let errors = collect_errors(&env, source);
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    let error = &errors[0];
+    assert!(
+        matches!(*error, TravelerErrorKind::MacroRedefinitionMismatch(..)),
+        "{:?}",
+        error
+    );
+    assert_eq!(error.severity(), Severity::Warning);
+
+    let notes = error.notes();
+    assert_eq!(notes.len(), 1);
+    // The note should point at the FOO of the first definition.
+    assert_eq!(notes[0].0.byte, source.find("FOO").unwrap() as u32);
+}
+
+#[test]
+fn identical_redefinitions_are_allowed() {
+    let env = CompileEnv::default();
+    let errors = collect_errors(
+        &env,
+        r#"
+        #define EMPTY
+        #define EMPTY
+        #define SINGLE 1
+        #define SINGLE 1
+        #define OBJECT a + b
+        #define OBJECT a  +  b
+        #define FUNC(x, ...) x(__VA_ARGS__)
+        #define FUNC(x, ...) x(__VA_ARGS__)
+        "#,
+    );
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+#[test]
+fn redefinitions_with_different_whitespace_or_params_mismatch() {
+    let env = CompileEnv::default();
+    let errors = collect_errors(
+        &env,
+        r#"
+        #define OBJECT a+b
+        #define OBJECT a + b
+        #define FUNC(x) x
+        #define FUNC(y) y
+        "#,
+    );
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+}
+
+#[test]
+fn redefinitions_in_system_headers_are_allowed() {
+    let env = CompileEnv::default();
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+    let mut lexer = Lexer::new(&env, callback);
+    let mut tokens = lexer.lex_bytes(
+        FileId::new(0).unwrap(),
+        b"#define FOO 1\n#define FOO 2\nFOO\n",
+    );
+    tokens.set_system_header(true);
+    let tokens = Arc::new(tokens);
+    env.file_id_to_tokens.push(tokens.clone());
+
+    let mut traveler = Traveler::new(&env, |err: TravelerError| -> bool {
+        panic!("An error should not have occured: {:?}", err);
+    });
+    traveler.load_start(tokens).unwrap();
+    assert_eq!(
+        traveler.head().kind(),
+        &Number(env.cache().get_or_cache("2"))
+    );
+}
+
+#[test]
+fn mutually_recursive_macros_stop_expanding() {
+    let env = CompileEnv::default();
+    let cache = env.cache();
+    run_test(
+        &env,
+        &["#define A(x) B(x)\n#define B(x) A(x)\nA(1)\n"],
+        &[
+            Identifier(cache.get_or_cache("A")),
+            LParen,
+            Number(cache.get_or_cache("1")),
+            RParen,
+        ],
+    );
+}
+
+#[test]
+fn deep_macro_expansions_hit_the_depth_limit() {
+    let env = CompileEnv::new(CompileSettings {
+        max_macro_depth: 8,
+        ..CompileSettings::default()
+    });
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+    let mut lexer = Lexer::new(&env, callback);
+    let source = format!("#define F(x) x\n{}1{}\n", "F(".repeat(8), ")".repeat(8));
+    let tokens = Arc::new(lexer.lex_bytes(FileId::new(0).unwrap(), source.as_bytes()));
+    env.file_id_to_tokens.push(tokens.clone());
+
+    let mut errors = Vec::new();
+    let mut traveler = Traveler::new(&env, |err: TravelerError| {
+        errors.push(err.kind);
+        false
+    });
+    traveler.load_start(tokens).unwrap();
+    assert_eq!(
+        traveler.head().kind(),
+        &Number(env.cache().get_or_cache("1"))
+    );
+
+    let source = format!("#define F(x) x\n{}1{}\n", "F(".repeat(9), ")".repeat(9));
+    let tokens = Arc::new(lexer.lex_bytes(FileId::new(0).unwrap(), source.as_bytes()));
+    assert!(traveler.load_start(tokens).is_err());
+    drop(traveler);
+    assert!(
+        matches!(*errors.as_slice(), [
+            TravelerErrorKind::MacroExpansionDepthExceeded(8)
+        ]),
+        "{:?}",
+        errors
+    );
+}
+
+/// Defines F0 through F{count - 1} so that each expands to the next (and the last expands to its
+/// argument). Then F0(1) is invoked twice.
+fn chained_macros_source(count: usize) -> Vec<u8> {
+    let mut source: Vec<_> = (1..count)
+        .map(|i| format!("#define F{}(x) F{}(x)\n", i - 1, i))
+        .collect();
+    source.push(format!("#define F{}(x) x\nF0(1) F0(1)\n", count - 1));
+    source.concat().into_bytes()
+}
+
+#[test]
+fn macros_chained_through_their_bodies_hit_the_depth_limit() {
+    let env = CompileEnv::new(CompileSettings {
+        max_macro_depth: 8,
+        ..CompileSettings::default()
+    });
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+    let mut lexer = Lexer::new(&env, callback);
+    let source = chained_macros_source(8);
+    let tokens = Arc::new(lexer.lex_bytes(FileId::new(0).unwrap(), &source));
+    env.file_id_to_tokens.push(tokens.clone());
+
+    let mut errors = Vec::new();
+    let mut traveler = Traveler::new(&env, |err: TravelerError| {
+        errors.push(err.kind);
+        false
+    });
+    traveler.load_start(tokens).unwrap();
+    assert_eq!(
+        traveler.head().kind(),
+        &Number(env.cache().get_or_cache("1"))
+    );
+    // Leaving the first expansion lowers the depth again for the second one.
+    assert_eq!(
+        traveler.move_forward().unwrap().kind(),
+        &Number(env.cache().get_or_cache("1"))
+    );
+    assert_eq!(traveler.move_forward().unwrap().kind(), &Eof);
+
+    let source = chained_macros_source(9);
+    let tokens = Arc::new(lexer.lex_bytes(FileId::new(0).unwrap(), &source));
+    assert!(traveler.load_start(tokens).is_err());
+    drop(traveler);
+    assert!(
+        matches!(*errors.as_slice(), [
+            TravelerErrorKind::MacroExpansionDepthExceeded(8)
+        ]),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn slow_travels_hit_the_deadline() {
+    let env = CompileEnv::new(CompileSettings {
+        deadline: Some(Duration::from_nanos(1)),
+        ..CompileSettings::default()
+    });
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+    let mut lexer = Lexer::new(&env, callback);
+    let mut source = "#define A x x x x x x x x
+#define B A A A A A A A A
+#define C B B B B B B B B
+#define D C C C C C C C C
+"
+    .to_owned();
+    source.push_str(&"D\n".repeat(32));
+    let tokens = Arc::new(lexer.lex_bytes(FileId::new(0).unwrap(), source.as_bytes()));
+    env.file_id_to_tokens.push(tokens.clone());
+
+    let mut errors = Vec::new();
+    let mut traveler = Traveler::new(&env, |err: TravelerError| {
+        errors.push(err.kind);
+        false
+    });
+    let mut result = traveler.load_start(tokens);
+    while result.is_ok() && !matches!(traveler.head().kind(), Eof) {
+        result = traveler.move_forward().map(|_| ());
+    }
+    assert!(result.is_err());
+    drop(traveler);
+    assert!(
+        matches!(*errors.as_slice(), [
+            TravelerErrorKind::TimeLimitExceeded(..)
+        ]),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn object_macros_expand_to_strings() {
+    let env = CompileEnv::default();
+    let cache = env.cache();
+    let source = "#define A B\n#define B 42\n#define SUM 1 + A\n#define F(x) x\n";
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+    let mut lexer = Lexer::new(&env, callback);
+    let tokens = Arc::new(lexer.lex_bytes(FileId::new(0).unwrap(), source.as_bytes()));
+
+    let mut traveler = Traveler::new(&env, |err: TravelerError| -> bool {
+        panic!("An error should not have occured: {:?}", err);
+    });
+    traveler.load_start(tokens).unwrap();
+
+    let mut expand = |name: &str| traveler.expand_macro_to_string(&cache.get_or_cache(name));
+    assert_eq!(expand("A").as_deref(), Some("42"));
+    assert_eq!(expand("SUM").as_deref(), Some("1 + 42"));
+    assert_eq!(expand("F"), None);
+    assert_eq!(expand("UNDEFINED"), None);
+    // Expanding shouldn't move the traveler.
+    assert_eq!(traveler.head().kind(), &Eof);
+}
+
+fn warn_unused_params_env() -> CompileEnv {
+    CompileEnv::new(CompileSettings {
+        warn_unused_macro_params: true,
+        ..CompileSettings::default()
+    })
+}
+
+#[test]
+fn unused_macro_parameters_are_reported() {
+    let env = warn_unused_params_env();
+    let errors = collect_errors(&env, "#define F(a, b) a\n");
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    match errors[0] {
+        TravelerErrorKind::UnusedMacroParameter(ref id, ref param) => {
+            assert_eq!(id.string(), "F");
+            assert_eq!(param.string(), "b");
+        },
+        ref error => panic!("Expected an unused parameter warning, found {:?}", error),
+    }
+}
+
+#[test]
+fn stringified_and_joined_macro_parameters_are_used() {
+    let env = warn_unused_params_env();
+    let errors = collect_errors(&env, "#define F(a, b, c) #a b ## c\n");
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+#[test]
+fn unused_macro_parameters_in_system_headers_are_allowed() {
+    let env = warn_unused_params_env();
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+    let mut lexer = Lexer::new(&env, callback);
+    let mut tokens = lexer.lex_bytes(FileId::new(0).unwrap(), b"#define UNUSED(x)\nend");
+    tokens.set_system_header(true);
+    let tokens = Arc::new(tokens);
+    env.file_id_to_tokens.push(tokens.clone());
+
+    let mut traveler = Traveler::new(&env, |err: TravelerError| -> bool {
+        panic!("An error should not have occured: {:?}", err);
+    });
+    traveler.load_start(tokens).unwrap();
+    assert_eq!(
+        traveler.head().kind(),
+        &Identifier(env.cache().get_or_cache("end"))
+    );
+}
+
+#[test]
+fn fatal_unused_parameter_warnings_still_define_the_macro() {
+    let env = warn_unused_params_env();
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+    let mut lexer = Lexer::new(&env, callback);
+    let tokens = Arc::new(lexer.lex_bytes(FileId::new(0).unwrap(), b"#define F(a, b) a\nend"));
+    let expr = lexer.lex_bytes(FileId::new(1).unwrap(), b"defined(F)");
+    // Exclude the Eof token.
+    let expr = &expr[..expr.len() - 1];
+    env.file_id_to_tokens.push(tokens.clone());
+
+    let mut traveler = Traveler::new(&env, |err: TravelerError| -> bool {
+        matches!(err.kind, TravelerErrorKind::UnusedMacroParameter(..))
+    });
+    assert!(traveler.load_start(tokens).is_err());
+    assert_eq!(traveler.eval_pp_expr(expr).unwrap(), 1);
+}
+
+fn predefined_env() -> CompileEnv {
+    CompileEnv::new(CompileSettings {
+        defines: vec![
+            ("FLAG".to_owned(), None),
+            ("VALUE".to_owned(), Some("value".to_owned())),
+            ("ID(x)".to_owned(), Some("x".to_owned())),
+        ],
+        ..CompileSettings::default()
+    })
+}
+
+#[test]
+fn predefined_macros_are_expanded() {
+    let env = predefined_env();
+    let cache = env.cache();
+    run_test(&env, &["#if FLAG == 1\nVALUE ID(arg)\n#endif\n"], &[
+        Identifier(cache.get_or_cache("value")),
+        Identifier(cache.get_or_cache("arg")),
+    ]);
+}
+
+#[test]
+fn predefined_macros_can_be_redefined() {
+    let env = predefined_env();
+    let cache = env.cache();
+    run_test(
+        &env,
+        &["#undef VALUE\n#define VALUE other\nVALUE\n#undef FLAG\nFLAG\n"],
+        &[
+            Identifier(cache.get_or_cache("other")),
+            Identifier(cache.get_or_cache("FLAG")),
         ],
     );
 }
+
+#[test]
+fn predefined_macros_are_lexed_once_per_env() {
+    let env = predefined_env();
+    let first = env.predefined_tokens().unwrap().unwrap();
+    let second = env.predefined_tokens().unwrap().unwrap();
+    assert!(Arc::ptr_eq(&first, &second));
+    assert_eq!(env.file_id_to_tokens.len(), 1);
+}
+
+#[test]
+fn predefined_macros_follow_a_reconfigured_env() {
+    let env = predefined_env();
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+    let tokens = Lexer::new(&env, callback).lex_bytes(FileId::new(0).unwrap(), b"VALUE");
+    let tokens = Arc::new(tokens);
+    env.file_id_to_tokens.push(tokens.clone());
+    let mut traveler = Traveler::new(&env, |err: TravelerError| {
+        panic!("An error should not have occured: {:?}", err);
+    });
+
+    let mut travel = |defines| {
+        env.reconfigure(CompileSettings {
+            defines,
+            ..CompileSettings::default()
+        });
+        traveler.load_start(tokens.clone()).unwrap();
+        traveler.head().kind().clone()
+    };
+    let value = |defined: &str| Some(defined.to_owned());
+    let other = travel(vec![("VALUE".to_owned(), value("other"))]);
+    assert_eq!(other, Identifier(env.cache().get_or_cache("other")));
+    // The same defines aren't lexed again.
+    let ids = env.file_id_to_tokens.len();
+    let other = travel(vec![("VALUE".to_owned(), value("other"))]);
+    assert_eq!(other, Identifier(env.cache().get_or_cache("other")));
+    assert_eq!(env.file_id_to_tokens.len(), ids);
+    let undefined = travel(Vec::new());
+    assert_eq!(undefined, Identifier(env.cache().get_or_cache("VALUE")));
+}
+
+#[test]
+fn builtin_macros_expand_where_they_are_used() {
+    let env = CompileEnv::default();
+    let cache = env.cache();
+    let string = |text: &str| String {
+        encoding: StringEnc::Default,
+        has_escapes: false,
+        is_char: false,
+        str_data: Arc::new(Box::from(text)),
+    };
+    run_test(
+        &env,
+        &[
+            "#define LINE __LINE__\n#define STR(x) #x\n#define XSTR(x) STR(x)\n\
+           LINE\n__LINE__ XSTR(__LINE__)\n__FILE__ __COUNTER__ __COUNTER__\n\
+           #ifdef __COUNTER__\nyes\n#endif\n",
+        ],
+        &[
+            Number(cache.get_or_cache("4")),
+            Number(cache.get_or_cache("5")),
+            string("5"),
+            string("<unknown>"),
+            Number(cache.get_or_cache("0")),
+            Number(cache.get_or_cache("1")),
+            Identifier(cache.get_or_cache("yes")),
+        ],
+    );
+}
+
+#[test]
+fn include_level_is_how_deeply_the_file_is_included() {
+    let env = CompileEnv::default();
+    let cache = env.cache();
+    // Every include is of the second source, so it includes itself once more.
+    run_test(
+        &env,
+        &[
+            "__INCLUDE_LEVEL__\n#include \"header.h\"\n__INCLUDE_LEVEL__\n",
+            "#ifndef NESTED\n#define NESTED\n__INCLUDE_LEVEL__\n#include \"header.h\"\n\
+             #else\n__INCLUDE_LEVEL__\n#endif\n",
+        ],
+        &[
+            Number(cache.get_or_cache("0")),
+            Number(cache.get_or_cache("1")),
+            Number(cache.get_or_cache("2")),
+            Number(cache.get_or_cache("0")),
+        ],
+    );
+}
+
+#[test]
+fn date_and_time_macros_expand_to_the_start_of_compilation() {
+    let env = CompileEnv::default();
+    let string = |str_data: &Arc<Box<str>>| String {
+        encoding: StringEnc::Default,
+        has_escapes: false,
+        is_char: false,
+        str_data: str_data.clone(),
+    };
+    run_test(&env, &["__DATE__ __TIME__ __DATE__\n"], &[
+        string(env.start_date()),
+        string(env.start_time()),
+        string(env.start_date()),
+    ]);
+    // Like "Jan  1 1970" and "00:00:00".
+    assert_eq!(env.start_date().len(), 11);
+    assert_eq!(env.start_time().len(), 8);
+}
+
+#[test]
+fn loading_a_state_restores_the_counter() {
+    let env = CompileEnv::default();
+    let cache = env.cache();
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+    let mut lexer = Lexer::new(&env, callback);
+    let tokens = lexer.lex_bytes(
+        FileId::new(0).unwrap(),
+        b"__COUNTER__ __COUNTER__ __COUNTER__",
+    );
+    env.file_id_to_tokens.push(Arc::new(tokens));
+
+    let mut traveler = Traveler::new(&env, |err: TravelerError| {
+        panic!("An error should not have occured: {:?}", err);
+    });
+    traveler
+        .load_start(env.file_id_to_tokens.get_arc(FileId::new(0).unwrap()).unwrap())
+        .unwrap();
+    let start = traveler.save_state();
+    let expected = [
+        Number(cache.get_or_cache("0")),
+        Number(cache.get_or_cache("1")),
+        Number(cache.get_or_cache("2")),
+        Eof,
+    ];
+    for _ in 0..2 {
+        let mut travelled: Vec<TokenKind> = vec![traveler.head().kind().clone()];
+        while !matches!(*traveler.head().kind(), Eof) {
+            travelled.push(traveler.move_forward().unwrap().kind().clone());
+        }
+        assert_eq!(travelled, expected);
+        traveler.load_state(start.clone());
+    }
+}
+
+#[test]
+fn the_counter_is_shared_by_every_traveler() {
+    let env = CompileEnv::default();
+    let cache = env.cache();
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+    let mut lexer = Lexer::new(&env, callback);
+    let tokens = Arc::new(lexer.lex_bytes(FileId::new(0).unwrap(), b"__COUNTER__ __COUNTER__"));
+    env.file_id_to_tokens.push(tokens.clone());
+
+    let mut travelled = Vec::new();
+    for _ in 0..2 {
+        let mut traveler = Traveler::new(&env, |err: TravelerError| {
+            panic!("An error should not have occured: {:?}", err);
+        });
+        traveler.load_start(tokens.clone()).unwrap();
+        while !matches!(*traveler.head().kind(), Eof) {
+            travelled.push(traveler.head().kind().clone());
+            traveler.move_forward().unwrap();
+        }
+    }
+    assert_eq!(travelled, [
+        Number(cache.get_or_cache("0")),
+        Number(cache.get_or_cache("1")),
+        Number(cache.get_or_cache("2")),
+        Number(cache.get_or_cache("3")),
+    ]);
+}
+
+#[test]
+fn loading_a_state_does_not_rewind_the_shared_counter() {
+    let env = CompileEnv::default();
+    let cache = env.cache();
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+    let mut lexer = Lexer::new(&env, callback);
+    let tokens = Arc::new(lexer.lex_bytes(FileId::new(0).unwrap(), b"__COUNTER__ __COUNTER__"));
+    env.file_id_to_tokens.push(tokens.clone());
+
+    let travel = |traveler: &mut Traveler<_>| {
+        let mut travelled = Vec::new();
+        while !matches!(*traveler.head().kind(), Eof) {
+            travelled.push(traveler.head().kind().clone());
+            traveler.move_forward().unwrap();
+        }
+        travelled
+    };
+    let new_traveler = || {
+        Traveler::new(&env, |err: TravelerError| {
+            panic!("An error should not have occured: {:?}", err);
+        })
+    };
+    let mut first = new_traveler();
+    first.load_start(tokens.clone()).unwrap();
+    let start = first.save_state();
+    travel(&mut first);
+    first.load_state(start);
+
+    let mut second = new_traveler();
+    second.load_start(tokens).unwrap();
+    let number = |text: &str| Number(cache.get_or_cache(text));
+    assert_eq!(travel(&mut second), [number("2"), number("3")]);
+    assert_eq!(travel(&mut first), [number("0"), number("1")]);
+}
+
+#[test]
+fn previewed_expansions_match_actual_expansions() {
+    let env = CompileEnv::default();
+    let cache = env.cache();
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+    let mut lexer = Lexer::new(&env, callback);
+    let tokens = lexer.lex_bytes(
+        FileId::new(0).unwrap(),
+        b"#define ADD(a, b) ((a) + (b))\n#define ONE 1\nstart ADD(ONE, ADD(2, 3)) end",
+    );
+    env.file_id_to_tokens.push(Arc::new(tokens));
+
+    let mut traveler = Traveler::new(&env, |err: TravelerError| {
+        panic!("An error should not have occured: {:?}", err);
+    });
+    traveler
+        .load_start(env.file_id_to_tokens.get_arc(FileId::new(0).unwrap()).unwrap())
+        .unwrap();
+    assert_eq!(
+        traveler.head().kind(),
+        &Identifier(cache.get_or_cache("start"))
+    );
+    let state = traveler.save_state_before_next();
+
+    let preview = traveler
+        .preview_expansion_at(&state)
+        .expect("ADD(...) should be previewed.");
+    // The preview shouldn't move the traveler.
+    assert_eq!(
+        traveler.head().kind(),
+        &Identifier(cache.get_or_cache("start"))
+    );
+
+    let mut actual = Vec::new();
+    while !matches!(*traveler.move_forward().unwrap().kind(), Identifier(ref id) if id.string() == "end")
+    {
+        actual.push(traveler.head().kind().clone());
+    }
+    let preview: Vec<TokenKind> = preview.iter().map(|token| token.kind().clone()).collect();
+    assert_eq!(preview, actual);
+    // ((1) + (((2) + (3))))
+    assert_eq!(preview.len(), 17);
+
+    // The end isn't a macro, so it can't be previewed.
+    let state = traveler.save_state();
+    assert!(traveler.preview_expansion_at(&state).is_none());
+}
+
+#[test]
+fn only_macro_invocations_can_be_previewed() {
+    let env = CompileEnv::default();
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+    let mut lexer = Lexer::new(&env, callback);
+    let tokens = lexer.lex_bytes(
+        FileId::new(0).unwrap(),
+        b"#define F(a, b) a\nstart (F) F x F(1) F(1, 2",
+    );
+    env.file_id_to_tokens.push(Arc::new(tokens));
+
+    // The preview's errors (like F(1) missing an argument) shouldn't be reported.
+    let mut traveler = Traveler::new(&env, |err: TravelerError| {
+        panic!("An error should not have occured: {:?}", err);
+    });
+    traveler
+        .load_start(env.file_id_to_tokens.get_arc(FileId::new(0).unwrap()).unwrap())
+        .unwrap();
+    // Every token is previewed without the traveler expanding any of them.
+    let mut previews = Vec::new();
+    while !matches!(*traveler.head().kind(), Eof) {
+        let state = traveler.save_state_before_next();
+        previews.push(traveler.preview_expansion_at(&state));
+        traveler.load_state(state);
+    }
+    assert_eq!(previews.len(), 15);
+    assert!(previews.iter().all(Option::is_none), "{:?}", previews);
+}
+
+#[test]
+fn builtin_macros_can_be_disabled() {
+    let env = CompileEnv::new(CompileSettings {
+        builtin_macros: false,
+        ..CompileSettings::default()
+    });
+    let cache = env.cache();
+    run_test(&env, &["__LINE__ __COUNTER__\n"], &[
+        Identifier(cache.get_or_cache("__LINE__")),
+        Identifier(cache.get_or_cache("__COUNTER__")),
+    ]);
+}
+
+#[test]
+fn used_macros_are_tracked() {
+    let env = CompileEnv::default();
+    let source = "#define FOO 1\n#define BAR 2\nFOO FOO FOO\n";
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+    let mut lexer = Lexer::new(&env, callback);
+    let tokens = Arc::new(lexer.lex_bytes(FileId::new(0).unwrap(), source.as_bytes()));
+
+    let mut traveler = Traveler::new(&env, |err: TravelerError| -> bool {
+        panic!("An error should not have occured: {:?}", err);
+    });
+    traveler.load_start(tokens).unwrap();
+    while traveler.head().kind() != &Eof {
+        traveler.move_forward().unwrap();
+    }
+
+    let used: Vec<CachedString> = traveler.used_macro_names().collect();
+    assert_eq!(used.len(), 1);
+    assert_eq!(used[0].string(), "FOO");
+}
@@ -2,7 +2,9 @@
 // This source code is licensed under GPLv3 or any later version.
 mod conditional;
 mod include;
+mod line;
 mod macros;
+mod pragma;
 mod token_joining;
 
 use std::path::Path;
@@ -14,11 +16,14 @@ use vase::{
         TokenKind,
         Traveler,
         TravelerError,
+        TravelerErrorKind,
     },
     error::CodedError,
-    math::NonMaxU32,
     sync::Arc,
-    util::CachedString,
+    util::{
+        CachedString,
+        FileId,
+    },
 };
 
 fn run_test(env: &CompileEnv, sources: &[&str], expected: &[TokenKind]) {
@@ -28,10 +33,10 @@ fn run_test(env: &CompileEnv, sources: &[&str], expected: &[TokenKind]) {
         );
     }
 
-    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| Some(1.into());
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| Some(FileId::new(1).unwrap());
     let mut lexer = Lexer::new(&env, callback);
     for (i, source) in sources.iter().enumerate() {
-        let file_id = NonMaxU32::new(i as u32).unwrap();
+        let file_id = FileId::new(i as u32).unwrap();
         let tokens = lexer.lex_bytes(file_id, source.as_bytes());
         env.file_id_to_tokens.push(Arc::new(tokens));
     }
@@ -44,7 +49,7 @@ fn run_test(env: &CompileEnv, sources: &[&str], expected: &[TokenKind]) {
         );
     });
     traveler
-        .load_start(env.file_id_to_tokens.get_arc(0.into()).unwrap())
+        .load_start(env.file_id_to_tokens.get_arc(FileId::new(0).unwrap()).unwrap())
         .unwrap();
 
     for expected_token in expected.iter() {
@@ -54,3 +59,23 @@ fn run_test(env: &CompileEnv, sources: &[&str], expected: &[TokenKind]) {
 
     assert_eq!(traveler.head().kind(), &TokenKind::Eof);
 }
+
+/// Travels through the given source and returns the kinds of all the errors that were reported.
+fn collect_errors(env: &CompileEnv, source: &str) -> Vec<TravelerErrorKind> {
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| None;
+    let mut lexer = Lexer::new(env, callback);
+    let tokens = Arc::new(lexer.lex_bytes(FileId::new(0).unwrap(), source.as_bytes()));
+    env.file_id_to_tokens.push(tokens.clone());
+
+    let mut errors = Vec::new();
+    let mut traveler = Traveler::new(env, |err: TravelerError| {
+        errors.push(err.kind);
+        false
+    });
+    traveler.load_start(tokens).unwrap();
+    while !matches!(traveler.head().kind(), TokenKind::Eof) {
+        traveler.move_forward().unwrap();
+    }
+    drop(traveler);
+    errors
+}
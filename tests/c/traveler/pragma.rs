@@ -0,0 +1,285 @@
+// Copyright 2021. remilia-dev
+// This source code is licensed under GPLv3 or any later version.
+use std::path::Path;
+
+use vase::{
+    c::{
+        CompileEnv,
+        Lexer,
+        TokenKind::{
+            self,
+            *,
+        },
+        Traveler,
+        TravelerError,
+        TravelerErrorKind,
+    },
+    error::{
+        CodedError,
+        Severity,
+    },
+    sync::Arc,
+    util::{
+        CachedString,
+        FileId,
+    },
+};
+
+use super::{
+    collect_errors,
+    run_test,
+};
+
+#[test]
+fn pragma_once_prevents_reinclusion() {
+    let env = CompileEnv::default();
+    let cache = env.cache();
+    run_test(
+        &env,
+        &[
+            "#include \"once\"\n#include \"once\"\nafter",
+            "#pragma once\nincluded",
+        ],
+        &[
+            Identifier(cache.get_or_cache("included")),
+            Identifier(cache.get_or_cache("after")),
+        ],
+    );
+}
+
+#[test]
+fn loading_a_state_restores_pragma_once_files() {
+    let env = CompileEnv::default();
+    let cache = env.cache();
+    let callback = |_, _: &CachedString, _: &Option<Arc<Path>>| Some(FileId::new(1).unwrap());
+    let mut lexer = Lexer::new(&env, callback);
+    let sources = [
+        "before\n#include \"once\"\n#include \"once\"\nafter",
+        "#pragma once\nincluded",
+    ];
+    for (i, source) in sources.iter().enumerate() {
+        let tokens = lexer.lex_bytes(FileId::new_usize(i).unwrap(), source.as_bytes());
+        env.file_id_to_tokens.push(Arc::new(tokens));
+    }
+
+    let mut traveler = Traveler::new(&env, |err: TravelerError| {
+        panic!("An error should not have occured: {:?}", err);
+    });
+    traveler
+        .load_start(env.file_id_to_tokens.get_arc(FileId::new(0).unwrap()).unwrap())
+        .unwrap();
+    let start = traveler.save_state();
+    let expected = [
+        Identifier(cache.get_or_cache("before")),
+        Identifier(cache.get_or_cache("included")),
+        Identifier(cache.get_or_cache("after")),
+        Eof,
+    ];
+    for _ in 0..2 {
+        let mut travelled: Vec<TokenKind> = vec![traveler.head().kind().clone()];
+        while !matches!(*traveler.head().kind(), Eof) {
+            travelled.push(traveler.move_forward().unwrap().kind().clone());
+        }
+        assert_eq!(travelled, expected);
+        traveler.load_state(start.clone());
+    }
+}
+
+#[test]
+fn pragma_operator_acts_like_pragma() {
+    let env = CompileEnv::default();
+    let cache = env.cache();
+    run_test(
+        &env,
+        &[
+            "#include \"once\"\n#include \"once\"\nafter",
+            "_Pragma(\"once\") included",
+        ],
+        &[
+            Identifier(cache.get_or_cache("included")),
+            Identifier(cache.get_or_cache("after")),
+        ],
+    );
+}
+
+#[test]
+fn unknown_pragmas_are_ignored() {
+    let env = CompileEnv::default();
+    let cache = env.cache();
+    run_test(
+        &env,
+        &[r#"
+        #pragma GCC optimize("O3")
+        _Pragma("GCC optimize(\"O3\")") a
+        #define OPTIMIZE _Pragma("GCC optimize(\"O3\")")
+        OPTIMIZE b
+        "#],
+        &[
+            Identifier(cache.get_or_cache("a")),
+            Identifier(cache.get_or_cache("b")),
+        ],
+    );
+}
+
+#[test]
+fn malformed_pragma_operators_are_reported() {
+    let env = CompileEnv::default();
+    let errors = collect_errors(&env, "_Pragma once");
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(
+        errors[0],
+        TravelerErrorKind::PragmaExpectedLParen(..)
+    ));
+    let errors = collect_errors(&env, "_Pragma(once)");
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(
+        errors[0],
+        TravelerErrorKind::PragmaExpectedString(..)
+    ));
+    let errors = collect_errors(&env, "_Pragma(\"once\" x");
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(
+        errors[0],
+        TravelerErrorKind::PragmaExpectedRParen(..)
+    ));
+}
+
+#[test]
+fn pragma_messages_are_reported_as_notes() {
+    let env = CompileEnv::default();
+    for source in &[
+        "#pragma message(\"hello world\")\n",
+        "#pragma message \"hello world\"\n",
+        "_Pragma(\"message(\\\"hello world\\\")\")",
+    ] {
+        let errors = collect_errors(&env, source);
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert_eq!(errors[0].severity(), Severity::Note);
+        assert!(matches!(
+            errors[0],
+            TravelerErrorKind::MessagePragma(ref message) if &***message == "hello world"
+        ));
+    }
+
+    let errors = collect_errors(&env, "#pragma message(hello)\n");
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(
+        errors[0],
+        TravelerErrorKind::MessagePragmaExpectedString
+    ));
+}
+
+#[test]
+fn pragma_messages_join_adjacent_literals_and_keep_escapes() {
+    let env = CompileEnv::default();
+    for source in &[
+        "#pragma message(\"a \\\"quoted\\\" \" \"message)\")\n",
+        "_Pragma(\"message(\\\"a \\\\\\\"quoted\\\\\\\" \\\" \\\"message)\\\")\")",
+    ] {
+        let errors = collect_errors(&env, source);
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert!(
+            matches!(
+                errors[0],
+                TravelerErrorKind::MessagePragma(ref message)
+                    if &***message == "a \\\"quoted\\\" message)"
+            ),
+            "{:?}",
+            errors
+        );
+    }
+
+    let errors = collect_errors(&env, "#pragma message(\"unclosed\"\n");
+    assert!(
+        matches!(errors[..], [
+            TravelerErrorKind::MessagePragmaExpectedString
+        ]),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn only_the_message_pragma_is_a_message() {
+    let env = CompileEnv::default();
+    let errors = collect_errors(&env, "#pragma messages_x(1)\n_Pragma(\"message_y\")\n");
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+#[test]
+fn lines_after_a_message_are_not_skipped() {
+    let env = CompileEnv::default();
+    let errors = collect_errors(&env, "#warning first\n#warning second\n");
+    assert!(
+        matches!(errors[..], [
+            TravelerErrorKind::WarningPreprocessor(Some(ref first)),
+            TravelerErrorKind::WarningPreprocessor(Some(ref second)),
+        ] if &***first == "first" && &***second == "second"),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn lexer_errors_in_pragma_operators_are_reported() {
+    let env = CompileEnv::default();
+    let errors = collect_errors(&env, "_Pragma(\"message(\\\"unended)\")\n");
+    assert!(
+        matches!(errors[..], [
+            TravelerErrorKind::Lexer(..),
+            TravelerErrorKind::MessagePragmaExpectedString,
+        ]),
+        "{:?}",
+        errors
+    );
+}
+
+#[test]
+fn pragma_operators_with_the_same_content_share_a_file() {
+    let env = CompileEnv::default();
+    let source = "#define HELLO _Pragma(\"message(\\\"hello\\\")\")\nHELLO HELLO HELLO\n";
+    let errors = collect_errors(&env, source);
+    assert_eq!(errors.len(), 3, "{:?}", errors);
+    // The source and the single lexed _Pragma content.
+    assert_eq!(env.file_id_to_tokens.iter().count(), 2);
+}
+
+#[test]
+fn popping_a_macro_restores_its_pushed_definition() {
+    let env = CompileEnv::default();
+    let cache = env.cache();
+    let number = |text: &str| Number(cache.get_or_cache(text));
+    run_test(
+        &env,
+        &[
+            "#define X 1\n#pragma push_macro(\"X\")\n#undef X\n#define X 2\nX\n\
+           _Pragma(\"pop_macro(\\\"X\\\")\")\nX\n",
+        ],
+        &[number("2"), number("1")],
+    );
+    // A macro that was undefined when it was pushed is undefined again when popped.
+    let env = CompileEnv::default();
+    let cache = env.cache();
+    let number = |text: &str| Number(cache.get_or_cache(text));
+    run_test(
+        &env,
+        &["#pragma push_macro(\"Y\")\n#define Y 1\nY\n#pragma pop_macro(\"Y\")\nY\n"],
+        &[number("1"), Identifier(cache.get_or_cache("Y"))],
+    );
+}
+
+#[test]
+fn popping_more_than_was_pushed_warns() {
+    let env = CompileEnv::default();
+    let errors = collect_errors(
+        &env,
+        "#define X 1\n#pragma push_macro(\"X\")\n#pragma pop_macro(\"X\")\n\
+         #pragma pop_macro(\"X\")\n",
+    );
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert_eq!(errors[0].severity(), Severity::Warning);
+    assert!(matches!(
+        errors[0],
+        TravelerErrorKind::PopMacroUnderflow(ref id) if id.string() == "X"
+    ));
+}
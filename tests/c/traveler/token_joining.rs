@@ -5,11 +5,15 @@ use vase::{
         CompileEnv,
         StringEnc,
         TokenKind::*,
+        TravelerErrorKind,
     },
     sync::Arc,
 };
 
-use super::run_test;
+use super::{
+    collect_errors,
+    run_test,
+};
 
 #[test]
 fn can_join_symbols() {
@@ -218,3 +222,61 @@ fn can_join_empty_func_macro() {
         ],
     );
 }
+
+#[test]
+fn joiner_outside_of_macro_is_stray() {
+    let env = CompileEnv::default();
+    let errors = collect_errors(
+        &env,
+        r#"
+        #define JOIN(A, B) A ## B
+        a ## b
+        JOIN(c, d) ## e
+        "#,
+    );
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+    for error in errors {
+        assert!(
+            matches!(error, TravelerErrorKind::StrayHashHash),
+            "{:?}",
+            error
+        );
+    }
+}
+
+#[test]
+fn empty_arguments_are_placemarkers_when_joined() {
+    let env = CompileEnv::default();
+    let cache = env.cache();
+    run_test(
+        &env,
+        &[r#"
+        #define CAT(a, b) [a ## b]
+        CAT(,) CAT(x,) CAT(,y)
+        #define SUFFIX(a) a ## z
+        SUFFIX() SUFFIX(w)
+        #define JOIN3(a, b, c) a ## b ## c
+        JOIN3(,,r) JOIN3(s,,)
+        // The result of joining a placemarker is still rescanned for macros.
+        #define EMPTY
+        #define JOIN_EMPTY(a) a ## EMPTY
+        JOIN_EMPTY()
+        end
+        "#],
+        &[
+            LBracket { alt: false },
+            RBracket { alt: false },
+            LBracket { alt: false },
+            Identifier(cache.get_or_cache("x")),
+            RBracket { alt: false },
+            LBracket { alt: false },
+            Identifier(cache.get_or_cache("y")),
+            RBracket { alt: false },
+            Identifier(cache.get_or_cache("z")),
+            Identifier(cache.get_or_cache("wz")),
+            Identifier(cache.get_or_cache("r")),
+            Identifier(cache.get_or_cache("s")),
+            Identifier(cache.get_or_cache("end")),
+        ],
+    );
+}